@@ -0,0 +1,51 @@
+//! Compares a tree storing 1 KiB values in its leaves with one storing them boxed, and with
+//! small values as a baseline. Run with `cargo bench --bench large_values`.
+//!
+//! The leaves are allocated on their own and node resizes only move pointers to them, so the
+//! value size shows in the cost of copying the value into and out of its leaf but not in the
+//! structural changes.
+extern crate adaptive_radix_tree;
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use adaptive_radix_tree::art::ArtTree;
+
+const ENTRIES: u32 = 200_000;
+
+type Big = [u64; 128];
+
+/// Keys spread over the whole key space, so that the inserts fill and grow nodes at every level
+fn key(i: u32) -> [u8; 4] {
+    i.wrapping_mul(2_654_435_761).to_be_bytes()
+}
+
+fn run<V, F>(make_value: F) -> Duration
+where
+    F: Fn(u32) -> V,
+{
+    let start = Instant::now();
+    let mut tree = ArtTree::<V>::new();
+    for i in 0..ENTRIES {
+        tree.insert(&key(i), make_value(i));
+    }
+    for i in 0..ENTRIES {
+        black_box(tree.get(&key(i)));
+    }
+    for i in 0..ENTRIES {
+        black_box(tree.delete(&key(i)));
+    }
+    start.elapsed()
+}
+
+fn main() {
+    for round in 1..=3 {
+        let small = run(u64::from);
+        let inline = run(|i| -> Big { [u64::from(i); 128] });
+        let boxed = run(|i| -> Box<Big> { Box::new([u64::from(i); 128]) });
+        println!(
+            "round {}: u64 {:?}, [u64; 128] {:?}, Box<[u64; 128]> {:?}",
+            round, small, inline, boxed
+        );
+    }
+}
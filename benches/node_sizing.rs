@@ -0,0 +1,88 @@
+//! Compares the node sizing presets of `ArtTree` on trees whose nodes have 12, 24 and 40
+//! children. Run with `cargo bench --bench node_sizing`.
+//!
+//! With 12 and 24 children the default sizing keeps Node16s and Node32s, which
+//! `NodeSizing::FAST` grows into Node48s. With 40 children the default sizing keeps Node48s and
+//! the fast one Node256s. The churn deletes and reinserts half of the children of every bottom
+//! node, which the compact sizing answers by shrinking the nodes and growing them again.
+extern crate adaptive_radix_tree;
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use adaptive_radix_tree::art::{ArtTree, NodeSizing};
+
+const ENTRIES: u32 = 200_000;
+
+/// Spells `i` in base `fanout`, so that every internal node has `fanout` children
+fn key(i: u32, fanout: u32) -> [u8; 4] {
+    let mut key = [0; 4];
+    let mut rest = i;
+    for byte in key.iter_mut().rev() {
+        *byte = (rest % fanout) as u8;
+        rest /= fanout;
+    }
+    key
+}
+
+#[derive(Default)]
+struct Timings {
+    insert: Duration,
+    lookup: Duration,
+    scan: Duration,
+    churn: Duration,
+}
+
+fn time<F: FnOnce()>(total: &mut Duration, f: F) {
+    let start = Instant::now();
+    f();
+    *total += start.elapsed();
+}
+
+fn run(sizing: NodeSizing, fanout: u32, timings: &mut Timings) {
+    let mut tree = ArtTree::with_node_sizing(sizing);
+    time(&mut timings.insert, || {
+        for i in 0..ENTRIES {
+            tree.insert(&key(i, fanout), i);
+        }
+    });
+    time(&mut timings.lookup, || {
+        for i in 0..ENTRIES {
+            black_box(tree.get(&key(i, fanout)));
+        }
+    });
+    time(&mut timings.scan, || {
+        for _ in 0..10 {
+            black_box(tree.entries().count());
+        }
+    });
+    let churned = (0..ENTRIES).filter(|i| i % fanout >= fanout / 2);
+    time(&mut timings.churn, || {
+        for i in churned.clone() {
+            tree.delete(&key(i, fanout));
+        }
+        for i in churned {
+            tree.insert(&key(i, fanout), i);
+        }
+    });
+}
+
+fn main() {
+    let presets = [
+        ("default", NodeSizing::DEFAULT),
+        ("compact", NodeSizing::COMPACT),
+        ("fast", NodeSizing::FAST),
+    ];
+    for &fanout in [12, 24, 40].iter() {
+        for &(name, sizing) in presets.iter() {
+            let mut timings = Timings::default();
+            for _ in 0..3 {
+                run(sizing, fanout, &mut timings);
+            }
+            println!(
+                "fanout {:2} {:8} insert {:>10.2?} lookup {:>10.2?} scan {:>10.2?} churn {:>10.2?}",
+                fanout, name, timings.insert, timings.lookup, timings.scan, timings.churn
+            );
+        }
+    }
+}
@@ -0,0 +1,173 @@
+#![no_main]
+
+//! Runs a sequence of operations against both an `ArtTree` and a `BTreeMap` model and checks
+//! that they agree after every step.
+
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+use adaptive_radix_tree::art::ArtTree;
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+/// Raw key bytes, turned into a prefix-free key by `key`
+#[derive(Arbitrary, Debug)]
+struct RawKey(Vec<u8>);
+
+/// The tree only supports prefix-free keys: `00` bytes are mapped to `01` and every key is
+/// terminated by `00`. Bytes are folded into a small alphabet half of the time, so that keys
+/// share long prefixes (beyond the compressed path stored in the nodes).
+fn key(raw: &RawKey) -> Vec<u8> {
+    let fold = raw.0.first().is_some_and(|b| b & 1 == 1);
+    let mut key: Vec<u8> = raw
+        .0
+        .iter()
+        .map(|&b| if fold { b % 3 + 1 } else { b.max(1) })
+        .collect();
+    key.push(0);
+    key
+}
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Insert(RawKey, u16),
+    Get(RawKey),
+    GetMut(RawKey, u16),
+    Delete(RawKey),
+    Increment(RawKey, u16),
+    PopFirst,
+    PopLast,
+    Range(Option<RawKey>, Option<RawKey>, bool),
+    RemoveBelow(RawKey),
+    Floor(RawKey),
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    let mut tree = ArtTree::<u64>::new();
+    let mut model = BTreeMap::<Vec<u8>, u64>::new();
+
+    for op in &ops {
+        match op {
+            Op::Insert(raw, value) => {
+                let key = key(raw);
+                let value = u64::from(*value);
+                assert_eq!(tree.insert(&key, value), model.insert(key, value));
+            }
+            Op::Get(raw) => {
+                let key = key(raw);
+                assert_eq!(tree.get(&key), model.get(&key));
+                assert_eq!(tree.contains_key(&key), model.contains_key(&key));
+            }
+            Op::GetMut(raw, value) => {
+                let key = key(raw);
+                let value = u64::from(*value);
+                match (tree.get_mut(&key), model.get_mut(&key)) {
+                    (Some(actual), Some(expected)) => {
+                        assert_eq!(actual, expected);
+                        *actual = value;
+                        *expected = value;
+                    }
+                    (actual, expected) => assert_eq!(actual, expected),
+                }
+            }
+            Op::Delete(raw) => {
+                let key = key(raw);
+                assert_eq!(tree.delete(&key), model.remove(&key));
+            }
+            Op::Increment(raw, delta) => {
+                let key = key(raw);
+                let delta = u64::from(*delta);
+                let expected = model.entry(key.clone()).or_insert(0);
+                *expected += delta;
+                assert_eq!(tree.increment(&key, delta), *expected);
+            }
+            Op::PopFirst => {
+                let expected = model.keys().next().cloned();
+                let expected = expected.map(|key| {
+                    let value = model.remove(&key).unwrap();
+                    (key, value)
+                });
+                let actual = tree.pop_first().map(|(key, value)| (key.to_vec(), value));
+                assert_eq!(actual, expected);
+            }
+            Op::PopLast => {
+                let expected = model.keys().next_back().cloned();
+                let expected = expected.map(|key| {
+                    let value = model.remove(&key).unwrap();
+                    (key, value)
+                });
+                let actual = tree.pop_last().map(|(key, value)| (key.to_vec(), value));
+                assert_eq!(actual, expected);
+            }
+            Op::Range(start, end, inclusive) => {
+                let start = start.as_ref().map(key);
+                let end = end.as_ref().map(key);
+                if let (Some(start), Some(end)) = (&start, &end) {
+                    // BTreeMap panics on inverted ranges
+                    if start > end || (start == end && !inclusive) {
+                        continue;
+                    }
+                }
+                let start_bound = match &start {
+                    Some(start) => Bound::Included(&start[..]),
+                    None => Bound::Unbounded,
+                };
+                let end_bound = match (&end, inclusive) {
+                    (Some(end), true) => Bound::Included(&end[..]),
+                    (Some(end), false) => Bound::Excluded(&end[..]),
+                    (None, _) => Bound::Unbounded,
+                };
+                let actual: Vec<_> = tree
+                    .range((start_bound, end_bound))
+                    .map(|(key, value)| (key.to_vec(), *value))
+                    .collect();
+                let expected: Vec<_> = model
+                    .range::<[u8], _>((start_bound, end_bound))
+                    .map(|(key, value)| (key.clone(), *value))
+                    .collect();
+                assert_eq!(actual, expected);
+            }
+            Op::RemoveBelow(raw) => {
+                let key = key(raw);
+                let kept = model.split_off(&key);
+                assert_eq!(tree.remove_below(&key), model.len());
+                model = kept;
+            }
+            Op::Floor(raw) => {
+                let key = key(raw);
+                let actual = tree.floor(&key).map(|(key, value)| (key.to_vec(), *value));
+                let expected = model
+                    .range::<[u8], _>((Bound::Unbounded, Bound::Included(&key[..])))
+                    .next_back()
+                    .map(|(key, value)| (key.clone(), *value));
+                assert_eq!(actual, expected);
+            }
+        }
+
+        check_invariants(&tree, &model);
+    }
+});
+
+fn check_invariants(tree: &ArtTree<u64>, model: &BTreeMap<Vec<u8>, u64>) {
+    assert_eq!(tree.len(), model.len());
+    assert_eq!(tree.is_empty(), model.is_empty());
+    assert_eq!(
+        tree.minimum().map(|(key, value)| (key.to_vec(), *value)),
+        model
+            .iter()
+            .next()
+            .map(|(key, value)| (key.clone(), *value))
+    );
+    assert_eq!(
+        tree.maximum().map(|(key, value)| (key.to_vec(), *value)),
+        model
+            .iter()
+            .next_back()
+            .map(|(key, value)| (key.clone(), *value))
+    );
+    let entries = tree.entries();
+    assert_eq!(entries.len(), model.len());
+    assert!(entries
+        .map(|(key, value)| (key.to_vec(), *value))
+        .eq(model.iter().map(|(key, value)| (key.clone(), *value))));
+}
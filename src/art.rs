@@ -1,14 +1,171 @@
-use std::cmp::min;
+use std::cmp::{max, min, Ordering};
 
+use std::fmt;
 use std::mem;
+use std::ops::Add;
+use std::sync::Arc;
+
+use crate::simd::{find_key_16, find_key_32, find_key_portable};
+
+#[cfg(feature = "aggregate")]
+mod aggregate;
+mod arena;
+mod bloom;
+mod builder;
+mod children;
+mod clone;
+mod common_prefix;
+mod debug_print;
+mod delta;
+mod diff;
+mod digest;
+mod duplicate;
+mod entry;
+mod estimate;
+mod explain;
+mod fallible;
+mod frozen;
+mod heat;
+mod inline_key;
+mod iter;
+mod journal;
+mod memory;
+#[cfg(feature = "merkle")]
+mod merkle;
+mod metrics;
+mod neighbor;
+mod optimize;
+mod prefix_report;
+mod profile;
+mod purge;
+#[cfg(feature = "rand")]
+mod sample;
+mod scope;
+mod set_ops;
+mod sizing;
+mod sort;
+mod swap;
+mod trace;
+mod txn;
+mod validate;
+mod visit;
+
+#[cfg(feature = "aggregate")]
+pub use self::aggregate::{Aggregate, AnnotatedArtTree, Count};
+pub use self::arena::{ArenaKey, ArtTreeArena, KeyArena};
+pub use self::builder::ArtBuilder;
+pub use self::children::Branch;
+pub use self::debug_print::DebugPrint;
+pub use self::delta::{Changes, Delta};
+pub use self::diff::{Diff, DiffEntry};
+pub use self::duplicate::{DuplicatePolicy, InsertError, InsertOutcome};
+pub use self::entry::{Entry, OccupiedEntry, VacantEntry};
+pub use self::estimate::CountEstimate;
+pub use self::explain::{Explain, ExplainEnd, ExplainStep};
+pub use self::frozen::FrozenArtTree;
+pub use self::inline_key::{ArtTreeInline, InlineKey, INLINE_KEY_CAPACITY};
+pub use self::iter::{
+    Groups, IntoIter, Iter, IterMut, Prefix, PrefixKeys, PrefixMut, Range, RangeMut,
+};
+pub use self::journal::{Journal, JournalEntry, JournalOp};
+pub use self::memory::MemoryStats;
+#[cfg(feature = "merkle")]
+pub use self::merkle::{HashOracle, SyncDiff};
+#[cfg(feature = "metrics")]
+pub use self::metrics::ArtMetrics;
+pub use self::optimize::{NodeStats, OptimizeReport};
+pub use self::prefix_report::PrefixReport;
+#[cfg(feature = "profile")]
+pub use self::profile::{profile, take_profile, OpProfile};
+pub use self::scope::{Scope, ScopeMut};
+pub use self::sizing::NodeSizing;
+pub use self::sort::sort_by_key_bytes;
+pub use self::txn::Txn;
+pub use self::validate::InvariantViolation;
+pub use self::visit::{NodeHeader, NodeKind, Visitor};
+
+use self::bloom::KeyFilter;
+pub(crate) use self::builder::{BuildSink, SortedPath};
+use self::delta::DirtyKeys;
+use self::heat::Heat;
+use self::metrics::Counters;
+use self::profile::hooks as profiling;
+pub(crate) use self::sort::encode_prefix_free;
+pub(crate) use self::trace::BulkLoad;
 
 const MAX_PREFIX_LEN: usize = 10;
 
-#[derive(Debug, Clone)]
-enum Node<V> {
+#[derive(Debug, Default)]
+enum Node<V, K> {
+    #[default]
     Empty,
-    Leaf(Box<ArtNodeLeaf<V>>),
-    Internal(Box<ArtNodeInternal<V>>),
+    Leaf(Box<ArtNodeLeaf<V, K>>),
+    Internal(Box<ArtNodeInternal<V, K>>),
+}
+
+/// The leaf `Node::recursive_delete` removes: the leaf of a key, or the leaf with the minimum
+/// or maximum key, for popping entries without knowing their keys.
+#[derive(Clone, Copy)]
+enum DeleteTarget<'a> {
+    Key(&'a [u8]),
+    First,
+    Last,
+}
+
+/// Outcome of `Node::recursive_upsert`, pointing at the stored value
+enum Upsert<'a, V> {
+    Inserted(&'a mut V),
+    Existing(&'a mut V),
+}
+
+/// Allocations made before an insert, taken by `Node::recursive_upsert` instead of allocating
+/// when it changes the tree
+struct Spare<V, K> {
+    /// An empty Node4 to split a node into
+    node4: Option<Box<ArtNodeInternal<V, K>>>,
+    /// The empty arrays of the node type a full node grows into
+    grown: Option<ArtNodeInternalInner<V, K>>,
+}
+
+impl<V, K> Default for Spare<V, K> {
+    fn default() -> Self {
+        Self {
+            node4: None,
+            grown: None,
+        }
+    }
+}
+
+/// Value stored by `ArtTree::insert_with_key`, either by itself or in a leaf taken out of the
+/// tree. A leaf is reused for the new key as is, so that its value is not moved.
+enum NewEntry<V, K> {
+    Value(V),
+    Leaf(Box<ArtNodeLeaf<V, K>>),
+    /// A leaf that holds the new key already
+    Keyed(Box<ArtNodeLeaf<V, K>>),
+}
+
+impl<V, K> NewEntry<V, K> {
+    fn into_leaf<F: FnOnce() -> K>(self, make_key: F) -> Box<ArtNodeLeaf<V, K>> {
+        match self {
+            NewEntry::Value(value) => Box::new(ArtNodeLeaf {
+                value,
+                key: make_key(),
+            }),
+            NewEntry::Leaf(mut leaf) => {
+                leaf.key = make_key();
+                leaf
+            }
+            NewEntry::Keyed(leaf) => leaf,
+        }
+    }
+
+    fn into_value(self) -> V {
+        match self {
+            NewEntry::Value(value) => value,
+            NewEntry::Leaf(leaf) | NewEntry::Keyed(leaf) => leaf.value,
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -18,49 +175,374 @@ struct InternalNodeHeader {
     partial: [u8; MAX_PREFIX_LEN],
 }
 
-#[derive(Debug, Clone)]
-pub struct ArtNodeLeaf<V> {
+#[derive(Debug)]
+pub struct ArtNodeLeaf<V, K = Box<[u8]>> {
     pub value: V,
-    key: Box<[u8]>,
+    key: K,
 }
 
-#[derive(Debug, Clone)]
-struct ArtNodeInternal<V> {
+#[derive(Debug)]
+struct ArtNodeInternal<V, K> {
     header: InternalNodeHeader,
-    inner: ArtNodeInternalInner<V>,
+    inner: ArtNodeInternalInner<V, K>,
+    heat: Heat,
+    /// Cached hash of the subtree, cleared whenever the subtree may change
+    #[cfg(feature = "merkle")]
+    hash: std::sync::OnceLock<[u8; 32]>,
+    /// Cached summary of the subtree for the aggregate of its type id, cleared whenever the
+    /// subtree may change
+    #[cfg(feature = "aggregate")]
+    summary: std::sync::OnceLock<aggregate::CachedSummary>,
 }
 
 #[derive(Debug, Clone)]
-enum ArtNodeInternalInner<V> {
+enum ArtNodeInternalInner<V, K> {
     Node4 {
         keys: [u8; 4],
-        children: [Node<V>; 4],
+        children: Box<[Node<V, K>; 4]>,
     },
     Node16 {
         keys: [u8; 16],
-        children: [Node<V>; 16],
+        children: Box<[Node<V, K>; 16]>,
+    },
+    Node32 {
+        keys: [u8; 32],
+        children: Box<[Node<V, K>; 32]>,
     },
     Node48 {
-        keys: [u8; 256],
-        children: [Node<V>; 48],
+        keys: Box<[u8; 256]>,
+        children: Box<[Node<V, K>; 48]>,
     },
     Node256 {
-        children: [Node<V>; 256],
+        children: Box<[Node<V, K>; 256]>,
     },
 }
 
-#[derive(Debug, Clone)]
-pub struct ArtTree<V> {
-    root: Node<V>,
+impl<V, K> ArtNodeInternalInner<V, K> {
+    /// Returns the empty arrays of the node type a full node of the given type grows into,
+    /// taking those in `spare` if they are of that type
+    fn grown(kind: NodeKind, spare: &mut Option<Self>) -> Self {
+        match (kind, spare.take()) {
+            (NodeKind::Node4, Some(inner @ ArtNodeInternalInner::Node16 { .. }))
+            | (NodeKind::Node16, Some(inner @ ArtNodeInternalInner::Node32 { .. }))
+            | (NodeKind::Node32, Some(inner @ ArtNodeInternalInner::Node48 { .. }))
+            | (NodeKind::Node48, Some(inner @ ArtNodeInternalInner::Node256 { .. })) => inner,
+            (NodeKind::Node4, _) => ArtNodeInternalInner::Node16 {
+                keys: [0; 16],
+                children: Box::new([Node::INIT; 16]),
+            },
+            (NodeKind::Node16, _) => ArtNodeInternalInner::Node32 {
+                keys: [0; 32],
+                children: Box::new([Node::INIT; 32]),
+            },
+            (NodeKind::Node32, _) => ArtNodeInternalInner::Node48 {
+                keys: Box::new([0; 256]),
+                children: Box::new([Node::INIT; 48]),
+            },
+            (NodeKind::Node48, _) => ArtNodeInternalInner::Node256 {
+                children: Box::new([Node::INIT; 256]),
+            },
+            (NodeKind::Node256, _) => unreachable!("a Node256 does not grow"),
+        }
+    }
+}
+
+/// Owned storage for the key of a leaf.
+///
+/// `Box<[u8]>` is the default. With the `bytes` feature, `bytes::Bytes` can be used instead so
+/// that leaves share the (reference-counted) buffer a key arrived in rather than copying it.
+pub trait LeafKey: AsRef<[u8]> {
+    /// Creates an owned key by copying the given bytes.
+    fn from_slice(key: &[u8]) -> Self;
+
+    /// Creates the key of a new leaf of a tree, which may store the bytes in the key arena of
+    /// the tree. Copies the bytes with `from_slice` by default.
+    fn intern(key: &[u8], arena: &mut KeyArena) -> Self
+    where
+        Self: Sized,
+    {
+        let _ = arena;
+        Self::from_slice(key)
+    }
+
+    /// Returns the number of bytes the key owns outside the leaf, for
+    /// [`ArtTree::memory_stats`].
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+impl LeafKey for Box<[u8]> {
+    fn from_slice(key: &[u8]) -> Self {
+        key.into()
+    }
+
+    fn heap_size(&self) -> usize {
+        self.len()
+    }
+}
+
+/// Keys of a fixed length are stored inline in the leaf, see [`ArtTreeFixed`].
+impl<const N: usize> LeafKey for [u8; N] {
+    fn from_slice(key: &[u8]) -> Self {
+        assert_eq!(
+            key.len(),
+            N,
+            "a key of {} bytes does not fit a tree of {}-byte keys",
+            key.len(),
+            N
+        );
+        let mut bytes = [0; N];
+        bytes.copy_from_slice(key);
+        bytes
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl LeafKey for bytes::Bytes {
+    fn from_slice(key: &[u8]) -> Self {
+        bytes::Bytes::copy_from_slice(key)
+    }
+
+    /// Counts the visible bytes, a buffer shared by several keys is counted once per key
+    fn heap_size(&self) -> usize {
+        self.len()
+    }
+}
+
+/// Function folding a merge operand into the value stored at a key, see
+/// [`ArtTree::with_merge_operator`].
+///
+/// It is called with the key, the current value (`None` if the key is missing) and the operand,
+/// and returns the value to store.
+pub type MergeFn<V> = dyn Fn(&[u8], Option<V>, V) -> V + Send + Sync;
+
+struct MergeOperator<V>(Arc<MergeFn<V>>);
+
+impl<V> Clone for MergeOperator<V> {
+    fn clone(&self) -> Self {
+        MergeOperator(self.0.clone())
+    }
+}
+
+impl<V> fmt::Debug for MergeOperator<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MergeOperator")
+    }
+}
+
+/// Callbacks invoked after the contents of a tree change, registered with
+/// [`ArtTree::add_observer`].
+///
+/// Values changed in place through `get_mut` and similar references are not reported. A
+/// [`merge`](ArtTree::merge) into an existing key is reported as a delete of the old value
+/// followed by an insert of the merged one.
+pub trait MutationObserver<V> {
+    /// Called after a new key was inserted.
+    fn on_insert(&self, _key: &[u8], _value: &V) {}
+
+    /// Called after the value of an existing key was replaced.
+    fn on_replace(&self, _key: &[u8], _old_value: &V, _new_value: &V) {}
+
+    /// Called after a key was deleted.
+    fn on_delete(&self, _key: &[u8], _value: &V) {}
+
+    /// Called after [`ArtTree::merge`] merged an operand into the value of an existing key. The
+    /// merge operator consumed the old value, so only the new one is passed.
+    fn on_merge(&self, _key: &[u8], _new_value: &V) {}
+}
+
+struct Observers<V>(Vec<Arc<dyn MutationObserver<V> + Send + Sync>>);
+
+impl<V> Observers<V> {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn inserted(&self, key: &[u8], value: &V) {
+        for observer in &self.0 {
+            observer.on_insert(key, value);
+        }
+    }
+
+    fn replaced(&self, key: &[u8], old_value: &V, new_value: &V) {
+        for observer in &self.0 {
+            observer.on_replace(key, old_value, new_value);
+        }
+    }
+
+    fn deleted(&self, key: &[u8], value: &V) {
+        for observer in &self.0 {
+            observer.on_delete(key, value);
+        }
+    }
+
+    fn merged(&self, key: &[u8], new_value: &V) {
+        for observer in &self.0 {
+            observer.on_merge(key, new_value);
+        }
+    }
+}
+
+impl<V> Default for Observers<V> {
+    fn default() -> Self {
+        Observers(Vec::new())
+    }
+}
+
+impl<V> Clone for Observers<V> {
+    fn clone(&self) -> Self {
+        Observers(self.0.clone())
+    }
+}
+
+impl<V> fmt::Debug for Observers<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Observers({})", self.0.len())
+    }
+}
+
+/// An adaptive radix tree mapping byte string keys to values of type `V`, storing the keys of
+/// the leaves as `K`.
+///
+/// Every entry is stored in a leaf allocated on its own. Growing, shrinking and splitting nodes
+/// only moves pointers to the leaves, so a value stays at its address from its insertion until
+/// it is removed or replaced, and a large `V` does not need to be boxed to keep structural
+/// changes cheap. See `benches/large_values.rs`.
+#[derive(Debug)]
+pub struct ArtTree<V, K = Box<[u8]>> {
+    root: Node<V, K>,
     size: u64,
+    merge_operator: Option<MergeOperator<V>>,
+    observers: Observers<V>,
+    counters: Counters,
+    sizing: NodeSizing,
+    max_key_len: usize,
+    duplicates: DuplicatePolicy<V>,
+    dirty: DirtyKeys,
+    bloom: KeyFilter,
+    keys: KeyArena,
 }
 
+/// An `ArtTree` whose keys all have the length `N`, e.g. big-endian integers or UUIDs.
+///
+/// The leaves store the keys inline as `[u8; N]` rather than in a separate allocation, and,
+/// since fixed-length keys are never prefixes of each other, any set of keys can be stored.
+/// Lookups accept any slice, inserting a key of another length panics. Create one with
+/// `ArtTreeFixed::default()`.
+pub type ArtTreeFixed<const N: usize, V> = ArtTree<V, [u8; N]>;
+
 impl<V> ArtTree<V> {
     pub fn new() -> Self {
-        Self {
-            root: Node::Empty,
-            size: 0,
+        Self::default()
+    }
+
+    /// Creates an empty tree expecting to hold about `capacity` entries, see
+    /// [`reserve`](ArtTree::reserve).
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut tree = Self::default();
+        tree.reserve(capacity);
+        tree
+    }
+
+    /// Creates an empty tree whose [`merge`](ArtTree::merge) folds operands into the stored
+    /// values using the given function.
+    pub fn with_merge_operator<F>(merge: F) -> Self
+    where
+        F: Fn(&[u8], Option<V>, V) -> V + Send + Sync + 'static,
+    {
+        let mut tree = Self::default();
+        tree.set_merge_operator(merge);
+        tree
+    }
+
+    /// Creates an empty tree whose internal nodes change their type at the given numbers of
+    /// children.
+    ///
+    /// # Panics
+    ///
+    /// If the numbers are out of the ranges documented on [`NodeSizing`].
+    pub fn with_node_sizing(sizing: NodeSizing) -> Self {
+        let mut tree = Self::default();
+        tree.set_node_sizing(sizing);
+        tree
+    }
+
+    /// Creates an empty tree accepting keys of at most `max` bytes, see
+    /// [`set_max_key_len`](ArtTree::set_max_key_len).
+    pub fn with_max_key_len(max: usize) -> Self {
+        let mut tree = Self::default();
+        tree.set_max_key_len(max);
+        tree
+    }
+}
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Returns the number of keys in the tree
+    pub fn len(&self) -> usize {
+        self.size as usize
+    }
+
+    /// Returns true if the tree contains no keys
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Prepares the tree for `additional` more entries.
+    ///
+    /// Nodes are allocated one by one as keys are inserted, so only the auxiliary structures are
+    /// sized up front: a Bloom filter, enabled now or later, is sized for the expected number of
+    /// entries instead of being rebuilt repeatedly while the tree grows.
+    pub fn reserve(&mut self, additional: usize) {
+        self.reserve_bloom_filter(additional);
+    }
+
+    /// Returns true if a value is stored at the given key
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns a reference to the value stored at the given key if it exists
+    pub fn get(&self, key: &[u8]) -> Option<&V> {
+        if !self.bloom.may_contain(key) {
+            self.counters.lookup(0);
+            return None;
         }
+        let mut n_iter = &self.root;
+        let mut depth = 0;
+        let mut visited = 0;
+        let result = loop {
+            visited += 1;
+            match n_iter {
+                Node::Leaf(leaf) => {
+                    if leaf.matches(key) {
+                        break Some(&leaf.value);
+                    }
+                    break None;
+                }
+                Node::Internal(internal) => {
+                    internal.heat.touch(&self.sizing);
+                    let header = internal.header;
+
+                    if header.partial_len != 0 {
+                        let prefix_len = header.check_prefix(key, depth);
+                        if prefix_len != min(MAX_PREFIX_LEN, header.partial_len) {
+                            break None;
+                        }
+                        depth += header.partial_len;
+                    }
+
+                    match key.get(depth).and_then(|&c| internal.find_child(c)) {
+                        Some(child) => n_iter = child,
+                        None => break None,
+                    }
+                    depth += 1;
+                }
+                Node::Empty => break None,
+            }
+        };
+        self.counters.lookup(visited);
+        result
     }
 
     /// Searches for a value in the ARV tree
@@ -69,71 +551,164 @@ impl<V> ArtTree<V> {
     /// @return NULL if the item was not found, otherwise
     /// the value pointer is returned.
     pub fn get_mut(&mut self, key: &[u8]) -> Option<&mut V> {
+        if !self.bloom.may_contain(key) {
+            self.counters.lookup(0);
+            return None;
+        }
         let mut n_iter = &mut self.root;
         let mut depth = 0;
-        loop {
+        let mut visited = 0;
+        let result = loop {
+            visited += 1;
             match *n_iter {
                 Node::Leaf(ref mut leaf) => {
                     if leaf.matches(key) {
-                        return Some(&mut leaf.value);
+                        self.dirty.mark(key);
+                        break Some(&mut leaf.value);
                     }
-                    return None;
+                    break None;
                 }
                 Node::Internal(ref mut internal) => {
+                    internal.invalidate_caches();
+                    internal.heat.touch(&self.sizing);
                     let header = internal.header;
 
                     if header.partial_len != 0 {
                         let prefix_len = header.check_prefix(key, depth);
                         if prefix_len != min(MAX_PREFIX_LEN, header.partial_len) {
-                            return None;
+                            break None;
                         }
-                        depth = depth + header.partial_len;
+                        depth += header.partial_len;
                     }
 
-                    n_iter = internal.find_child_mut(key[depth])?;
+                    match key.get(depth).copied() {
+                        Some(c) => match internal.find_child_mut(c) {
+                            Some(child) => n_iter = child,
+                            None => break None,
+                        },
+                        None => break None,
+                    }
                     depth += 1;
                 }
-                Node::Empty => return None,
+                Node::Empty => break None,
             }
-        }
+        };
+        self.counters.lookup(visited);
+        result
     }
 
-    pub fn minimum(&self) -> Option<(&Box<[u8]>, &V)> {
+    pub fn minimum(&self) -> Option<(&K, &V)> {
         self.root.minimum().map(|leaf| (&leaf.key, &leaf.value))
     }
 
-    pub fn maximum(&self) -> Option<(&Box<[u8]>, &V)> {
+    pub fn maximum(&self) -> Option<(&K, &V)> {
         self.root.maximum().map(|leaf| (&leaf.key, &leaf.value))
     }
 
-    pub fn minimum_mut(&mut self) -> Option<(&mut Box<[u8]>, &mut V)> {
-        self.root
-            .minimum_mut()
-            .map(|leaf| (&mut leaf.key, &mut leaf.value))
+    pub fn minimum_mut(&mut self) -> Option<(&mut K, &mut V)> {
+        let dirty = &mut self.dirty;
+        self.root.minimum_mut().map(|leaf| {
+            dirty.mark(leaf.key());
+            (&mut leaf.key, &mut leaf.value)
+        })
     }
 
-    pub fn maximum_mut(&mut self) -> Option<(&mut Box<[u8]>, &mut V)> {
+    pub fn maximum_mut(&mut self) -> Option<(&mut K, &mut V)> {
+        let dirty = &mut self.dirty;
+        self.root.maximum_mut().map(|leaf| {
+            dirty.mark(leaf.key());
+            (&mut leaf.key, &mut leaf.value)
+        })
+    }
+
+    /// Returns the entry with the greatest key less than or equal to the given key.
+    pub fn floor(&self, key: &[u8]) -> Option<(&[u8], &V)> {
         self.root
-            .maximum_mut()
-            .map(|leaf| (&mut leaf.key, &mut leaf.value))
+            .floor(key, 0)
+            .map(|leaf| (leaf.key(), &leaf.value))
     }
 
-    pub fn pop_first(&mut self) -> Option<(Box<[u8]>, V)> {
-        let (min_key, _) = self.minimum()?;
-        let min_key = min_key.clone();
-        let key_tmp = min_key.clone();
-        let key = key_tmp.as_ref();
-        let min_val = self.delete(key).unwrap();
-        return Some((min_key, min_val));
+    /// Returns the entry with the minimum key among the keys starting with `prefix`.
+    pub fn min_prefix(&self, prefix: &[u8]) -> Option<(&[u8], &V)> {
+        let (node, _) = self.root.prefix_root(prefix, 0)?;
+        node.minimum().map(|leaf| (leaf.key(), &leaf.value))
     }
 
-    pub fn pop_last(&mut self) -> Option<(Box<[u8]>, V)> {
-        let (min_key, _) = self.maximum()?;
-        let min_key = min_key.clone();
-        let key_tmp = min_key.clone();
-        let key = key_tmp.as_ref();
-        let min_val = self.delete(key).unwrap();
-        return Some((min_key, min_val));
+    /// Returns the entry with the maximum key among the keys starting with `prefix`.
+    pub fn max_prefix(&self, prefix: &[u8]) -> Option<(&[u8], &V)> {
+        let (node, _) = self.root.prefix_root(prefix, 0)?;
+        node.maximum().map(|leaf| (leaf.key(), &leaf.value))
+    }
+
+    /// Returns a standalone tree with clones of the entries whose keys start with `prefix`.
+    ///
+    /// The subtree covering the prefix is cloned as is, with its compressed path extended to
+    /// start from the first key byte, instead of reinserting the entries one by one.
+    pub fn subtree(&self, prefix: &[u8]) -> Self
+    where
+        K: Clone,
+        V: Clone,
+    {
+        let root = match self.root.prefix_root(prefix, 0) {
+            Some((Node::Internal(internal), depth)) => {
+                let path = internal.minimum().unwrap().key();
+                let path_end = depth + internal.header.partial_len;
+                let mut root = internal.clone();
+                let copy_len = min(MAX_PREFIX_LEN, path_end);
+                root.header.partial_len = path_end;
+                root.header.partial[..copy_len].copy_from_slice(&path[..copy_len]);
+                Node::Internal(root)
+            }
+            Some((node, _)) => node.clone(),
+            None => Node::Empty,
+        };
+
+        let size = iter::RawIter::new(&root).count() as u64;
+        Self {
+            root,
+            size,
+            ..Self::default()
+        }
+    }
+
+    /// Removes and returns the entry with the minimum key in a single descent
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        self.delete_leaf(DeleteTarget::First)
+            .map(|leaf| (leaf.key, leaf.value))
+    }
+
+    /// Removes and returns the entry with the maximum key in a single descent
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        self.delete_leaf(DeleteTarget::Last)
+            .map(|leaf| (leaf.key, leaf.value))
+    }
+
+    /// Removes and returns the entry with the minimum key if `pred` approves of it, in a single
+    /// descent. `pred` is not called on an empty tree.
+    pub fn pop_first_if<F>(&mut self, pred: F) -> Option<(K, V)>
+    where
+        F: FnOnce(&[u8], &V) -> bool,
+    {
+        let mut pred = Some(pred);
+        self.delete_leaf_if(DeleteTarget::First, &mut |leaf| {
+            pred.take()
+                .is_some_and(|pred| pred(leaf.key(), &leaf.value))
+        })
+        .map(|leaf| (leaf.key, leaf.value))
+    }
+
+    /// Removes and returns the entry with the maximum key if `pred` approves of it, in a single
+    /// descent. `pred` is not called on an empty tree.
+    pub fn pop_last_if<F>(&mut self, pred: F) -> Option<(K, V)>
+    where
+        F: FnOnce(&[u8], &V) -> bool,
+    {
+        let mut pred = Some(pred);
+        self.delete_leaf_if(DeleteTarget::Last, &mut |leaf| {
+            pred.take()
+                .is_some_and(|pred| pred(leaf.key(), &leaf.value))
+        })
+        .map(|leaf| (leaf.key, leaf.value))
     }
 
     /// inserts a new value into the art tree
@@ -142,12 +717,319 @@ impl<V> ArtTree<V> {
     /// @arg value opaque value.
     /// @return null if the item was newly inserted, otherwise
     /// the old value pointer is returned.
+    ///
+    /// # Panics
+    ///
+    /// If the key is longer than [`max_key_len`](Self::max_key_len), for which
+    /// [`try_insert`](Self::try_insert) returns an error instead.
     pub fn insert(&mut self, key: &[u8], value: V) -> Option<V> {
-        let result = self.root.recursive_insert(key, value, 0, true);
-        if result.is_none() {
+        self.insert_with_key(key, |arena| K::intern(key, arena), NewEntry::Value(value))
+    }
+
+    /// Inserts a value under an already owned key, which is stored in the leaf as is if the key
+    /// is new (e.g. a `bytes::Bytes` slice of a larger buffer is kept without copying).
+    ///
+    /// Returns the previous value if the key was already present.
+    pub fn insert_key(&mut self, key: K, value: V) -> Option<V>
+    where
+        K: Clone,
+    {
+        self.insert_with_key(key.as_ref(), |_| key.clone(), NewEntry::Value(value))
+    }
+
+    fn insert_with_key<F>(&mut self, key: &[u8], make_key: F, entry: NewEntry<V, K>) -> Option<V>
+    where
+        F: FnOnce(&mut KeyArena) -> K,
+    {
+        self.insert_with_spare(key, make_key, entry, &mut Spare::default())
+    }
+
+    /// Inserts like `insert_with_key`, taking the allocations the insert needs from `spare` if
+    /// they were made before
+    fn insert_with_spare<F>(
+        &mut self,
+        key: &[u8],
+        make_key: F,
+        entry: NewEntry<V, K>,
+        spare: &mut Spare<V, K>,
+    ) -> Option<V>
+    where
+        F: FnOnce(&mut KeyArena) -> K,
+    {
+        self.check_key_len(key);
+        let mut entry = Some(entry);
+        self.counters.insert();
+        self.dirty.mark(key);
+        self.maintain_bloom_filter();
+        let arena = &mut self.keys;
+        let old_value = match self.root.recursive_upsert(
+            key,
+            || entry.take().unwrap().into_leaf(|| make_key(arena)),
+            0,
+            &self.counters,
+            &self.sizing,
+            spare,
+        ) {
+            Upsert::Inserted(new_value) => {
+                self.size += 1;
+                self.bloom.inserted(key);
+                self.observers.inserted(key, new_value);
+                None
+            }
+            Upsert::Existing(current) => {
+                let old_value = mem::replace(current, entry.take().unwrap().into_value());
+                self.observers.replaced(key, &old_value, current);
+                Some(old_value)
+            }
+        };
+        self.validate_path(key);
+        old_value
+    }
+
+    /// Returns a mutable reference to the value stored at the given key, inserting the value
+    /// returned by `default` first if the key is not present.
+    pub fn get_or_insert_with<F>(&mut self, key: &[u8], default: F) -> &mut V
+    where
+        F: FnOnce() -> V,
+    {
+        // The returned reference borrows the tree, so the path is checked before the insert,
+        // as left by earlier mutations
+        self.validate_path(key);
+        self.check_key_len(key);
+        self.counters.insert();
+        self.dirty.mark(key);
+        self.maintain_bloom_filter();
+        let arena = &mut self.keys;
+        match self.root.recursive_upsert(
+            key,
+            || Box::new(ArtNodeLeaf::new(K::intern(key, arena), default())),
+            0,
+            &self.counters,
+            &self.sizing,
+            &mut Spare::default(),
+        ) {
+            Upsert::Inserted(value) => {
+                self.size += 1;
+                self.bloom.inserted(key);
+                self.observers.inserted(key, value);
+                value
+            }
+            Upsert::Existing(value) => value,
+        }
+    }
+
+    /// Adds `delta` to the value stored at the given key and returns the new value. A missing key
+    /// is inserted with the value `delta`.
+    ///
+    /// Unlike a `get_mut` followed by an `insert`, this descends the tree only once. The old
+    /// value is only cloned if observers are registered, to pass it to them.
+    pub fn increment(&mut self, key: &[u8], delta: V) -> V
+    where
+        V: Add<Output = V> + Default + Clone,
+    {
+        self.check_key_len(key);
+        self.counters.insert();
+        self.dirty.mark(key);
+        self.maintain_bloom_filter();
+        let arena = &mut self.keys;
+        let value = match self.root.recursive_upsert(
+            key,
+            || Box::new(ArtNodeLeaf::new(K::intern(key, arena), V::default())),
+            0,
+            &self.counters,
+            &self.sizing,
+            &mut Spare::default(),
+        ) {
+            Upsert::Inserted(value) => {
+                self.size += 1;
+                self.bloom.inserted(key);
+                *value = mem::take(value) + delta;
+                self.observers.inserted(key, value);
+                value.clone()
+            }
+            Upsert::Existing(value) => {
+                // The old value is only kept for the observers
+                if self.observers.is_empty() {
+                    *value = mem::take(value) + delta;
+                } else {
+                    let old_value = value.clone();
+                    *value = mem::take(value) + delta;
+                    self.observers.replaced(key, &old_value, value);
+                }
+                value.clone()
+            }
+        };
+        self.validate_path(key);
+        value
+    }
+
+    /// Returns a snapshot of the operation counters of the tree.
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> ArtMetrics {
+        self.counters.snapshot()
+    }
+
+    /// Registers an observer that is notified of every insert, replace and delete.
+    ///
+    /// Clones of the tree share the observers registered before cloning.
+    pub fn add_observer(&mut self, observer: Arc<dyn MutationObserver<V> + Send + Sync>) {
+        self.observers.0.push(observer);
+    }
+
+    /// Sets the function used by [`merge`](ArtTree::merge), replacing any previous one.
+    pub fn set_merge_operator<F>(&mut self, merge: F)
+    where
+        F: Fn(&[u8], Option<V>, V) -> V + Send + Sync + 'static,
+    {
+        self.merge_operator = Some(MergeOperator(Arc::new(merge)));
+    }
+
+    /// Returns the numbers of children at which the internal nodes change their type
+    pub fn node_sizing(&self) -> NodeSizing {
+        self.sizing
+    }
+
+    /// Sets the numbers of children at which the internal nodes change their type. Existing
+    /// nodes are only converted by later inserts and deletes reaching them.
+    ///
+    /// # Panics
+    ///
+    /// If the numbers are out of the ranges documented on [`NodeSizing`].
+    pub fn set_node_sizing(&mut self, sizing: NodeSizing) {
+        sizing.check();
+        self.sizing = sizing;
+    }
+
+    /// Returns the length of the longest key the tree accepts
+    pub fn max_key_len(&self) -> usize {
+        self.max_key_len
+    }
+
+    /// Limits the length of the keys the tree accepts, e.g. to keep untrusted keys from
+    /// building long paths. Longer keys make [`insert`](Self::insert) and the other methods
+    /// adding keys panic, and the fallible ones such as [`try_insert`](Self::try_insert) return
+    /// [`InsertError::KeyTooLong`]. Keys already in the tree are kept.
+    pub fn set_max_key_len(&mut self, max: usize) {
+        self.max_key_len = max;
+    }
+
+    /// Panics if the tree does not accept `key`
+    fn check_key_len(&self, key: &[u8]) {
+        assert!(
+            key.len() <= self.max_key_len,
+            "the key of {} bytes is longer than the maximum of {}",
+            key.len(),
+            self.max_key_len
+        );
+    }
+
+    /// Folds `operand` into the value stored at the given key using the tree's merge operator,
+    /// inserting the result of merging into `None` if the key is missing.
+    ///
+    /// A missing key is inserted in a single descent. The leaf of a present key is taken out of
+    /// the tree for the merge operator to consume the old value and put back with the result,
+    /// so if the operator panics, the key is removed from the tree. Either way observers see
+    /// one insert or one [`on_merge`](MutationObserver::on_merge).
+    ///
+    /// Without a merge operator the operand simply replaces the stored value, like `insert`.
+    pub fn merge(&mut self, key: &[u8], operand: V) {
+        let merge = match &self.merge_operator {
+            Some(MergeOperator(merge)) => merge.clone(),
+            None => {
+                self.insert(key, operand);
+                return;
+            }
+        };
+        self.check_key_len(key);
+        self.counters.insert();
+        self.dirty.mark(key);
+        self.maintain_bloom_filter();
+        let arena = &mut self.keys;
+        let mut operand = Some(operand);
+        if let Upsert::Inserted(value) = self.root.recursive_upsert(
+            key,
+            || {
+                let value = merge(key, None, operand.take().unwrap());
+                Box::new(ArtNodeLeaf::new(K::intern(key, arena), value))
+            },
+            0,
+            &self.counters,
+            &self.sizing,
+            &mut Spare::default(),
+        ) {
             self.size += 1;
+            self.bloom.inserted(key);
+            self.observers.inserted(key, value);
+            self.validate_path(key);
+            return;
+        }
+
+        // The leaf is taken out of the tree for the operator to consume the old value, so that
+        // a panicking operator leaves the tree consistent (without the key)
+        let (root, leaf) = mem::take(&mut self.root).recursive_delete(
+            DeleteTarget::Key(key),
+            &mut |_| true,
+            0,
+            &self.counters,
+            &self.sizing,
+        );
+        self.root = root;
+        self.size -= 1;
+        let ArtNodeLeaf {
+            key: leaf_key,
+            value,
+        } = *leaf.unwrap();
+        let merged = merge(key, Some(value), operand.take().unwrap());
+        let value = match self.root.recursive_upsert(
+            key,
+            || {
+                Box::new(ArtNodeLeaf {
+                    key: leaf_key,
+                    value: merged,
+                })
+            },
+            0,
+            &self.counters,
+            &self.sizing,
+            &mut Spare::default(),
+        ) {
+            Upsert::Inserted(value) => value,
+            Upsert::Existing(_) => unreachable!(),
+        };
+        self.size += 1;
+        self.observers.merged(key, value);
+        self.validate_path(key);
+    }
+
+    /// Moves the value stored at `old` to `new`, replacing any value stored at `new`. Returns
+    /// false and leaves the tree unchanged if `old` is not present.
+    ///
+    /// The leaf holding the value is moved to the new key, so `V` does not need to implement
+    /// `Clone` and the value stays at its address unless it replaces a value stored at `new`.
+    pub fn rekey(&mut self, old: &[u8], new: &[u8]) -> bool {
+        // Checked before the entry is taken out of the tree
+        self.check_key_len(new);
+        match self.delete_leaf(DeleteTarget::Key(old)) {
+            Some(leaf) => {
+                self.insert_with_key(new, |arena| K::intern(new, arena), NewEntry::Leaf(leaf));
+                true
+            }
+            None => false,
         }
-        result
+    }
+
+    /// Moves the entry with the minimum key to `new`, replacing any value stored at `new`, and
+    /// returns the key it had. Returns `None` if the tree is empty.
+    ///
+    /// Equivalent to `pop_first` followed by inserting the value at `new`, as done to reschedule
+    /// the next timer of a timer queue, but the leaf is reused for the new key instead of being
+    /// freed and allocated again. See [`rekey`](Self::rekey) to move any other entry.
+    pub fn reschedule_first(&mut self, new: &[u8]) -> Option<K> {
+        self.check_key_len(new);
+        let mut leaf = self.delete_leaf(DeleteTarget::First)?;
+        let old = mem::replace(&mut leaf.key, K::intern(new, &mut self.keys));
+        self.insert_with_key(new, |_| unreachable!(), NewEntry::Keyed(leaf));
+        Some(old)
     }
 
     /// Deletes a value from the ARV tree
@@ -156,10 +1038,34 @@ impl<V> ArtTree<V> {
     /// @return NULL if the item was not found, otherwise
     /// the value pointer is returned.
     pub fn delete(&mut self, key: &[u8]) -> Option<V> {
-        let (root, result) = mem::take(&mut self.root).recursive_delete(key, 0);
+        self.delete_leaf(DeleteTarget::Key(key))
+            .map(|leaf| leaf.value)
+    }
+
+    fn delete_leaf(&mut self, target: DeleteTarget<'_>) -> Option<Box<ArtNodeLeaf<V, K>>> {
+        self.delete_leaf_if(target, &mut |_| true)
+    }
+
+    /// Deletes the leaf found for `target` if `approve` returns true for it
+    fn delete_leaf_if(
+        &mut self,
+        target: DeleteTarget<'_>,
+        approve: &mut dyn FnMut(&ArtNodeLeaf<V, K>) -> bool,
+    ) -> Option<Box<ArtNodeLeaf<V, K>>> {
+        let (root, result) = mem::take(&mut self.root).recursive_delete(
+            target,
+            approve,
+            0,
+            &self.counters,
+            &self.sizing,
+        );
         self.root = root;
-        if result.is_some() {
+        if let Some(leaf) = &result {
             self.size -= 1;
+            self.dirty.mark(leaf.key());
+            self.observers.deleted(leaf.key(), &leaf.value);
+            self.bloom.deleted(1);
+            self.validate_path(leaf.key());
         }
         result
     }
@@ -179,23 +1085,34 @@ impl<V> ArtTree<V> {
     }
 }
 
-impl<V> Default for Node<V> {
+impl<V, K> Default for ArtTree<V, K> {
     fn default() -> Self {
-        Self::Empty
+        Self {
+            root: Node::Empty,
+            size: 0,
+            merge_operator: None,
+            observers: Observers::default(),
+            counters: Counters::default(),
+            sizing: NodeSizing::DEFAULT,
+            max_key_len: usize::MAX,
+            duplicates: DuplicatePolicy::Replace,
+            dirty: DirtyKeys::default(),
+            bloom: KeyFilter::default(),
+            keys: KeyArena::default(),
+        }
     }
 }
 
-impl<V> Node<V> {
+impl<V, K> Node<V, K> {
     const INIT: Self = Node::Empty;
 
     fn is_empty(&self) -> bool {
-        match self {
-            Node::Empty => true,
-            _ => false,
-        }
+        matches!(self, Node::Empty)
     }
+}
 
-    fn minimum(&self) -> Option<&ArtNodeLeaf<V>> {
+impl<V, K: LeafKey> Node<V, K> {
+    fn minimum(&self) -> Option<&ArtNodeLeaf<V, K>> {
         match self {
             Node::Empty => None,
             Node::Leaf(leaf) => Some(leaf.as_ref()),
@@ -203,7 +1120,7 @@ impl<V> Node<V> {
         }
     }
 
-    fn minimum_mut(&mut self) -> Option<&mut ArtNodeLeaf<V>> {
+    fn minimum_mut(&mut self) -> Option<&mut ArtNodeLeaf<V, K>> {
         match self {
             Node::Empty => None,
             Node::Leaf(leaf) => Some(leaf.as_mut()),
@@ -211,7 +1128,7 @@ impl<V> Node<V> {
         }
     }
 
-    fn maximum(&self) -> Option<&ArtNodeLeaf<V>> {
+    fn maximum(&self) -> Option<&ArtNodeLeaf<V, K>> {
         match self {
             Node::Empty => None,
             Node::Leaf(leaf) => Some(leaf.as_ref()),
@@ -219,7 +1136,7 @@ impl<V> Node<V> {
         }
     }
 
-    fn maximum_mut(&mut self) -> Option<&mut ArtNodeLeaf<V>> {
+    fn maximum_mut(&mut self) -> Option<&mut ArtNodeLeaf<V, K>> {
         match self {
             Node::Empty => None,
             Node::Leaf(leaf) => Some(leaf.as_mut()),
@@ -227,446 +1144,641 @@ impl<V> Node<V> {
         }
     }
 
-    fn pop_first(&mut self) -> Option<(Box<[u8]>, V)> {
+    fn floor(&self, key: &[u8], depth: usize) -> Option<&ArtNodeLeaf<V, K>> {
         match self {
             Node::Empty => None,
-            Node::Leaf(_) => {
-                match mem::take(self) {
-                    Node::Leaf(leaf) => return Some((leaf.key, leaf.value)),
-                    _ => unreachable!(),
-                };
+            Node::Leaf(leaf) => {
+                if leaf.key() <= key {
+                    Some(leaf.as_ref())
+                } else {
+                    None
+                }
             }
-            Node::Internal(internal) => internal.pop_first(),
+            Node::Internal(internal) => match internal.compare_path(key, depth) {
+                Ordering::Less => internal.maximum(),
+                Ordering::Greater => None,
+                Ordering::Equal => {
+                    // Every key in this subtree is longer than the path, so if the key ends
+                    // within the path, all of them are greater than it
+                    let depth = depth + internal.header.partial_len;
+                    let c = *key.get(depth)?;
+                    internal
+                        .find_child(c)
+                        .and_then(|child| child.floor(key, depth + 1))
+                        .or_else(|| internal.last_child_before(c)?.maximum())
+                }
+            },
         }
     }
 
-    fn pop_last(&mut self) -> Option<(Box<[u8]>, V)> {
+    /// Returns the leaf with the greatest key less than `key`, like `floor` without the key
+    /// itself
+    fn last_before(&self, key: &[u8], depth: usize) -> Option<&ArtNodeLeaf<V, K>> {
         match self {
             Node::Empty => None,
-            Node::Leaf(_) => {
-                match mem::take(self) {
-                    Node::Leaf(leaf) => return Some((leaf.key, leaf.value)),
-                    _ => unreachable!(),
-                };
+            Node::Leaf(leaf) if leaf.key() < key => Some(leaf.as_ref()),
+            Node::Leaf(_) => None,
+            Node::Internal(internal) => match internal.compare_path(key, depth) {
+                Ordering::Less => internal.maximum(),
+                Ordering::Greater => None,
+                Ordering::Equal => {
+                    let depth = depth + internal.header.partial_len;
+                    let c = *key.get(depth)?;
+                    internal
+                        .find_child(c)
+                        .and_then(|child| child.last_before(key, depth + 1))
+                        .or_else(|| internal.last_child_before(c)?.maximum())
+                }
+            },
+        }
+    }
+
+    /// Returns the node (and its depth) whose subtree holds exactly the keys starting with
+    /// `prefix`, or `None` if no key starts with it.
+    fn prefix_root(&self, prefix: &[u8], mut depth: usize) -> Option<(&Self, usize)> {
+        let mut node = self;
+        loop {
+            match node {
+                Node::Empty => return None,
+                Node::Leaf(leaf) => {
+                    return if leaf.key().starts_with(prefix) {
+                        Some((node, depth))
+                    } else {
+                        None
+                    };
+                }
+                Node::Internal(internal) => {
+                    // All keys below the node share the path up to `path_end`
+                    let path = internal.minimum().unwrap().key();
+                    let path_end = depth + internal.header.partial_len;
+                    if prefix.len() <= path_end {
+                        return if path.starts_with(prefix) {
+                            Some((node, depth))
+                        } else {
+                            None
+                        };
+                    }
+                    if path[..path_end] != prefix[..path_end] {
+                        return None;
+                    }
+                    node = internal.find_child(prefix[path_end])?;
+                    depth = path_end + 1;
+                }
             }
-            Node::Internal(internal) => internal.pop_last(),
         }
     }
 
-    fn recursive_insert(
+    /// Mutable counterpart of [`prefix_root`](Node::prefix_root), invalidating the hashes on the
+    /// path to the returned node
+    fn prefix_root_mut(&mut self, prefix: &[u8], mut depth: usize) -> Option<&mut Self> {
+        let mut node = self;
+        loop {
+            let c = match &*node {
+                Node::Empty => return None,
+                Node::Leaf(leaf) if leaf.key().starts_with(prefix) => return Some(node),
+                Node::Leaf(_) => return None,
+                Node::Internal(internal) => {
+                    let path = internal.minimum().unwrap().key();
+                    let path_end = depth + internal.header.partial_len;
+                    if prefix.len() <= path_end {
+                        return if path.starts_with(prefix) {
+                            Some(node)
+                        } else {
+                            None
+                        };
+                    }
+                    if path[..path_end] != prefix[..path_end] {
+                        return None;
+                    }
+                    depth = path_end + 1;
+                    prefix[path_end]
+                }
+            };
+            node = match node {
+                Node::Internal(internal) => {
+                    internal.invalidate_caches();
+                    internal.find_child_mut(c)?
+                }
+                _ => unreachable!(),
+            };
+        }
+    }
+
+    /// Finds the value stored at `key`, inserting the leaf returned by `make_leaf` if the key is
+    /// not present yet. Both the lookup and the insertion happen in a single descent. A node
+    /// split off to make room for the leaf, or the arrays of a node growing to make room for it,
+    /// take the allocations in `spare` if there are any.
+    /// `make_leaf` is called before any node is changed, so that it may panic.
+    fn recursive_upsert<F>(
         &mut self,
         key: &[u8],
-        value: V,
+        make_leaf: F,
         mut depth: usize,
-        replace: bool,
-    ) -> Option<V> {
-        let mut split = false;
-        let mut split_internal = false;
-        let mut prefix_save = 0;
-
-        match *self {
-            Node::Leaf(ref mut leaf) => {
+        counters: &Counters,
+        sizing: &NodeSizing,
+        spare: &mut Spare<V, K>,
+    ) -> Upsert<'_, V>
+    where
+        F: FnOnce() -> Box<ArtNodeLeaf<V, K>>,
+    {
+        enum Action {
+            Existing,
+            Descend,
+            AddChild,
+            Split,
+            SplitInternal(usize),
+            Fill,
+        }
+
+        profiling::visit();
+
+        // Decide what to do first, so that the mutable borrows below can be returned
+        let action = match *self {
+            Node::Leaf(ref leaf) => {
                 // Check if we are updating an existing value
                 if leaf.matches(key) {
-                    let old_value = std::mem::replace(&mut leaf.value, value);
-                    return Some(old_value);
+                    Action::Existing
+                } else {
+                    // New value, we must split the leaf into a node4
+                    Action::Split
                 }
-
-                // New value, we must split the leaf into a node4
-                split = true;
             }
-            Node::Internal(ref mut internal) => {
+            Node::Internal(ref internal) => {
+                internal.heat.touch(sizing);
                 let n = internal.header;
 
-                // Check if given node has a prefix
-                if n.partial_len != 0 {
-                    // Determine if the prefixes differ, since we need to split
-                    let prefix_diff = internal.prefix_mismatch(key, depth);
-                    if prefix_diff >= n.partial_len {
-                        depth += n.partial_len;
-
-                        // Find a child to recurse to
-                        let child = internal.find_child_mut(key[depth]);
-                        if let Some(node) = child {
-                            return node.recursive_insert(key, value, depth + 1, replace);
-                        } else {
-                            // No child, node goes within us
-                            let new_leaf = Node::Leaf(Box::new(ArtNodeLeaf::new(key, value)));
-                            internal.add_child(key[depth], new_leaf);
-
-                            return None;
-                        }
-                    }
-
-                    split_internal = true;
-                    prefix_save = prefix_diff;
+                // Check if given node has a prefix, and if the prefixes differ, since we need
+                // to split
+                let prefix_diff = if n.partial_len != 0 {
+                    internal.prefix_mismatch(key, depth)
                 } else {
-                    let child = internal.find_child_mut(key[depth]);
-                    if let Some(node) = child {
-                        return node.recursive_insert(key, value, depth + 1, replace);
-                    }
-
-                    let new_leaf = Node::Leaf(Box::new(ArtNodeLeaf::new(key, value)));
-                    internal.add_child(key[depth], new_leaf);
+                    0
+                };
+                if n.partial_len > MAX_PREFIX_LEN && prefix_diff >= MAX_PREFIX_LEN {
+                    // The stored prefix matched, the rest of the path was read from a leaf
+                    counters.prefix_leaf_peek();
+                }
+                if prefix_diff < n.partial_len {
+                    Action::SplitInternal(prefix_diff)
+                } else {
+                    depth += n.partial_len;
 
-                    return None;
+                    // Find a child to recurse to, otherwise the node goes within us
+                    if internal.find_child_index(key[depth]).is_some() {
+                        Action::Descend
+                    } else {
+                        Action::AddChild
+                    }
                 }
             }
-            Node::Empty => {
-                let new_leaf = Box::new(ArtNodeLeaf::new(key, value));
-                *self = Node::Leaf(new_leaf);
-                return None;
-            }
+            Node::Empty => Action::Fill,
         };
 
-        if split {
-            // Create a new leaf
-            let mut new_leaf = ArtNodeLeaf::new(key, value);
-
-            // Determine longest prefix
-            let longest_prefix = match self {
-                Node::Leaf(ref leaf) => leaf.longest_common_prefix(&mut new_leaf, depth),
+        match action {
+            Action::Existing => match self {
+                Node::Leaf(leaf) => Upsert::Existing(&mut leaf.value),
                 _ => unreachable!(),
-            };
-            let mut partial_new = [0u8; MAX_PREFIX_LEN];
-            for i in 0..min(MAX_PREFIX_LEN, longest_prefix) {
-                partial_new[i] = key[depth + i];
-            }
-
-            let arr = [Node::<V>::INIT; 4];
-
-            let internal = Node::Internal(Box::new(ArtNodeInternal {
-                header: InternalNodeHeader {
-                    partial_len: longest_prefix,
-                    num_children: 0,
-                    partial: partial_new,
-                },
-                inner: ArtNodeInternalInner::Node4 {
-                    keys: [0u8; 4],
-                    children: arr,
-                },
-            }));
-
-            match mem::replace(self, internal) {
-                Node::Leaf(old_leaf) => match self {
-                    Node::Internal(internal) => {
-                        internal.add_child(
-                            old_leaf.as_ref().key[depth + longest_prefix],
-                            Node::Leaf(old_leaf),
-                        );
-                        internal.add_child(
-                            new_leaf.key[depth + longest_prefix],
-                            Node::Leaf(Box::new(new_leaf)),
-                        );
+            },
+            Action::Descend => match self {
+                Node::Internal(internal) => {
+                    internal.invalidate_caches();
+                    internal
+                        .find_child_mut(key[depth])
+                        .unwrap()
+                        .recursive_upsert(key, make_leaf, depth + 1, counters, sizing, spare)
+                }
+                _ => unreachable!(),
+            },
+            Action::AddChild => match self {
+                Node::Internal(internal) => {
+                    internal.invalidate_caches();
+                    if internal.is_full(sizing) {
+                        counters.node_upgrade();
+                        trace::node_upgrade(key, depth, internal.node_kind());
                     }
-                    _ => unreachable!(),
-                },
+                    Upsert::Inserted(internal.add_leaf(
+                        key[depth],
+                        make_leaf(),
+                        sizing,
+                        &mut spare.grown,
+                    ))
+                }
                 _ => unreachable!(),
+            },
+            Action::Fill => {
+                *self = Node::Leaf(make_leaf());
+                match self {
+                    Node::Leaf(leaf) => Upsert::Inserted(&mut leaf.value),
+                    _ => unreachable!(),
+                }
             }
+            Action::Split => {
+                // Create a new leaf
+                let mut new_leaf = make_leaf();
 
-            return None;
-        }
-
-        if split_internal {
-            let prefix_diff = prefix_save;
-
-            // Create a new node
-            let mut partial = [0u8; MAX_PREFIX_LEN];
-            let partial_len = {
-                let n = match self {
-                    Node::Internal(ref internal) => internal.header,
+                // Determine longest prefix
+                let longest_prefix = match self {
+                    Node::Leaf(ref leaf) => leaf.longest_common_prefix(&mut new_leaf, depth),
                     _ => unreachable!(),
                 };
-                for i in 0..min(MAX_PREFIX_LEN, prefix_diff) {
-                    partial[i] = n.partial[i];
-                }
-                n.partial_len
-            };
-
-            let new_node = Node::Internal(Box::new(ArtNodeInternal {
-                header: InternalNodeHeader {
-                    partial_len: prefix_diff,
-                    num_children: 0,
-                    partial,
-                },
-                inner: ArtNodeInternalInner::Node4 {
-                    keys: [0u8; 4],
-                    children: [Node::<V>::INIT; 4],
-                },
-            }));
-
-            // Adjust the prefix of the old node
-            if partial_len <= MAX_PREFIX_LEN {
-                match mem::replace(self, new_node) {
-                    Node::Internal(mut old_node) => {
-                        old_node.header.partial_len -= prefix_diff + 1;
-                        for i in (0..min(MAX_PREFIX_LEN, old_node.header.partial_len)).rev() {
-                            old_node.header.partial[i] =
-                                old_node.header.partial[prefix_diff + 1 + i];
-                        }
-                        match self {
-                            Node::Internal(ref mut new_internal) => {
-                                new_internal.add_child(
-                                    old_node.header.partial[prefix_diff],
-                                    Node::Internal(old_node),
-                                );
-
-                                let new_leaf = ArtNodeLeaf::new(key, value);
-                                new_internal.add_child(
-                                    key[depth + prefix_diff],
-                                    Node::Leaf(Box::new(new_leaf)),
-                                );
-
-                                return None;
-                            }
-                            _ => unreachable!(),
+                let mut partial_new = [0u8; MAX_PREFIX_LEN];
+                let copy_len = min(MAX_PREFIX_LEN, longest_prefix);
+                partial_new[..copy_len].copy_from_slice(&key[depth..depth + copy_len]);
+
+                let internal = Node::Internal(ArtNodeInternal::boxed_node4(
+                    InternalNodeHeader {
+                        partial_len: longest_prefix,
+                        num_children: 0,
+                        partial: partial_new,
+                    },
+                    &mut spare.node4,
+                ));
+
+                match mem::replace(self, internal) {
+                    Node::Leaf(old_leaf) => match self {
+                        Node::Internal(internal) => {
+                            internal.add_child(
+                                old_leaf.key()[depth + longest_prefix],
+                                Node::Leaf(old_leaf),
+                                sizing,
+                            );
+                            let c = new_leaf.key()[depth + longest_prefix];
+                            Upsert::Inserted(internal.add_leaf(
+                                c,
+                                new_leaf,
+                                sizing,
+                                &mut spare.grown,
+                            ))
                         }
-                    }
+                        _ => unreachable!(),
+                    },
                     _ => unreachable!(),
                 }
-            } else {
-                match mem::replace(self, new_node) {
+            }
+            Action::SplitInternal(prefix_diff) => {
+                counters.prefix_split();
+                let new_leaf = make_leaf();
+
+                // Create a new node
+                let mut partial = [0u8; MAX_PREFIX_LEN];
+                let partial_len = {
+                    let n = match self {
+                        Node::Internal(ref internal) => internal.header,
+                        _ => unreachable!(),
+                    };
+                    let copy_len = min(MAX_PREFIX_LEN, prefix_diff);
+                    partial[..copy_len].copy_from_slice(&n.partial[..copy_len]);
+                    n.partial_len
+                };
+                trace::prefix_split(key, depth, partial_len, prefix_diff);
+
+                let new_node = Node::Internal(ArtNodeInternal::boxed_node4(
+                    InternalNodeHeader {
+                        partial_len: prefix_diff,
+                        num_children: 0,
+                        partial,
+                    },
+                    &mut spare.node4,
+                ));
+
+                // Adjust the prefix of the old node
+                let (c, old_node) = match mem::replace(self, new_node) {
+                    Node::Internal(mut old_node) if partial_len <= MAX_PREFIX_LEN => {
+                        let c = old_node.header.partial[prefix_diff];
+                        old_node.header.partial_len -= prefix_diff + 1;
+                        let shift_len = min(MAX_PREFIX_LEN, old_node.header.partial_len);
+                        old_node
+                            .header
+                            .partial
+                            .copy_within(prefix_diff + 1..prefix_diff + 1 + shift_len, 0);
+                        (c, old_node)
+                    }
                     Node::Internal(mut internal) => {
                         internal.header.partial_len -= prefix_diff + 1;
                         let l = internal.minimum().unwrap();
-                        let mut temp = vec![0u8; min(MAX_PREFIX_LEN, internal.header.partial_len)];
-                        let c = l.key[depth + prefix_diff];
-                        for i in 0..min(MAX_PREFIX_LEN, internal.header.partial_len) {
-                            temp[i] = l.key[depth + prefix_diff + 1 + i];
-                        }
-                        for i in 0..min(MAX_PREFIX_LEN, internal.header.partial_len) {
-                            internal.header.partial[i] = temp[i];
-                        }
-
-                        match *self {
-                            Node::Internal(ref mut new_internal) => {
-                                new_internal.add_child(c, Node::Internal(internal));
+                        let copy_len = min(MAX_PREFIX_LEN, internal.header.partial_len);
+                        let c = l.key()[depth + prefix_diff];
+                        let start = depth + prefix_diff + 1;
+                        let mut temp = [0u8; MAX_PREFIX_LEN];
+                        temp[..copy_len].copy_from_slice(&l.key()[start..start + copy_len]);
+                        internal.header.partial[..copy_len].copy_from_slice(&temp[..copy_len]);
+                        (c, internal)
+                    }
+                    _ => unreachable!(),
+                };
 
-                                let new_leaf = ArtNodeLeaf::new(key, value);
-                                new_internal.add_child(
-                                    key[depth + prefix_diff],
-                                    Node::Leaf(Box::new(new_leaf)),
-                                );
+                match self {
+                    Node::Internal(ref mut new_internal) => {
+                        new_internal.add_child(c, Node::Internal(old_node), sizing);
 
-                                return None;
-                            }
-                            _ => unreachable!(),
-                        }
+                        Upsert::Inserted(new_internal.add_leaf(
+                            key[depth + prefix_diff],
+                            new_leaf,
+                            sizing,
+                            &mut spare.grown,
+                        ))
                     }
                     _ => unreachable!(),
                 }
             }
         }
-
-        unreachable!()
     }
 
-    fn recursive_delete(self, key: &[u8], mut depth: usize) -> (Self, Option<V>) {
-        return match self {
+    fn recursive_delete(
+        self,
+        target: DeleteTarget<'_>,
+        approve: &mut dyn FnMut(&ArtNodeLeaf<V, K>) -> bool,
+        mut depth: usize,
+        counters: &Counters,
+        sizing: &NodeSizing,
+    ) -> (Self, Option<Box<ArtNodeLeaf<V, K>>>) {
+        match self {
             Node::Leaf(leaf) => {
-                if leaf.matches(key) {
-                    (Node::Empty, Some(leaf.value))
+                let matches = match target {
+                    DeleteTarget::Key(key) => leaf.matches(key),
+                    DeleteTarget::First | DeleteTarget::Last => true,
+                };
+                if matches && approve(&leaf) {
+                    (Node::Empty, Some(leaf))
                 } else {
                     (Node::Leaf(leaf), None)
                 }
             }
             Node::Internal(mut internal) => {
-                // Bail if the prefix does not match
-                if internal.header.partial_len != 0 {
-                    let prefix_len = internal.header.check_prefix(key, depth);
-                    if prefix_len != min(MAX_PREFIX_LEN, internal.header.partial_len) {
-                        return (Node::Internal(internal), None);
+                let c = match target {
+                    DeleteTarget::Key(key) => {
+                        // Bail if the prefix does not match
+                        if internal.header.partial_len != 0 {
+                            let prefix_len = internal.header.check_prefix(key, depth);
+                            if prefix_len != min(MAX_PREFIX_LEN, internal.header.partial_len) {
+                                return (Node::Internal(internal), None);
+                            }
+                        }
+                        key.get(depth + internal.header.partial_len).copied()
                     }
-                    depth += internal.header.partial_len;
-                }
+                    DeleteTarget::First => internal.first_child_byte(),
+                    DeleteTarget::Last => internal.last_child_byte(),
+                };
+                depth += internal.header.partial_len;
 
                 // Find child node
-                let child_pos = internal.find_child_index(key[depth]);
-                if child_pos.is_none() {
-                    return (Node::Internal(internal), None);
-                }
-                let child_pos = child_pos.unwrap();
+                let (c, child_pos) = match c.and_then(|c| Some((c, internal.find_child_index(c)?)))
+                {
+                    Some(found) => found,
+                    None => return (Node::Internal(internal), None),
+                };
 
-                match *internal {
-                    ArtNodeInternal {
-                        ref mut header,
-                        ref mut inner,
+                internal.invalidate_caches();
+                internal.heat.touch(sizing);
+                let ArtNodeInternal {
+                    ref mut header,
+                    ref mut inner,
+                    ref heat,
+                    ..
+                } = *internal;
+                match inner {
+                    ArtNodeInternalInner::Node4 {
+                        ref mut children,
+                        ref mut keys,
+                        ..
                     } => {
-                        match inner {
-                            ArtNodeInternalInner::Node4 {
-                                ref mut children,
-                                ref mut keys,
-                                ..
-                            } => {
-                                let (child_res, return_val) = mem::take(&mut children[child_pos])
-                                    .recursive_delete(key, depth + 1);
-                                children[child_pos] = child_res;
-                                if children[child_pos].is_empty() {
-                                    for i in (child_pos + 1)..header.num_children as usize {
-                                        keys[i - 1] = keys[i];
-                                        children[i - 1] = mem::take(&mut children[i]);
-                                    }
-                                    keys[(header.num_children - 1) as usize] = 0;
-                                    header.num_children -= 1;
-
-                                    // Remove nodes with only a single child
-                                    if header.num_children == 1 {
-                                        match mem::take(&mut children[0]) {
-                                            Node::Internal(mut internal) => {
-                                                // Concatenate the prefixes
-                                                let mut prefix = header.partial_len;
-                                                if prefix < MAX_PREFIX_LEN {
-                                                    header.partial[prefix] = keys[0];
-                                                    prefix += 1;
-                                                }
-                                                if prefix < MAX_PREFIX_LEN {
-                                                    let sub_prefix = min(
-                                                        internal.header.partial_len,
-                                                        MAX_PREFIX_LEN - prefix,
-                                                    );
-                                                    for i in 0..sub_prefix {
-                                                        header.partial[prefix + i] =
-                                                            internal.header.partial[i];
-                                                    }
-                                                    prefix += sub_prefix;
-                                                }
-
-                                                // Store the prefix in the child
-                                                for i in 0..min(prefix, MAX_PREFIX_LEN) {
-                                                    internal.header.partial[i] = header.partial[i];
-                                                }
-                                                internal.header.partial_len +=
-                                                    header.partial_len + 1;
-
-                                                return (Node::Internal(internal), return_val);
-                                            }
-                                            Node::Leaf(leaf) => {
-                                                return (Node::Leaf(leaf), return_val);
+                        let (child_res, return_val) = mem::take(&mut children[child_pos])
+                            .recursive_delete(target, approve, depth + 1, counters, sizing);
+                        children[child_pos] = child_res;
+                        if children[child_pos].is_empty() {
+                            for i in (child_pos + 1)..header.num_children as usize {
+                                keys[i - 1] = keys[i];
+                                children[i - 1] = mem::take(&mut children[i]);
+                            }
+                            keys[(header.num_children - 1) as usize] = 0;
+                            header.num_children -= 1;
+
+                            // Remove nodes with only a single child
+                            if header.num_children == 1 {
+                                match mem::take(&mut children[0]) {
+                                    Node::Internal(mut internal) => {
+                                        // Concatenate the prefixes
+                                        let mut prefix = header.partial_len;
+                                        if prefix < MAX_PREFIX_LEN {
+                                            header.partial[prefix] = keys[0];
+                                            prefix += 1;
+                                        }
+                                        if prefix < MAX_PREFIX_LEN {
+                                            let sub_prefix = min(
+                                                internal.header.partial_len,
+                                                MAX_PREFIX_LEN - prefix,
+                                            );
+                                            for i in 0..sub_prefix {
+                                                header.partial[prefix + i] =
+                                                    internal.header.partial[i];
                                             }
-                                            _ => unreachable!(),
+                                            prefix += sub_prefix;
                                         }
-                                    }
-                                }
-                                (Node::Internal(internal), return_val)
-                            }
-                            ArtNodeInternalInner::Node16 {
-                                ref mut children,
-                                ref mut keys,
-                                ..
-                            } => {
-                                let (child_res, return_val) = mem::take(&mut children[child_pos])
-                                    .recursive_delete(key, depth + 1);
-                                children[child_pos] = child_res;
-                                if children[child_pos].is_empty() {
-                                    for i in (child_pos + 1)..header.num_children as usize {
-                                        keys[i - 1] = keys[i];
-                                        children[i - 1] = mem::take(&mut children[i]);
-                                    }
-                                    keys[(header.num_children - 1) as usize] = 0;
-                                    header.num_children -= 1;
-
-                                    if header.num_children == 3 {
-                                        let mut children_new: [Node<V>; 4] = [Node::INIT; 4];
-                                        let mut keys_new: [u8; 4] = [0; 4];
 
-                                        for i in 0..header.num_children as usize {
-                                            keys_new[i] = keys[i];
-                                            children_new[i] = mem::take(&mut children[i]);
+                                        // Store the prefix in the child
+                                        for i in 0..min(prefix, MAX_PREFIX_LEN) {
+                                            internal.header.partial[i] = header.partial[i];
                                         }
+                                        internal.header.partial_len += header.partial_len + 1;
 
-                                        let new_node = Node::Internal(Box::new(ArtNodeInternal {
-                                            header: *header,
-                                            inner: ArtNodeInternalInner::Node4 {
-                                                keys: keys_new,
-                                                children: children_new,
-                                            },
-                                        }));
-                                        return (new_node, return_val);
+                                        return (Node::Internal(internal), return_val);
                                     }
+                                    Node::Leaf(leaf) => {
+                                        return (Node::Leaf(leaf), return_val);
+                                    }
+                                    _ => unreachable!(),
                                 }
-                                (Node::Internal(internal), return_val)
                             }
-                            ArtNodeInternalInner::Node48 { keys, children } => {
-                                let (child_res, return_val) = mem::take(&mut children[child_pos])
-                                    .recursive_delete(key, depth + 1);
-                                children[child_pos] = child_res;
-                                if children[child_pos].is_empty() {
-                                    let c = key[depth];
-                                    let pos = keys[c as usize] as usize;
-                                    //let pos = child_pos + 1;
-                                    keys[c as usize] = 0;
-                                    children[pos - 1] = Node::Empty;
-
-                                    header.num_children -= 1;
-
-                                    if header.num_children == 12 {
-                                        let mut children_new: [Node<V>; 16] = [Node::INIT; 16];
-                                        let mut keys_new: [u8; 16] = [0; 16];
-                                        let mut child = 0;
-                                        for i in 0..256 {
-                                            let pos = keys[i] as usize;
-                                            if pos != 0 {
-                                                keys_new[child] = i as u8;
-                                                children_new[child] =
-                                                    mem::take(&mut children[pos - 1]);
-                                                child += 1;
-                                            }
-                                        }
+                        }
+                        (Node::Internal(internal), return_val)
+                    }
+                    ArtNodeInternalInner::Node16 {
+                        ref mut children,
+                        ref mut keys,
+                        ..
+                    } => {
+                        let (child_res, return_val) = mem::take(&mut children[child_pos])
+                            .recursive_delete(target, approve, depth + 1, counters, sizing);
+                        children[child_pos] = child_res;
+                        if children[child_pos].is_empty() {
+                            for i in (child_pos + 1)..header.num_children as usize {
+                                keys[i - 1] = keys[i];
+                                children[i - 1] = mem::take(&mut children[i]);
+                            }
+                            keys[(header.num_children - 1) as usize] = 0;
+                            header.num_children -= 1;
+
+                            if heat.should_shrink(NodeKind::Node16, header.num_children, sizing) {
+                                counters.node_downgrade();
+                                trace::node_downgrade(
+                                    return_val.as_deref(),
+                                    depth,
+                                    NodeKind::Node16,
+                                );
+                                let mut children_new = Box::new([Node::INIT; 4]);
+                                let mut keys_new: [u8; 4] = [0; 4];
 
-                                        let new_node = Node::Internal(Box::new(ArtNodeInternal {
-                                            header: *header,
-                                            inner: ArtNodeInternalInner::Node16 {
-                                                keys: keys_new,
-                                                children: children_new,
-                                            },
-                                        }));
-                                        return (new_node, return_val);
-                                    }
+                                for i in 0..header.num_children as usize {
+                                    keys_new[i] = keys[i];
+                                    children_new[i] = mem::take(&mut children[i]);
                                 }
-                                (Node::Internal(internal), return_val)
+
+                                let new_node = Node::Internal(Box::new(
+                                    ArtNodeInternal::new(
+                                        *header,
+                                        ArtNodeInternalInner::Node4 {
+                                            keys: keys_new,
+                                            children: children_new,
+                                        },
+                                    )
+                                    .with_heat(heat),
+                                ));
+                                return (new_node, return_val);
                             }
-                            ArtNodeInternalInner::Node256 { children } => {
-                                let (child_res, return_val) = mem::take(&mut children[child_pos])
-                                    .recursive_delete(key, depth + 1);
-                                children[child_pos] = child_res;
-                                if children[child_pos].is_empty() {
-                                    header.num_children -= 1;
-
-                                    // Resize to a node48 on underflow, not immediately to prevent
-                                    // thrashing if we sit on the 48/49 boundary
-                                    if header.num_children == 37 {
-                                        let mut children_new = [Node::INIT; 48];
-                                        let mut keys_new: [u8; 256] = [0; 256];
-
-                                        let mut pos = 0;
-                                        for i in 0..256 {
-                                            if !children[i].is_empty() {
-                                                children_new[pos] = mem::take(&mut children[i]);
-                                                keys_new[i] = (pos + 1) as u8;
-                                                pos += 1;
-                                            }
-                                        }
+                        }
+                        (Node::Internal(internal), return_val)
+                    }
+                    ArtNodeInternalInner::Node32 {
+                        ref mut children,
+                        ref mut keys,
+                        ..
+                    } => {
+                        let (child_res, return_val) = mem::take(&mut children[child_pos])
+                            .recursive_delete(target, approve, depth + 1, counters, sizing);
+                        children[child_pos] = child_res;
+                        if children[child_pos].is_empty() {
+                            for i in (child_pos + 1)..header.num_children as usize {
+                                keys[i - 1] = keys[i];
+                                children[i - 1] = mem::take(&mut children[i]);
+                            }
+                            keys[(header.num_children - 1) as usize] = 0;
+                            header.num_children -= 1;
+
+                            if heat.should_shrink(NodeKind::Node32, header.num_children, sizing) {
+                                counters.node_downgrade();
+                                trace::node_downgrade(
+                                    return_val.as_deref(),
+                                    depth,
+                                    NodeKind::Node32,
+                                );
+                                let mut children_new = Box::new([Node::INIT; 16]);
+                                let mut keys_new: [u8; 16] = [0; 16];
+
+                                for i in 0..header.num_children as usize {
+                                    keys_new[i] = keys[i];
+                                    children_new[i] = mem::take(&mut children[i]);
+                                }
 
-                                        let new_node = Node::Internal(Box::new(ArtNodeInternal {
-                                            header: *header,
-                                            inner: ArtNodeInternalInner::Node48 {
-                                                keys: keys_new,
-                                                children: children_new,
-                                            },
-                                        }));
+                                let new_node = Node::Internal(Box::new(
+                                    ArtNodeInternal::new(
+                                        *header,
+                                        ArtNodeInternalInner::Node16 {
+                                            keys: keys_new,
+                                            children: children_new,
+                                        },
+                                    )
+                                    .with_heat(heat),
+                                ));
+                                return (new_node, return_val);
+                            }
+                        }
+                        (Node::Internal(internal), return_val)
+                    }
+                    ArtNodeInternalInner::Node48 { keys, children } => {
+                        let (child_res, return_val) = mem::take(&mut children[child_pos])
+                            .recursive_delete(target, approve, depth + 1, counters, sizing);
+                        children[child_pos] = child_res;
+                        if children[child_pos].is_empty() {
+                            let pos = keys[c as usize] as usize;
+                            //let pos = child_pos + 1;
+                            keys[c as usize] = 0;
+                            children[pos - 1] = Node::Empty;
+
+                            header.num_children -= 1;
+
+                            if heat.should_shrink(NodeKind::Node48, header.num_children, sizing) {
+                                counters.node_downgrade();
+                                trace::node_downgrade(
+                                    return_val.as_deref(),
+                                    depth,
+                                    NodeKind::Node48,
+                                );
+                                let mut children_new = Box::new([Node::INIT; 32]);
+                                let mut keys_new: [u8; 32] = [0; 32];
+                                let mut child = 0;
+                                for (i, &pos) in keys.iter().enumerate() {
+                                    let pos = pos as usize;
+                                    if pos != 0 {
+                                        keys_new[child] = i as u8;
+                                        children_new[child] = mem::take(&mut children[pos - 1]);
+                                        child += 1;
+                                    }
+                                }
 
-                                        return (new_node, return_val);
+                                let new_node = Node::Internal(Box::new(
+                                    ArtNodeInternal::new(
+                                        *header,
+                                        ArtNodeInternalInner::Node32 {
+                                            keys: keys_new,
+                                            children: children_new,
+                                        },
+                                    )
+                                    .with_heat(heat),
+                                ));
+                                return (new_node, return_val);
+                            }
+                        }
+                        (Node::Internal(internal), return_val)
+                    }
+                    ArtNodeInternalInner::Node256 { children } => {
+                        let (child_res, return_val) = mem::take(&mut children[child_pos])
+                            .recursive_delete(target, approve, depth + 1, counters, sizing);
+                        children[child_pos] = child_res;
+                        if children[child_pos].is_empty() {
+                            header.num_children -= 1;
+
+                            // Resize to a node48 on underflow, not immediately to prevent
+                            // thrashing if we sit on the 48/49 boundary
+                            if heat.should_shrink(NodeKind::Node256, header.num_children, sizing) {
+                                counters.node_downgrade();
+                                trace::node_downgrade(
+                                    return_val.as_deref(),
+                                    depth,
+                                    NodeKind::Node256,
+                                );
+                                let mut children_new = Box::new([Node::INIT; 48]);
+                                let mut keys_new = Box::new([0u8; 256]);
+
+                                let mut pos = 0;
+                                for i in 0..256 {
+                                    if !children[i].is_empty() {
+                                        children_new[pos] = mem::take(&mut children[i]);
+                                        keys_new[i] = (pos + 1) as u8;
+                                        pos += 1;
                                     }
                                 }
 
-                                (Node::Internal(internal), return_val)
+                                let new_node = Node::Internal(Box::new(
+                                    ArtNodeInternal::new(
+                                        *header,
+                                        ArtNodeInternalInner::Node48 {
+                                            keys: keys_new,
+                                            children: children_new,
+                                        },
+                                    )
+                                    .with_heat(heat),
+                                ));
+
+                                return (new_node, return_val);
                             }
                         }
+
+                        (Node::Internal(internal), return_val)
                     }
                 }
             }
             Node::Empty => (self, None),
-        };
+        }
     }
 
     /// Recursively iterates over the tree
@@ -682,8 +1794,56 @@ impl<V> Node<V> {
     }
 }
 
-impl<V> ArtNodeInternal<V> {
-    fn find_child_mut(&mut self, c: u8) -> Option<&mut Node<V>> {
+impl<V, K> ArtNodeInternal<V, K> {
+    fn new(header: InternalNodeHeader, inner: ArtNodeInternalInner<V, K>) -> Self {
+        Self {
+            header,
+            inner,
+            heat: Heat::default(),
+            #[cfg(feature = "merkle")]
+            hash: Default::default(),
+            #[cfg(feature = "aggregate")]
+            summary: Default::default(),
+        }
+    }
+
+    /// Boxes a new empty Node4, taking the one in `spare` if there is one
+    fn boxed_node4(header: InternalNodeHeader, spare: &mut Option<Box<Self>>) -> Box<Self> {
+        match spare.take() {
+            Some(mut node) => {
+                debug_assert!(node.header.num_children == 0);
+                node.header = header;
+                node
+            }
+            None => Box::new(Self::new(
+                header,
+                ArtNodeInternalInner::Node4 {
+                    keys: [0; 4],
+                    children: Box::new([Node::INIT; 4]),
+                },
+            )),
+        }
+    }
+
+    /// Carries the access count of the node this one replaces over
+    fn with_heat(mut self, heat: &Heat) -> Self {
+        self.heat = heat.clone();
+        self
+    }
+
+    /// Drops the cached subtree hash and summary, called on every node whose subtree is about
+    /// to change.
+    #[inline(always)]
+    fn invalidate_caches(&mut self) {
+        #[cfg(feature = "merkle")]
+        self.hash.take();
+        #[cfg(feature = "aggregate")]
+        self.summary.take();
+    }
+}
+
+impl<V, K: LeafKey> ArtNodeInternal<V, K> {
+    fn find_child_mut(&mut self, c: u8) -> Option<&mut Node<V, K>> {
         let n = self.header;
         match &mut self.inner {
             ArtNodeInternalInner::Node4 { keys, children, .. } => {
@@ -694,74 +1854,53 @@ impl<V> ArtNodeInternal<V> {
                 }
             }
             ArtNodeInternalInner::Node16 { keys, children } => {
-                for i in 0..n.num_children as usize {
-                    if keys[i] == c {
-                        return Some(&mut children[i]);
-                    }
+                if let Some(i) = find_key_16(keys, n.num_children as usize, c) {
+                    return Some(&mut children[i]);
+                }
+            }
+            ArtNodeInternalInner::Node32 { keys, children } => {
+                if let Some(i) = find_key_32(keys, n.num_children as usize, c) {
+                    return Some(&mut children[i]);
                 }
             }
             ArtNodeInternalInner::Node48 { keys, children } => {
                 let idx = keys[c as usize] as usize;
-                return if idx != 0 {
-                    Some(&mut children[idx - 1])
-                } else {
-                    None
-                };
+                if idx != 0 {
+                    return Some(&mut children[idx - 1]);
+                }
             }
             ArtNodeInternalInner::Node256 { children } => {
                 let node = &mut children[c as usize];
-                return if node.is_empty() { None } else { Some(node) };
+                if !node.is_empty() {
+                    return Some(node);
+                }
             }
         }
-        return None;
+        None
     }
 
-    fn find_child(&self, c: u8) -> Option<&Node<V>> {
-        let n = self.header;
+    fn find_child(&self, c: u8) -> Option<&Node<V, K>> {
+        let idx = self.find_child_index(c)?;
         match &self.inner {
-            ArtNodeInternalInner::Node4 { keys, children, .. } => {
-                for i in 0..n.num_children as usize {
-                    if keys[i] == c {
-                        return Some(&children[i]);
-                    }
-                }
-            }
-            ArtNodeInternalInner::Node16 { keys, children } => {
-                for i in 0..n.num_children as usize {
-                    if keys[i] == c {
-                        return Some(&children[i]);
-                    }
-                }
-            }
-            ArtNodeInternalInner::Node48 { keys, children } => {
-                let idx = keys[c as usize] as usize;
-                return if idx != 0 {
-                    Some(&children[idx - 1])
-                } else {
-                    None
-                };
-            }
-            ArtNodeInternalInner::Node256 { children } => return children.get(c as usize),
+            ArtNodeInternalInner::Node4 { children, .. } => Some(&children[idx]),
+            ArtNodeInternalInner::Node16 { children, .. } => Some(&children[idx]),
+            ArtNodeInternalInner::Node32 { children, .. } => Some(&children[idx]),
+            ArtNodeInternalInner::Node48 { children, .. } => Some(&children[idx]),
+            ArtNodeInternalInner::Node256 { children } => Some(&children[idx]),
         }
-        return None;
     }
 
     fn find_child_index(&self, c: u8) -> Option<usize> {
         let n = self.header;
         match &self.inner {
             ArtNodeInternalInner::Node4 { keys, .. } => {
-                for i in 0..n.num_children as usize {
-                    if keys[i] == c {
-                        return Some(i);
-                    }
-                }
+                return find_key_portable(&keys[..n.num_children as usize], c);
             }
             ArtNodeInternalInner::Node16 { keys, .. } => {
-                for i in 0..min(16, n.num_children as usize) {
-                    if keys[i] == c {
-                        return Some(i);
-                    }
-                }
+                return find_key_16(keys, min(16, n.num_children as usize), c);
+            }
+            ArtNodeInternalInner::Node32 { keys, .. } => {
+                return find_key_32(keys, min(32, n.num_children as usize), c);
             }
             ArtNodeInternalInner::Node48 { keys, .. } => {
                 let idx = keys[c as usize] as usize;
@@ -769,14 +1908,84 @@ impl<V> ArtNodeInternal<V> {
                     return Some(idx - 1);
                 }
             }
-            ArtNodeInternalInner::Node256 { .. } => {
-                return Some(c as usize);
+            ArtNodeInternalInner::Node256 { children } => {
+                if !children[c as usize].is_empty() {
+                    return Some(c as usize);
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the smallest key byte that has a child.
+    fn first_child_byte(&self) -> Option<u8> {
+        let n = self.header.num_children as usize;
+        match &self.inner {
+            ArtNodeInternalInner::Node4 { keys, .. } => keys[..n].first().copied(),
+            ArtNodeInternalInner::Node16 { keys, .. } => keys[..n].first().copied(),
+            ArtNodeInternalInner::Node32 { keys, .. } => keys[..n].first().copied(),
+            ArtNodeInternalInner::Node48 { keys, .. } => {
+                keys.iter().position(|&idx| idx != 0).map(|c| c as u8)
+            }
+            ArtNodeInternalInner::Node256 { children } => children
+                .iter()
+                .position(|child| !child.is_empty())
+                .map(|c| c as u8),
+        }
+    }
+
+    /// Returns the largest key byte that has a child.
+    fn last_child_byte(&self) -> Option<u8> {
+        let n = self.header.num_children as usize;
+        match &self.inner {
+            ArtNodeInternalInner::Node4 { keys, .. } => keys[..n].last().copied(),
+            ArtNodeInternalInner::Node16 { keys, .. } => keys[..n].last().copied(),
+            ArtNodeInternalInner::Node32 { keys, .. } => keys[..n].last().copied(),
+            ArtNodeInternalInner::Node48 { keys, .. } => {
+                keys.iter().rposition(|&idx| idx != 0).map(|c| c as u8)
             }
+            ArtNodeInternalInner::Node256 { children } => children
+                .iter()
+                .rposition(|child| !child.is_empty())
+                .map(|c| c as u8),
+        }
+    }
+
+    /// Returns true if adding another child grows the node into the next node type.
+    fn is_full(&self, sizing: &NodeSizing) -> bool {
+        self.header.num_children >= self.heat.grow_point(self.node_kind(), sizing)
+    }
+
+    /// Adds a new leaf as a child and returns a reference to its value. Growing the node takes
+    /// the arrays in `grown` if they are of the next node type.
+    fn add_leaf(
+        &mut self,
+        c: u8,
+        leaf: Box<ArtNodeLeaf<V, K>>,
+        sizing: &NodeSizing,
+        grown: &mut Option<ArtNodeInternalInner<V, K>>,
+    ) -> &mut V {
+        self.add_child_growing(c, Node::Leaf(leaf), sizing, grown);
+        match self.find_child_mut(c) {
+            Some(Node::Leaf(leaf)) => &mut leaf.value,
+            _ => unreachable!(),
         }
-        return None;
     }
 
-    fn add_child(&mut self, c: u8, child: Node<V>) {
+    fn add_child(&mut self, c: u8, child: Node<V, K>, sizing: &NodeSizing) {
+        self.add_child_growing(c, child, sizing, &mut None)
+    }
+
+    /// Adds a child like `add_child`, growing the node into the arrays in `grown` if they are of
+    /// the next node type
+    fn add_child_growing(
+        &mut self,
+        c: u8,
+        child: Node<V, K>,
+        sizing: &NodeSizing,
+        grown: &mut Option<ArtNodeInternalInner<V, K>>,
+    ) {
+        let full = self.is_full(sizing);
         let n = &mut self.header;
 
         match self.inner {
@@ -784,7 +1993,7 @@ impl<V> ArtNodeInternal<V> {
                 ref mut keys,
                 ref mut children,
             } => {
-                if n.num_children < 4 {
+                if !full {
                     let m = n.num_children;
                     let idx = keys.iter().position(|&key| c < key).unwrap_or(m as usize);
                     for i in (idx..m as usize).rev() {
@@ -796,25 +2005,27 @@ impl<V> ArtNodeInternal<V> {
                     children[idx] = child;
                     n.num_children += 1;
                 } else {
-                    let mut children_new: [Node<V>; 16] = [Node::<V>::INIT; 16];
-                    let mut keys_new: [u8; 16] = [0; 16];
-                    for i in 0..4 {
-                        keys_new[i] = keys[i];
-                        children_new[i] = mem::replace(&mut children[i], Node::Empty);
+                    let mut inner = ArtNodeInternalInner::grown(NodeKind::Node4, grown);
+                    if let ArtNodeInternalInner::Node16 {
+                        keys: ref mut keys_new,
+                        children: ref mut children_new,
+                    } = inner
+                    {
+                        for i in 0..n.num_children as usize {
+                            keys_new[i] = keys[i];
+                            children_new[i] = mem::replace(&mut children[i], Node::Empty);
+                        }
                     }
 
-                    self.inner = ArtNodeInternalInner::Node16 {
-                        keys: keys_new,
-                        children: children_new,
-                    };
-                    self.add_child(c, child);
+                    self.inner = inner;
+                    self.add_child(c, child, sizing);
                 }
             }
             ArtNodeInternalInner::Node16 {
                 ref mut keys,
                 ref mut children,
             } => {
-                if n.num_children < 16 {
+                if !full {
                     let m = n.num_children as usize;
                     let idx = keys[0..m].iter().position(|&key| c < key).unwrap_or(m);
                     for i in (idx..m).rev() {
@@ -826,43 +2037,79 @@ impl<V> ArtNodeInternal<V> {
                     children[idx] = child;
                     n.num_children += 1;
                 } else {
-                    let mut children_new: [Node<V>; 48] = [Node::INIT; 48];
-                    let mut keys_new: [u8; 256] = [0; 256];
+                    let mut inner = ArtNodeInternalInner::grown(NodeKind::Node16, grown);
+                    if let ArtNodeInternalInner::Node32 {
+                        keys: ref mut keys_new,
+                        children: ref mut children_new,
+                    } = inner
+                    {
+                        for i in 0..n.num_children as usize {
+                            keys_new[i] = keys[i];
+                            children_new[i] = mem::replace(&mut children[i], Node::Empty);
+                        }
+                    }
+
+                    self.inner = inner;
+                    self.add_child(c, child, sizing);
+                }
+            }
+            ArtNodeInternalInner::Node32 {
+                ref mut keys,
+                ref mut children,
+            } => {
+                if !full {
+                    let m = n.num_children as usize;
+                    let idx = keys[0..m].iter().position(|&key| c < key).unwrap_or(m);
+                    for i in (idx..m).rev() {
+                        keys[i + 1] = keys[i];
+                        children[i + 1] = mem::replace(&mut children[i], Node::Empty);
+                    }
 
-                    for i in 0..16 {
-                        keys_new[keys[i] as usize] = (i + 1) as u8;
-                        children_new[i] = mem::replace(&mut children[i], Node::Empty);
+                    keys[idx] = c;
+                    children[idx] = child;
+                    n.num_children += 1;
+                } else {
+                    let mut inner = ArtNodeInternalInner::grown(NodeKind::Node32, grown);
+                    if let ArtNodeInternalInner::Node48 {
+                        keys: ref mut keys_new,
+                        children: ref mut children_new,
+                    } = inner
+                    {
+                        for i in 0..n.num_children as usize {
+                            keys_new[keys[i] as usize] = (i + 1) as u8;
+                            children_new[i] = mem::replace(&mut children[i], Node::Empty);
+                        }
                     }
 
-                    self.inner = ArtNodeInternalInner::Node48 {
-                        keys: keys_new,
-                        children: children_new,
-                    };
-                    self.add_child(c, child);
+                    self.inner = inner;
+                    self.add_child(c, child, sizing);
                 }
             }
             ArtNodeInternalInner::Node48 {
                 ref mut keys,
                 ref mut children,
             } => {
-                if n.num_children < 48 {
+                if !full {
                     let pos = children.iter().position(|child| child.is_empty()).unwrap();
                     children[pos] = child;
                     keys[c as usize] = (pos + 1) as u8;
                     n.num_children += 1;
                 } else {
-                    let mut children_new: [Node<V>; 256] = [Node::INIT; 256];
-                    for (i, &key) in keys.iter().enumerate() {
-                        if key != 0 {
-                            let idx = (key - 1) as usize;
-                            children_new[i] = mem::replace(&mut children[idx], Node::Empty);
+                    let mut inner = ArtNodeInternalInner::grown(NodeKind::Node48, grown);
+                    if let ArtNodeInternalInner::Node256 {
+                        children: ref mut children_new,
+                    } = inner
+                    {
+                        for (i, &key) in keys.iter().enumerate() {
+                            if key != 0 {
+                                let idx = (key - 1) as usize;
+                                children_new[i] = mem::replace(&mut children[idx], Node::Empty);
+                            }
                         }
                     }
 
-                    self.inner = ArtNodeInternalInner::Node256 {
-                        children: children_new,
-                    };
-                    self.add_child(c, child);
+                    self.inner = inner;
+                    self.add_child(c, child, sizing);
                 }
             }
             ArtNodeInternalInner::Node256 { ref mut children } => {
@@ -872,10 +2119,11 @@ impl<V> ArtNodeInternal<V> {
         }
     }
 
-    fn minimum(&self) -> Option<&ArtNodeLeaf<V>> {
+    fn minimum(&self) -> Option<&ArtNodeLeaf<V, K>> {
         match &self.inner {
             ArtNodeInternalInner::Node4 { children, .. } => children[0].minimum(),
             ArtNodeInternalInner::Node16 { children, .. } => children[0].minimum(),
+            ArtNodeInternalInner::Node32 { children, .. } => children[0].minimum(),
             ArtNodeInternalInner::Node48 { keys, children, .. } => {
                 let idx = keys.iter().position(|&key| key != 0).unwrap();
                 let idx = (keys[idx] - 1) as usize;
@@ -891,10 +2139,12 @@ impl<V> ArtNodeInternal<V> {
         }
     }
 
-    fn minimum_mut(&mut self) -> Option<&mut ArtNodeLeaf<V>> {
+    fn minimum_mut(&mut self) -> Option<&mut ArtNodeLeaf<V, K>> {
+        self.invalidate_caches();
         match &mut self.inner {
             ArtNodeInternalInner::Node4 { children, .. } => children[0].minimum_mut(),
             ArtNodeInternalInner::Node16 { children, .. } => children[0].minimum_mut(),
+            ArtNodeInternalInner::Node32 { children, .. } => children[0].minimum_mut(),
             ArtNodeInternalInner::Node48 { keys, children, .. } => {
                 let idx = keys.iter().position(|&key| key != 0).unwrap();
                 let idx = (keys[idx] - 1) as usize;
@@ -910,66 +2160,7 @@ impl<V> ArtNodeInternal<V> {
         }
     }
 
-    fn pop_first(&mut self) -> Option<(Box<[u8]>, V)> {
-        match self.inner {
-            ArtNodeInternalInner::Node4 {
-                ref mut children, ..
-            } => children[0].pop_first(),
-            ArtNodeInternalInner::Node16 {
-                ref mut children, ..
-            } => children[0].pop_first(),
-            ArtNodeInternalInner::Node48 {
-                ref mut keys,
-                ref mut children,
-                ..
-            } => {
-                let idx = keys.iter().position(|&key| key != 0).unwrap();
-                let idx = (keys[idx] - 1) as usize;
-                children[idx].pop_first()
-            }
-            ArtNodeInternalInner::Node256 {
-                ref mut children, ..
-            } => {
-                let idx = children.iter().position(|child| !child.is_empty());
-                match idx {
-                    None => None,
-                    Some(i) => children[i].pop_first(),
-                }
-            }
-        }
-    }
-
-    fn pop_last(&mut self) -> Option<(Box<[u8]>, V)> {
-        let n = &self.header;
-        match self.inner {
-            ArtNodeInternalInner::Node4 {
-                ref mut children, ..
-            } => children[(n.num_children - 1) as usize].pop_last(),
-            ArtNodeInternalInner::Node16 {
-                ref mut children, ..
-            } => children[(n.num_children - 1) as usize].pop_last(),
-            ArtNodeInternalInner::Node48 {
-                ref mut keys,
-                ref mut children,
-                ..
-            } => {
-                let idx = keys.iter().rev().rposition(|&key| key != 0).unwrap();
-                let idx = (keys[idx] - 1) as usize;
-                children[idx].pop_last()
-            }
-            ArtNodeInternalInner::Node256 {
-                ref mut children, ..
-            } => {
-                let idx = children
-                    .iter()
-                    .rposition(|child| !child.is_empty())
-                    .unwrap();
-                children[idx].pop_last()
-            }
-        }
-    }
-
-    fn maximum(&self) -> Option<&ArtNodeLeaf<V>> {
+    fn maximum(&self) -> Option<&ArtNodeLeaf<V, K>> {
         let n = &self.header;
         match &self.inner {
             ArtNodeInternalInner::Node4 { children, .. } => {
@@ -978,6 +2169,9 @@ impl<V> ArtNodeInternal<V> {
             ArtNodeInternalInner::Node16 { children, .. } => {
                 children[(n.num_children - 1) as usize].maximum()
             }
+            ArtNodeInternalInner::Node32 { children, .. } => {
+                children[(n.num_children - 1) as usize].maximum()
+            }
             ArtNodeInternalInner::Node48 { keys, children, .. } => {
                 let idx = keys.iter().rposition(|&i| i != 0).unwrap();
                 children[(keys[idx] - 1) as usize].maximum()
@@ -992,7 +2186,8 @@ impl<V> ArtNodeInternal<V> {
         }
     }
 
-    fn maximum_mut(&mut self) -> Option<&mut ArtNodeLeaf<V>> {
+    fn maximum_mut(&mut self) -> Option<&mut ArtNodeLeaf<V, K>> {
+        self.invalidate_caches();
         let n = &self.header;
         match &mut self.inner {
             ArtNodeInternalInner::Node4 { children, .. } => {
@@ -1001,6 +2196,9 @@ impl<V> ArtNodeInternal<V> {
             ArtNodeInternalInner::Node16 { children, .. } => {
                 children[(n.num_children - 1) as usize].maximum_mut()
             }
+            ArtNodeInternalInner::Node32 { children, .. } => {
+                children[(n.num_children - 1) as usize].maximum_mut()
+            }
             ArtNodeInternalInner::Node48 { keys, children, .. } => {
                 let idx = keys.iter().rposition(|&key| key != 0).unwrap();
                 let idx = (keys[idx] - 1) as usize;
@@ -1041,9 +2239,19 @@ impl<V> ArtNodeInternal<V> {
                     }
                 }
             }
+            ArtNodeInternalInner::Node32 { children, .. } => {
+                for child in children.iter_mut() {
+                    if !child.is_empty() {
+                        let result = child.recursive_iter(callback);
+                        if result {
+                            return result;
+                        }
+                    }
+                }
+            }
             ArtNodeInternalInner::Node48 { keys, children, .. } => {
-                for i in 0..256 {
-                    let idx = keys[i] as usize;
+                for &idx in keys.iter() {
+                    let idx = idx as usize;
                     if idx != 0 {
                         let result = children[idx - 1].recursive_iter(callback);
                         if result {
@@ -1067,17 +2275,61 @@ impl<V> ArtNodeInternal<V> {
     }
 }
 
-impl<V> ArtNodeInternal<V> {
+impl<V, K: LeafKey> ArtNodeInternal<V, K> {
+    /// Compares the compressed path of this node (the `partial_len` bytes starting at `depth`)
+    /// with the same bytes of the key. If the key ends within the path, only the bytes it has are
+    /// compared.
+    fn compare_path(&self, key: &[u8], depth: usize) -> Ordering {
+        let n = &self.header;
+        let key = &key[min(depth, key.len())..];
+        let cmp_len = min(n.partial_len, key.len());
+        if n.partial_len <= MAX_PREFIX_LEN {
+            return n.partial[..cmp_len].cmp(&key[..cmp_len]);
+        }
+
+        // The prefix is only partially stored, take the full path from a leaf
+        let l = self.minimum().unwrap();
+        l.key()[depth..depth + cmp_len].cmp(&key[..cmp_len])
+    }
+
+    /// Returns the child with the greatest key byte less than `c`.
+    fn last_child_before(&self, c: u8) -> Option<&Node<V, K>> {
+        let n = self.header.num_children as usize;
+        match &self.inner {
+            ArtNodeInternalInner::Node4 { keys, children } => keys[..n]
+                .iter()
+                .rposition(|&key| key < c)
+                .map(|i| &children[i]),
+            ArtNodeInternalInner::Node16 { keys, children } => keys[..n]
+                .iter()
+                .rposition(|&key| key < c)
+                .map(|i| &children[i]),
+            ArtNodeInternalInner::Node32 { keys, children } => keys[..n]
+                .iter()
+                .rposition(|&key| key < c)
+                .map(|i| &children[i]),
+            ArtNodeInternalInner::Node48 { keys, children } => keys[..c as usize]
+                .iter()
+                .rfind(|&&idx| idx != 0)
+                .map(|&idx| &children[idx as usize - 1]),
+            ArtNodeInternalInner::Node256 { children } => children[..c as usize]
+                .iter()
+                .rfind(|child| !child.is_empty()),
+        }
+    }
+
     /// Calculates the index at which the prefixes mismatch
-    fn prefix_mismatch(&mut self, key: &[u8], depth: usize) -> usize {
+    fn prefix_mismatch(&self, key: &[u8], depth: usize) -> usize {
         let n = &self.header;
         let max_cmp = min(min(MAX_PREFIX_LEN, n.partial_len), key.len() - depth);
         let idx = (0..max_cmp)
             .into_iter()
             .position(|i| n.partial[i] != key[depth + i]);
         if let Some(id) = idx {
+            profiling::compared(id + 1);
             return id;
         }
+        profiling::compared(max_cmp);
 
         let idx = max_cmp;
 
@@ -1085,15 +2337,18 @@ impl<V> ArtNodeInternal<V> {
         if n.partial_len > MAX_PREFIX_LEN {
             // Prefix is longer than what we've checked, find a leaf
             let l = self.minimum().unwrap();
-            let max_cmp = min(l.key.len(), key.len()) - depth;
+            let max_cmp = min(l.key().len(), key.len()) - depth;
             for i in idx..max_cmp {
-                if l.key[(i + depth)] != key[(depth + i)] {
+                if l.key()[i + depth] != key[depth + i] {
+                    profiling::compared(i + 1 - idx);
                     return i;
                 }
             }
+            profiling::compared(max_cmp.saturating_sub(idx));
+            return max(idx, max_cmp);
         }
 
-        return idx;
+        idx
     }
 }
 
@@ -1103,38 +2358,39 @@ impl InternalNodeHeader {
     fn check_prefix(&self, key: &[u8], depth: usize) -> usize {
         let max_cmp = min(min(self.partial_len, MAX_PREFIX_LEN), key.len() - depth);
         for idx in 0..max_cmp {
-            if self.partial[idx] != key[(depth + idx)] {
+            if self.partial[idx] != key[depth + idx] {
+                profiling::compared(idx + 1);
                 return idx;
             }
         }
-        return max_cmp;
+        profiling::compared(max_cmp);
+        max_cmp
     }
 }
 
-impl<V> ArtNodeLeaf<V> {
-    fn new(key: &[u8], value: V) -> Self {
-        Self {
-            value,
-            key: key.into(),
-        }
+impl<V, K: LeafKey> ArtNodeLeaf<V, K> {
+    fn new(key: K, value: V) -> Self {
+        Self { value, key }
+    }
+
+    fn key(&self) -> &[u8] {
+        self.key.as_ref()
     }
 
     /// Checks if a leaf's key matches a key
     /// @return true if they match.
     fn matches(&self, key: &[u8]) -> bool {
-        if self.key.len() != key.len() {
-            return false;
-        }
-        self.key.iter().zip(key).all(|(a, b)| *a == *b)
+        self.key() == key
     }
 
     fn longest_common_prefix(&self, other: &mut Self, depth: usize) -> usize {
-        let max_cmp = min(self.key.len(), other.key.len()) - depth;
+        let (key, other_key) = (self.key(), other.key());
+        let max_cmp = min(key.len(), other_key.len()) - depth;
         for idx in 0..max_cmp {
-            if self.key[depth + idx] != other.key[depth + idx] {
+            if key[depth + idx] != other_key[depth + idx] {
                 return idx;
             }
         }
-        return max_cmp;
+        max_cmp
     }
 }
@@ -0,0 +1,239 @@
+use std::any::{Any, TypeId};
+use std::ops::{Bound, Deref, DerefMut, RangeBounds};
+use std::sync::Arc;
+
+use super::purge::Side;
+use super::{ArtNodeInternal, ArtNodeInternalInner, ArtTree, LeafKey, Node};
+
+/// Summary cached in an internal node, with the type id of the aggregate that computed it
+pub(super) type CachedSummary = (TypeId, Arc<dyn Any + Send + Sync>);
+
+/// An associative summary of entries, e.g. their count, the sum or the maximum of their values,
+/// kept for every subtree of an [`AnnotatedArtTree`].
+///
+/// `combine` has to be associative with `empty` as its identity. It does not have to be
+/// commutative: summaries are always combined in ascending key order. Summaries are cached by
+/// the type of the aggregate, so all values of the type have to compute the same summaries.
+pub trait Aggregate<V>: 'static {
+    type Summary: Clone + Send + Sync + 'static;
+
+    /// Returns the summary of no entries
+    fn empty(&self) -> Self::Summary;
+
+    /// Returns the summary of a single entry
+    fn leaf(&self, key: &[u8], value: &V) -> Self::Summary;
+
+    /// Returns the summary of the entries of `left` followed by those of `right`
+    fn combine(&self, left: &Self::Summary, right: &Self::Summary) -> Self::Summary;
+}
+
+/// Counts the entries
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Count;
+
+impl<V> Aggregate<V> for Count {
+    type Summary = usize;
+
+    fn empty(&self) -> usize {
+        0
+    }
+
+    fn leaf(&self, _key: &[u8], _value: &V) -> usize {
+        1
+    }
+
+    fn combine(&self, left: &usize, right: &usize) -> usize {
+        left + right
+    }
+}
+
+/// An `ArtTree` keeping the summary of an [`Aggregate`] for every subtree, so that the entries
+/// under a prefix are summarized in time proportional to the depth of the tree.
+///
+/// The tree is used as is through `Deref`. Every mutation drops the cached summaries on the
+/// path it changes, and the next query recomputes only those nodes from the summaries of their
+/// children.
+#[derive(Debug, Clone)]
+pub struct AnnotatedArtTree<V, A, K = Box<[u8]>> {
+    tree: ArtTree<V, K>,
+    aggregate: A,
+}
+
+impl<V, A: Aggregate<V>> AnnotatedArtTree<V, A> {
+    pub fn new(aggregate: A) -> Self {
+        Self::with_tree(ArtTree::new(), aggregate)
+    }
+}
+
+impl<V, A: Aggregate<V>, K: LeafKey> AnnotatedArtTree<V, A, K> {
+    /// Annotates the entries of an existing tree. Summaries are computed by the first query.
+    pub fn with_tree(tree: ArtTree<V, K>, aggregate: A) -> Self {
+        Self { tree, aggregate }
+    }
+
+    pub fn aggregate(&self) -> &A {
+        &self.aggregate
+    }
+
+    /// Returns the summary of all entries
+    pub fn summary(&self) -> A::Summary {
+        self.tree.root.summary(&self.aggregate)
+    }
+
+    /// Returns the summary of the entries whose keys start with `prefix`
+    pub fn aggregate_prefix(&self, prefix: &[u8]) -> A::Summary {
+        match self.tree.root.prefix_root(prefix, 0) {
+            Some((node, _)) => node.summary(&self.aggregate),
+            None => self.aggregate.empty(),
+        }
+    }
+
+    /// Returns the summary of the entries whose keys fall in the given range.
+    ///
+    /// Only the paths towards the bounds are descended, the subtrees between them contribute
+    /// their cached summaries.
+    pub fn aggregate_range<'r, R>(&self, range: R) -> A::Summary
+    where
+        R: RangeBounds<&'r [u8]>,
+    {
+        let (start, end) = (range.start_bound().cloned(), range.end_bound().cloned());
+        self.tree.root.summary_range(start, end, 0, &self.aggregate)
+    }
+
+    pub fn into_inner(self) -> ArtTree<V, K> {
+        self.tree
+    }
+}
+
+impl<V, A, K> Deref for AnnotatedArtTree<V, A, K> {
+    type Target = ArtTree<V, K>;
+
+    fn deref(&self) -> &ArtTree<V, K> {
+        &self.tree
+    }
+}
+
+impl<V, A, K> DerefMut for AnnotatedArtTree<V, A, K> {
+    fn deref_mut(&mut self) -> &mut ArtTree<V, K> {
+        &mut self.tree
+    }
+}
+
+impl<V, K: LeafKey> Node<V, K> {
+    pub(super) fn summary<A: Aggregate<V>>(&self, aggregate: &A) -> A::Summary {
+        match self {
+            Node::Empty => aggregate.empty(),
+            Node::Leaf(leaf) => aggregate.leaf(leaf.key(), &leaf.value),
+            Node::Internal(internal) => internal.summary(aggregate),
+        }
+    }
+
+    /// Returns the summary of the entries of the node within the range, descending only into
+    /// the children that the bounds lead to
+    fn summary_range<A: Aggregate<V>>(
+        &self,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+        depth: usize,
+        aggregate: &A,
+    ) -> A::Summary {
+        let internal = match self {
+            Node::Internal(internal) => internal,
+            Node::Leaf(leaf) if !RangeBounds::<&[u8]>::contains(&(start, end), &leaf.key()) => {
+                return aggregate.empty();
+            }
+            _ => return self.summary(aggregate),
+        };
+        if let (Bound::Unbounded, Bound::Unbounded) = (start, end) {
+            return internal.summary(aggregate);
+        }
+
+        // Like `detach_range`, child key bytes from `from` to `to` are (partly) in range
+        let (from, descend_from) = match internal.side(start, depth) {
+            Some(Side::Below) => return aggregate.empty(),
+            Some(Side::At(c)) => (c, Some(c)),
+            Some(Side::Above) | None => (0, None),
+        };
+        let (to, descend_to) = match internal.side(end, depth) {
+            Some(Side::Above) => return aggregate.empty(),
+            Some(Side::At(c)) => (c, Some(c)),
+            Some(Side::Below) | None => (u8::MAX, None),
+        };
+        let child_depth = depth + internal.header.partial_len + 1;
+        let mut summary = aggregate.empty();
+        internal.for_children_within(from, to, |key, child| {
+            let child_summary = if Some(key) == descend_from || Some(key) == descend_to {
+                let start = if Some(key) == descend_from {
+                    start
+                } else {
+                    Bound::Unbounded
+                };
+                let end = if Some(key) == descend_to {
+                    end
+                } else {
+                    Bound::Unbounded
+                };
+                child.summary_range(start, end, child_depth, aggregate)
+            } else {
+                child.summary(aggregate)
+            };
+            summary = aggregate.combine(&summary, &child_summary);
+        });
+        summary
+    }
+}
+
+impl<V, K: LeafKey> ArtNodeInternal<V, K> {
+    /// Returns the cached summary of the subtree, computing it from the children if needed. A
+    /// node holds the summary of one aggregate type, others are recomputed on every call.
+    fn summary<A: Aggregate<V>>(&self, aggregate: &A) -> A::Summary {
+        let id = TypeId::of::<A>();
+        let (cached_id, summary) = self
+            .summary
+            .get_or_init(|| (id, Arc::new(self.combine_children(aggregate))));
+        match summary.downcast_ref::<A::Summary>() {
+            Some(summary) if *cached_id == id => summary.clone(),
+            _ => self.combine_children(aggregate),
+        }
+    }
+
+    /// Calls `f` with the children whose key byte is in `from..=to`, in ascending order
+    fn for_children_within<F>(&self, from: u8, to: u8, mut f: F)
+    where
+        F: FnMut(u8, &Node<V, K>),
+    {
+        let n = self.header.num_children as usize;
+        let (keys, children) = match &self.inner {
+            ArtNodeInternalInner::Node4 { keys, children } => (&keys[..n], &children[..n]),
+            ArtNodeInternalInner::Node16 { keys, children } => (&keys[..n], &children[..n]),
+            ArtNodeInternalInner::Node32 { keys, children } => (&keys[..n], &children[..n]),
+            ArtNodeInternalInner::Node48 { keys, children } => {
+                for key in from..=to {
+                    if let Some(idx) = keys[key as usize].checked_sub(1) {
+                        f(key, &children[idx as usize]);
+                    }
+                }
+                return;
+            }
+            ArtNodeInternalInner::Node256 { children } => {
+                for key in from..=to {
+                    if !children[key as usize].is_empty() {
+                        f(key, &children[key as usize]);
+                    }
+                }
+                return;
+            }
+        };
+        for (&key, child) in keys.iter().zip(children) {
+            if (from..=to).contains(&key) {
+                f(key, child);
+            }
+        }
+    }
+
+    fn combine_children<A: Aggregate<V>>(&self, aggregate: &A) -> A::Summary {
+        self.children_from(0).fold(aggregate.empty(), |acc, child| {
+            aggregate.combine(&acc, &child.summary(aggregate))
+        })
+    }
+}
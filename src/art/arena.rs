@@ -0,0 +1,225 @@
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::ops::{Deref, Range};
+use std::ptr;
+use std::slice;
+use std::sync::Arc;
+
+use super::{ArtTree, LeafKey};
+
+/// Size of the blocks a [`KeyArena`] appends keys to
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Leaf key storage pointing into the key arena of its tree rather than owning an allocation.
+///
+/// An `ArenaKey` takes 16 bytes like a `Box<[u8]>`: a reference-counted pointer to an arena
+/// block and the position of the key within it. Inserting a new key appends its bytes to the
+/// arena, which saves the allocator overhead of one allocation per key. A key that repeats or
+/// extends the key interned just before it (e.g. a `merge`, which deletes and reinserts the
+/// key) shares its bytes.
+///
+/// The arena is append-only: the bytes of deleted keys are freed together with their block once
+/// no key refers to it anymore.
+#[derive(Clone)]
+pub struct ArenaKey {
+    chunk: Arc<Chunk>,
+    offset: u32,
+    len: u32,
+}
+
+/// An `ArtTree` storing its keys in a shared arena, see [`ArenaKey`]. Create one with
+/// `ArtTreeArena::default()`.
+pub type ArtTreeArena<V> = ArtTree<V, ArenaKey>;
+
+/// Append-only storage for the keys of a tree, used by leaf key types such as [`ArenaKey`] that
+/// do not allocate every key separately. Every tree owns one, it stays empty for other key types.
+#[derive(Default)]
+pub struct KeyArena {
+    /// The block keys are appended to
+    chunk: Option<Arc<Chunk>>,
+    /// Number of bytes of the block written
+    filled: usize,
+    /// Position of the key interned last within the block
+    last: Range<usize>,
+    stats: ArenaStats,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(super) struct ArenaStats {
+    /// Number of blocks allocated
+    pub(super) chunks: usize,
+    /// Total size of the blocks
+    pub(super) allocated_bytes: usize,
+    /// Key bytes shared with the previously interned key instead of being appended
+    pub(super) deduplicated_bytes: usize,
+}
+
+/// A block of arena memory. The bytes below the fill mark of the arena never change again, only
+/// the arena appending to the block writes above it.
+struct Chunk(Box<[UnsafeCell<u8>]>);
+
+// SAFETY: the bytes a key refers to are written once, before the key is created, and never
+// change afterwards. The unwritten rest of a block is only accessed by the single arena
+// appending to it, through `&mut KeyArena`.
+unsafe impl Sync for Chunk {}
+
+impl Chunk {
+    fn new(len: usize) -> Arc<Self> {
+        Arc::new(Chunk((0..len).map(|_| UnsafeCell::new(0)).collect()))
+    }
+
+    /// Returns written bytes of the block
+    fn bytes(&self, range: Range<usize>) -> &[u8] {
+        let cells = &self.0[range];
+        // SAFETY: `UnsafeCell<u8>` has the layout of `u8`, and written bytes are not modified
+        unsafe { slice::from_raw_parts(UnsafeCell::raw_get(cells.as_ptr()), cells.len()) }
+    }
+
+    /// Writes `bytes` to the block at `offset`
+    ///
+    /// # Safety
+    ///
+    /// The range has to be above the fill mark, so that no key refers to it, and the caller has
+    /// to be the arena appending to the block.
+    unsafe fn write(&self, offset: usize, bytes: &[u8]) {
+        let cells = &self.0[offset..offset + bytes.len()];
+        ptr::copy_nonoverlapping(
+            bytes.as_ptr(),
+            UnsafeCell::raw_get(cells.as_ptr()),
+            bytes.len(),
+        );
+    }
+}
+
+impl KeyArena {
+    /// Returns a key referring to a copy of `key` in the arena
+    pub(super) fn intern(&mut self, key: &[u8]) -> ArenaKey {
+        if let Some(chunk) = &self.chunk {
+            let last = chunk.bytes(self.last.clone());
+            if last.starts_with(key) {
+                self.stats.deduplicated_bytes += key.len();
+                return ArenaKey::new(chunk, self.last.start, key.len());
+            }
+            // The last key is at the end of the block, so a key extending it only appends the
+            // rest of its bytes
+            let rest = &key[last.len().min(key.len())..];
+            if key.starts_with(last) && self.last.end == self.filled && rest.len() <= self.space() {
+                // SAFETY: the bytes after the fill mark are not referred to by any key
+                unsafe { chunk.write(self.filled, rest) };
+                self.stats.deduplicated_bytes += last.len();
+                self.filled += rest.len();
+                self.last.end = self.filled;
+                return ArenaKey::new(chunk, self.last.start, key.len());
+            }
+        }
+
+        if key.len() > self.space() {
+            if key.len() > CHUNK_SIZE / 2 {
+                // A long key gets a block of its own, leaving the current block for others
+                self.stats.chunks += 1;
+                self.stats.allocated_bytes += key.len();
+                return ArenaKey::from_slice(key);
+            }
+            self.chunk = Some(Chunk::new(CHUNK_SIZE));
+            self.filled = 0;
+            self.stats.chunks += 1;
+            self.stats.allocated_bytes += CHUNK_SIZE;
+        }
+        let chunk = self.chunk.as_ref().unwrap();
+        // SAFETY: the bytes after the fill mark are not referred to by any key
+        unsafe { chunk.write(self.filled, key) };
+        self.last = self.filled..self.filled + key.len();
+        self.filled = self.last.end;
+        ArenaKey::new(chunk, self.last.start, key.len())
+    }
+
+    pub(super) fn stats(&self) -> ArenaStats {
+        self.stats
+    }
+
+    /// Number of bytes left in the current block
+    fn space(&self) -> usize {
+        self.chunk
+            .as_ref()
+            .map_or(0, |chunk| chunk.0.len() - self.filled)
+    }
+}
+
+/// A clone starts with an empty arena, only one arena appends to a block.
+impl Clone for KeyArena {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl fmt::Debug for KeyArena {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyArena")
+            .field("filled", &self.filled)
+            .field("stats", &self.stats)
+            .finish()
+    }
+}
+
+impl ArenaKey {
+    fn new(chunk: &Arc<Chunk>, offset: usize, len: usize) -> Self {
+        ArenaKey {
+            chunk: chunk.clone(),
+            offset: offset as u32,
+            len: len as u32,
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        let offset = self.offset as usize;
+        self.chunk.bytes(offset..offset + self.len as usize)
+    }
+}
+
+impl LeafKey for ArenaKey {
+    /// Copies the key into a block of its own, keys inserted into a tree are interned in its
+    /// arena instead
+    fn from_slice(key: &[u8]) -> Self {
+        assert!(
+            key.len() <= u32::MAX as usize,
+            "a key of {} bytes is too long for an arena",
+            key.len()
+        );
+        let chunk = Chunk::new(key.len());
+        // SAFETY: the block is new, no key refers to it yet
+        unsafe { chunk.write(0, key) };
+        ArenaKey::new(&chunk, 0, key.len())
+    }
+
+    fn intern(key: &[u8], arena: &mut KeyArena) -> Self {
+        arena.intern(key)
+    }
+}
+
+impl Deref for ArenaKey {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl AsRef<[u8]> for ArenaKey {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl fmt::Debug for ArenaKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_bytes().fmt(f)
+    }
+}
+
+impl PartialEq for ArenaKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl Eq for ArenaKey {}
@@ -0,0 +1,182 @@
+use std::cmp::max;
+
+use super::iter::RawIter;
+use super::{ArtTree, LeafKey};
+
+/// Smallest number of keys a filter is sized for
+const MIN_CAPACITY: usize = 1024;
+
+/// Optional Bloom filter over the keys of a tree, consulted before descending on lookups.
+///
+/// Bits cannot be cleared, so deleted keys keep answering "maybe" until the filter is rebuilt.
+/// That happens once as many keys were inserted or deleted as the filter was sized for.
+#[derive(Debug, Clone, Default)]
+pub(super) struct KeyFilter {
+    filter: Option<Bloom>,
+    /// Number of entries the filter is sized for at least, see `ArtTree::reserve`
+    reserved: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Bloom {
+    bits: Vec<u64>,
+    hashes: u32,
+    bits_per_key: usize,
+    capacity: usize,
+    updates: usize,
+}
+
+impl Bloom {
+    fn new(bits_per_key: usize, capacity: usize) -> Self {
+        let hashes = ((bits_per_key as f64 * std::f64::consts::LN_2).round() as u32).clamp(1, 30);
+        Bloom {
+            bits: vec![0; (bits_per_key * capacity).div_ceil(64)],
+            hashes,
+            bits_per_key,
+            capacity,
+            updates: 0,
+        }
+    }
+
+    fn num_bits(&self) -> u64 {
+        self.bits.len() as u64 * 64
+    }
+
+    fn insert(&mut self, key: &[u8]) {
+        for bit in positions(key, self.hashes, self.num_bits()) {
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+        self.updates += 1;
+    }
+
+    fn may_contain(&self, key: &[u8]) -> bool {
+        positions(key, self.hashes, self.num_bits())
+            .all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+}
+
+impl KeyFilter {
+    /// Returns false only if the key is definitely not in the tree
+    pub(super) fn may_contain(&self, key: &[u8]) -> bool {
+        match &self.filter {
+            Some(bloom) => bloom.may_contain(key),
+            None => true,
+        }
+    }
+
+    pub(super) fn inserted(&mut self, key: &[u8]) {
+        if let Some(bloom) = &mut self.filter {
+            bloom.insert(key);
+        }
+    }
+
+    pub(super) fn deleted(&mut self, count: usize) {
+        if let Some(bloom) = &mut self.filter {
+            bloom.updates += count;
+        }
+    }
+
+    /// Empties the filter, keeping its configuration
+    pub(super) fn clear(&mut self) {
+        if let Some(bloom) = &mut self.filter {
+            *bloom = Bloom::new(bloom.bits_per_key, MIN_CAPACITY);
+        }
+    }
+
+    /// Returns the bits per key of the filter, if there is one
+    pub(super) fn bits_per_key(&self) -> Option<usize> {
+        self.filter.as_ref().map(|bloom| bloom.bits_per_key)
+    }
+
+    pub(super) fn reserve(&mut self, entries: usize) {
+        self.reserved = max(self.reserved, entries);
+    }
+
+    fn needs_rebuild(&self) -> bool {
+        matches!(&self.filter, Some(bloom) if bloom.updates >= bloom.capacity)
+    }
+}
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Maintains a Bloom filter over the keys of the tree with `bits_per_key` bits per key, so
+    /// that `get`, `get_mut` and `contains_key` return early for most absent keys instead of
+    /// descending the tree.
+    ///
+    /// The filter is resized as the tree grows, and rebuilt to forget deleted keys. About 10 bits
+    /// per key give a false positive rate of 1%. Panics if `bits_per_key` is zero.
+    pub fn enable_bloom_filter(&mut self, bits_per_key: usize) {
+        assert!(
+            bits_per_key > 0,
+            "a Bloom filter needs at least one bit per key"
+        );
+        self.rebuild_bloom_filter(bits_per_key);
+    }
+
+    /// Drops the Bloom filter, if any.
+    pub fn disable_bloom_filter(&mut self) {
+        self.bloom.filter = None;
+    }
+
+    /// Returns true if lookups consult a Bloom filter.
+    pub fn has_bloom_filter(&self) -> bool {
+        self.bloom.filter.is_some()
+    }
+
+    /// Rebuilds the filter from the current keys if it filled up with inserts and deletes.
+    pub(super) fn maintain_bloom_filter(&mut self) {
+        if self.bloom.needs_rebuild() {
+            let bits_per_key = self.bloom.filter.as_ref().unwrap().bits_per_key;
+            self.rebuild_bloom_filter(bits_per_key);
+        }
+    }
+
+    /// Sizes the filter for `additional` more entries, rebuilding it now if they would not fit.
+    pub(super) fn reserve_bloom_filter(&mut self, additional: usize) {
+        self.bloom.reserve(self.len() + additional);
+        match &self.bloom.filter {
+            Some(bloom) if bloom.updates + additional > bloom.capacity => {
+                let bits_per_key = bloom.bits_per_key;
+                self.rebuild_bloom_filter(bits_per_key);
+            }
+            _ => {}
+        }
+    }
+
+    fn rebuild_bloom_filter(&mut self, bits_per_key: usize) {
+        let capacity = max(max(2 * self.len(), self.bloom.reserved), MIN_CAPACITY);
+        let mut bloom = Bloom::new(bits_per_key, capacity);
+        for leaf in RawIter::new(&self.root) {
+            bloom.insert(leaf.key());
+        }
+        self.bloom.filter = Some(bloom);
+    }
+}
+
+/// Bit positions of the key, using double hashing on a single 64-bit hash
+fn positions(key: &[u8], hashes: u32, num_bits: u64) -> impl Iterator<Item = usize> {
+    let h1 = hash(key);
+    let h2 = mix(h1 ^ 0x9e37_79b9_7f4a_7c15) | 1;
+    (0..u64::from(hashes)).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % num_bits) as usize)
+}
+
+/// Hashes the key 8 bytes at a time
+fn hash(key: &[u8]) -> u64 {
+    let mut h = mix(key.len() as u64);
+    let mut chunks = key.chunks_exact(8);
+    for chunk in &mut chunks {
+        let mut word = [0; 8];
+        word.copy_from_slice(chunk);
+        h = mix(h ^ u64::from_le_bytes(word));
+    }
+    let mut word = [0; 8];
+    word[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+    mix(h ^ u64::from_le_bytes(word))
+}
+
+/// The splitmix64 finalizer
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
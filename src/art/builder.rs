@@ -0,0 +1,207 @@
+use std::cmp::min;
+
+use super::{
+    ArtNodeLeaf, ArtTree, BulkLoad, InternalNodeHeader, KeyArena, LeafKey, Node, NodeSizing,
+    MAX_PREFIX_LEN,
+};
+
+/// Builds an `ArtTree` from entries pushed in ascending key order, without searching the tree
+/// for every key.
+///
+/// Only the rightmost path of the tree, which the next keys may still extend, is kept open.
+/// The nodes left of it are finished as soon as a key branches off to their right. This makes
+/// it a cheap way to load the output of an external sort; [`FlatArtBuilder`] builds the flat
+/// format the same way.
+///
+/// Like in an `ArtTree`, no key may be a prefix of another one.
+///
+/// [`FlatArtBuilder`]: crate::flat_art::FlatArtBuilder
+#[derive(Debug)]
+pub struct ArtBuilder<V, K = Box<[u8]>> {
+    path: SortedPath<Box<ArtNodeLeaf<V, K>>, Node<V, K>>,
+    keys: KeyArena,
+}
+
+impl<V> ArtBuilder<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<V, K> Default for ArtBuilder<V, K> {
+    fn default() -> Self {
+        Self {
+            path: SortedPath::default(),
+            keys: KeyArena::default(),
+        }
+    }
+}
+
+impl<V, K: LeafKey> ArtBuilder<V, K> {
+    /// Adds an entry.
+    ///
+    /// # Panics
+    ///
+    /// If `key` is not greater than the key pushed before it, or the key before it is a prefix
+    /// of it.
+    pub fn push(&mut self, key: &[u8], value: V) {
+        self.path.check_order(key);
+        let leaf = Box::new(ArtNodeLeaf::new(K::intern(key, &mut self.keys), value));
+        self.path.push(&mut TreeSink, key, leaf);
+    }
+
+    /// Returns the number of entries pushed
+    pub fn len(&self) -> usize {
+        self.path.len
+    }
+
+    /// Returns true if no entry was pushed
+    pub fn is_empty(&self) -> bool {
+        self.path.len == 0
+    }
+
+    /// Closes the rightmost path and returns the tree holding the entries.
+    pub fn finish(self) -> ArtTree<V, K> {
+        let phase = BulkLoad::enter("finish_tree");
+        phase.entries(self.path.len);
+        let size = self.path.len as u64;
+        ArtTree {
+            root: self.path.finish(&mut TreeSink).unwrap_or(Node::Empty),
+            size,
+            keys: self.keys,
+            ..ArtTree::default()
+        }
+    }
+}
+
+/// Turns the leaves and closed nodes of a `SortedPath` into subtrees, every node after its
+/// children.
+pub(crate) trait BuildSink<L, N> {
+    /// Makes the subtree of a leaf for `key`, of which the nodes above it hold the first
+    /// `depth` bytes
+    fn leaf(&mut self, leaf: L, key: &[u8], depth: usize) -> N;
+
+    /// Makes an inner node with the compressed `path` and at least two children, sorted by
+    /// their key byte
+    fn inner(&mut self, path: &[u8], children: Vec<(u8, N)>) -> N;
+}
+
+/// The rightmost path of a tree built from keys in ascending order: the inner nodes that can
+/// still get children, and the leaf of the last key, which is not attached yet since the next
+/// key decides the node it hangs from.
+#[derive(Debug, Clone)]
+pub(crate) struct SortedPath<L, N> {
+    /// Open inner nodes from the root down: the position of the key byte of their children
+    /// and the children closed so far
+    open: Vec<(usize, Vec<(u8, N)>)>,
+    last_leaf: Option<L>,
+    last_key: Vec<u8>,
+    pub(crate) len: usize,
+}
+
+impl<L, N> Default for SortedPath<L, N> {
+    fn default() -> Self {
+        Self {
+            open: Vec::new(),
+            last_leaf: None,
+            last_key: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<L, N> SortedPath<L, N> {
+    /// Panics unless `key` can be pushed after the last key
+    pub(crate) fn check_order(&self, key: &[u8]) {
+        if self.len > 0 {
+            assert!(
+                self.last_key[..] < *key,
+                "keys have to be pushed in ascending order"
+            );
+            assert!(
+                !key.starts_with(&self.last_key),
+                "a key is a prefix of another key"
+            );
+        }
+    }
+
+    /// Adds the leaf of `key`, closing the nodes below the point where it branches off the
+    /// rightmost path. The order has to be checked with `check_order` first.
+    pub(crate) fn push(&mut self, sink: &mut impl BuildSink<L, N>, key: &[u8], leaf: L) {
+        self.len += 1;
+        let last_leaf = match self.last_leaf.replace(leaf) {
+            Some(last_leaf) => last_leaf,
+            None => {
+                self.last_key.extend_from_slice(key);
+                return;
+            }
+        };
+        let branch = self
+            .last_key
+            .iter()
+            .zip(key)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        let mut tail = sink.leaf(last_leaf, &self.last_key, self.parent_depth(branch) + 1);
+        while let Some((depth, _)) = self.open.last() {
+            if *depth <= branch {
+                break;
+            }
+            let (depth, mut children) = self.open.pop().unwrap();
+            children.push((self.last_key[depth], tail));
+            let start = self.parent_depth(branch) + 1;
+            tail = sink.inner(&self.last_key[start..depth], children);
+        }
+        let c = self.last_key[branch];
+        match self.open.last_mut() {
+            Some((depth, children)) if *depth == branch => children.push((c, tail)),
+            _ => self.open.push((branch, vec![(c, tail)])),
+        }
+
+        self.last_key.clear();
+        self.last_key.extend_from_slice(key);
+    }
+
+    /// Closes the rightmost path and returns the root, or `None` if nothing was pushed
+    pub(crate) fn finish(mut self, sink: &mut impl BuildSink<L, N>) -> Option<N> {
+        let last_leaf = self.last_leaf.take()?;
+        let depth = self.open.last().map_or(0, |(depth, _)| depth + 1);
+        let mut tail = sink.leaf(last_leaf, &self.last_key, depth);
+        while let Some((depth, mut children)) = self.open.pop() {
+            children.push((self.last_key[depth], tail));
+            let start = self.open.last().map_or(0, |(depth, _)| depth + 1);
+            tail = sink.inner(&self.last_key[start..depth], children);
+        }
+        Some(tail)
+    }
+
+    /// Returns the key byte position of the node that the subtree closed below it hangs from,
+    /// when the next key branches off at `branch`: the open node at or below `branch`, or a new
+    /// node at `branch`
+    fn parent_depth(&self, branch: usize) -> usize {
+        self.open
+            .last()
+            .map_or(branch, |(depth, _)| (*depth).max(branch))
+    }
+}
+
+struct TreeSink;
+
+impl<V, K: LeafKey> BuildSink<Box<ArtNodeLeaf<V, K>>, Node<V, K>> for TreeSink {
+    fn leaf(&mut self, leaf: Box<ArtNodeLeaf<V, K>>, _key: &[u8], _depth: usize) -> Node<V, K> {
+        Node::Leaf(leaf)
+    }
+
+    fn inner(&mut self, path: &[u8], children: Vec<(u8, Node<V, K>)>) -> Node<V, K> {
+        let mut partial = [0; MAX_PREFIX_LEN];
+        let stored = min(path.len(), MAX_PREFIX_LEN);
+        partial[..stored].copy_from_slice(&path[..stored]);
+        let header = InternalNodeHeader {
+            partial_len: path.len(),
+            num_children: 0,
+            partial,
+        };
+        Node::from_children(header, children, &NodeSizing::DEFAULT)
+    }
+}
@@ -0,0 +1,53 @@
+use super::{ArtTree, LeafKey, Node};
+
+/// What follows a byte returned by [`ArtTree::children_of`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Branch<'a, V> {
+    /// A single key continues with the byte
+    Leaf { key: &'a [u8], value: &'a V },
+    /// Several keys continue with the byte
+    Subtree,
+}
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Returns the distinct bytes that follow `prefix` in the keys starting with it, in
+    /// ascending order, each with the leaf it leads to if a single key continues with it.
+    ///
+    /// This is a directory listing for keys of a namespace: only the node covering the prefix
+    /// is read, the subtrees below it are not walked.
+    pub fn children_of(&self, prefix: &[u8]) -> Vec<(u8, Branch<'_, V>)> {
+        let (node, depth) = match self.root.prefix_root(prefix, 0) {
+            Some(found) => found,
+            None => return Vec::new(),
+        };
+        match node {
+            Node::Internal(internal) if depth + internal.header.partial_len > prefix.len() => {
+                // The prefix ends inside the compressed path, which continues with one byte
+                let path = internal.minimum().unwrap().key();
+                vec![(path[prefix.len()], Branch::Subtree)]
+            }
+            Node::Internal(internal) => internal
+                .keyed_children()
+                .into_iter()
+                .map(|(c, child)| (c, Branch::of(child)))
+                .collect(),
+            Node::Leaf(leaf) => match leaf.key().get(prefix.len()) {
+                Some(&c) => vec![(c, Branch::of(node))],
+                None => Vec::new(),
+            },
+            Node::Empty => Vec::new(),
+        }
+    }
+}
+
+impl<'a, V> Branch<'a, V> {
+    fn of<K: LeafKey>(node: &'a Node<V, K>) -> Self {
+        match node {
+            Node::Leaf(leaf) => Branch::Leaf {
+                key: leaf.key(),
+                value: &leaf.value,
+            },
+            _ => Branch::Subtree,
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use super::{ArtNodeInternal, ArtNodeInternalInner, ArtNodeLeaf, ArtTree, Node};
+
+impl<V: Clone, K: Clone> Clone for ArtTree<V, K> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            size: self.size,
+            merge_operator: self.merge_operator.clone(),
+            observers: self.observers.clone(),
+            counters: self.counters.clone(),
+            sizing: self.sizing,
+            max_key_len: self.max_key_len,
+            duplicates: self.duplicates,
+            dirty: self.dirty.clone(),
+            bloom: self.bloom.clone(),
+            keys: self.keys.clone(),
+        }
+    }
+
+    /// Overwrites the tree with a copy of `source`, reusing the allocations of the nodes of
+    /// `self` wherever both trees have a node of the same type at the same position.
+    fn clone_from(&mut self, source: &Self) {
+        self.root.clone_from(&source.root);
+        self.size = source.size;
+        self.merge_operator.clone_from(&source.merge_operator);
+        self.observers.clone_from(&source.observers);
+        self.counters.clone_from(&source.counters);
+        self.sizing = source.sizing;
+        self.max_key_len = source.max_key_len;
+        self.duplicates = source.duplicates;
+        self.dirty.clone_from(&source.dirty);
+        self.bloom.clone_from(&source.bloom);
+        self.keys.clone_from(&source.keys);
+    }
+}
+
+impl<V: Clone, K: Clone> Clone for Node<V, K> {
+    fn clone(&self) -> Self {
+        match self {
+            Node::Empty => Node::Empty,
+            Node::Leaf(leaf) => Node::Leaf(leaf.clone()),
+            Node::Internal(internal) => Node::Internal(internal.clone()),
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        match (&mut *self, source) {
+            // `Box::clone_from` clones into the existing allocation
+            (Node::Leaf(leaf), Node::Leaf(source)) => leaf.clone_from(source),
+            (Node::Internal(internal), Node::Internal(source)) => internal.clone_from(source),
+            (_, source) => *self = source.clone(),
+        }
+    }
+}
+
+impl<V: Clone, K: Clone> Clone for ArtNodeLeaf<V, K> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            key: self.key.clone(),
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.value.clone_from(&source.value);
+        self.key.clone_from(&source.key);
+    }
+}
+
+impl<V: Clone, K: Clone> Clone for ArtNodeInternal<V, K> {
+    fn clone(&self) -> Self {
+        Self {
+            header: self.header,
+            inner: self.inner.clone(),
+            heat: self.heat.clone(),
+            #[cfg(feature = "merkle")]
+            hash: self.hash.clone(),
+            #[cfg(feature = "aggregate")]
+            summary: self.summary.clone(),
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.header = source.header;
+        self.heat = source.heat.clone();
+        #[cfg(feature = "merkle")]
+        self.hash.clone_from(&source.hash);
+        #[cfg(feature = "aggregate")]
+        self.summary.clone_from(&source.summary);
+
+        use ArtNodeInternalInner::*;
+        match (&mut self.inner, &source.inner) {
+            (
+                Node4 { keys, children },
+                Node4 {
+                    keys: k,
+                    children: c,
+                },
+            ) => {
+                *keys = *k;
+                clone_children_from(&mut children[..], &c[..]);
+            }
+            (
+                Node16 { keys, children },
+                Node16 {
+                    keys: k,
+                    children: c,
+                },
+            ) => {
+                *keys = *k;
+                clone_children_from(&mut children[..], &c[..]);
+            }
+            (
+                Node32 { keys, children },
+                Node32 {
+                    keys: k,
+                    children: c,
+                },
+            ) => {
+                *keys = *k;
+                clone_children_from(&mut children[..], &c[..]);
+            }
+            (
+                Node48 { keys, children },
+                Node48 {
+                    keys: k,
+                    children: c,
+                },
+            ) => {
+                **keys = **k;
+                clone_children_from(&mut children[..], &c[..]);
+            }
+            (Node256 { children }, Node256 { children: c }) => {
+                clone_children_from(&mut children[..], &c[..])
+            }
+            (inner, source) => *inner = source.clone(),
+        }
+    }
+}
+
+fn clone_children_from<V: Clone, K: Clone>(children: &mut [Node<V, K>], source: &[Node<V, K>]) {
+    for (child, source) in children.iter_mut().zip(source) {
+        child.clone_from(source);
+    }
+}
@@ -0,0 +1,47 @@
+use std::ops::{Bound, RangeBounds};
+
+use super::{ArtTree, LeafKey, Node, MAX_PREFIX_LEN};
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Returns the longest prefix shared by all keys, empty if the tree is empty.
+    ///
+    /// This is the compressed path of the root node. A path longer than the prefix stored in the
+    /// node is read from the leftmost leaf, which takes a descent to it.
+    pub fn common_prefix(&self) -> &[u8] {
+        match &self.root {
+            Node::Empty => &[],
+            Node::Leaf(leaf) => leaf.key(),
+            Node::Internal(internal) => {
+                let len = internal.header.partial_len;
+                if len <= MAX_PREFIX_LEN {
+                    &internal.header.partial[..len]
+                } else {
+                    &internal.minimum().unwrap().key()[..len]
+                }
+            }
+        }
+    }
+
+    /// Returns the longest prefix shared by the keys that fall in the given range, empty if
+    /// there are none, e.g. to choose the boundaries of shards.
+    ///
+    /// Keys are sorted, so this is the prefix the first and the last key in the range share,
+    /// which are found by two descents.
+    pub fn common_prefix_range<'r, R>(&self, range: R) -> &[u8]
+    where
+        R: RangeBounds<&'r [u8]>,
+    {
+        let first = match self.range((range.start_bound(), range.end_bound())).next() {
+            Some((key, _)) => key,
+            None => return &[],
+        };
+        let last = match range.end_bound() {
+            Bound::Included(end) => self.root.floor(end, 0),
+            Bound::Excluded(end) => self.root.last_before(end, 0),
+            Bound::Unbounded => self.root.maximum(),
+        };
+        let last = last.unwrap().key();
+        let len = first.iter().zip(last).take_while(|(a, b)| a == b).count();
+        &first[..len]
+    }
+}
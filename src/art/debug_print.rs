@@ -0,0 +1,148 @@
+use std::cmp::min;
+use std::fmt;
+
+use super::iter::RawIter;
+use super::{ArtNodeInternal, ArtNodeInternalInner, ArtTree, LeafKey, Node, MAX_PREFIX_LEN};
+
+/// Key bytes shown per leaf, and characters shown per value
+const KEY_SUMMARY_LEN: usize = 32;
+const VALUE_SUMMARY_LEN: usize = 40;
+
+/// An indented rendering of the node structure of a tree.
+///
+/// Created by [`ArtTree::debug_print`].
+pub struct DebugPrint<'a, V, K = Box<[u8]>> {
+    tree: &'a ArtTree<V, K>,
+    max_depth: usize,
+}
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Returns a `Display` rendering of the nodes of the tree: one line per node with its type,
+    /// compressed path (hex) and number of children, or its key (hex) and value, indented below
+    /// its parent and labelled with the key byte leading to it.
+    ///
+    /// ```text
+    /// Node4 prefix=00 children=2
+    /// |-- 01: Leaf 000101 => 1
+    /// `-- 02: Leaf 000201 => 2
+    /// ```
+    pub fn debug_print(&self) -> DebugPrint<'_, V, K> {
+        DebugPrint {
+            tree: self,
+            max_depth: usize::MAX,
+        }
+    }
+}
+
+impl<'a, V, K> DebugPrint<'a, V, K> {
+    /// Renders only the nodes at most `max_depth` levels below the root. Deeper subtrees are
+    /// summarized by their number of entries.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+}
+
+impl<'a, V: fmt::Debug, K: LeafKey> fmt::Display for DebugPrint<'a, V, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut indent = String::new();
+        self.write_node(f, &self.tree.root, 0, &mut indent)
+    }
+}
+
+impl<'a, V: fmt::Debug, K: LeafKey> DebugPrint<'a, V, K> {
+    /// Writes the rest of the line of `node` and the lines of its subtree, each child line
+    /// starting with `indent`
+    fn write_node(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        node: &Node<V, K>,
+        depth: usize,
+        indent: &mut String,
+    ) -> fmt::Result {
+        match node {
+            Node::Empty => writeln!(f, "Empty"),
+            Node::Leaf(leaf) => {
+                write!(f, "Leaf ")?;
+                write_hex(f, leaf.key(), KEY_SUMMARY_LEN)?;
+                let value = format!("{:?}", leaf.value);
+                match value.char_indices().nth(VALUE_SUMMARY_LEN) {
+                    Some((end, _)) => writeln!(f, " => {}...", &value[..end]),
+                    None => writeln!(f, " => {}", value),
+                }
+            }
+            Node::Internal(internal) => {
+                let header = internal.header;
+                write!(f, "{:?} prefix=", internal.node_kind())?;
+                write_hex(
+                    f,
+                    &header.partial[..min(header.partial_len, MAX_PREFIX_LEN)],
+                    MAX_PREFIX_LEN,
+                )?;
+                if header.partial_len > MAX_PREFIX_LEN {
+                    write!(f, "..+{}", header.partial_len - MAX_PREFIX_LEN)?;
+                }
+                writeln!(f, " children={}", header.num_children)?;
+
+                if depth >= self.max_depth {
+                    let entries = RawIter::new(node).count();
+                    return writeln!(f, "{}`-- ... ({} entries)", indent, entries);
+                }
+                let children = internal.keyed_children();
+                let last = children.len().saturating_sub(1);
+                for (i, (key, child)) in children.into_iter().enumerate() {
+                    let (branch, continuation) = if i == last {
+                        ("`-- ", "    ")
+                    } else {
+                        ("|-- ", "|   ")
+                    };
+                    write!(f, "{}{}{:02x}: ", indent, branch, key)?;
+                    indent.push_str(continuation);
+                    self.write_node(f, child, depth + 1, indent)?;
+                    indent.truncate(indent.len() - continuation.len());
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn write_hex(f: &mut fmt::Formatter<'_>, bytes: &[u8], max_len: usize) -> fmt::Result {
+    for byte in &bytes[..min(bytes.len(), max_len)] {
+        write!(f, "{:02x}", byte)?;
+    }
+    if bytes.len() > max_len {
+        write!(f, "..")?;
+    }
+    Ok(())
+}
+
+impl<V, K> ArtNodeInternal<V, K> {
+    /// Returns the children together with their key bytes, in ascending order.
+    pub(super) fn keyed_children(&self) -> Vec<(u8, &Node<V, K>)> {
+        let n = self.header.num_children as usize;
+        match &self.inner {
+            ArtNodeInternalInner::Node4 { keys, children } => {
+                keys.iter().copied().zip(children.iter()).take(n).collect()
+            }
+            ArtNodeInternalInner::Node16 { keys, children } => {
+                keys.iter().copied().zip(children.iter()).take(n).collect()
+            }
+            ArtNodeInternalInner::Node32 { keys, children } => {
+                keys.iter().copied().zip(children.iter()).take(n).collect()
+            }
+            ArtNodeInternalInner::Node48 { keys, children } => keys
+                .iter()
+                .enumerate()
+                .filter(|&(_, &idx)| idx != 0)
+                .map(|(key, &idx)| (key as u8, &children[idx as usize - 1]))
+                .collect(),
+            ArtNodeInternalInner::Node256 { children } => children
+                .iter()
+                .enumerate()
+                .filter(|(_, child)| !child.is_empty())
+                .map(|(key, child)| (key as u8, child))
+                .collect(),
+        }
+    }
+}
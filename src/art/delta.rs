@@ -0,0 +1,93 @@
+use std::collections::btree_set;
+use std::collections::BTreeSet;
+
+use super::{ArtTree, LeafKey};
+
+/// A change to a single key since the tree was last marked clean.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delta<'a, V> {
+    /// The key was inserted or its value may have been modified; carries the current value
+    Upsert(&'a [u8], &'a V),
+    /// The key was deleted
+    Delete(&'a [u8]),
+}
+
+/// Keys touched since the last `mark_clean`. Nothing is recorded until tracking is enabled.
+#[derive(Debug, Clone, Default)]
+pub(super) struct DirtyKeys(Option<BTreeSet<Box<[u8]>>>);
+
+impl DirtyKeys {
+    pub(super) fn mark(&mut self, key: &[u8]) {
+        if let Some(keys) = &mut self.0 {
+            if !keys.contains(key) {
+                keys.insert(key.into());
+            }
+        }
+    }
+}
+
+/// Iterator over the changes of a tree since it was last marked clean, in ascending key order.
+///
+/// Created by [`ArtTree::changes`].
+pub struct Changes<'a, V, K = Box<[u8]>> {
+    tree: &'a ArtTree<V, K>,
+    keys: Option<btree_set::Iter<'a, Box<[u8]>>>,
+}
+
+impl<'a, V, K: LeafKey> Iterator for Changes<'a, V, K> {
+    type Item = Delta<'a, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key: &'a [u8] = self.keys.as_mut()?.next()?;
+        Some(match self.tree.get(key) {
+            Some(value) => Delta::Upsert(key, value),
+            None => Delta::Delete(key),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.keys {
+            Some(keys) => keys.size_hint(),
+            None => (0, Some(0)),
+        }
+    }
+}
+
+impl<'a, V, K: LeafKey> ExactSizeIterator for Changes<'a, V, K> {}
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Starts recording which keys are changed, so that [`changes`](ArtTree::changes) can
+    /// enumerate them. The current contents are considered clean.
+    ///
+    /// Every key handed out mutably (`get_mut`, `entries_mut`, ...) counts as changed, whether
+    /// or not its value was actually written.
+    pub fn track_changes(&mut self) {
+        if self.dirty.0.is_none() {
+            self.dirty.0 = Some(BTreeSet::new());
+        }
+    }
+
+    /// Returns true if changes are being recorded.
+    pub fn is_tracking_changes(&self) -> bool {
+        self.dirty.0.is_some()
+    }
+
+    /// Forgets the changes recorded so far, making the current contents the new baseline.
+    pub fn mark_clean(&mut self) {
+        if let Some(keys) = &mut self.dirty.0 {
+            keys.clear();
+        }
+    }
+
+    /// Returns the keys changed since change tracking started or since the last
+    /// [`mark_clean`](ArtTree::mark_clean), as upserts carrying the current value or deletes.
+    ///
+    /// A key inserted and deleted again in between is reported as a delete. Without change
+    /// tracking the iterator is empty.
+    pub fn changes(&self) -> Changes<'_, V, K> {
+        Changes {
+            tree: self,
+            keys: self.dirty.0.as_ref().map(|keys| keys.iter()),
+        }
+    }
+}
@@ -0,0 +1,183 @@
+#[cfg(feature = "merkle")]
+use std::cmp::Ordering;
+#[cfg(feature = "merkle")]
+use std::hash::Hash;
+use std::iter::Peekable;
+use std::sync::Arc;
+use std::vec;
+
+#[cfg(feature = "merkle")]
+use super::iter::RawIter;
+#[cfg(feature = "merkle")]
+use super::set_ops::{branches, common_len, path, Branches};
+#[cfg(feature = "merkle")]
+use super::Node;
+use super::{ArtTree, FrozenArtTree, Iter, LeafKey};
+
+/// An entry that differs between two trees, as yielded by [`ArtTree::diff`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffEntry<'a, V> {
+    /// The key is only in the new tree
+    Added(&'a [u8], &'a V),
+    /// The key is only in the old tree
+    Removed(&'a [u8], &'a V),
+    /// The key is in both trees with different values, the old one first
+    Changed(&'a [u8], &'a V, &'a V),
+}
+
+/// Iterator over the entries that differ between two trees, in ascending key order.
+///
+/// Created by [`ArtTree::diff`], [`FrozenArtTree::diff`] and, with the `merkle` feature,
+/// `ArtTree::diff_by_hash`.
+pub struct Diff<'a, V, K: LeafKey = Box<[u8]>> {
+    old: Peekable<Iter<'a, V, K>>,
+    new: Peekable<Iter<'a, V, K>>,
+    /// Set if the trees are known to be equal without comparing their entries
+    equal: bool,
+    /// The differences if they were found up front, in which case the iterators are not used
+    found: Option<vec::IntoIter<DiffEntry<'a, V>>>,
+}
+
+impl<'a, V: PartialEq, K: LeafKey> Iterator for Diff<'a, V, K> {
+    type Item = DiffEntry<'a, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.equal {
+            return None;
+        }
+        if let Some(found) = &mut self.found {
+            return found.next();
+        }
+        let (old, new) = (&mut self.old, &mut self.new);
+        loop {
+            let entry = match (old.peek(), new.peek()) {
+                (Some(&(old_key, _)), Some(&(new_key, _))) if old_key < new_key => {
+                    let (key, value) = old.next().unwrap();
+                    DiffEntry::Removed(key, value)
+                }
+                (Some(&(old_key, _)), Some(&(new_key, _))) if old_key > new_key => {
+                    let (key, value) = new.next().unwrap();
+                    DiffEntry::Added(key, value)
+                }
+                (Some(_), Some(_)) => {
+                    let (key, old_value) = old.next().unwrap();
+                    let (_, new_value) = new.next().unwrap();
+                    if old_value == new_value {
+                        continue;
+                    }
+                    DiffEntry::Changed(key, old_value, new_value)
+                }
+                (Some(_), None) => {
+                    let (key, value) = old.next().unwrap();
+                    DiffEntry::Removed(key, value)
+                }
+                (None, Some(_)) => {
+                    let (key, value) = new.next().unwrap();
+                    DiffEntry::Added(key, value)
+                }
+                (None, None) => return None,
+            };
+            return Some(entry);
+        }
+    }
+}
+
+impl<V: PartialEq, K: LeafKey> ArtTree<V, K> {
+    /// Returns the entries added, removed and changed going from this tree to `new`, in
+    /// ascending key order.
+    ///
+    /// Trees do not share nodes, so both trees are walked in full and every diff takes time
+    /// linear in the sizes of both trees, even if they differ in a single key. With the
+    /// `merkle` feature, `diff_by_hash` skips the subtrees whose cached hashes are equal.
+    pub fn diff<'a>(&'a self, new: &'a Self) -> Diff<'a, V, K> {
+        Diff {
+            old: self.entries().peekable(),
+            new: new.entries().peekable(),
+            equal: false,
+            found: None,
+        }
+    }
+}
+
+#[cfg(feature = "merkle")]
+impl<V: PartialEq + Hash, K: LeafKey> ArtTree<V, K> {
+    /// Returns the entries added, removed and changed going from this tree to `new`, like
+    /// [`diff`](ArtTree::diff), but descends both trees together and skips the subtrees whose
+    /// hashes are equal.
+    ///
+    /// The differences are found when this is called. Hashes are cached in the nodes as for
+    /// [`root_hash`](ArtTree::root_hash), so after the first call only the subtrees changed
+    /// since are hashed again, and a diff takes time in the number of differing entries
+    /// rather than in the sizes of the trees.
+    pub fn diff_by_hash<'a>(&'a self, new: &'a Self) -> Diff<'a, V, K> {
+        let mut found = Vec::new();
+        diff_nodes(&self.root, 0, &new.root, 0, 0, &mut found);
+        Diff {
+            found: Some(found.into_iter()),
+            ..self.diff(new)
+        }
+    }
+}
+
+/// Appends the differences between node `a`, whose compressed path starts at `a_start`, and
+/// node `b` starting at `b_start`, both of which match the keys up to `depth`.
+#[cfg(feature = "merkle")]
+fn diff_nodes<'a, V: PartialEq + Hash, K: LeafKey>(
+    a: &'a Node<V, K>,
+    a_start: usize,
+    b: &'a Node<V, K>,
+    b_start: usize,
+    depth: usize,
+    found: &mut Vec<DiffEntry<'a, V>>,
+) {
+    if a.hash() == b.hash() {
+        return;
+    }
+    if a.is_empty() || b.is_empty() {
+        let removed = RawIter::new(a).map(|leaf| DiffEntry::Removed(leaf.key(), &leaf.value));
+        let added = RawIter::new(b).map(|leaf| DiffEntry::Added(leaf.key(), &leaf.value));
+        found.extend(removed.chain(added));
+        return;
+    }
+    if let (Node::Leaf(l), Node::Leaf(r)) = (a, b) {
+        match l.key().cmp(r.key()) {
+            Ordering::Less => {
+                found.push(DiffEntry::Removed(l.key(), &l.value));
+                found.push(DiffEntry::Added(r.key(), &r.value));
+            }
+            Ordering::Greater => {
+                found.push(DiffEntry::Added(r.key(), &r.value));
+                found.push(DiffEntry::Removed(l.key(), &l.value));
+            }
+            Ordering::Equal if l.value != r.value => {
+                found.push(DiffEntry::Changed(l.key(), &l.value, &r.value));
+            }
+            Ordering::Equal => {}
+        }
+        return;
+    }
+
+    let (a_path, b_path) = (path(a, a_start, depth), path(b, b_start, depth));
+    let m = common_len(a_path, b_path);
+    let child_depth = depth + m + 1;
+    let pairs = Branches::new(
+        branches(a, a_start, a_path, m, child_depth),
+        branches(b, b_start, b_path, m, child_depth),
+    );
+    for (_, left, right) in pairs {
+        let (l, l_start) = left.unwrap_or((&Node::Empty, child_depth));
+        let (r, r_start) = right.unwrap_or((&Node::Empty, child_depth));
+        diff_nodes(l, l_start, r, r_start, child_depth, found);
+    }
+}
+
+impl<V: PartialEq, K: LeafKey> FrozenArtTree<V, K> {
+    /// Returns the entries added, removed and changed going from this tree to `new`, like
+    /// [`ArtTree::diff`]. Handles cloned from one another share their tree and are known to be
+    /// equal without walking it.
+    pub fn diff<'a>(&'a self, new: &'a Self) -> Diff<'a, V, K> {
+        let mut diff = self.tree.diff(&new.tree);
+        diff.equal = Arc::ptr_eq(&self.tree, &new.tree);
+        diff
+    }
+}
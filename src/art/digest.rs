@@ -0,0 +1,77 @@
+use std::hash::{Hash, Hasher};
+
+use super::iter::RawIter;
+use super::{ArtTree, LeafKey};
+
+/// Feeds `Hash` implementations into another hasher with integers in little-endian order and
+/// `usize`/`isize` widened to 64 bits, so that the digests agree across platforms.
+pub(super) struct LittleEndian<H>(pub(super) H);
+
+impl<H: Hasher> Hasher for LittleEndian<H> {
+    fn finish(&self) -> u64 {
+        self.0.finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.write(bytes);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16);
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32);
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as u64);
+    }
+}
+
+impl<V: Hash, K: LeafKey> ArtTree<V, K> {
+    /// Feeds the entries of the tree into `hasher` in ascending key order: the number of
+    /// entries, then the length and bytes of every key followed by its value.
+    ///
+    /// Integers written by the `Hash` implementations of the values are fed in little-endian
+    /// order, so trees with equal contents produce the same digest regardless of their shape,
+    /// their insertion history or the platform. Use a hasher with a fixed algorithm to compare
+    /// digests across machines; `DefaultHasher` may change between Rust releases.
+    pub fn content_hash<H: Hasher>(&self, hasher: &mut H) {
+        let mut writer = LittleEndian(hasher);
+        writer.write_u64(self.size);
+        for leaf in RawIter::new(&self.root) {
+            writer.write_u64(leaf.key().len() as u64);
+            writer.write(leaf.key());
+            leaf.value.hash(&mut writer);
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use std::error;
+use std::fmt;
+use std::mem;
+
+use super::{ArtNodeLeaf, ArtTree, LeafKey, Spare, Upsert};
+
+/// What an insert does with a key that is already present, passed to
+/// [`ArtTree::insert_with_policy`] or set for [`ArtTree::try_insert`] with
+/// [`ArtTree::set_duplicate_policy`].
+pub enum DuplicatePolicy<V> {
+    /// Store the new value, like [`ArtTree::insert`]
+    Replace,
+    /// Keep the stored value and hand the new one back
+    KeepExisting,
+    /// Fail with [`InsertError::DuplicateKey`]
+    Error,
+    /// Store the value returned by the function, called with the key, the stored value and the
+    /// new one
+    MergeWith(fn(&[u8], &V, V) -> V),
+}
+
+impl<V> Clone for DuplicatePolicy<V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<V> Copy for DuplicatePolicy<V> {}
+
+impl<V> fmt::Debug for DuplicatePolicy<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DuplicatePolicy::Replace => f.write_str("Replace"),
+            DuplicatePolicy::KeepExisting => f.write_str("KeepExisting"),
+            DuplicatePolicy::Error => f.write_str("Error"),
+            DuplicatePolicy::MergeWith(_) => f.write_str("MergeWith"),
+        }
+    }
+}
+
+/// The result of an insert that did not fail, returned by [`ArtTree::insert_with_policy`] and
+/// [`ArtTree::try_insert`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertOutcome<V> {
+    /// The key was not present
+    Inserted,
+    /// The new value replaced the returned one
+    Replaced(V),
+    /// The key kept its value, the returned new value was not stored
+    Kept(V),
+    /// The new value was merged into the stored one
+    Merged,
+}
+
+/// An insert that failed, handing the value back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertError<V> {
+    /// The key is present and the policy is [`DuplicatePolicy::Error`]
+    DuplicateKey(V),
+    /// The key is longer than [`ArtTree::max_key_len`]
+    KeyTooLong(V),
+    /// The allocator ran out of memory, see [`ArtTree::try_insert_alloc`]
+    AllocFailed(V),
+}
+
+impl<V> InsertError<V> {
+    /// Returns the value that was not inserted
+    pub fn into_value(self) -> V {
+        match self {
+            InsertError::DuplicateKey(value)
+            | InsertError::KeyTooLong(value)
+            | InsertError::AllocFailed(value) => value,
+        }
+    }
+}
+
+impl<V> fmt::Display for InsertError<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InsertError::DuplicateKey(_) => write!(f, "the key is already present"),
+            InsertError::KeyTooLong(_) => {
+                write!(f, "the key is longer than the maximum key length")
+            }
+            InsertError::AllocFailed(_) => write!(f, "memory allocation failed"),
+        }
+    }
+}
+
+impl<V: fmt::Debug> error::Error for InsertError<V> {}
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Returns the policy used by [`try_insert`](Self::try_insert)
+    pub fn duplicate_policy(&self) -> DuplicatePolicy<V> {
+        self.duplicates
+    }
+
+    /// Sets the policy used by [`try_insert`](Self::try_insert). [`insert`](Self::insert) always
+    /// replaces the stored value.
+    pub fn set_duplicate_policy(&mut self, policy: DuplicatePolicy<V>) {
+        self.duplicates = policy;
+    }
+
+    /// Inserts the given value at the given key, handling a present key as the tree's
+    /// [`duplicate_policy`](Self::duplicate_policy) says.
+    pub fn try_insert(&mut self, key: &[u8], value: V) -> Result<InsertOutcome<V>, InsertError<V>> {
+        self.insert_with_policy(key, value, self.duplicates)
+    }
+
+    /// Inserts the given value at the given key, handling a present key as `policy` says. Fails
+    /// with [`InsertError::KeyTooLong`] for a key longer than
+    /// [`max_key_len`](Self::max_key_len).
+    ///
+    /// Like [`insert`](Self::insert), this descends the tree only once, also when the key is
+    /// present.
+    pub fn insert_with_policy(
+        &mut self,
+        key: &[u8],
+        value: V,
+        policy: DuplicatePolicy<V>,
+    ) -> Result<InsertOutcome<V>, InsertError<V>> {
+        if key.len() > self.max_key_len {
+            return Err(InsertError::KeyTooLong(value));
+        }
+        let mut value = Some(value);
+        self.counters.insert();
+        self.maintain_bloom_filter();
+        let arena = &mut self.keys;
+        let outcome = match self.root.recursive_upsert(
+            key,
+            || {
+                Box::new(ArtNodeLeaf::new(
+                    K::intern(key, arena),
+                    value.take().unwrap(),
+                ))
+            },
+            0,
+            &self.counters,
+            &self.sizing,
+            &mut Spare::default(),
+        ) {
+            Upsert::Inserted(new_value) => {
+                self.size += 1;
+                self.dirty.mark(key);
+                self.bloom.inserted(key);
+                self.observers.inserted(key, new_value);
+                Ok(InsertOutcome::Inserted)
+            }
+            Upsert::Existing(current) => {
+                let value = value.take().unwrap();
+                let new_value = match policy {
+                    DuplicatePolicy::Replace => value,
+                    DuplicatePolicy::KeepExisting => return Ok(InsertOutcome::Kept(value)),
+                    DuplicatePolicy::Error => return Err(InsertError::DuplicateKey(value)),
+                    DuplicatePolicy::MergeWith(merge) => merge(key, current, value),
+                };
+                self.dirty.mark(key);
+                let old_value = mem::replace(current, new_value);
+                self.observers.replaced(key, &old_value, current);
+                match policy {
+                    DuplicatePolicy::MergeWith(_) => Ok(InsertOutcome::Merged),
+                    _ => Ok(InsertOutcome::Replaced(old_value)),
+                }
+            }
+        };
+        self.validate_path(key);
+        outcome
+    }
+}
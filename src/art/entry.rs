@@ -0,0 +1,142 @@
+use std::borrow::Cow;
+
+use super::{ArtTree, LeafKey};
+
+/// A view into a single key of an `ArtTree`, which is either occupied or vacant.
+///
+/// Created by [`ArtTree::entry`]. Looking the key up again is needed for each access through the
+/// entry; [`ArtTree::get_or_insert_with`] covers the common upsert in a single descent.
+pub enum Entry<'a, V, K = Box<[u8]>> {
+    Occupied(OccupiedEntry<'a, V, K>),
+    Vacant(VacantEntry<'a, V, K>),
+}
+
+/// A view into a key stored in an `ArtTree`.
+pub struct OccupiedEntry<'a, V, K = Box<[u8]>> {
+    tree: &'a mut ArtTree<V, K>,
+    key: Cow<'a, [u8]>,
+}
+
+/// A view into a key missing from an `ArtTree`.
+pub struct VacantEntry<'a, V, K = Box<[u8]>> {
+    tree: &'a mut ArtTree<V, K>,
+    key: &'a [u8],
+}
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Returns the entry of the given key for in-place manipulation.
+    pub fn entry<'a>(&'a mut self, key: &'a [u8]) -> Entry<'a, V, K> {
+        if self.contains_key(key) {
+            Entry::Occupied(OccupiedEntry {
+                tree: self,
+                key: Cow::Borrowed(key),
+            })
+        } else {
+            Entry::Vacant(VacantEntry { tree: self, key })
+        }
+    }
+
+    /// Returns the entry with the minimum key for in-place manipulation, or `None` if the tree
+    /// is empty.
+    pub fn first_entry(&mut self) -> Option<OccupiedEntry<'_, V, K>> {
+        let key = self.minimum()?.0.as_ref().to_vec();
+        Some(OccupiedEntry {
+            tree: self,
+            key: Cow::Owned(key),
+        })
+    }
+
+    /// Returns the entry with the maximum key for in-place manipulation, or `None` if the tree
+    /// is empty.
+    pub fn last_entry(&mut self) -> Option<OccupiedEntry<'_, V, K>> {
+        let key = self.maximum()?.0.as_ref().to_vec();
+        Some(OccupiedEntry {
+            tree: self,
+            key: Cow::Owned(key),
+        })
+    }
+}
+
+impl<'a, V, K: LeafKey> Entry<'a, V, K> {
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &[u8] {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Returns the value of the entry, inserting `default` first if the entry is vacant.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    /// Returns the value of the entry, inserting the result of `default` first if the entry is
+    /// vacant.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Returns the value of the entry, inserting `V::default()` first if the entry is vacant.
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /// Calls `f` on the value if the entry is occupied.
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, V, K: LeafKey> OccupiedEntry<'a, V, K> {
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// Returns a reference to the value of the entry.
+    pub fn get(&self) -> &V {
+        self.tree.get(&self.key).unwrap()
+    }
+
+    /// Returns a mutable reference to the value of the entry.
+    pub fn get_mut(&mut self) -> &mut V {
+        self.tree.get_mut(&self.key).unwrap()
+    }
+
+    /// Converts the entry into a mutable reference to its value.
+    pub fn into_mut(self) -> &'a mut V {
+        self.tree.get_mut(&self.key).unwrap()
+    }
+
+    /// Replaces the value of the entry and returns the old value.
+    pub fn insert(&mut self, value: V) -> V {
+        self.tree.insert(&self.key, value).unwrap()
+    }
+
+    /// Removes the entry from the tree and returns its value.
+    pub fn remove(self) -> V {
+        self.tree.delete(&self.key).unwrap()
+    }
+}
+
+impl<'a, V, K: LeafKey> VacantEntry<'a, V, K> {
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &[u8] {
+        self.key
+    }
+
+    /// Inserts the value at the key of the entry and returns a mutable reference to it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.tree.get_or_insert_with(self.key, || value)
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::VecDeque;
+
+use super::{ArtTree, LeafKey, Node};
+
+/// Number of internal nodes expanded exactly before switching to extrapolation.
+const EXPAND_BUDGET: usize = 64;
+/// Number of random descents used to extrapolate the size of each unexpanded node.
+const PROBES: u32 = 4;
+
+/// Approximate number of entries under a prefix.
+///
+/// Created by [`ArtTree::estimate_count_prefix`]. The true count always lies within
+/// `lower..=upper`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CountEstimate {
+    pub lower: usize,
+    pub estimate: usize,
+    pub upper: usize,
+}
+
+impl CountEstimate {
+    /// Returns true if the estimate is the exact count
+    pub fn is_exact(&self) -> bool {
+        self.lower == self.upper
+    }
+}
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Estimates the number of entries whose keys start with `prefix` without visiting all of
+    /// them.
+    ///
+    /// The top of the subtree covering the prefix is counted exactly. Below that, the size of
+    /// each remaining node is extrapolated from a few descents that multiply the fan-outs met
+    /// on the way down to a leaf (Knuth's estimator). Small subtrees are counted exactly.
+    pub fn estimate_count_prefix(&self, prefix: &[u8]) -> CountEstimate {
+        let root = match self.root.prefix_root(prefix, 0) {
+            Some((node, _)) => node,
+            None => {
+                return CountEstimate {
+                    lower: 0,
+                    estimate: 0,
+                    upper: 0,
+                }
+            }
+        };
+
+        let mut frontier = VecDeque::new();
+        frontier.push_back(root);
+        let mut leaves = 0;
+        let mut expanded = 0;
+        while let Some(node) = frontier.pop_front() {
+            match node {
+                Node::Leaf(_) => leaves += 1,
+                Node::Internal(internal) => {
+                    if expanded == EXPAND_BUDGET {
+                        frontier.push_front(node);
+                        break;
+                    }
+                    expanded += 1;
+                    frontier.extend(internal.children_from(0));
+                }
+                Node::Empty => {}
+            }
+        }
+        if frontier.is_empty() {
+            return CountEstimate {
+                lower: leaves,
+                estimate: leaves,
+                upper: leaves,
+            };
+        }
+
+        let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+        let mut lower = leaves;
+        let mut estimate = leaves as f64;
+        for node in frontier {
+            match node {
+                Node::Leaf(_) => {
+                    lower += 1;
+                    estimate += 1.0;
+                }
+                Node::Internal(internal) => {
+                    // Every child holds at least one leaf
+                    lower += internal.header.num_children as usize;
+                    let probes: f64 = (0..PROBES).map(|_| node.probe_size(&mut rng)).sum();
+                    estimate += probes / f64::from(PROBES);
+                }
+                Node::Empty => {}
+            }
+        }
+
+        let upper = self.len();
+        CountEstimate {
+            lower,
+            estimate: (estimate.round() as usize).max(lower).min(upper),
+            upper,
+        }
+    }
+}
+
+impl<V, K> Node<V, K> {
+    /// Descends to a random leaf and returns the product of the fan-outs on the way, an
+    /// unbiased estimate of the number of leaves below the node.
+    fn probe_size(&self, rng: &mut XorShift) -> f64 {
+        let mut node = self;
+        let mut size = 1.0;
+        loop {
+            match node {
+                Node::Internal(internal) => {
+                    let n = internal.header.num_children as u64;
+                    size *= n as f64;
+                    let pick = (rng.next() % n) as usize;
+                    node = internal.children_from(0).nth(pick).unwrap();
+                }
+                Node::Leaf(_) => return size,
+                Node::Empty => return 0.0,
+            }
+        }
+    }
+}
+
+/// Small deterministic generator, so that estimates are reproducible.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
@@ -0,0 +1,183 @@
+use std::cmp::min;
+use std::fmt;
+
+use super::{ArtTree, LeafKey, Node, NodeKind, MAX_PREFIX_LEN};
+
+/// An internal node passed by a lookup, see [`ArtTree::explain`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExplainStep {
+    /// The type of the node
+    pub kind: NodeKind,
+    /// The number of key bytes consumed above the node
+    pub depth: usize,
+    /// The number of children of the node
+    pub num_children: usize,
+    /// The length of the compressed path of the node
+    pub prefix_len: usize,
+    /// The bytes of the compressed path the key matched. Lookups only compare the stored part
+    /// of paths longer than the inline prefix buffer, the rest is checked against the leaf.
+    pub matched_prefix: Vec<u8>,
+    /// The key byte of the child the lookup descended to, `None` for the node it stopped at
+    pub child_byte: Option<u8>,
+}
+
+/// Where and why a lookup stopped, see [`ArtTree::explain`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExplainEnd {
+    /// The lookup reached the leaf of the key
+    Found,
+    /// The tree is empty
+    EmptyTree,
+    /// The Bloom filter ruled the key out before the descent
+    Filtered,
+    /// The key diverged from the compressed path of the last node at `offset`, `found` is
+    /// `None` if the key ended inside the path
+    PrefixMismatch {
+        offset: usize,
+        expected: u8,
+        found: Option<u8>,
+    },
+    /// The key ended at the last node without selecting a child
+    KeyExhausted,
+    /// The last node has no child for the next key byte
+    NoChild(u8),
+    /// The lookup reached the leaf of another key, which first differs from the key at `offset`
+    LeafMismatch { leaf_key: Vec<u8>, offset: usize },
+}
+
+/// A trace of the descent of a lookup, returned by [`ArtTree::explain`].
+///
+/// The `Display` implementation prints one line per node followed by the outcome, for pasting
+/// into bug reports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explain {
+    /// The internal nodes passed, from the root down
+    pub steps: Vec<ExplainStep>,
+    /// Why the lookup stopped
+    pub end: ExplainEnd,
+}
+
+impl Explain {
+    /// Returns true if the lookup found the key
+    pub fn found(&self) -> bool {
+        self.end == ExplainEnd::Found
+    }
+}
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Looks up the key like [`get`](Self::get) and returns a trace of the descent: the type,
+    /// matched prefix and chosen child of every node passed, and where and why the lookup
+    /// stopped.
+    pub fn explain(&self, key: &[u8]) -> Explain {
+        let mut steps = Vec::new();
+        if !self.bloom.may_contain(key) {
+            return Explain {
+                steps,
+                end: ExplainEnd::Filtered,
+            };
+        }
+        let mut n_iter = &self.root;
+        let mut depth = 0;
+        let end = loop {
+            match n_iter {
+                Node::Empty => break ExplainEnd::EmptyTree,
+                Node::Leaf(leaf) => {
+                    if leaf.matches(key) {
+                        break ExplainEnd::Found;
+                    }
+                    let leaf_key = leaf.key();
+                    let offset = leaf_key
+                        .iter()
+                        .zip(key)
+                        .position(|(a, b)| a != b)
+                        .unwrap_or_else(|| min(leaf_key.len(), key.len()));
+                    break ExplainEnd::LeafMismatch {
+                        leaf_key: leaf_key.to_vec(),
+                        offset,
+                    };
+                }
+                Node::Internal(internal) => {
+                    let header = internal.header;
+                    let stored = &header.partial[..min(header.partial_len, MAX_PREFIX_LEN)];
+                    let matched = header.check_prefix(key, depth);
+                    steps.push(ExplainStep {
+                        kind: internal.node_kind(),
+                        depth,
+                        num_children: header.num_children as usize,
+                        prefix_len: header.partial_len,
+                        matched_prefix: stored[..matched].to_vec(),
+                        child_byte: None,
+                    });
+                    if matched != stored.len() {
+                        break ExplainEnd::PrefixMismatch {
+                            offset: depth + matched,
+                            expected: stored[matched],
+                            found: key.get(depth + matched).copied(),
+                        };
+                    }
+                    depth += header.partial_len;
+
+                    let c = match key.get(depth) {
+                        Some(&c) => c,
+                        None => break ExplainEnd::KeyExhausted,
+                    };
+                    match internal.find_child(c) {
+                        Some(child) => {
+                            steps.last_mut().unwrap().child_byte = Some(c);
+                            n_iter = child;
+                        }
+                        None => break ExplainEnd::NoChild(c),
+                    }
+                    depth += 1;
+                }
+            }
+        };
+        Explain { steps, end }
+    }
+}
+
+impl fmt::Display for Explain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            write!(
+                f,
+                "{:?} with {} children at depth {}, matched prefix {:?} of {} bytes",
+                step.kind, step.num_children, step.depth, step.matched_prefix, step.prefix_len
+            )?;
+            if let Some(c) = step.child_byte {
+                write!(f, ", child {:#04x}", c)?;
+            }
+            writeln!(f)?;
+        }
+        match &self.end {
+            ExplainEnd::Found => write!(f, "found"),
+            ExplainEnd::EmptyTree => write!(f, "not found: the tree is empty"),
+            ExplainEnd::Filtered => write!(f, "not found: ruled out by the Bloom filter"),
+            ExplainEnd::PrefixMismatch {
+                offset,
+                expected,
+                found: Some(found),
+            } => write!(
+                f,
+                "not found: prefix mismatch at offset {}, expected {:#04x}, found {:#04x}",
+                offset, expected, found
+            ),
+            ExplainEnd::PrefixMismatch {
+                offset,
+                expected,
+                found: None,
+            } => write!(
+                f,
+                "not found: the key ends at offset {} inside the prefix, expected {:#04x}",
+                offset, expected
+            ),
+            ExplainEnd::KeyExhausted => write!(f, "not found: the key ends at an internal node"),
+            ExplainEnd::NoChild(c) => write!(f, "not found: no child for {:#04x}", c),
+            ExplainEnd::LeafMismatch { leaf_key, offset } => write!(
+                f,
+                "not found: reached the leaf of {:?}, which differs at offset {}",
+                leaf_key, offset
+            ),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use std::alloc::{self, Layout};
+
+use super::{
+    ArtNodeInternal, ArtNodeInternalInner, ArtNodeLeaf, ArtTree, InsertError, InternalNodeHeader,
+    LeafKey, NewEntry, Node, NodeKind, NodeSizing, Spare, MAX_PREFIX_LEN,
+};
+
+/// Moves `value` to the heap, handing it back if the allocator fails
+fn try_box<T>(value: T) -> Result<Box<T>, T> {
+    let layout = Layout::new::<T>();
+    if layout.size() == 0 {
+        return Ok(Box::new(value));
+    }
+    // SAFETY: the layout has a non-zero size
+    let ptr = unsafe { alloc::alloc(layout) } as *mut T;
+    if ptr.is_null() {
+        return Err(value);
+    }
+    // SAFETY: the memory was allocated by the global allocator with the layout of `T`, as `Box`
+    // expects, and is initialized before the box takes it over
+    unsafe {
+        ptr.write(value);
+        Ok(Box::from_raw(ptr))
+    }
+}
+
+impl<V> ArtTree<V> {
+    /// Inserts the given value at the given key like [`insert`](Self::insert), but fails with
+    /// [`InsertError::AllocFailed`] instead of aborting if the allocator runs out of memory.
+    ///
+    /// Everything the insert may need, the leaf, its key, a node to split off and the arrays of
+    /// a full node to grow, is allocated before the tree is touched, so a failed insert leaves
+    /// the tree as it was. What the insert does not use is freed again. Allocations made by an enabled Bloom
+    /// filter, dirty key tracking, observers or the checks of the `validate` feature are not
+    /// covered.
+    pub fn try_insert_alloc(&mut self, key: &[u8], value: V) -> Result<Option<V>, InsertError<V>> {
+        if key.len() > self.max_key_len() {
+            return Err(InsertError::KeyTooLong(value));
+        }
+        if self.contains_key(key) {
+            // Replacing a value allocates nothing
+            return Ok(self.insert(key, value));
+        }
+
+        let mut bytes = Vec::new();
+        if bytes.try_reserve_exact(key.len()).is_err() {
+            return Err(InsertError::AllocFailed(value));
+        }
+        bytes.extend_from_slice(key);
+        let leaf = try_box(ArtNodeLeaf::new(bytes.into_boxed_slice(), value))
+            .map_err(|leaf| InsertError::AllocFailed(leaf.value))?;
+        let mut spare = Spare::default();
+        if !self.is_empty() {
+            let children = match try_box([Node::INIT; 4]) {
+                Ok(children) => children,
+                Err(_) => return Err(InsertError::AllocFailed(leaf.value)),
+            };
+            let node = ArtNodeInternal::new(
+                InternalNodeHeader {
+                    partial_len: 0,
+                    num_children: 0,
+                    partial: [0; MAX_PREFIX_LEN],
+                },
+                ArtNodeInternalInner::Node4 {
+                    keys: [0; 4],
+                    children,
+                },
+            );
+            match try_box(node) {
+                Ok(node) => spare.node4 = Some(node),
+                Err(_) => return Err(InsertError::AllocFailed(leaf.value)),
+            }
+        }
+        if let Some(kind) = growing_node(&self.root, key, &self.sizing) {
+            match try_grown(kind) {
+                Some(inner) => spare.grown = Some(inner),
+                None => return Err(InsertError::AllocFailed(leaf.value)),
+            }
+        }
+        Ok(self.insert_with_spare(key, |_| unreachable!(), NewEntry::Keyed(leaf), &mut spare))
+    }
+}
+
+/// Returns the type of the node the insert of the absent `key` adds its leaf to, if that node may
+/// have to grow for it. Nodes growing at the points of a hot node are counted as well, as the
+/// insert may heat them up.
+fn growing_node<V, K: LeafKey>(
+    root: &Node<V, K>,
+    key: &[u8],
+    sizing: &NodeSizing,
+) -> Option<NodeKind> {
+    let mut node = root;
+    let mut depth = 0;
+    while let Node::Internal(internal) = node {
+        let n = internal.header;
+        if n.partial_len != 0 && internal.prefix_mismatch(key, depth) < n.partial_len {
+            // The insert splits the prefix into a new Node4
+            return None;
+        }
+        depth += n.partial_len;
+        match internal.find_child(key[depth]) {
+            Some(child) => node = child,
+            None => {
+                let kind = internal.node_kind();
+                return Some(kind).filter(|&kind| n.num_children >= sizing.grow_point(kind));
+            }
+        }
+        depth += 1;
+    }
+    None
+}
+
+/// Allocates the empty arrays of the node type a full node of the given type grows into
+fn try_grown<V, K>(kind: NodeKind) -> Option<ArtNodeInternalInner<V, K>> {
+    Some(match kind {
+        NodeKind::Node4 => ArtNodeInternalInner::Node16 {
+            keys: [0; 16],
+            children: try_box([Node::INIT; 16]).ok()?,
+        },
+        NodeKind::Node16 => ArtNodeInternalInner::Node32 {
+            keys: [0; 32],
+            children: try_box([Node::INIT; 32]).ok()?,
+        },
+        NodeKind::Node32 => ArtNodeInternalInner::Node48 {
+            keys: try_box([0; 256]).ok()?,
+            children: try_box([Node::INIT; 48]).ok()?,
+        },
+        NodeKind::Node48 => ArtNodeInternalInner::Node256 {
+            children: try_box([Node::INIT; 256]).ok()?,
+        },
+        NodeKind::Node256 => unreachable!("a Node256 does not grow"),
+    })
+}
@@ -0,0 +1,110 @@
+use std::fmt;
+use std::ops::RangeBounds;
+use std::sync::Arc;
+
+use super::{ArtTree, Iter, LeafKey, Prefix, PrefixKeys, Range};
+
+/// An immutable, cheaply cloneable handle to a finished `ArtTree`.
+///
+/// Created by [`ArtTree::freeze`]. Clones share the same tree, so it can be handed out to many
+/// threads without any locking.
+pub struct FrozenArtTree<V, K = Box<[u8]>> {
+    pub(super) tree: Arc<ArtTree<V, K>>,
+}
+
+impl<V, K> Clone for FrozenArtTree<V, K> {
+    fn clone(&self) -> Self {
+        Self {
+            tree: self.tree.clone(),
+        }
+    }
+}
+
+impl<V: fmt::Debug, K: fmt::Debug> fmt::Debug for FrozenArtTree<V, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FrozenArtTree").field(&self.tree).finish()
+    }
+}
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Turns the tree into a read-only handle that can be shared across threads.
+    pub fn freeze(self) -> FrozenArtTree<V, K> {
+        FrozenArtTree {
+            tree: Arc::new(self),
+        }
+    }
+}
+
+impl<V, K: LeafKey> FrozenArtTree<V, K> {
+    /// Returns the number of keys in the tree
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns true if the tree contains no keys
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Returns true if a value is stored at the given key
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.tree.contains_key(key)
+    }
+
+    /// Returns a reference to the value stored at the given key if it exists
+    pub fn get(&self, key: &[u8]) -> Option<&V> {
+        self.tree.get(key)
+    }
+
+    pub fn minimum(&self) -> Option<(&K, &V)> {
+        self.tree.minimum()
+    }
+
+    pub fn maximum(&self) -> Option<(&K, &V)> {
+        self.tree.maximum()
+    }
+
+    /// Returns the entry with the greatest key less than or equal to the given key.
+    pub fn floor(&self, key: &[u8]) -> Option<(&[u8], &V)> {
+        self.tree.floor(key)
+    }
+
+    /// Returns an iterator over the key-value pairs of the tree in ascending key order.
+    pub fn entries(&self) -> Iter<'_, V, K> {
+        self.tree.entries()
+    }
+
+    /// Returns an iterator over the key-value pairs whose keys fall in the given range, in
+    /// ascending key order.
+    pub fn range<'r, R>(&self, range: R) -> Range<'_, V, K>
+    where
+        R: RangeBounds<&'r [u8]>,
+    {
+        self.tree.range(range)
+    }
+
+    /// Returns an iterator over the key-value pairs whose keys start with `prefix`, in
+    /// ascending key order.
+    pub fn scan_prefix(&self, prefix: &[u8]) -> Prefix<'_, V, K> {
+        self.tree.scan_prefix(prefix)
+    }
+
+    /// Returns an iterator over the keys starting with `prefix`, in ascending order.
+    pub fn keys_with_prefix(&self, prefix: &[u8]) -> PrefixKeys<'_, V, K> {
+        self.tree.keys_with_prefix(prefix)
+    }
+
+    /// Returns the tree back if this is the only handle to it.
+    pub fn try_unfreeze(self) -> Result<ArtTree<V, K>, Self> {
+        Arc::try_unwrap(self.tree).map_err(|tree| Self { tree })
+    }
+}
+
+impl<'a, V, K: LeafKey> IntoIterator for &'a FrozenArtTree<V, K> {
+    type Item = (&'a [u8], &'a V);
+    type IntoIter = Iter<'a, V, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries()
+    }
+}
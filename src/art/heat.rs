@@ -0,0 +1,82 @@
+#[cfg(feature = "adaptive")]
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use super::{NodeKind, NodeSizing};
+
+/// Number of point lookups and mutations that recently passed through an internal node. It
+/// compiles to nothing without the `adaptive` feature.
+///
+/// A node is hot once the count reaches [`NodeSizing::hot_accesses`]. Hot nodes grow at the
+/// points of the sizing and postpone shrinking until the fewest children the sizing would
+/// allow, halving the count every time. Cold nodes shrink at the points of the sizing but only
+/// grow once they are full. The count is capped at four times the hot threshold, so a hot node
+/// that stops being accessed turns cold within three postponed shrinks.
+#[cfg(feature = "adaptive")]
+#[derive(Debug, Default)]
+pub(super) struct Heat(AtomicU32);
+
+#[cfg(feature = "adaptive")]
+impl Heat {
+    /// Counts an access. Concurrent lookups may lose counts, which only delays heating up.
+    #[inline(always)]
+    pub(super) fn touch(&self, sizing: &NodeSizing) {
+        let count = self.0.load(Ordering::Relaxed);
+        if count < sizing.hot_accesses.saturating_mul(4) {
+            self.0.store(count + 1, Ordering::Relaxed);
+        }
+    }
+
+    fn is_hot(&self, sizing: &NodeSizing) -> bool {
+        self.0.load(Ordering::Relaxed) >= sizing.hot_accesses
+    }
+
+    /// Returns the number of children at which a node of the given type grows
+    pub(super) fn grow_point(&self, kind: NodeKind, sizing: &NodeSizing) -> u16 {
+        let grow_point = sizing.grow_point(kind);
+        if self.is_hot(sizing) {
+            grow_point
+        } else {
+            grow_point.max(kind.capacity() as u16)
+        }
+    }
+
+    /// Returns true if a node of the given type left with `n` children shrinks
+    pub(super) fn should_shrink(&self, kind: NodeKind, n: u16, sizing: &NodeSizing) -> bool {
+        if n > sizing.shrink_point(kind) {
+            return false;
+        }
+        if self.is_hot(sizing) && n > sizing.shrink_floor(kind) {
+            let count = self.0.load(Ordering::Relaxed);
+            self.0.store(count / 2, Ordering::Relaxed);
+            return false;
+        }
+        true
+    }
+}
+
+#[cfg(feature = "adaptive")]
+impl Clone for Heat {
+    fn clone(&self) -> Self {
+        Self(AtomicU32::new(self.0.load(Ordering::Relaxed)))
+    }
+}
+
+#[cfg(not(feature = "adaptive"))]
+#[derive(Debug, Default, Clone)]
+pub(super) struct Heat {}
+
+#[cfg(not(feature = "adaptive"))]
+impl Heat {
+    #[inline(always)]
+    pub(super) fn touch(&self, _sizing: &NodeSizing) {}
+
+    #[inline(always)]
+    pub(super) fn grow_point(&self, kind: NodeKind, sizing: &NodeSizing) -> u16 {
+        sizing.grow_point(kind)
+    }
+
+    #[inline(always)]
+    pub(super) fn should_shrink(&self, kind: NodeKind, n: u16, sizing: &NodeSizing) -> bool {
+        n <= sizing.shrink_point(kind)
+    }
+}
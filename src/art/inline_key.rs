@@ -0,0 +1,134 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+
+use super::{ArtTree, LeafKey};
+
+/// Number of key bytes an [`InlineKey`] stores without allocating
+pub const INLINE_KEY_CAPACITY: usize = 22;
+
+/// Leaf key storage keeping keys of up to [`INLINE_KEY_CAPACITY`] bytes inline, and longer keys
+/// in a `Box<[u8]>`.
+///
+/// An `InlineKey` takes 24 bytes, 8 more than a `Box<[u8]>`, but saves the separate allocation
+/// (and its allocator overhead) for short keys, and their bytes are read from the leaf itself.
+#[derive(Clone)]
+pub struct InlineKey(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    Inline {
+        len: u8,
+        bytes: [u8; INLINE_KEY_CAPACITY],
+    },
+    Heap(Box<[u8]>),
+}
+
+/// An `ArtTree` storing short keys inline in the leaves, see [`InlineKey`]. Create one with
+/// `ArtTreeInline::default()`.
+pub type ArtTreeInline<V> = ArtTree<V, InlineKey>;
+
+impl InlineKey {
+    /// Returns true if the key is stored without a separate allocation
+    pub fn is_inline(&self) -> bool {
+        matches!(self.0, Repr::Inline { .. })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match &self.0 {
+            Repr::Inline { len, bytes } => &bytes[..*len as usize],
+            Repr::Heap(bytes) => bytes,
+        }
+    }
+}
+
+impl LeafKey for InlineKey {
+    fn from_slice(key: &[u8]) -> Self {
+        if key.len() <= INLINE_KEY_CAPACITY {
+            let mut bytes = [0; INLINE_KEY_CAPACITY];
+            bytes[..key.len()].copy_from_slice(key);
+            InlineKey(Repr::Inline {
+                len: key.len() as u8,
+                bytes,
+            })
+        } else {
+            InlineKey(Repr::Heap(key.into()))
+        }
+    }
+
+    fn heap_size(&self) -> usize {
+        match &self.0 {
+            Repr::Inline { .. } => 0,
+            Repr::Heap(bytes) => bytes.len(),
+        }
+    }
+}
+
+impl From<&[u8]> for InlineKey {
+    fn from(key: &[u8]) -> Self {
+        Self::from_slice(key)
+    }
+}
+
+impl From<InlineKey> for Vec<u8> {
+    fn from(key: InlineKey) -> Self {
+        match key.0 {
+            Repr::Inline { len, bytes } => bytes[..len as usize].to_vec(),
+            Repr::Heap(bytes) => bytes.into(),
+        }
+    }
+}
+
+impl Deref for InlineKey {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl AsRef<[u8]> for InlineKey {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl Borrow<[u8]> for InlineKey {
+    fn borrow(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl fmt::Debug for InlineKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_bytes().fmt(f)
+    }
+}
+
+impl PartialEq for InlineKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl Eq for InlineKey {}
+
+impl PartialOrd for InlineKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for InlineKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+impl Hash for InlineKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state)
+    }
+}
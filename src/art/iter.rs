@@ -0,0 +1,831 @@
+use std::cmp::Ordering;
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+use std::slice;
+use std::vec;
+
+use super::delta::DirtyKeys;
+use super::{ArtNodeInternal, ArtNodeInternalInner, ArtNodeLeaf, ArtTree, LeafKey, Node};
+
+/// Cursor over the children of a single internal node, in ascending key byte order.
+pub(super) enum Children<'a, V, K> {
+    /// Node4/Node16/Node32 (only the used prefix of the array) and Node256 (empty slots are
+    /// skipped).
+    Sorted(slice::Iter<'a, Node<V, K>>),
+    /// Node48, whose children array is indexed through the 256 key slots.
+    Indexed {
+        keys: slice::Iter<'a, u8>,
+        children: &'a [Node<V, K>; 48],
+    },
+}
+
+impl<'a, V, K> Iterator for Children<'a, V, K> {
+    type Item = &'a Node<V, K>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Children::Sorted(children) => children.find(|child| !child.is_empty()),
+            Children::Indexed { keys, children } => keys
+                .find(|&&idx| idx != 0)
+                .map(|&idx| &children[idx as usize - 1]),
+        }
+    }
+}
+
+impl<V, K> ArtNodeInternal<V, K> {
+    /// Returns a cursor over the children whose key byte is at least `c`.
+    pub(super) fn children_from(&self, c: u8) -> Children<'_, V, K> {
+        let n = self.header.num_children as usize;
+        match &self.inner {
+            ArtNodeInternalInner::Node4 { keys, children } => {
+                let start = keys[..n].iter().position(|&key| key >= c).unwrap_or(n);
+                Children::Sorted(children[start..n].iter())
+            }
+            ArtNodeInternalInner::Node16 { keys, children } => {
+                let start = keys[..n].iter().position(|&key| key >= c).unwrap_or(n);
+                Children::Sorted(children[start..n].iter())
+            }
+            ArtNodeInternalInner::Node32 { keys, children } => {
+                let start = keys[..n].iter().position(|&key| key >= c).unwrap_or(n);
+                Children::Sorted(children[start..n].iter())
+            }
+            ArtNodeInternalInner::Node48 { keys, children } => Children::Indexed {
+                keys: keys[c as usize..].iter(),
+                children,
+            },
+            ArtNodeInternalInner::Node256 { children } => {
+                Children::Sorted(children[c as usize..].iter())
+            }
+        }
+    }
+}
+
+/// Depth-first walk over the leaves of a (sub)tree in ascending key order.
+pub(super) struct RawIter<'a, V, K> {
+    stack: Vec<Children<'a, V, K>>,
+}
+
+impl<'a, V, K> RawIter<'a, V, K> {
+    pub(super) fn new(root: &'a Node<V, K>) -> Self {
+        Self {
+            stack: vec![Children::Sorted(slice::from_ref(root).iter())],
+        }
+    }
+
+    fn empty() -> Self {
+        Self { stack: Vec::new() }
+    }
+}
+
+impl<'a, V, K: LeafKey> RawIter<'a, V, K> {
+    /// Creates an iterator over the leaves whose key is greater than or equal to `start`.
+    ///
+    /// Only the path towards `start` is descended: subtrees whose compressed path compares
+    /// greater are taken as a whole, and those that compare less are skipped.
+    pub(super) fn seek(root: &'a Node<V, K>, start: &[u8]) -> Self {
+        let mut stack = Vec::new();
+        let mut node = root;
+        let mut depth = 0;
+        loop {
+            match node {
+                Node::Empty => break,
+                Node::Leaf(leaf) => {
+                    if leaf.key() >= start {
+                        stack.push(Children::Sorted(slice::from_ref(node).iter()));
+                    }
+                    break;
+                }
+                Node::Internal(internal) => {
+                    match internal.compare_path(start, depth) {
+                        Ordering::Less => break,
+                        Ordering::Greater => {
+                            stack.push(Children::Sorted(slice::from_ref(node).iter()));
+                            break;
+                        }
+                        Ordering::Equal => {}
+                    }
+
+                    depth += internal.header.partial_len;
+                    let c = match start.get(depth) {
+                        Some(&c) => c,
+                        None => {
+                            // The start key ends within the path, everything below is greater
+                            stack.push(Children::Sorted(slice::from_ref(node).iter()));
+                            break;
+                        }
+                    };
+
+                    let mut children = internal.children_from(c);
+                    match internal.find_child(c) {
+                        Some(child) => {
+                            // Step over the child we are about to descend into
+                            children.next();
+                            stack.push(children);
+                            node = child;
+                            depth += 1;
+                        }
+                        None => {
+                            stack.push(children);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        Self { stack }
+    }
+}
+
+impl<'a, V, K> Iterator for RawIter<'a, V, K> {
+    type Item = &'a ArtNodeLeaf<V, K>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.last_mut()?.next() {
+                Some(Node::Leaf(leaf)) => return Some(leaf),
+                Some(Node::Internal(internal)) => self.stack.push(internal.children_from(0)),
+                Some(Node::Empty) => unreachable!(),
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+/// Mutable cursor over the children of a single internal node, in ascending key byte order.
+enum ChildrenMut<'a, V, K> {
+    /// Node4/Node16/Node32 (only the used prefix of the array) and Node256 (empty slots are
+    /// skipped).
+    Sorted(slice::IterMut<'a, Node<V, K>>),
+    /// Node48, whose children are collected in key order up front.
+    Collected(vec::IntoIter<&'a mut Node<V, K>>),
+}
+
+impl<'a, V, K> Iterator for ChildrenMut<'a, V, K> {
+    type Item = &'a mut Node<V, K>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            ChildrenMut::Sorted(children) => children.find(|child| !child.is_empty()),
+            ChildrenMut::Collected(children) => children.next(),
+        }
+    }
+}
+
+impl<V, K> ArtNodeInternal<V, K> {
+    /// Returns a mutable cursor over all children.
+    fn children_mut(&mut self) -> ChildrenMut<'_, V, K> {
+        self.invalidate_caches();
+        let n = self.header.num_children as usize;
+        match &mut self.inner {
+            ArtNodeInternalInner::Node4 { children, .. } => {
+                ChildrenMut::Sorted(children[..n].iter_mut())
+            }
+            ArtNodeInternalInner::Node16 { children, .. } => {
+                ChildrenMut::Sorted(children[..n].iter_mut())
+            }
+            ArtNodeInternalInner::Node32 { children, .. } => {
+                ChildrenMut::Sorted(children[..n].iter_mut())
+            }
+            ArtNodeInternalInner::Node48 { keys, children } => {
+                let mut slots = [0u8; 48];
+                for (c, &idx) in keys.iter().enumerate() {
+                    if idx != 0 {
+                        slots[idx as usize - 1] = c as u8;
+                    }
+                }
+                let mut sorted: Vec<_> = children
+                    .iter_mut()
+                    .zip(slots.iter())
+                    .filter(|(child, _)| !child.is_empty())
+                    .collect();
+                sorted.sort_unstable_by_key(|&(_, &c)| c);
+                let sorted: Vec<_> = sorted.into_iter().map(|(child, _)| child).collect();
+                ChildrenMut::Collected(sorted.into_iter())
+            }
+            ArtNodeInternalInner::Node256 { children } => ChildrenMut::Sorted(children.iter_mut()),
+        }
+    }
+
+    /// Splits the children whose key byte is at least `c` into the child at `c`, if any, and a
+    /// mutable cursor over the ones after it.
+    fn split_children_mut(&mut self, c: u8) -> (Option<&mut Node<V, K>>, ChildrenMut<'_, V, K>) {
+        self.invalidate_caches();
+        let n = self.header.num_children as usize;
+        match &mut self.inner {
+            ArtNodeInternalInner::Node4 { keys, children } => {
+                split_sorted_mut(&keys[..n], &mut children[..n], c)
+            }
+            ArtNodeInternalInner::Node16 { keys, children } => {
+                split_sorted_mut(&keys[..n], &mut children[..n], c)
+            }
+            ArtNodeInternalInner::Node32 { keys, children } => {
+                split_sorted_mut(&keys[..n], &mut children[..n], c)
+            }
+            ArtNodeInternalInner::Node48 { keys, children } => {
+                let mut slots = [0u8; 48];
+                for (key, &idx) in keys.iter().enumerate() {
+                    if idx != 0 {
+                        slots[idx as usize - 1] = key as u8;
+                    }
+                }
+                let mut sorted: Vec<_> = children
+                    .iter_mut()
+                    .zip(slots.iter())
+                    .filter(|(child, &key)| !child.is_empty() && key >= c)
+                    .collect();
+                sorted.sort_unstable_by_key(|&(_, &key)| key);
+                let mut sorted = sorted.into_iter().peekable();
+                let child = match sorted.peek() {
+                    Some((_, &key)) if key == c => sorted.next().map(|(child, _)| child),
+                    _ => None,
+                };
+                let rest: Vec<_> = sorted.map(|(child, _)| child).collect();
+                (child, ChildrenMut::Collected(rest.into_iter()))
+            }
+            ArtNodeInternalInner::Node256 { children } => {
+                let (child, rest) = children[c as usize..].split_first_mut().unwrap();
+                let child = if child.is_empty() { None } else { Some(child) };
+                (child, ChildrenMut::Sorted(rest.iter_mut()))
+            }
+        }
+    }
+}
+
+fn split_sorted_mut<'a, V, K>(
+    keys: &[u8],
+    children: &'a mut [Node<V, K>],
+    c: u8,
+) -> (Option<&'a mut Node<V, K>>, ChildrenMut<'a, V, K>) {
+    let start = keys.iter().position(|&key| key >= c).unwrap_or(keys.len());
+    let children = &mut children[start..];
+    if keys.get(start) == Some(&c) {
+        let (child, rest) = children.split_first_mut().unwrap();
+        (Some(child), ChildrenMut::Sorted(rest.iter_mut()))
+    } else {
+        (None, ChildrenMut::Sorted(children.iter_mut()))
+    }
+}
+
+/// Mutable depth-first walk over the leaves of a (sub)tree in ascending key order.
+pub(super) struct RawIterMut<'a, V, K> {
+    stack: Vec<ChildrenMut<'a, V, K>>,
+}
+
+impl<'a, V, K> RawIterMut<'a, V, K> {
+    pub(super) fn new(root: &'a mut Node<V, K>) -> Self {
+        Self {
+            stack: vec![ChildrenMut::Sorted(slice::from_mut(root).iter_mut())],
+        }
+    }
+}
+
+impl<'a, V, K: LeafKey> RawIterMut<'a, V, K> {
+    /// Creates a mutable iterator over the leaves whose key is greater than or equal to `start`,
+    /// descending only the path towards `start` like [`RawIter::seek`].
+    pub(super) fn seek(root: &'a mut Node<V, K>, start: &[u8]) -> Self {
+        enum Step {
+            Stop,
+            Whole,
+            Descend(u8),
+        }
+
+        let mut stack = Vec::new();
+        let mut node = root;
+        let mut depth = 0;
+        loop {
+            let step = match &*node {
+                Node::Empty => Step::Stop,
+                Node::Leaf(leaf) if leaf.key() >= start => Step::Whole,
+                Node::Leaf(_) => Step::Stop,
+                Node::Internal(internal) => match internal.compare_path(start, depth) {
+                    Ordering::Less => Step::Stop,
+                    Ordering::Greater => Step::Whole,
+                    Ordering::Equal => {
+                        depth += internal.header.partial_len;
+                        match start.get(depth) {
+                            Some(&c) => Step::Descend(c),
+                            // The start key ends within the path, everything below is greater
+                            None => Step::Whole,
+                        }
+                    }
+                },
+            };
+
+            match step {
+                Step::Stop => break,
+                Step::Whole => {
+                    stack.push(ChildrenMut::Sorted(slice::from_mut(node).iter_mut()));
+                    break;
+                }
+                Step::Descend(c) => {
+                    let internal = match node {
+                        Node::Internal(internal) => internal,
+                        _ => unreachable!(),
+                    };
+                    let (child, rest) = internal.split_children_mut(c);
+                    stack.push(rest);
+                    match child {
+                        Some(child) => {
+                            node = child;
+                            depth += 1;
+                        }
+                        None => break,
+                    }
+                }
+            }
+        }
+        Self { stack }
+    }
+}
+
+impl<'a, V, K> Iterator for RawIterMut<'a, V, K> {
+    type Item = &'a mut ArtNodeLeaf<V, K>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.last_mut()?.next() {
+                Some(Node::Leaf(leaf)) => return Some(leaf),
+                Some(Node::Internal(internal)) => self.stack.push(internal.children_mut()),
+                Some(Node::Empty) => unreachable!(),
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+impl<V, K> ArtNodeInternal<V, K> {
+    /// Moves the children out of the node, in ascending key byte order.
+    fn into_children(self) -> Vec<Node<V, K>> {
+        let n = self.header.num_children as usize;
+        match self.inner {
+            ArtNodeInternalInner::Node4 { children, .. } => {
+                IntoIterator::into_iter(*children).take(n).collect()
+            }
+            ArtNodeInternalInner::Node16 { children, .. } => {
+                IntoIterator::into_iter(*children).take(n).collect()
+            }
+            ArtNodeInternalInner::Node32 { children, .. } => {
+                IntoIterator::into_iter(*children).take(n).collect()
+            }
+            ArtNodeInternalInner::Node48 { keys, mut children } => keys
+                .iter()
+                .filter(|&&idx| idx != 0)
+                .map(|&idx| mem::take(&mut children[idx as usize - 1]))
+                .collect(),
+            ArtNodeInternalInner::Node256 { children } => IntoIterator::into_iter(*children)
+                .filter(|child| !child.is_empty())
+                .collect(),
+        }
+    }
+}
+
+/// An owning iterator over the entries of an `ArtTree` in ascending key order.
+///
+/// Created by the `IntoIterator` implementation of `ArtTree`.
+pub struct IntoIter<V, K = Box<[u8]>> {
+    stack: Vec<vec::IntoIter<Node<V, K>>>,
+    remaining: usize,
+}
+
+impl<V, K> Iterator for IntoIter<V, K> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.last_mut()?.next() {
+                Some(Node::Leaf(leaf)) => {
+                    self.remaining -= 1;
+                    let ArtNodeLeaf { key, value } = *leaf;
+                    return Some((key, value));
+                }
+                Some(Node::Internal(internal)) => {
+                    self.stack.push(internal.into_children().into_iter())
+                }
+                Some(Node::Empty) => unreachable!(),
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<V, K> ExactSizeIterator for IntoIter<V, K> {}
+
+impl<V, K> IntoIterator for ArtTree<V, K> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<V, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter::new(self.root, self.size)
+    }
+}
+
+impl<V, K> IntoIter<V, K> {
+    /// Creates an iterator over the entries of the given (sub)trees, which must be in ascending
+    /// key order and hold `size` entries in total.
+    pub(super) fn from_nodes(nodes: Vec<Node<V, K>>, size: usize) -> Self {
+        IntoIter {
+            stack: vec![nodes.into_iter()],
+            remaining: size,
+        }
+    }
+
+    fn new(root: Node<V, K>, size: u64) -> Self {
+        let stack = if root.is_empty() {
+            Vec::new()
+        } else {
+            vec![vec![root].into_iter()]
+        };
+        IntoIter {
+            stack,
+            remaining: size as usize,
+        }
+    }
+}
+
+/// An iterator over the entries of an `ArtTree` in ascending key order.
+///
+/// Created by [`ArtTree::entries`].
+pub struct Iter<'a, V, K = Box<[u8]>> {
+    raw: RawIter<'a, V, K>,
+    remaining: usize,
+}
+
+impl<'a, V, K: LeafKey> Iterator for Iter<'a, V, K> {
+    type Item = (&'a [u8], &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let leaf = self.raw.next()?;
+        self.remaining -= 1;
+        Some((leaf.key(), &leaf.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, V, K: LeafKey> ExactSizeIterator for Iter<'a, V, K> {}
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Returns an iterator over the key-value pairs of the tree in ascending key order.
+    pub fn entries(&self) -> Iter<'_, V, K> {
+        Iter {
+            raw: RawIter::new(&self.root),
+            remaining: self.size as usize,
+        }
+    }
+
+    /// Returns an iterator over the key-value pairs of the tree in ascending key order, with
+    /// mutable references to the values.
+    pub fn entries_mut(&mut self) -> IterMut<'_, V, K> {
+        IterMut {
+            raw: RawIterMut::new(&mut self.root),
+            remaining: self.size as usize,
+            dirty: &mut self.dirty,
+        }
+    }
+
+    /// Consumes the tree and returns its entries in ascending key order.
+    pub fn into_sorted_vec(self) -> Vec<(K, V)> {
+        self.into_iter().collect()
+    }
+
+    /// Returns clones of the entries of the tree in ascending key order.
+    pub fn to_vec(&self) -> Vec<(K, V)>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        let mut entries = Vec::with_capacity(self.len());
+        entries.extend(RawIter::new(&self.root).map(|leaf| (leaf.key.clone(), leaf.value.clone())));
+        entries
+    }
+
+    /// Removes all entries from the tree and returns them in ascending key order.
+    ///
+    /// Observers are notified of the deletion of every entry up front.
+    pub fn drain(&mut self) -> IntoIter<V, K> {
+        for leaf in RawIter::new(&self.root) {
+            self.observers.deleted(leaf.key(), &leaf.value);
+            self.dirty.mark(leaf.key());
+        }
+        let root = mem::take(&mut self.root);
+        let size = mem::replace(&mut self.size, 0);
+        self.bloom.clear();
+        IntoIter::new(root, size)
+    }
+
+    /// Returns an iterator over the key-value pairs whose keys fall in the given range, in
+    /// ascending key order.
+    pub fn range<'r, R>(&self, range: R) -> Range<'_, V, K>
+    where
+        R: RangeBounds<&'r [u8]>,
+    {
+        Range::new(&self.root, range)
+    }
+
+    /// Returns an iterator over the entries whose keys fall in the given range, in ascending key
+    /// order, with mutable references to the values.
+    pub fn range_mut<'r, R>(&mut self, range: R) -> RangeMut<'_, V, K>
+    where
+        R: RangeBounds<&'r [u8]>,
+    {
+        let start = range.start_bound().map(|key| key.to_vec());
+        let end = range.end_bound().map(|key| key.to_vec());
+        let raw = match &start {
+            Bound::Included(key) | Bound::Excluded(key) => RawIterMut::seek(&mut self.root, key),
+            Bound::Unbounded => RawIterMut::new(&mut self.root),
+        };
+        RangeMut {
+            raw,
+            start,
+            end,
+            dirty: &mut self.dirty,
+        }
+    }
+
+    /// Returns an iterator over the key-value pairs whose keys start with `prefix`, in
+    /// ascending key order. Only the subtree covering the prefix is visited.
+    pub fn scan_prefix(&self, prefix: &[u8]) -> Prefix<'_, V, K> {
+        let raw = match self.root.prefix_root(prefix, 0) {
+            Some((node, _)) => RawIter::new(node),
+            None => RawIter::empty(),
+        };
+        Prefix { raw }
+    }
+
+    /// Calls `f` with every entry whose key starts with `prefix`, in ascending key order. Only
+    /// the subtree covering the prefix is visited.
+    pub fn apply_to_prefix<F>(&mut self, prefix: &[u8], mut f: F)
+    where
+        F: FnMut(&[u8], &mut V),
+    {
+        for (key, value) in self.iter_prefix_mut(prefix) {
+            f(key, value);
+        }
+    }
+
+    /// Returns an iterator over the entries whose keys start with `prefix`, in ascending key
+    /// order, with mutable references to the values. Only the subtree covering the prefix is
+    /// visited.
+    pub fn iter_prefix_mut(&mut self, prefix: &[u8]) -> PrefixMut<'_, V, K> {
+        let raw = match self.root.prefix_root_mut(prefix, 0) {
+            Some(node) => RawIterMut::new(node),
+            None => RawIterMut { stack: Vec::new() },
+        };
+        PrefixMut {
+            raw,
+            dirty: &mut self.dirty,
+        }
+    }
+
+    /// Returns an iterator over the groups of entries whose keys share their first `prefix_len`
+    /// bytes, in ascending key order. Each group is yielded as its shared prefix (shorter if the
+    /// key itself is shorter) and an iterator over its entries.
+    ///
+    /// Finding the next group takes a single descent, so groups can be skipped cheaply.
+    pub fn groups_by_prefix(&self, prefix_len: usize) -> Groups<'_, V, K> {
+        Groups {
+            tree: self,
+            prefix_len,
+            next: Some(Vec::new()),
+        }
+    }
+
+    /// Returns an iterator over the keys starting with `prefix`, in ascending order.
+    pub fn keys_with_prefix(&self, prefix: &[u8]) -> PrefixKeys<'_, V, K> {
+        PrefixKeys {
+            inner: self.scan_prefix(prefix),
+        }
+    }
+}
+
+/// An iterator over the entries of an `ArtTree` whose keys share a prefix.
+///
+/// Created by [`ArtTree::scan_prefix`].
+pub struct Prefix<'a, V, K = Box<[u8]>> {
+    raw: RawIter<'a, V, K>,
+}
+
+impl<'a, V, K: LeafKey> Iterator for Prefix<'a, V, K> {
+    type Item = (&'a [u8], &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let leaf = self.raw.next()?;
+        Some((leaf.key(), &leaf.value))
+    }
+}
+
+/// A mutable iterator over the entries of an `ArtTree` whose keys share a prefix.
+///
+/// Created by [`ArtTree::iter_prefix_mut`].
+pub struct PrefixMut<'a, V, K = Box<[u8]>> {
+    raw: RawIterMut<'a, V, K>,
+    dirty: &'a mut DirtyKeys,
+}
+
+impl<'a, V, K: LeafKey> Iterator for PrefixMut<'a, V, K> {
+    type Item = (&'a [u8], &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let ArtNodeLeaf { key, value } = self.raw.next()?;
+        let key: &'a K = key;
+        self.dirty.mark(key.as_ref());
+        Some((key.as_ref(), value))
+    }
+}
+
+/// An iterator over the keys of an `ArtTree` that share a prefix.
+///
+/// Created by [`ArtTree::keys_with_prefix`].
+pub struct PrefixKeys<'a, V, K = Box<[u8]>> {
+    inner: Prefix<'a, V, K>,
+}
+
+impl<'a, V, K: LeafKey> Iterator for PrefixKeys<'a, V, K> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, _)| key)
+    }
+}
+
+/// A mutable iterator over the entries of an `ArtTree` in ascending key order.
+///
+/// Created by [`ArtTree::entries_mut`].
+pub struct IterMut<'a, V, K = Box<[u8]>> {
+    raw: RawIterMut<'a, V, K>,
+    remaining: usize,
+    dirty: &'a mut DirtyKeys,
+}
+
+impl<'a, V, K: LeafKey> Iterator for IterMut<'a, V, K> {
+    type Item = (&'a [u8], &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let ArtNodeLeaf { key, value } = self.raw.next()?;
+        let key: &'a K = key;
+        self.remaining -= 1;
+        self.dirty.mark(key.as_ref());
+        Some((key.as_ref(), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, V, K: LeafKey> ExactSizeIterator for IterMut<'a, V, K> {}
+
+impl<'a, V, K: LeafKey> IntoIterator for &'a ArtTree<V, K> {
+    type Item = (&'a [u8], &'a V);
+    type IntoIter = Iter<'a, V, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries()
+    }
+}
+
+impl<'a, V, K: LeafKey> IntoIterator for &'a mut ArtTree<V, K> {
+    type Item = (&'a [u8], &'a mut V);
+    type IntoIter = IterMut<'a, V, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries_mut()
+    }
+}
+
+/// An iterator over a key range of an `ArtTree` in ascending key order.
+///
+/// Created by [`ArtTree::range`].
+pub struct Range<'a, V, K = Box<[u8]>> {
+    raw: RawIter<'a, V, K>,
+    start: Bound<Vec<u8>>,
+    end: Bound<Vec<u8>>,
+}
+
+impl<'a, V, K: LeafKey> Range<'a, V, K> {
+    pub(super) fn new<'r, R>(root: &'a Node<V, K>, range: R) -> Self
+    where
+        R: RangeBounds<&'r [u8]>,
+    {
+        let start = range.start_bound().map(|key| key.to_vec());
+        let end = range.end_bound().map(|key| key.to_vec());
+        let raw = match &start {
+            Bound::Included(key) | Bound::Excluded(key) => RawIter::seek(root, key),
+            Bound::Unbounded => RawIter::new(root),
+        };
+        Self { raw, start, end }
+    }
+}
+
+impl<'a, V, K: LeafKey> Iterator for Range<'a, V, K> {
+    type Item = (&'a [u8], &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut leaf = self.raw.next()?;
+        if let Bound::Excluded(start) = &self.start {
+            if leaf.key() == start.as_slice() {
+                leaf = self.raw.next()?;
+            }
+            self.start = Bound::Unbounded;
+        }
+
+        let in_range = match &self.end {
+            Bound::Included(end) => leaf.key() <= end.as_slice(),
+            Bound::Excluded(end) => leaf.key() < end.as_slice(),
+            Bound::Unbounded => true,
+        };
+        if !in_range {
+            self.raw.stack.clear();
+            return None;
+        }
+        Some((leaf.key(), &leaf.value))
+    }
+}
+
+/// A mutable iterator over a key range of an `ArtTree` in ascending key order.
+///
+/// Created by [`ArtTree::range_mut`].
+pub struct RangeMut<'a, V, K = Box<[u8]>> {
+    raw: RawIterMut<'a, V, K>,
+    start: Bound<Vec<u8>>,
+    end: Bound<Vec<u8>>,
+    dirty: &'a mut DirtyKeys,
+}
+
+impl<'a, V, K: LeafKey> Iterator for RangeMut<'a, V, K> {
+    type Item = (&'a [u8], &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut leaf = self.raw.next()?;
+        if let Bound::Excluded(start) = &self.start {
+            if leaf.key() == start.as_slice() {
+                leaf = self.raw.next()?;
+            }
+            self.start = Bound::Unbounded;
+        }
+
+        let ArtNodeLeaf { key, value } = leaf;
+        let key: &'a K = key;
+        let key = key.as_ref();
+        let in_range = match &self.end {
+            Bound::Included(end) => key <= end.as_slice(),
+            Bound::Excluded(end) => key < end.as_slice(),
+            Bound::Unbounded => true,
+        };
+        if !in_range {
+            self.raw.stack.clear();
+            return None;
+        }
+        self.dirty.mark(key);
+        Some((key, value))
+    }
+}
+
+/// An iterator over the groups of entries of an `ArtTree` that share a fixed-length prefix.
+///
+/// Created by [`ArtTree::groups_by_prefix`].
+pub struct Groups<'a, V, K = Box<[u8]>> {
+    tree: &'a ArtTree<V, K>,
+    prefix_len: usize,
+    /// Lower bound of the next group, `None` once past the greatest possible prefix
+    next: Option<Vec<u8>>,
+}
+
+impl<'a, V, K: LeafKey> Iterator for Groups<'a, V, K> {
+    type Item = (Vec<u8>, Prefix<'a, V, K>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.next.take()?;
+        let leaf = RawIter::seek(&self.tree.root, &start).next()?;
+        let key = leaf.key();
+        let prefix = key[..key.len().min(self.prefix_len)].to_vec();
+
+        self.next = prefix_end(&prefix);
+
+        let entries = self.tree.scan_prefix(&prefix);
+        Some((prefix, entries))
+    }
+}
+
+/// Returns the smallest key greater than every key starting with `prefix`, or `None` if there
+/// is none
+pub(super) fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end = prefix.to_vec();
+    while end.last() == Some(&u8::MAX) {
+        end.pop();
+    }
+    *end.last_mut()? += 1;
+    Some(end)
+}
@@ -0,0 +1,137 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use super::{ArtTree, LeafKey, MutationObserver};
+
+/// Kind of a mutation recorded in a [`Journal`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalOp {
+    Insert,
+    Replace,
+    Delete,
+}
+
+/// A mutation recorded in a [`Journal`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry<V> {
+    /// Sequence number of the mutation, starting from 1
+    pub seq: u64,
+    pub op: JournalOp,
+    pub key: Box<[u8]>,
+    /// The new value of an insert or a replace, `None` for a delete
+    pub value: Option<V>,
+}
+
+impl<V: Clone> JournalEntry<V> {
+    /// Applies the mutation to a tree, e.g. a follower replaying the journal of a leader
+    pub fn apply_to<K: LeafKey>(&self, tree: &mut ArtTree<V, K>) {
+        match &self.value {
+            Some(value) => {
+                tree.insert(&self.key, value.clone());
+            }
+            None => {
+                tree.delete(&self.key);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Log<V> {
+    entries: VecDeque<JournalEntry<V>>,
+    last_seq: u64,
+}
+
+/// In-memory changelog of the mutations of the trees it observes, recording every insert,
+/// replace and delete reported to a [`MutationObserver`] with a sequence number.
+///
+/// Register it with [`ArtTree::add_observer`], then read the mutations after a sequence number
+/// with [`journal_since`](Self::journal_since) and discard the ones every reader has seen with
+/// [`truncate_journal`](Self::truncate_journal). Values changed in place through `get_mut` are
+/// not recorded, and clones of an observed tree record into the same journal.
+#[derive(Debug)]
+pub struct Journal<V> {
+    log: Mutex<Log<V>>,
+}
+
+impl<V: Clone> Journal<V> {
+    pub fn new() -> Self {
+        Self {
+            log: Mutex::new(Log {
+                entries: VecDeque::new(),
+                last_seq: 0,
+            }),
+        }
+    }
+
+    /// Returns the sequence number of the latest recorded mutation, or 0 if none was recorded
+    pub fn last_seq(&self) -> u64 {
+        self.log.lock().unwrap().last_seq
+    }
+
+    /// Returns the number of retained entries
+    pub fn len(&self) -> usize {
+        self.log.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the retained mutations with a sequence number greater than `seq` in the order
+    /// they were made. The entries are copied out, so the tree may change while they are read.
+    pub fn journal_since(&self, seq: u64) -> impl Iterator<Item = JournalEntry<V>> {
+        let log = self.log.lock().unwrap();
+        let start = log.entries.partition_point(|entry| entry.seq <= seq);
+        log.entries
+            .range(start..)
+            .cloned()
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// Discards the entries with a sequence number up to `seq` and returns how many were
+    /// discarded
+    pub fn truncate_journal(&self, seq: u64) -> usize {
+        let mut log = self.log.lock().unwrap();
+        let end = log.entries.partition_point(|entry| entry.seq <= seq);
+        log.entries.drain(..end);
+        end
+    }
+
+    fn record(&self, op: JournalOp, key: &[u8], value: Option<&V>) {
+        let mut log = self.log.lock().unwrap();
+        log.last_seq += 1;
+        let seq = log.last_seq;
+        log.entries.push_back(JournalEntry {
+            seq,
+            op,
+            key: key.into(),
+            value: value.cloned(),
+        });
+    }
+}
+
+impl<V: Clone> Default for Journal<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: Clone> MutationObserver<V> for Journal<V> {
+    fn on_insert(&self, key: &[u8], value: &V) {
+        self.record(JournalOp::Insert, key, Some(value));
+    }
+
+    fn on_replace(&self, key: &[u8], _old_value: &V, new_value: &V) {
+        self.record(JournalOp::Replace, key, Some(new_value));
+    }
+
+    fn on_delete(&self, key: &[u8], _value: &V) {
+        self.record(JournalOp::Delete, key, None);
+    }
+
+    fn on_merge(&self, key: &[u8], new_value: &V) {
+        self.record(JournalOp::Replace, key, Some(new_value));
+    }
+}
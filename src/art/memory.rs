@@ -0,0 +1,132 @@
+use std::cmp::Ordering;
+use std::mem;
+
+use super::iter::RawIter;
+use super::visit::{NodeHeader, NodeKind, Visitor};
+use super::{ArtNodeInternal, ArtNodeLeaf, ArtTree, LeafKey, Node};
+
+/// Estimated heap memory used by the nodes and keys of a tree, not counting memory owned by the
+/// values.
+///
+/// Created by [`ArtTree::memory_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// Number of internal nodes
+    pub internal_nodes: usize,
+    /// Bytes allocated for the internal nodes
+    pub internal_node_bytes: usize,
+    /// Number of leaves
+    pub leaves: usize,
+    /// Bytes allocated for the leaves, including the values and keys stored in them
+    pub leaf_bytes: usize,
+    /// Total length of the keys
+    pub key_bytes: usize,
+    /// Number of allocations holding key bytes outside the leaves
+    pub key_allocations: usize,
+    /// Bytes allocated for keys outside the leaves
+    pub key_heap_bytes: usize,
+    /// Bytes of keys interned into the key arena that share the bytes of another key, see
+    /// [`ArenaKey`](super::ArenaKey)
+    pub deduplicated_key_bytes: usize,
+}
+
+impl MemoryStats {
+    /// Returns the total number of bytes allocated for nodes and keys
+    pub fn total_bytes(&self) -> usize {
+        self.internal_node_bytes + self.leaf_bytes + self.key_heap_bytes
+    }
+}
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Estimates the heap memory used by the tree. Keys in the key arena are counted by the
+    /// blocks the arena of this tree allocated, so a clone sharing the keys of its source counts
+    /// none of them.
+    pub fn memory_stats(&self) -> MemoryStats {
+        let mut stats = MemoryStats::default();
+        self.accept(&mut InternalNodes {
+            stats: &mut stats,
+            allocated_bytes: ArtNodeInternal::<V, K>::allocated_bytes,
+        });
+        for leaf in RawIter::new(&self.root) {
+            stats.leaves += 1;
+            stats.key_bytes += leaf.key().len();
+            let heap_size = leaf.key.heap_size();
+            if heap_size > 0 {
+                stats.key_allocations += 1;
+                stats.key_heap_bytes += heap_size;
+            }
+        }
+        stats.leaf_bytes = stats.leaves * mem::size_of::<ArtNodeLeaf<V, K>>();
+
+        let arena = self.keys.stats();
+        stats.key_allocations += arena.chunks;
+        stats.key_heap_bytes += arena.allocated_bytes;
+        stats.deduplicated_key_bytes = arena.deduplicated_bytes;
+        stats
+    }
+
+    /// Returns the bytes of the nodes on the path of `key` as counted by
+    /// [`memory_stats`](Self::memory_stats): the internal nodes whose compressed paths the key
+    /// follows, and its leaf with the allocation of its key if the key is present. Keys in the
+    /// key arena count 0.
+    ///
+    /// Inserting or deleting `key` only replaces nodes on its path, so the difference of the
+    /// path memory before and after is the change of the total. This descends the tree only
+    /// once, so memory can be tracked on every insert and delete instead of walking the whole
+    /// tree.
+    pub fn path_memory(&self, key: &[u8]) -> usize {
+        let mut node = &self.root;
+        let mut bytes = 0;
+        let mut depth = 0;
+        loop {
+            match node {
+                Node::Leaf(leaf) => {
+                    if leaf.matches(key) {
+                        bytes += mem::size_of::<ArtNodeLeaf<V, K>>() + leaf.key.heap_size();
+                    }
+                    return bytes;
+                }
+                Node::Internal(internal) => {
+                    if internal.compare_path(key, depth) != Ordering::Equal {
+                        return bytes;
+                    }
+                    bytes += ArtNodeInternal::<V, K>::allocated_bytes(internal.node_kind());
+                    depth += internal.header.partial_len;
+                    node = match key.get(depth).and_then(|&c| internal.find_child(c)) {
+                        Some(child) => child,
+                        None => return bytes,
+                    };
+                    depth += 1;
+                }
+                Node::Empty => return bytes,
+            }
+        }
+    }
+}
+
+impl<V, K> ArtNodeInternal<V, K> {
+    /// Returns the bytes allocated for an internal node of the given type, including its child
+    /// array and, for a Node48, its index of key bytes.
+    pub(super) fn allocated_bytes(kind: NodeKind) -> usize {
+        mem::size_of::<Self>()
+            + match kind {
+                NodeKind::Node4 => mem::size_of::<[Node<V, K>; 4]>(),
+                NodeKind::Node16 => mem::size_of::<[Node<V, K>; 16]>(),
+                NodeKind::Node32 => mem::size_of::<[Node<V, K>; 32]>(),
+                NodeKind::Node48 => mem::size_of::<([u8; 256], [Node<V, K>; 48])>(),
+                NodeKind::Node256 => mem::size_of::<[Node<V, K>; 256]>(),
+            }
+    }
+}
+
+struct InternalNodes<'a> {
+    stats: &'a mut MemoryStats,
+    allocated_bytes: fn(NodeKind) -> usize,
+}
+
+impl<V> Visitor<V> for InternalNodes<'_> {
+    fn visit_internal(&mut self, _header: &NodeHeader<'_>, kind: NodeKind) {
+        self.stats.internal_nodes += 1;
+        self.stats.internal_node_bytes += (self.allocated_bytes)(kind);
+    }
+}
@@ -0,0 +1,267 @@
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+use sha2::{Digest, Sha256};
+
+use super::digest::LittleEndian;
+use super::iter::RawIter;
+use super::{ArtNodeInternal, ArtNodeLeaf, ArtTree, LeafKey, Node};
+
+const LEAF_TAG: u8 = 0;
+const INTERNAL_TAG: u8 = 1;
+
+/// Subtrees with at most this many local entries are compared entry by entry.
+const SYNC_BATCH: usize = 16;
+
+/// Adapts SHA-256 to `Hasher`, to be wrapped in `LittleEndian`.
+struct Sha256Writer(Sha256);
+
+impl Hasher for Sha256Writer {
+    fn finish(&self) -> u64 {
+        unreachable!("the digest is read through the inner hasher")
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+}
+
+impl<V: Hash, K: LeafKey> ArtNodeLeaf<V, K> {
+    fn hash(&self) -> [u8; 32] {
+        let mut writer = LittleEndian(Sha256Writer(Sha256::new()));
+        writer.write_u8(LEAF_TAG);
+        writer.write_u64(self.key().len() as u64);
+        writer.write(self.key());
+        self.value.hash(&mut writer);
+        (writer.0).0.finalize().into()
+    }
+}
+
+impl<V: Hash, K: LeafKey> ArtNodeInternal<V, K> {
+    /// Hashes the children in key order. The compressed path and node type are left out, as the
+    /// leaves hold the full keys, so equal contents always hash the same.
+    fn hash(&self) -> [u8; 32] {
+        *self.hash.get_or_init(|| {
+            let mut hasher = Sha256::new();
+            hasher.update([INTERNAL_TAG]);
+            for child in self.children_from(0) {
+                hasher.update(child.hash());
+            }
+            hasher.finalize().into()
+        })
+    }
+}
+
+impl<V: Hash, K: LeafKey> Node<V, K> {
+    pub(super) fn hash(&self) -> [u8; 32] {
+        match self {
+            Node::Empty => Sha256::digest([INTERNAL_TAG]).into(),
+            Node::Leaf(leaf) => leaf.hash(),
+            Node::Internal(internal) => internal.hash(),
+        }
+    }
+}
+
+impl<V: Hash, K: LeafKey> ArtTree<V, K> {
+    /// Returns a SHA-256 hash of the contents of the tree.
+    ///
+    /// Subtree hashes are cached in the nodes and only recomputed below nodes changed since the
+    /// last call. Trees with equal contents have equal hashes.
+    pub fn root_hash(&self) -> [u8; 32] {
+        self.root.hash()
+    }
+
+    /// Returns the hash of the entries whose keys start with `prefix`, or `None` if there are
+    /// none. It equals the `root_hash` of [`subtree(prefix)`](ArtTree::subtree).
+    pub fn subtree_hash(&self, prefix: &[u8]) -> Option<[u8; 32]> {
+        self.root
+            .prefix_root(prefix, 0)
+            .map(|(node, _)| node.hash())
+    }
+}
+
+/// Source of the subtree hashes of a (usually remote) tree, driven by [`ArtTree::sync_diff`].
+///
+/// Prefixes are byte strings: the children of a prefix are the prefix extended by one byte, no
+/// matter how the tree compresses its paths. `ArtTree` implements it for comparing two local
+/// trees.
+pub trait HashOracle {
+    /// Returns the hash of the entries whose keys start with `prefix`, or `None` if there are
+    /// none.
+    fn subtree_hash(&mut self, prefix: &[u8]) -> Option<[u8; 32]>;
+
+    /// Returns the non-empty child prefixes of `prefix` as the extending byte and the hash of the
+    /// entries under the child, in ascending byte order.
+    fn children_hashes(&mut self, prefix: &[u8]) -> Vec<(u8, [u8; 32])>;
+
+    /// Returns the keys starting with `prefix` and the hashes of their entries, in ascending key
+    /// order.
+    fn entry_hashes(&mut self, prefix: &[u8]) -> Vec<(Vec<u8>, [u8; 32])>;
+}
+
+/// Keys that differ between two trees, as found by [`ArtTree::sync_diff`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncDiff {
+    /// Keys only present in the local tree
+    pub local_only: Vec<Vec<u8>>,
+    /// Keys only present in the remote tree
+    pub remote_only: Vec<Vec<u8>>,
+    /// Keys present in both trees with different values
+    pub differing: Vec<Vec<u8>>,
+}
+
+impl<V: Hash, K: LeafKey> HashOracle for ArtTree<V, K> {
+    fn subtree_hash(&mut self, prefix: &[u8]) -> Option<[u8; 32]> {
+        ArtTree::subtree_hash(self, prefix)
+    }
+
+    fn children_hashes(&mut self, prefix: &[u8]) -> Vec<(u8, [u8; 32])> {
+        ArtTree::children_hashes(self, prefix)
+    }
+
+    fn entry_hashes(&mut self, prefix: &[u8]) -> Vec<(Vec<u8>, [u8; 32])> {
+        ArtTree::entry_hashes(self, prefix)
+    }
+}
+
+impl<V: Hash, K: LeafKey> ArtTree<V, K> {
+    /// Returns the non-empty child prefixes of `prefix` with their hashes, see
+    /// [`HashOracle::children_hashes`].
+    pub fn children_hashes(&self, prefix: &[u8]) -> Vec<(u8, [u8; 32])> {
+        let depth = prefix.len();
+        match self.root.prefix_root(prefix, 0) {
+            Some((Node::Leaf(leaf), _)) => match leaf.key().get(depth) {
+                Some(&c) => vec![(c, leaf.hash())],
+                None => Vec::new(),
+            },
+            Some((node @ Node::Internal(internal), node_depth)) => {
+                let path_end = node_depth + internal.header.partial_len;
+                if depth < path_end {
+                    // The prefix ends within the compressed path, which has a single child
+                    let path = internal.minimum().unwrap().key();
+                    return vec![(path[depth], node.hash())];
+                }
+                internal
+                    .children_from(0)
+                    .map(|child| (child.minimum().unwrap().key()[depth], child.hash()))
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the keys starting with `prefix` with the hashes of their entries, see
+    /// [`HashOracle::entry_hashes`].
+    pub fn entry_hashes(&self, prefix: &[u8]) -> Vec<(Vec<u8>, [u8; 32])> {
+        match self.root.prefix_root(prefix, 0) {
+            Some((node, _)) => RawIter::new(node)
+                .map(|leaf| (leaf.key().to_vec(), leaf.hash()))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Finds the keys whose entries differ between this tree and `remote`.
+    ///
+    /// Hashes are compared top-down and only the prefixes whose hashes differ are descended, so
+    /// the number of oracle calls grows with the size of the difference rather than with the
+    /// size of the trees. Small subtrees are compared entry by entry in a single call.
+    pub fn sync_diff<O: HashOracle>(&self, remote: &mut O) -> SyncDiff {
+        let mut diff = SyncDiff::default();
+        if ArtTree::subtree_hash(self, &[]) != remote.subtree_hash(&[]) {
+            self.diff_prefix(remote, &mut Vec::new(), &mut diff);
+        }
+        diff
+    }
+
+    /// Collects the differences under a prefix whose hashes are known to differ.
+    fn diff_prefix<O: HashOracle>(
+        &self,
+        remote: &mut O,
+        prefix: &mut Vec<u8>,
+        diff: &mut SyncDiff,
+    ) {
+        let small = match self.root.prefix_root(prefix, 0) {
+            Some((node, _)) => RawIter::new(node).nth(SYNC_BATCH).is_none(),
+            None => true,
+        };
+        let remote_children = if small {
+            Vec::new()
+        } else {
+            remote.children_hashes(prefix)
+        };
+        if remote_children.is_empty() {
+            let local = self.entry_hashes(prefix);
+            let remote = remote.entry_hashes(prefix);
+            diff_entries(local, remote, diff);
+            return;
+        }
+
+        let local_children = self.children_hashes(prefix);
+        let mut local_iter = local_children.into_iter().peekable();
+        let mut remote_iter = remote_children.into_iter().peekable();
+        loop {
+            let c = match (local_iter.peek(), remote_iter.peek()) {
+                (Some(&(l, local_hash)), Some(&(r, remote_hash))) => match l.cmp(&r) {
+                    Ordering::Less => {
+                        local_iter.next();
+                        l
+                    }
+                    Ordering::Greater => {
+                        remote_iter.next();
+                        r
+                    }
+                    Ordering::Equal => {
+                        local_iter.next();
+                        remote_iter.next();
+                        if local_hash == remote_hash {
+                            continue;
+                        }
+                        l
+                    }
+                },
+                (Some(&(l, _)), None) => {
+                    local_iter.next();
+                    l
+                }
+                (None, Some(&(r, _))) => {
+                    remote_iter.next();
+                    r
+                }
+                (None, None) => break,
+            };
+            prefix.push(c);
+            self.diff_prefix(remote, prefix, diff);
+            prefix.pop();
+        }
+    }
+}
+
+/// Merges two sorted lists of entry hashes into `diff`.
+fn diff_entries(
+    local: Vec<(Vec<u8>, [u8; 32])>,
+    remote: Vec<(Vec<u8>, [u8; 32])>,
+    diff: &mut SyncDiff,
+) {
+    let mut local = local.into_iter().peekable();
+    let mut remote = remote.into_iter().peekable();
+    loop {
+        let order = match (local.peek(), remote.peek()) {
+            (Some((l, _)), Some((r, _))) => l.cmp(r),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => break,
+        };
+        match order {
+            Ordering::Less => diff.local_only.push(local.next().unwrap().0),
+            Ordering::Greater => diff.remote_only.push(remote.next().unwrap().0),
+            Ordering::Equal => {
+                let (key, local_hash) = local.next().unwrap();
+                let (_, remote_hash) = remote.next().unwrap();
+                if local_hash != remote_hash {
+                    diff.differing.push(key);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+#[cfg(feature = "metrics")]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::profile::hooks as profiling;
+
+/// Snapshot of the operation counters of an `ArtTree`.
+///
+/// Created by [`ArtTree::metrics`](super::ArtTree::metrics).
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ArtMetrics {
+    /// Number of `get` and `get_mut` calls
+    pub lookups: u64,
+    /// Number of calls that insert or update a key (`insert`, `get_or_insert_with`, ...)
+    pub inserts: u64,
+    /// Number of nodes grown into the next larger node type
+    pub node_upgrades: u64,
+    /// Number of nodes shrunk into the next smaller node type
+    pub node_downgrades: u64,
+    /// Number of compressed paths split because an inserted key diverged from them
+    pub prefix_splits: u64,
+    /// Total number of nodes visited by all lookups
+    pub lookup_depth_total: u64,
+    /// Number of inserts that compared a compressed path longer than the stored prefix against
+    /// a leaf
+    pub prefix_leaf_peeks: u64,
+}
+
+#[cfg(feature = "metrics")]
+impl ArtMetrics {
+    /// Returns the average number of nodes visited per lookup.
+    pub fn average_lookup_depth(&self) -> f64 {
+        if self.lookups == 0 {
+            0.0
+        } else {
+            self.lookup_depth_total as f64 / self.lookups as f64
+        }
+    }
+}
+
+/// Operation counters updated by the tree, which also feed the thread's `OpProfile`. They
+/// compile to nothing without the `metrics` and `profile` features.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Default)]
+pub(super) struct Counters {
+    lookups: AtomicU64,
+    inserts: AtomicU64,
+    node_upgrades: AtomicU64,
+    node_downgrades: AtomicU64,
+    prefix_splits: AtomicU64,
+    lookup_depth_total: AtomicU64,
+    prefix_leaf_peeks: AtomicU64,
+}
+
+#[cfg(feature = "metrics")]
+impl Counters {
+    pub(super) fn lookup(&self, depth: u64) {
+        self.lookups.fetch_add(1, Ordering::Relaxed);
+        self.lookup_depth_total.fetch_add(depth, Ordering::Relaxed);
+        profiling::lookup(depth);
+    }
+
+    pub(super) fn insert(&self) {
+        self.inserts.fetch_add(1, Ordering::Relaxed);
+        profiling::insert();
+    }
+
+    pub(super) fn node_upgrade(&self) {
+        self.node_upgrades.fetch_add(1, Ordering::Relaxed);
+        profiling::conversion();
+    }
+
+    pub(super) fn node_downgrade(&self) {
+        self.node_downgrades.fetch_add(1, Ordering::Relaxed);
+        profiling::conversion();
+    }
+
+    pub(super) fn prefix_split(&self) {
+        self.prefix_splits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn prefix_leaf_peek(&self) {
+        self.prefix_leaf_peeks.fetch_add(1, Ordering::Relaxed);
+        profiling::leaf_peek();
+    }
+
+    pub(super) fn snapshot(&self) -> ArtMetrics {
+        ArtMetrics {
+            lookups: self.lookups.load(Ordering::Relaxed),
+            inserts: self.inserts.load(Ordering::Relaxed),
+            node_upgrades: self.node_upgrades.load(Ordering::Relaxed),
+            node_downgrades: self.node_downgrades.load(Ordering::Relaxed),
+            prefix_splits: self.prefix_splits.load(Ordering::Relaxed),
+            lookup_depth_total: self.lookup_depth_total.load(Ordering::Relaxed),
+            prefix_leaf_peeks: self.prefix_leaf_peeks.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl Clone for Counters {
+    fn clone(&self) -> Self {
+        let snapshot = self.snapshot();
+        Self {
+            lookups: AtomicU64::new(snapshot.lookups),
+            inserts: AtomicU64::new(snapshot.inserts),
+            node_upgrades: AtomicU64::new(snapshot.node_upgrades),
+            node_downgrades: AtomicU64::new(snapshot.node_downgrades),
+            prefix_splits: AtomicU64::new(snapshot.prefix_splits),
+            lookup_depth_total: AtomicU64::new(snapshot.lookup_depth_total),
+            prefix_leaf_peeks: AtomicU64::new(snapshot.prefix_leaf_peeks),
+        }
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+#[derive(Debug, Default, Clone)]
+pub(super) struct Counters {}
+
+#[cfg(not(feature = "metrics"))]
+impl Counters {
+    #[inline(always)]
+    pub(super) fn lookup(&self, depth: u64) {
+        profiling::lookup(depth);
+    }
+
+    #[inline(always)]
+    pub(super) fn insert(&self) {
+        profiling::insert();
+    }
+
+    #[inline(always)]
+    pub(super) fn node_upgrade(&self) {
+        profiling::conversion();
+    }
+
+    #[inline(always)]
+    pub(super) fn node_downgrade(&self) {
+        profiling::conversion();
+    }
+
+    #[inline(always)]
+    pub(super) fn prefix_split(&self) {}
+
+    #[inline(always)]
+    pub(super) fn prefix_leaf_peek(&self) {
+        profiling::leaf_peek();
+    }
+}
@@ -0,0 +1,106 @@
+use std::cmp::Ordering;
+use std::ops::Bound;
+
+use super::{ArtNodeInternal, ArtNodeInternalInner, ArtNodeLeaf, ArtTree, LeafKey, Node};
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Returns the entry with the smallest key greater than `key`, with a mutable reference to
+    /// its value. `key` itself does not need to be present.
+    pub fn next_after_mut(&mut self, key: &[u8]) -> Option<(&[u8], &mut V)> {
+        self.range_mut((Bound::Excluded(key), Bound::Unbounded))
+            .next()
+    }
+
+    /// Returns the entry with the greatest key less than `key`, with a mutable reference to its
+    /// value. `key` itself does not need to be present.
+    pub fn prev_before_mut(&mut self, key: &[u8]) -> Option<(&[u8], &mut V)> {
+        let ArtNodeLeaf { key, value } = self.root.last_before_mut(key, 0)?;
+        let key: &K = key;
+        let key = key.as_ref();
+        self.dirty.mark(key);
+        Some((key, value))
+    }
+}
+
+impl<V, K: LeafKey> Node<V, K> {
+    /// Returns the leaf with the greatest key less than `key`, like `floor` without the key
+    /// itself
+    fn last_before_mut(&mut self, key: &[u8], depth: usize) -> Option<&mut ArtNodeLeaf<V, K>> {
+        match self {
+            Node::Empty => None,
+            Node::Leaf(leaf) if leaf.key() < key => Some(leaf),
+            Node::Leaf(_) => None,
+            Node::Internal(internal) => match internal.compare_path(key, depth) {
+                Ordering::Less => internal.maximum_mut(),
+                Ordering::Greater => None,
+                Ordering::Equal => {
+                    // Every key in this subtree is longer than the path, so if the key ends
+                    // within the path, all of them are greater than it
+                    let depth = depth + internal.header.partial_len;
+                    internal.last_child_before_mut(key, depth)
+                }
+            },
+        }
+    }
+}
+
+impl<V, K: LeafKey> ArtNodeInternal<V, K> {
+    /// Returns the leaf with the greatest key less than `key` among the children, which are at
+    /// `depth` of the key. It is found below the child at the key byte of `key` or else is the
+    /// maximum of the child before it.
+    fn last_child_before_mut(
+        &mut self,
+        key: &[u8],
+        depth: usize,
+    ) -> Option<&mut ArtNodeLeaf<V, K>> {
+        let c = *key.get(depth)?;
+        self.invalidate_caches();
+        let n = self.header.num_children as usize;
+        let at = self.find_child_index(c);
+        let (before, children): (_, &mut [Node<V, K>]) = match &mut self.inner {
+            ArtNodeInternalInner::Node4 { keys, children } => (
+                keys[..n].iter().rposition(|&key| key < c),
+                &mut children[..],
+            ),
+            ArtNodeInternalInner::Node16 { keys, children } => (
+                keys[..n].iter().rposition(|&key| key < c),
+                &mut children[..],
+            ),
+            ArtNodeInternalInner::Node32 { keys, children } => (
+                keys[..n].iter().rposition(|&key| key < c),
+                &mut children[..],
+            ),
+            ArtNodeInternalInner::Node48 { keys, children } => (
+                keys[..c as usize]
+                    .iter()
+                    .rfind(|&&idx| idx != 0)
+                    .map(|&idx| idx as usize - 1),
+                &mut children[..],
+            ),
+            ArtNodeInternalInner::Node256 { children } => (
+                children[..c as usize]
+                    .iter()
+                    .rposition(|child| !child.is_empty()),
+                &mut children[..],
+            ),
+        };
+
+        let (child, before) = match (at, before) {
+            (Some(at), Some(before)) if before < at => {
+                let (low, high) = children.split_at_mut(at);
+                (Some(&mut high[0]), Some(&mut low[before]))
+            }
+            (Some(at), Some(before)) => {
+                let (low, high) = children.split_at_mut(before);
+                (Some(&mut low[at]), Some(&mut high[0]))
+            }
+            (Some(at), None) => (Some(&mut children[at]), None),
+            (None, Some(before)) => (None, Some(&mut children[before])),
+            (None, None) => (None, None),
+        };
+        match child.and_then(|child| child.last_before_mut(key, depth + 1)) {
+            Some(leaf) => Some(leaf),
+            None => before?.maximum_mut(),
+        }
+    }
+}
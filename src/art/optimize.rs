@@ -0,0 +1,100 @@
+use super::visit::{NodeHeader, NodeKind, Visitor};
+use super::{ArtTree, LeafKey, Node, NodeSizing};
+
+/// Numbers of internal nodes by type and how full they are.
+///
+/// Created by [`ArtTree::node_stats`] and reported by [`ArtTree::optimize`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NodeStats {
+    pub node4: usize,
+    pub node16: usize,
+    pub node32: usize,
+    pub node48: usize,
+    pub node256: usize,
+    /// Number of children of all internal nodes
+    pub children: usize,
+    /// Number of child slots of all internal nodes, the sum of the capacities of their types
+    pub slots: usize,
+}
+
+impl NodeStats {
+    /// Returns the number of internal nodes
+    pub fn internal_nodes(&self) -> usize {
+        self.node4 + self.node16 + self.node32 + self.node48 + self.node256
+    }
+
+    /// Returns the share of the child slots holding a child, 1 for a tree without internal
+    /// nodes
+    pub fn fill_ratio(&self) -> f64 {
+        if self.slots == 0 {
+            1.0
+        } else {
+            self.children as f64 / self.slots as f64
+        }
+    }
+}
+
+impl<V> Visitor<V> for NodeStats {
+    fn visit_internal(&mut self, header: &NodeHeader<'_>, kind: NodeKind) {
+        match kind {
+            NodeKind::Node4 => self.node4 += 1,
+            NodeKind::Node16 => self.node16 += 1,
+            NodeKind::Node32 => self.node32 += 1,
+            NodeKind::Node48 => self.node48 += 1,
+            NodeKind::Node256 => self.node256 += 1,
+        }
+        self.children += header.num_children();
+        self.slots += kind.capacity();
+    }
+}
+
+/// The internal nodes of a tree before and after [`ArtTree::optimize`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OptimizeReport {
+    pub before: NodeStats,
+    pub after: NodeStats,
+}
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Counts the internal nodes by type and their children
+    pub fn node_stats(&self) -> NodeStats {
+        let mut stats = NodeStats::default();
+        self.accept(&mut stats);
+        stats
+    }
+
+    /// Rebuilds every internal node into the type that inserting its children one by one would
+    /// give with the [`NodeSizing`] of the tree, and reports the nodes before and after.
+    ///
+    /// Deletes leave nodes in larger types than their children need, down to the shrink points
+    /// of the sizing, so this is worth running after heavy churn. Trees filled by inserts only
+    /// or built with an [`ArtBuilder`](super::ArtBuilder) are already in this form, except that
+    /// with the `adaptive` feature cold nodes grow late; their access counts start over here.
+    /// The leaves are moved, not copied.
+    pub fn optimize(&mut self) -> OptimizeReport {
+        let before = self.node_stats();
+        let root = std::mem::take(&mut self.root);
+        self.root = root.optimized(&self.sizing);
+        OptimizeReport {
+            before,
+            after: self.node_stats(),
+        }
+    }
+}
+
+impl<V, K: LeafKey> Node<V, K> {
+    fn optimized(self, sizing: &NodeSizing) -> Self {
+        match self {
+            Node::Internal(internal) => {
+                let header = internal.header;
+                let children = internal
+                    .into_keyed_children()
+                    .into_iter()
+                    .map(|(key, child)| (key, child.optimized(sizing)))
+                    .collect();
+                Node::from_children(header, children, sizing)
+            }
+            node => node,
+        }
+    }
+}
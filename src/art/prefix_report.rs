@@ -0,0 +1,100 @@
+use std::collections::BTreeMap;
+
+use super::visit::{NodeHeader, NodeKind, Visitor};
+use super::{ArtNodeInternal, ArtTree, LeafKey, MAX_PREFIX_LEN};
+
+/// How effective path compression is for the keys of a tree.
+///
+/// Created by [`ArtTree::prefix_report`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrefixReport {
+    /// Number of internal nodes
+    pub internal_nodes: usize,
+    /// Number of internal nodes with a compressed path
+    pub compressed_nodes: usize,
+    /// Total length of the compressed paths, i.e. the number of single-child nodes that path
+    /// compression avoids
+    pub compressed_bytes: usize,
+    /// Estimated memory saved by path compression: one Node4 per compressed byte
+    pub bytes_saved: usize,
+    /// Number of internal nodes by the length of their compressed path
+    pub partial_len_histogram: BTreeMap<usize, usize>,
+    /// Number of bytes of a compressed path stored in the node (`MAX_PREFIX_LEN`)
+    pub max_prefix_len: usize,
+    /// Number of internal nodes whose compressed path is longer than `max_prefix_len`. Inserts
+    /// below them compare the rest of the path against a leaf.
+    pub overflowing_nodes: usize,
+    /// Number of entries below at least one overflowing node
+    pub entries_below_overflow: usize,
+}
+
+impl PrefixReport {
+    /// Returns the length of the longest compressed path, the smallest stored prefix length for
+    /// which no path of the tree would overflow
+    pub fn max_partial_len(&self) -> usize {
+        self.partial_len_histogram
+            .keys()
+            .next_back()
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Reports how many bytes path compression saves for the keys of the tree, the distribution
+    /// of the compressed path lengths and how many of the paths are too long to be stored in
+    /// their node. With the `metrics` feature, `ArtMetrics::prefix_leaf_peeks` counts how often
+    /// inserts had to read such a path from a leaf.
+    pub fn prefix_report(&self) -> PrefixReport {
+        let mut collector = Collector {
+            report: PrefixReport {
+                max_prefix_len: MAX_PREFIX_LEN,
+                ..PrefixReport::default()
+            },
+            open_overflows: 0,
+        };
+        self.accept(&mut collector);
+
+        let mut report = collector.report;
+        report.bytes_saved =
+            report.compressed_bytes * ArtNodeInternal::<V, K>::allocated_bytes(NodeKind::Node4);
+        report
+    }
+}
+
+struct Collector {
+    report: PrefixReport,
+    /// Number of overflowing nodes on the current path
+    open_overflows: usize,
+}
+
+impl<V> Visitor<V> for Collector {
+    fn visit_internal(&mut self, header: &NodeHeader<'_>, _kind: NodeKind) {
+        let report = &mut self.report;
+        report.internal_nodes += 1;
+        if header.prefix_len() > 0 {
+            report.compressed_nodes += 1;
+            report.compressed_bytes += header.prefix_len();
+        }
+        *report
+            .partial_len_histogram
+            .entry(header.prefix_len())
+            .or_insert(0) += 1;
+        if header.prefix_len() > MAX_PREFIX_LEN {
+            report.overflowing_nodes += 1;
+            self.open_overflows += 1;
+        }
+    }
+
+    fn leave_internal(&mut self, header: &NodeHeader<'_>, _kind: NodeKind) {
+        if header.prefix_len() > MAX_PREFIX_LEN {
+            self.open_overflows -= 1;
+        }
+    }
+
+    fn visit_leaf(&mut self, _key: &[u8], _value: &V) {
+        if self.open_overflows > 0 {
+            self.report.entries_below_overflow += 1;
+        }
+    }
+}
@@ -0,0 +1,147 @@
+#[cfg(feature = "profile")]
+use std::cell::Cell;
+
+/// Work done by the tree operations of one thread, collected with [`profile`] or
+/// [`take_profile`].
+///
+/// Unlike [`ArtMetrics`](super::ArtMetrics), which counts per tree, this attributes the cost of
+/// single operations: a growing `nodes_visited` per operation points at a deeper tree, a growing
+/// `node_conversions` at nodes churning between types.
+#[cfg(feature = "profile")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OpProfile {
+    /// Number of `get` and `get_mut` calls
+    pub lookups: u64,
+    /// Number of calls that insert or update a key
+    pub inserts: u64,
+    /// Number of nodes visited by lookups and inserts, leaves included
+    pub nodes_visited: u64,
+    /// Number of compressed path bytes compared against a key by lookups, inserts and deletes
+    pub prefix_bytes_compared: u64,
+    /// Number of inserts that compared a compressed path longer than the stored prefix against
+    /// a leaf
+    pub leaf_peeks: u64,
+    /// Number of nodes grown or shrunk into another node type
+    pub node_conversions: u64,
+}
+
+#[cfg(feature = "profile")]
+impl OpProfile {
+    const ZERO: OpProfile = OpProfile {
+        lookups: 0,
+        inserts: 0,
+        nodes_visited: 0,
+        prefix_bytes_compared: 0,
+        leaf_peeks: 0,
+        node_conversions: 0,
+    };
+
+    /// Returns the average number of nodes visited per lookup or insert.
+    pub fn average_depth(&self) -> f64 {
+        let ops = self.lookups + self.inserts;
+        if ops == 0 {
+            0.0
+        } else {
+            self.nodes_visited as f64 / ops as f64
+        }
+    }
+
+    fn merged(self, other: OpProfile) -> OpProfile {
+        OpProfile {
+            lookups: self.lookups + other.lookups,
+            inserts: self.inserts + other.inserts,
+            nodes_visited: self.nodes_visited + other.nodes_visited,
+            prefix_bytes_compared: self.prefix_bytes_compared + other.prefix_bytes_compared,
+            leaf_peeks: self.leaf_peeks + other.leaf_peeks,
+            node_conversions: self.node_conversions + other.node_conversions,
+        }
+    }
+}
+
+#[cfg(feature = "profile")]
+thread_local! {
+    static PROFILE: Cell<OpProfile> = const { Cell::new(OpProfile::ZERO) };
+}
+
+/// Runs `f` and returns the work the tree operations on this thread did during it.
+///
+/// Calls nest: the work is also accumulated into an enclosing `profile` call or the profile
+/// returned by the next [`take_profile`].
+#[cfg(feature = "profile")]
+pub fn profile<T>(f: impl FnOnce() -> T) -> (T, OpProfile) {
+    let outer = take_profile();
+    let result = f();
+    let inner = take_profile();
+    PROFILE.with(|profile| profile.set(outer.merged(inner)));
+    (result, inner)
+}
+
+/// Returns the work the tree operations on this thread did since the last call, and starts
+/// over from zero.
+#[cfg(feature = "profile")]
+pub fn take_profile() -> OpProfile {
+    PROFILE.with(|profile| profile.replace(OpProfile::ZERO))
+}
+
+#[cfg(feature = "profile")]
+fn record(update: impl FnOnce(&mut OpProfile)) {
+    PROFILE.with(|profile| {
+        let mut current = profile.get();
+        update(&mut current);
+        profile.set(current);
+    });
+}
+
+/// Hooks updating the thread's profile. They compile to nothing without the `profile` feature.
+#[cfg(feature = "profile")]
+pub(super) mod hooks {
+    use super::record;
+
+    pub(in crate::art) fn lookup(visited: u64) {
+        record(|p| {
+            p.lookups += 1;
+            p.nodes_visited += visited;
+        });
+    }
+
+    pub(in crate::art) fn insert() {
+        record(|p| p.inserts += 1);
+    }
+
+    pub(in crate::art) fn visit() {
+        record(|p| p.nodes_visited += 1);
+    }
+
+    pub(in crate::art) fn compared(bytes: usize) {
+        record(|p| p.prefix_bytes_compared += bytes as u64);
+    }
+
+    pub(in crate::art) fn leaf_peek() {
+        record(|p| p.leaf_peeks += 1);
+    }
+
+    pub(in crate::art) fn conversion() {
+        record(|p| p.node_conversions += 1);
+    }
+}
+
+#[cfg(not(feature = "profile"))]
+pub(super) mod hooks {
+    #[inline(always)]
+    pub(in crate::art) fn lookup(_visited: u64) {}
+
+    #[inline(always)]
+    pub(in crate::art) fn insert() {}
+
+    #[inline(always)]
+    pub(in crate::art) fn visit() {}
+
+    #[inline(always)]
+    pub(in crate::art) fn compared(_bytes: usize) {}
+
+    #[inline(always)]
+    pub(in crate::art) fn leaf_peek() {}
+
+    #[inline(always)]
+    pub(in crate::art) fn conversion() {}
+}
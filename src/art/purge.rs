@@ -0,0 +1,437 @@
+use std::cmp::{min, Ordering};
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+
+use super::iter::{IntoIter, RawIter};
+use super::trace;
+use super::{
+    ArtNodeInternal, ArtNodeInternalInner, ArtNodeLeaf, ArtTree, InternalNodeHeader, LeafKey, Node,
+    NodeSizing, MAX_PREFIX_LEN,
+};
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Removes every entry whose key is less than `bound` and returns the number of removed
+    /// entries.
+    ///
+    /// Only the path towards `bound` is descended: subtrees entirely below it are detached as a
+    /// whole, and the nodes on the path are rebuilt once with their remaining children.
+    pub fn remove_below(&mut self, bound: &[u8]) -> usize {
+        self.detach_range(Bound::Unbounded, Bound::Excluded(bound))
+            .len()
+    }
+
+    /// Removes the entries whose keys fall in the given range and returns them in ascending key
+    /// order.
+    ///
+    /// The entries are removed when `drain_range` is called, not as the iterator is consumed.
+    /// Subtrees entirely within the range are detached as a whole, only the paths towards the
+    /// bounds are descended and rebuilt.
+    pub fn drain_range<'r, R>(&mut self, range: R) -> IntoIter<V, K>
+    where
+        R: RangeBounds<&'r [u8]>,
+    {
+        self.detach_range(range.start_bound().cloned(), range.end_bound().cloned())
+    }
+
+    /// Keeps only the entries for which `keep` returns true.
+    ///
+    /// The tree is walked once: rejected leaves are removed in place and the nodes that lost
+    /// children are rebuilt on the way up. Only the paths to the removed entries change.
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&[u8], &V) -> bool,
+    {
+        self.retain_leaves(&mut |leaf| keep(leaf.key(), &leaf.value), false);
+    }
+
+    /// Keeps only the entries for which `keep` returns true, passing it a mutable reference to
+    /// every value.
+    ///
+    /// Like [`retain`](Self::retain), except that every kept entry counts as changed, since
+    /// `keep` may have modified its value.
+    pub fn retain_mut<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&[u8], &mut V) -> bool,
+    {
+        self.retain_leaves(
+            &mut |ArtNodeLeaf { key, value }| keep(key.as_ref(), value),
+            true,
+        );
+    }
+
+    fn retain_leaves(
+        &mut self,
+        keep: &mut dyn FnMut(&mut ArtNodeLeaf<V, K>) -> bool,
+        modifies: bool,
+    ) {
+        let mut removed = Vec::new();
+        let dirty = &mut self.dirty;
+        let mut keep = |leaf: &mut ArtNodeLeaf<V, K>| {
+            let kept = keep(leaf);
+            if kept && modifies {
+                dirty.mark(leaf.key());
+            }
+            kept
+        };
+        self.root
+            .retain(&mut keep, modifies, &self.sizing, &mut removed);
+
+        for leaf in &removed {
+            self.dirty.mark(leaf.key());
+            self.observers.deleted(leaf.key(), &leaf.value);
+            self.validate_path(leaf.key());
+        }
+        self.size -= removed.len() as u64;
+        self.bloom.deleted(removed.len());
+    }
+
+    fn detach_range(&mut self, start: Bound<&[u8]>, end: Bound<&[u8]>) -> IntoIter<V, K> {
+        let mut detached = Vec::new();
+        let root = mem::take(&mut self.root);
+        self.root = root.detach_range(start, end, 0, &self.sizing, &mut detached);
+        for bound in [start, end] {
+            if let Bound::Included(bound) | Bound::Excluded(bound) = bound {
+                self.validate_path(bound);
+            }
+        }
+
+        let mut removed = 0;
+        for leaf in detached.iter().flat_map(RawIter::new) {
+            removed += 1;
+            self.dirty.mark(leaf.key());
+            self.observers.deleted(leaf.key(), &leaf.value);
+        }
+        self.size -= removed as u64;
+        self.bloom.deleted(removed);
+        IntoIter::from_nodes(detached, removed)
+    }
+}
+
+/// Where the keys of a subtree lie relative to a bound
+pub(super) enum Side {
+    /// All keys are less than the bound
+    Below,
+    /// All keys are greater than the bound
+    Above,
+    /// The path of the subtree is a prefix of the bound, which continues with the given byte
+    At(u8),
+}
+
+impl<V, K: LeafKey> ArtNodeInternal<V, K> {
+    pub(super) fn side(&self, bound: Bound<&[u8]>, depth: usize) -> Option<Side> {
+        let bound = match bound {
+            Bound::Included(bound) | Bound::Excluded(bound) => bound,
+            Bound::Unbounded => return None,
+        };
+        Some(match self.compare_path(bound, depth) {
+            Ordering::Less => Side::Below,
+            Ordering::Greater => Side::Above,
+            Ordering::Equal => match bound.get(depth + self.header.partial_len) {
+                Some(&c) => Side::At(c),
+                // The bound ends within the path, every key below is greater
+                None => Side::Above,
+            },
+        })
+    }
+}
+
+impl<V, K: LeafKey> Node<V, K> {
+    /// Moves the leaves and subtrees within the range into `detached`, in ascending key order,
+    /// and returns what remains of the node.
+    fn detach_range(
+        self,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+        depth: usize,
+        sizing: &NodeSizing,
+        detached: &mut Vec<Self>,
+    ) -> Self {
+        match self {
+            Node::Empty => Node::Empty,
+            Node::Leaf(leaf) => {
+                if RangeBounds::<&[u8]>::contains(&(start, end), &leaf.key()) {
+                    detached.push(Node::Leaf(leaf));
+                    Node::Empty
+                } else {
+                    Node::Leaf(leaf)
+                }
+            }
+            Node::Internal(internal) => {
+                // Child key bytes at or after `from` and at or before `to` are (partly) in range,
+                // the bounds themselves are descended into
+                let (from, descend_from) = match internal.side(start, depth) {
+                    Some(Side::Below) => return Node::Internal(internal),
+                    Some(Side::At(c)) => (c, Some(c)),
+                    Some(Side::Above) | None => (0, None),
+                };
+                let (to, descend_to) = match internal.side(end, depth) {
+                    Some(Side::Above) => return Node::Internal(internal),
+                    Some(Side::At(c)) => (c, Some(c)),
+                    Some(Side::Below) | None => (u8::MAX, None),
+                };
+                if from > to || !internal.has_child_within(from, to) {
+                    return Node::Internal(internal);
+                }
+
+                let child_depth = depth + internal.header.partial_len + 1;
+                let header = internal.header;
+                let mut children = Vec::with_capacity(header.num_children as usize);
+                for (key, child) in internal.into_keyed_children() {
+                    let child = if key < from || key > to {
+                        child
+                    } else if Some(key) == descend_from || Some(key) == descend_to {
+                        // A bound leading to another child does not constrain this one
+                        let start = if Some(key) == descend_from {
+                            start
+                        } else {
+                            Bound::Unbounded
+                        };
+                        let end = if Some(key) == descend_to {
+                            end
+                        } else {
+                            Bound::Unbounded
+                        };
+                        child.detach_range(start, end, child_depth, sizing, detached)
+                    } else {
+                        trace::subtree_detach(&child, child_depth);
+                        detached.push(child);
+                        continue;
+                    };
+                    if !child.is_empty() {
+                        children.push((key, child));
+                    }
+                }
+                Node::from_children(header, children, sizing)
+            }
+        }
+    }
+
+    /// Moves the leaves for which `keep` returns false into `removed`, in ascending key order,
+    /// and rebuilds the internal nodes that lost children. Returns true if the node changed.
+    ///
+    /// The caches of the changed nodes are dropped, or of every node if `keep` modifies values.
+    fn retain(
+        &mut self,
+        keep: &mut dyn FnMut(&mut ArtNodeLeaf<V, K>) -> bool,
+        modifies: bool,
+        sizing: &NodeSizing,
+        removed: &mut Vec<Box<ArtNodeLeaf<V, K>>>,
+    ) -> bool {
+        match self {
+            Node::Empty => false,
+            Node::Leaf(leaf) => {
+                if keep(leaf) {
+                    return false;
+                }
+                if let Node::Leaf(leaf) = mem::take(self) {
+                    removed.push(leaf);
+                }
+                true
+            }
+            Node::Internal(internal) => {
+                let mut changed = false;
+                let mut emptied = false;
+                internal.for_each_child_mut(|child| {
+                    if child.retain(keep, modifies, sizing, removed) {
+                        changed = true;
+                        emptied |= child.is_empty();
+                    }
+                });
+                if changed || modifies {
+                    internal.invalidate_caches();
+                }
+                if emptied {
+                    if let Node::Internal(internal) = mem::take(self) {
+                        let header = internal.header;
+                        let children = internal
+                            .into_keyed_children()
+                            .into_iter()
+                            .filter(|(_, child)| !child.is_empty())
+                            .collect();
+                        *self = Node::from_children(header, children, sizing);
+                    }
+                }
+                changed
+            }
+        }
+    }
+
+    /// Builds the node holding the given children (sorted by key byte), of the type that
+    /// inserting them one by one would grow into with the given sizing, merging a single
+    /// remaining child with the compressed path of the node.
+    pub(super) fn from_children(
+        mut header: InternalNodeHeader,
+        children: Vec<(u8, Self)>,
+        sizing: &NodeSizing,
+    ) -> Self {
+        header.num_children = children.len() as u16;
+        let n = header.num_children;
+        let inner = match children.len() {
+            0 => return Node::Empty,
+            1 => {
+                let (key, child) = children.into_iter().next().unwrap();
+                return match child {
+                    Node::Internal(mut internal) => {
+                        // Concatenate the prefixes, as when deleting down to a single child
+                        let mut prefix = header.partial_len;
+                        if prefix < MAX_PREFIX_LEN {
+                            header.partial[prefix] = key;
+                            prefix += 1;
+                        }
+                        if prefix < MAX_PREFIX_LEN {
+                            let sub_prefix =
+                                min(internal.header.partial_len, MAX_PREFIX_LEN - prefix);
+                            header.partial[prefix..prefix + sub_prefix]
+                                .copy_from_slice(&internal.header.partial[..sub_prefix]);
+                            prefix += sub_prefix;
+                        }
+                        let prefix = min(prefix, MAX_PREFIX_LEN);
+                        internal.header.partial[..prefix]
+                            .copy_from_slice(&header.partial[..prefix]);
+                        internal.header.partial_len += header.partial_len + 1;
+                        Node::Internal(internal)
+                    }
+                    leaf => leaf,
+                };
+            }
+            _ if n <= sizing.grow4 => {
+                let mut keys = [0; 4];
+                let mut nodes = [Node::INIT; 4];
+                for (i, (key, child)) in children.into_iter().enumerate() {
+                    keys[i] = key;
+                    nodes[i] = child;
+                }
+                ArtNodeInternalInner::Node4 {
+                    keys,
+                    children: Box::new(nodes),
+                }
+            }
+            _ if n <= sizing.grow16 => {
+                let mut keys = [0; 16];
+                let mut nodes = [Node::INIT; 16];
+                for (i, (key, child)) in children.into_iter().enumerate() {
+                    keys[i] = key;
+                    nodes[i] = child;
+                }
+                ArtNodeInternalInner::Node16 {
+                    keys,
+                    children: Box::new(nodes),
+                }
+            }
+            _ if n <= sizing.grow32 => {
+                let mut keys = [0; 32];
+                let mut nodes = [Node::INIT; 32];
+                for (i, (key, child)) in children.into_iter().enumerate() {
+                    keys[i] = key;
+                    nodes[i] = child;
+                }
+                ArtNodeInternalInner::Node32 {
+                    keys,
+                    children: Box::new(nodes),
+                }
+            }
+            _ if n <= sizing.grow48 => {
+                let mut keys = [0; 256];
+                let mut nodes = [Node::INIT; 48];
+                for (i, (key, child)) in children.into_iter().enumerate() {
+                    keys[key as usize] = i as u8 + 1;
+                    nodes[i] = child;
+                }
+                ArtNodeInternalInner::Node48 {
+                    keys: Box::new(keys),
+                    children: Box::new(nodes),
+                }
+            }
+            _ => {
+                let mut nodes = [Node::INIT; 256];
+                for (key, child) in children {
+                    nodes[key as usize] = child;
+                }
+                ArtNodeInternalInner::Node256 {
+                    children: Box::new(nodes),
+                }
+            }
+        };
+        Node::Internal(Box::new(ArtNodeInternal::new(header, inner)))
+    }
+}
+
+impl<V, K> ArtNodeInternal<V, K> {
+    /// Returns true if a child has a key byte in `from..=to`.
+    fn has_child_within(&self, from: u8, to: u8) -> bool {
+        let n = self.header.num_children as usize;
+        let (from, to) = (from as usize, to as usize);
+        match &self.inner {
+            ArtNodeInternalInner::Node4 { keys, .. } => keys[..n]
+                .iter()
+                .any(|&key| (from..=to).contains(&(key as usize))),
+            ArtNodeInternalInner::Node16 { keys, .. } => keys[..n]
+                .iter()
+                .any(|&key| (from..=to).contains(&(key as usize))),
+            ArtNodeInternalInner::Node32 { keys, .. } => keys[..n]
+                .iter()
+                .any(|&key| (from..=to).contains(&(key as usize))),
+            ArtNodeInternalInner::Node48 { keys, .. } => {
+                keys[from..=to].iter().any(|&idx| idx != 0)
+            }
+            ArtNodeInternalInner::Node256 { children } => {
+                children[from..=to].iter().any(|child| !child.is_empty())
+            }
+        }
+    }
+
+    /// Calls `f` with every child in ascending key byte order, keeping the caches of the node.
+    fn for_each_child_mut(&mut self, mut f: impl FnMut(&mut Node<V, K>)) {
+        let n = self.header.num_children as usize;
+        match &mut self.inner {
+            ArtNodeInternalInner::Node4 { children, .. } => children[..n].iter_mut().for_each(f),
+            ArtNodeInternalInner::Node16 { children, .. } => children[..n].iter_mut().for_each(f),
+            ArtNodeInternalInner::Node32 { children, .. } => children[..n].iter_mut().for_each(f),
+            ArtNodeInternalInner::Node48 { keys, children } => {
+                for &idx in keys.iter().filter(|&&idx| idx != 0) {
+                    f(&mut children[idx as usize - 1]);
+                }
+            }
+            ArtNodeInternalInner::Node256 { children } => children
+                .iter_mut()
+                .filter(|child| !child.is_empty())
+                .for_each(f),
+        }
+    }
+
+    /// Moves the children out of the node together with their key bytes, in ascending order.
+    pub(super) fn into_keyed_children(self) -> Vec<(u8, Node<V, K>)> {
+        let n = self.header.num_children as usize;
+        match self.inner {
+            ArtNodeInternalInner::Node4 { keys, children } => keys
+                .iter()
+                .copied()
+                .zip(IntoIterator::into_iter(*children))
+                .take(n)
+                .collect(),
+            ArtNodeInternalInner::Node16 { keys, children } => keys
+                .iter()
+                .copied()
+                .zip(IntoIterator::into_iter(*children))
+                .take(n)
+                .collect(),
+            ArtNodeInternalInner::Node32 { keys, children } => keys
+                .iter()
+                .copied()
+                .zip(IntoIterator::into_iter(*children))
+                .take(n)
+                .collect(),
+            ArtNodeInternalInner::Node48 { keys, mut children } => keys
+                .iter()
+                .enumerate()
+                .filter(|&(_, &idx)| idx != 0)
+                .map(|(key, &idx)| (key as u8, mem::take(&mut children[idx as usize - 1])))
+                .collect(),
+            ArtNodeInternalInner::Node256 { children } => IntoIterator::into_iter(*children)
+                .enumerate()
+                .filter(|(_, child)| !child.is_empty())
+                .map(|(key, child)| (key as u8, child))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use rand::Rng;
+
+use super::{ArtNodeLeaf, ArtTree, LeafKey, Node};
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Returns `n` randomly chosen entries, drawn independently (so the result may contain
+    /// duplicates).
+    ///
+    /// Each draw is a single descent from the root. As nodes do not know the size of their
+    /// subtrees, a child is picked with a probability proportional to its own number of
+    /// children, which makes the samples approximately uniform on reasonably balanced trees.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R, n: usize) -> Vec<(&[u8], &V)> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        (0..n)
+            .map(|_| {
+                let leaf = self.root.sample_leaf(rng);
+                (leaf.key(), &leaf.value)
+            })
+            .collect()
+    }
+}
+
+impl<V, K> Node<V, K> {
+    fn sample_weight(&self) -> u32 {
+        match self {
+            Node::Empty => 0,
+            Node::Leaf(_) => 1,
+            Node::Internal(internal) => u32::from(internal.header.num_children),
+        }
+    }
+
+    fn sample_leaf<R: Rng + ?Sized>(&self, rng: &mut R) -> &ArtNodeLeaf<V, K> {
+        let mut node = self;
+        loop {
+            match node {
+                Node::Leaf(leaf) => return leaf,
+                Node::Internal(internal) => {
+                    let total: u32 = internal.children_from(0).map(Node::sample_weight).sum();
+                    let mut pick = rng.gen_range(0..total);
+                    for child in internal.children_from(0) {
+                        let weight = child.sample_weight();
+                        if pick < weight {
+                            node = child;
+                            break;
+                        }
+                        pick -= weight;
+                    }
+                }
+                Node::Empty => unreachable!("sampled an empty node"),
+            }
+        }
+    }
+}
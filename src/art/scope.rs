@@ -0,0 +1,169 @@
+use std::ops::Bound;
+
+use super::iter::prefix_end;
+use super::{ArtTree, LeafKey};
+
+/// A view of the entries of an `ArtTree` whose keys start with a prefix, used with keys
+/// relative to the prefix.
+///
+/// Created by [`ArtTree::scoped`], and nested with [`Scope::scoped`], e.g. a scope per tenant
+/// and within it a scope per collection of a `tenant/collection/key` layout.
+pub struct Scope<'a, V, K = Box<[u8]>> {
+    tree: &'a ArtTree<V, K>,
+    prefix: Vec<u8>,
+}
+
+/// A mutable view of the entries of an `ArtTree` whose keys start with a prefix, used with
+/// keys relative to the prefix.
+///
+/// Created by [`ArtTree::scoped_mut`], and nested with [`ScopeMut::scoped_mut`].
+pub struct ScopeMut<'a, V, K = Box<[u8]>> {
+    tree: &'a mut ArtTree<V, K>,
+    prefix: Vec<u8>,
+}
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Returns a view of the entries whose keys start with `prefix`.
+    pub fn scoped(&self, prefix: &[u8]) -> Scope<'_, V, K> {
+        Scope {
+            tree: self,
+            prefix: prefix.to_vec(),
+        }
+    }
+
+    /// Returns a mutable view of the entries whose keys start with `prefix`, through which keys
+    /// are inserted and deleted relative to the prefix.
+    pub fn scoped_mut(&mut self, prefix: &[u8]) -> ScopeMut<'_, V, K> {
+        ScopeMut {
+            tree: self,
+            prefix: prefix.to_vec(),
+        }
+    }
+}
+
+/// Returns `prefix` followed by `key`
+fn join(prefix: &[u8], key: &[u8]) -> Vec<u8> {
+    let mut joined = Vec::with_capacity(prefix.len() + key.len());
+    joined.extend_from_slice(prefix);
+    joined.extend_from_slice(key);
+    joined
+}
+
+impl<'a, V, K: LeafKey> Scope<'a, V, K> {
+    /// Returns the prefix of the scope, including the prefixes of the scopes it is nested in
+    pub fn prefix(&self) -> &[u8] {
+        &self.prefix
+    }
+
+    /// Returns a view of the entries of this scope whose relative keys start with `prefix`
+    pub fn scoped(&self, prefix: &[u8]) -> Scope<'a, V, K> {
+        Scope {
+            tree: self.tree,
+            prefix: join(&self.prefix, prefix),
+        }
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&'a V> {
+        self.tree.get(&join(&self.prefix, key))
+    }
+
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.tree.contains_key(&join(&self.prefix, key))
+    }
+
+    /// Returns the number of entries in the scope. Takes time linear in it.
+    pub fn len(&self) -> usize {
+        self.tree.scan_prefix(&self.prefix).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.scan_prefix(&self.prefix).next().is_none()
+    }
+
+    /// Returns an iterator over the entries of the scope in ascending key order, with the keys
+    /// relative to the prefix
+    pub fn entries(&self) -> impl Iterator<Item = (&'a [u8], &'a V)> + 'a {
+        let len = self.prefix.len();
+        self.tree
+            .scan_prefix(&self.prefix)
+            .map(move |(key, value)| (&key[len..], value))
+    }
+}
+
+impl<'a, V, K: LeafKey> ScopeMut<'a, V, K> {
+    /// Returns the prefix of the scope, including the prefixes of the scopes it is nested in
+    pub fn prefix(&self) -> &[u8] {
+        &self.prefix
+    }
+
+    /// Returns a read-only view of this scope
+    pub fn as_scope(&self) -> Scope<'_, V, K> {
+        Scope {
+            tree: self.tree,
+            prefix: self.prefix.clone(),
+        }
+    }
+
+    /// Returns a mutable view of the entries of this scope whose relative keys start with
+    /// `prefix`
+    pub fn scoped_mut(&mut self, prefix: &[u8]) -> ScopeMut<'_, V, K> {
+        ScopeMut {
+            tree: self.tree,
+            prefix: join(&self.prefix, prefix),
+        }
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&V> {
+        self.tree.get(&join(&self.prefix, key))
+    }
+
+    pub fn get_mut(&mut self, key: &[u8]) -> Option<&mut V> {
+        self.tree.get_mut(&join(&self.prefix, key))
+    }
+
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.tree.contains_key(&join(&self.prefix, key))
+    }
+
+    /// Inserts a value at the prefixed key and returns the previous one, like
+    /// [`ArtTree::insert`].
+    pub fn insert(&mut self, key: &[u8], value: V) -> Option<V> {
+        self.tree.insert(&join(&self.prefix, key), value)
+    }
+
+    /// Deletes the value at the prefixed key and returns it, like [`ArtTree::delete`].
+    pub fn delete(&mut self, key: &[u8]) -> Option<V> {
+        self.tree.delete(&join(&self.prefix, key))
+    }
+
+    /// Removes every entry of the scope and returns the number of removed entries. Subtrees
+    /// within the scope are detached as a whole, see [`ArtTree::drain_range`].
+    pub fn clear(&mut self) -> usize {
+        let end = prefix_end(&self.prefix);
+        let end = match &end {
+            Some(end) => Bound::Excluded(&end[..]),
+            None => Bound::Unbounded,
+        };
+        self.tree
+            .drain_range((Bound::Included(&self.prefix[..]), end))
+            .len()
+    }
+
+    /// Returns an iterator over the entries of the scope in ascending key order, with the keys
+    /// relative to the prefix
+    pub fn entries(&self) -> impl Iterator<Item = (&[u8], &V)> + '_ {
+        let len = self.prefix.len();
+        self.tree
+            .scan_prefix(&self.prefix)
+            .map(move |(key, value)| (&key[len..], value))
+    }
+
+    /// Returns an iterator over the entries of the scope in ascending key order, with the keys
+    /// relative to the prefix and mutable references to the values
+    pub fn entries_mut(&mut self) -> impl Iterator<Item = (&[u8], &mut V)> + '_ {
+        let len = self.prefix.len();
+        self.tree
+            .iter_prefix_mut(&self.prefix)
+            .map(move |(key, value)| (&key[len..], value))
+    }
+}
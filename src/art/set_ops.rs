@@ -0,0 +1,302 @@
+use std::cmp::min;
+use std::iter::Peekable;
+use std::vec;
+
+use super::iter::RawIter;
+use super::{ArtNodeLeaf, ArtTree, InternalNodeHeader, LeafKey, Node, NodeSizing, MAX_PREFIX_LEN};
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Returns a new tree with the entries of both trees, combining the values of keys present in
+    /// both with `merge`.
+    ///
+    /// Both trees are descended together: subtrees found in only one of them, such as those below
+    /// diverging prefixes, are cloned as a whole. The result has the settings of this tree.
+    pub fn union_with<F>(&self, other: &Self, mut merge: F) -> Self
+    where
+        K: Clone,
+        V: Clone,
+        F: FnMut(&[u8], &V, &V) -> V,
+    {
+        let root = union(&self.root, 0, &other.root, 0, 0, &mut merge, &self.sizing);
+        self.with_root(root)
+    }
+
+    /// Returns a new tree with the entries of this tree whose keys are also in `other`.
+    ///
+    /// Both trees are descended together, skipping the subtrees of either side that have no
+    /// counterpart in the other. The result has the settings of this tree.
+    pub fn intersection<W, L: LeafKey>(&self, other: &ArtTree<W, L>) -> Self
+    where
+        K: Clone,
+        V: Clone,
+    {
+        let root = intersection(&self.root, 0, &other.root, 0, 0, &self.sizing);
+        self.with_root(root)
+    }
+
+    /// Returns a new tree with the entries of this tree whose keys are not in `other`.
+    ///
+    /// Both trees are descended together: subtrees of this tree that have no counterpart in
+    /// `other` are cloned as a whole. The result has the settings of this tree.
+    pub fn difference<W, L: LeafKey>(&self, other: &ArtTree<W, L>) -> Self
+    where
+        K: Clone,
+        V: Clone,
+    {
+        let root = difference(&self.root, 0, &other.root, 0, 0, &self.sizing);
+        self.with_root(root)
+    }
+
+    /// Returns a tree holding `root`, with the node sizing, key length limit, merge operator,
+    /// duplicate policy and Bloom filter of this tree.
+    fn with_root(&self, root: Node<V, K>) -> Self {
+        let mut tree = Self {
+            size: RawIter::new(&root).count() as u64,
+            root,
+            merge_operator: self.merge_operator.clone(),
+            sizing: self.sizing,
+            max_key_len: self.max_key_len,
+            duplicates: self.duplicates,
+            ..Self::default()
+        };
+        if let Some(bits_per_key) = self.bloom.bits_per_key() {
+            tree.enable_bloom_filter(bits_per_key);
+        }
+        tree
+    }
+}
+
+// The functions below combine a node `a`, whose compressed path starts at `a_start`, with a node
+// `b` starting at `b_start`, both of which match the keys up to `depth`. They return the node
+// replacing both at `depth`. Leaves count as nodes whose path is the rest of their key.
+
+fn union<V: Clone, K: LeafKey + Clone>(
+    a: &Node<V, K>,
+    a_start: usize,
+    b: &Node<V, K>,
+    b_start: usize,
+    depth: usize,
+    merge: &mut dyn FnMut(&[u8], &V, &V) -> V,
+    sizing: &NodeSizing,
+) -> Node<V, K> {
+    if a.is_empty() {
+        return clone_at(b, b_start, depth);
+    }
+    if b.is_empty() {
+        return clone_at(a, a_start, depth);
+    }
+    let (a_path, b_path) = (path(a, a_start, depth), path(b, b_start, depth));
+    let m = common_len(a_path, b_path);
+    if let (Node::Leaf(l), Node::Leaf(r)) = (a, b) {
+        if m == a_path.len() && m == b_path.len() {
+            return Node::Leaf(Box::new(ArtNodeLeaf {
+                value: merge(l.key(), &l.value, &r.value),
+                key: l.key.clone(),
+            }));
+        }
+    }
+
+    let child_depth = depth + m + 1;
+    let children = Branches::new(
+        branches(a, a_start, a_path, m, child_depth),
+        branches(b, b_start, b_path, m, child_depth),
+    )
+    .map(|(c, left, right)| {
+        let child = match (left, right) {
+            (Some((l, l_start)), Some((r, r_start))) => {
+                union(l, l_start, r, r_start, child_depth, merge, sizing)
+            }
+            (Some((node, start)), None) | (None, Some((node, start))) => {
+                clone_at(node, start, child_depth)
+            }
+            (None, None) => unreachable!(),
+        };
+        (c, child)
+    })
+    .collect();
+    Node::from_children(header(&a_path[..m]), children, sizing)
+}
+
+fn intersection<V: Clone, K: LeafKey + Clone, W, L: LeafKey>(
+    a: &Node<V, K>,
+    a_start: usize,
+    b: &Node<W, L>,
+    b_start: usize,
+    depth: usize,
+    sizing: &NodeSizing,
+) -> Node<V, K> {
+    if a.is_empty() || b.is_empty() {
+        return Node::Empty;
+    }
+    let (a_path, b_path) = (path(a, a_start, depth), path(b, b_start, depth));
+    let m = common_len(a_path, b_path);
+    if let (Node::Leaf(_), Node::Leaf(_)) = (a, b) {
+        if m == a_path.len() && m == b_path.len() {
+            return clone_at(a, a_start, depth);
+        }
+    }
+
+    let child_depth = depth + m + 1;
+    let children = Branches::new(
+        branches(a, a_start, a_path, m, child_depth),
+        branches(b, b_start, b_path, m, child_depth),
+    )
+    .filter_map(|(c, left, right)| match (left, right) {
+        (Some((l, l_start)), Some((r, r_start))) => {
+            let child = intersection(l, l_start, r, r_start, child_depth, sizing);
+            Some((c, child)).filter(|(_, child)| !child.is_empty())
+        }
+        _ => None,
+    })
+    .collect();
+    Node::from_children(header(&a_path[..m]), children, sizing)
+}
+
+fn difference<V: Clone, K: LeafKey + Clone, W, L: LeafKey>(
+    a: &Node<V, K>,
+    a_start: usize,
+    b: &Node<W, L>,
+    b_start: usize,
+    depth: usize,
+    sizing: &NodeSizing,
+) -> Node<V, K> {
+    if a.is_empty() {
+        return Node::Empty;
+    }
+    if b.is_empty() {
+        return clone_at(a, a_start, depth);
+    }
+    let (a_path, b_path) = (path(a, a_start, depth), path(b, b_start, depth));
+    let m = common_len(a_path, b_path);
+    if let (Node::Leaf(_), Node::Leaf(_)) = (a, b) {
+        if m == a_path.len() && m == b_path.len() {
+            return Node::Empty;
+        }
+    }
+
+    let child_depth = depth + m + 1;
+    let children = Branches::new(
+        branches(a, a_start, a_path, m, child_depth),
+        branches(b, b_start, b_path, m, child_depth),
+    )
+    .filter_map(|(c, left, right)| {
+        let child = match (left, right) {
+            (Some((l, l_start)), Some((r, r_start))) => {
+                difference(l, l_start, r, r_start, child_depth, sizing)
+            }
+            (Some((node, start)), None) => clone_at(node, start, child_depth),
+            (None, _) => return None,
+        };
+        Some((c, child)).filter(|(_, child)| !child.is_empty())
+    })
+    .collect();
+    Node::from_children(header(&a_path[..m]), children, sizing)
+}
+
+/// Returns the part of the path of a non-empty node after `depth`
+pub(super) fn path<V, K: LeafKey>(node: &Node<V, K>, start: usize, depth: usize) -> &[u8] {
+    match node {
+        Node::Leaf(leaf) => &leaf.key()[depth..],
+        Node::Internal(internal) => {
+            let len = internal.header.partial_len;
+            if len <= MAX_PREFIX_LEN {
+                &internal.header.partial[depth - start..len]
+            } else {
+                &internal.minimum().unwrap().key()[depth..start + len]
+            }
+        }
+        Node::Empty => &[],
+    }
+}
+
+pub(super) fn common_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+/// Returns the header of a new node with the given path
+fn header(path: &[u8]) -> InternalNodeHeader {
+    let mut partial = [0; MAX_PREFIX_LEN];
+    let copy_len = min(path.len(), MAX_PREFIX_LEN);
+    partial[..copy_len].copy_from_slice(&path[..copy_len]);
+    InternalNodeHeader {
+        partial_len: path.len(),
+        num_children: 0,
+        partial,
+    }
+}
+
+/// Clones the node, dropping the part of its path before `depth`
+fn clone_at<V: Clone, K: LeafKey + Clone>(
+    node: &Node<V, K>,
+    start: usize,
+    depth: usize,
+) -> Node<V, K> {
+    match node {
+        Node::Internal(internal) if depth > start => {
+            let mut clone = internal.clone();
+            clone.header = InternalNodeHeader {
+                num_children: internal.header.num_children,
+                ..header(path(node, start, depth))
+            };
+            clone.invalidate_caches();
+            Node::Internal(clone)
+        }
+        _ => node.clone(),
+    }
+}
+
+/// A node together with the depth its compressed path starts at
+pub(super) type Placed<'a, V, K> = (&'a Node<V, K>, usize);
+
+/// Returns what follows the first `m` bytes of the path of a node, by key byte: the node itself
+/// if its path continues, or its children (starting at `child_depth`) if the path ends there.
+pub(super) fn branches<'a, V, K>(
+    node: &'a Node<V, K>,
+    start: usize,
+    path: &[u8],
+    m: usize,
+    child_depth: usize,
+) -> Vec<(u8, Placed<'a, V, K>)> {
+    if m < path.len() {
+        return vec![(path[m], (node, start))];
+    }
+    match node {
+        Node::Internal(internal) => internal
+            .keyed_children()
+            .into_iter()
+            .map(|(c, child)| (c, (child, child_depth)))
+            .collect(),
+        _ => panic!("ArtTree keys must not be prefixes of each other"),
+    }
+}
+
+/// Pairs up the branches of two nodes by key byte, in ascending order.
+pub(super) struct Branches<A, B> {
+    left: Peekable<vec::IntoIter<(u8, A)>>,
+    right: Peekable<vec::IntoIter<(u8, B)>>,
+}
+
+impl<A, B> Branches<A, B> {
+    pub(super) fn new(left: Vec<(u8, A)>, right: Vec<(u8, B)>) -> Self {
+        Self {
+            left: left.into_iter().peekable(),
+            right: right.into_iter().peekable(),
+        }
+    }
+}
+
+impl<A, B> Iterator for Branches<A, B> {
+    type Item = (u8, Option<A>, Option<B>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let c = match (self.left.peek(), self.right.peek()) {
+            (Some(&(l, _)), Some(&(r, _))) => min(l, r),
+            (Some(&(l, _)), None) => l,
+            (None, Some(&(r, _))) => r,
+            (None, None) => return None,
+        };
+        let left = self.left.next_if(|&(l, _)| l == c).map(|(_, a)| a);
+        let right = self.right.next_if(|&(r, _)| r == c).map(|(_, b)| b);
+        Some((c, left, right))
+    }
+}
@@ -0,0 +1,171 @@
+use super::NodeKind;
+
+/// Numbers of children at which the internal nodes of an [`ArtTree`](super::ArtTree) change
+/// their type, set with [`ArtTree::with_node_sizing`](super::ArtTree::with_node_sizing).
+///
+/// A node grows into the next larger type when a child is added while it holds its `grow`
+/// number of children, and shrinks into the next smaller type when a delete leaves it with its
+/// `shrink` number of children, or with the `adaptive` feature also depending on how often the
+/// node is accessed, see [`hot_accesses`](Self::hot_accesses). The gap between the points of adjacent types keeps a node
+/// sitting on a boundary from being converted back and forth.
+///
+/// Every node type is allocated at its own size, so the points trade memory against lookup
+/// speed: growing late and shrinking early keeps nodes in the smaller types, with their dense
+/// child arrays that iterate and clone without skipping over empty slots, while growing early
+/// gives more nodes the direct child lookups of Node48 and Node256.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeSizing {
+    /// Children at which a Node4 grows into a Node16, 3 to 4
+    pub grow4: u16,
+    /// Children at which a Node16 grows into a Node32, `grow4 + 1` to 16
+    pub grow16: u16,
+    /// Children at which a Node32 grows into a Node48, `grow16 + 1` to 32
+    pub grow32: u16,
+    /// Children at which a Node48 grows into a Node256, `grow32 + 1` to 48
+    pub grow48: u16,
+    /// Children at which a Node16 shrinks into a Node4, 2 to `grow4 - 1`
+    pub shrink16: u16,
+    /// Children at which a Node32 shrinks into a Node16, `shrink16 + 1` to `grow16 - 1`
+    pub shrink32: u16,
+    /// Children at which a Node48 shrinks into a Node32, `shrink32 + 1` to `grow32 - 1`
+    pub shrink48: u16,
+    /// Children at which a Node256 shrinks into a Node48, `shrink48 + 1` to `grow48 - 1`
+    pub shrink256: u16,
+    /// Point lookups and mutations passing through a node after which it counts as hot, at
+    /// least 1. Only used with the `adaptive` feature.
+    ///
+    /// Hot nodes grow at the `grow` points and keep their type until they are left with the
+    /// fewest children the ranges above allow, e.g. `shrink48 + 1` for a Node256. Cold nodes
+    /// grow only once they are full and shrink at the `shrink` points. Every postponed shrink
+    /// halves the count of the node and the count is capped at four times this threshold, so
+    /// a node stays hot only while it keeps being accessed.
+    pub hot_accesses: u32,
+}
+
+impl NodeSizing {
+    /// Grows nodes when they are full and shrinks them about a quarter below the capacity of the
+    /// smaller type.
+    pub const DEFAULT: Self = Self {
+        grow4: 4,
+        grow16: 16,
+        grow32: 32,
+        grow48: 48,
+        shrink16: 3,
+        shrink32: 12,
+        shrink48: 24,
+        shrink256: 37,
+        hot_accesses: 256,
+    };
+
+    /// Grows nodes when they are full and shrinks them as soon as the children fit into the
+    /// smaller type, using the least memory, for workloads dominated by scans.
+    pub const COMPACT: Self = Self {
+        grow4: 4,
+        grow16: 16,
+        grow32: 32,
+        grow48: 48,
+        shrink16: 3,
+        shrink32: 15,
+        shrink48: 31,
+        shrink256: 47,
+        hot_accesses: 256,
+    };
+
+    /// Grows nodes into the types with direct child lookups early, passing over Node32, and
+    /// shrinks them late, for workloads dominated by point lookups.
+    pub const FAST: Self = Self {
+        grow4: 4,
+        grow16: 8,
+        grow32: 9,
+        grow48: 24,
+        shrink16: 2,
+        shrink32: 3,
+        shrink48: 4,
+        shrink256: 12,
+        hot_accesses: 256,
+    };
+
+    /// Panics if the points are out of their ranges
+    pub(super) fn check(&self) {
+        let ranges = [
+            ("grow4", self.grow4, 3, 4),
+            ("grow16", self.grow16, self.grow4.saturating_add(1), 16),
+            ("grow32", self.grow32, self.grow16.saturating_add(1), 32),
+            ("grow48", self.grow48, self.grow32.saturating_add(1), 48),
+            ("shrink16", self.shrink16, 2, self.grow4.saturating_sub(1)),
+            (
+                "shrink32",
+                self.shrink32,
+                self.shrink16.saturating_add(1),
+                self.grow16.saturating_sub(1),
+            ),
+            (
+                "shrink48",
+                self.shrink48,
+                self.shrink32.saturating_add(1),
+                self.grow32.saturating_sub(1),
+            ),
+            (
+                "shrink256",
+                self.shrink256,
+                self.shrink48.saturating_add(1),
+                self.grow48.saturating_sub(1),
+            ),
+        ];
+        assert!(
+            self.hot_accesses >= 1,
+            "hot_accesses is 0, expected at least 1"
+        );
+        for &(name, value, low, high) in ranges.iter() {
+            assert!(
+                low <= value && value <= high,
+                "{} is {}, expected {} to {}",
+                name,
+                value,
+                low,
+                high
+            );
+        }
+    }
+
+    /// Returns the number of children at which a node of the given type grows
+    pub(super) fn grow_point(&self, kind: NodeKind) -> u16 {
+        match kind {
+            NodeKind::Node4 => self.grow4,
+            NodeKind::Node16 => self.grow16,
+            NodeKind::Node32 => self.grow32,
+            NodeKind::Node48 => self.grow48,
+            NodeKind::Node256 => u16::MAX,
+        }
+    }
+
+    /// Returns the number of children at which a node of the given type shrinks
+    pub(super) fn shrink_point(&self, kind: NodeKind) -> u16 {
+        match kind {
+            NodeKind::Node4 => 0,
+            NodeKind::Node16 => self.shrink16,
+            NodeKind::Node32 => self.shrink32,
+            NodeKind::Node48 => self.shrink48,
+            NodeKind::Node256 => self.shrink256,
+        }
+    }
+
+    /// Returns the fewest children a node of the given type can shrink at without converting
+    /// back and forth
+    #[cfg(feature = "adaptive")]
+    pub(super) fn shrink_floor(&self, kind: NodeKind) -> u16 {
+        match kind {
+            NodeKind::Node4 => 0,
+            NodeKind::Node16 => 2,
+            NodeKind::Node32 => self.shrink16 + 1,
+            NodeKind::Node48 => self.shrink32 + 1,
+            NodeKind::Node256 => self.shrink48 + 1,
+        }
+    }
+}
+
+impl Default for NodeSizing {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
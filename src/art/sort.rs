@@ -0,0 +1,39 @@
+use super::ArtTree;
+
+/// Sorts `items` by the bytes returned by `key_fn`, comparing them lexicographically.
+///
+/// This is a radix sort: the items are inserted into an `ArtTree` and read back in key order.
+/// Keys do not need to be prefix-free, and the sort is stable: items with equal keys keep their
+/// relative order.
+pub fn sort_by_key_bytes<T, B, F>(items: impl IntoIterator<Item = T>, mut key_fn: F) -> Vec<T>
+where
+    B: AsRef<[u8]>,
+    F: FnMut(&T) -> B,
+{
+    let mut tree = ArtTree::<Vec<T>>::new();
+    let mut len = 0;
+    let mut key = Vec::new();
+    for item in items {
+        key.clear();
+        encode_prefix_free(key_fn(&item).as_ref(), &mut key);
+        tree.get_or_insert_with(&key, Vec::new).push(item);
+        len += 1;
+    }
+
+    let mut sorted = Vec::with_capacity(len);
+    sorted.extend(tree.into_iter().flat_map(|(_, items)| items));
+    sorted
+}
+
+/// Appends an encoding of `key` to `out` that keeps the byte order but is prefix-free: every
+/// `00` byte is escaped as `00 ff` and the key is terminated by `00 00`.
+pub(crate) fn encode_prefix_free(key: &[u8], out: &mut Vec<u8>) {
+    out.reserve(key.len() + 2);
+    for &b in key {
+        out.push(b);
+        if b == 0 {
+            out.push(0xff);
+        }
+    }
+    out.extend_from_slice(&[0, 0]);
+}
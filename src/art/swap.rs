@@ -0,0 +1,105 @@
+use std::cmp::min;
+use std::mem;
+
+use super::{ArtNodeInternal, ArtNodeInternalInner, ArtTree, LeafKey, Node, MAX_PREFIX_LEN};
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Exchanges the values stored at the keys `a` and `b` in place, without restructuring the
+    /// tree. Returns false and leaves the tree unchanged unless both keys are present.
+    pub fn swap(&mut self, a: &[u8], b: &[u8]) -> bool {
+        if a == b {
+            return self.contains_key(a);
+        }
+        match self.root.pair_mut(a, b, 0) {
+            Some((value_a, value_b)) => {
+                mem::swap(value_a, value_b);
+                self.dirty.mark(a);
+                self.dirty.mark(b);
+                self.observers.replaced(a, value_b, value_a);
+                self.observers.replaced(b, value_a, value_b);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<V, K: LeafKey> Node<V, K> {
+    /// Returns the values stored at the distinct keys `a` and `b`, if both are present below
+    /// this node. The keys are looked up together until their paths part.
+    fn pair_mut(&mut self, a: &[u8], b: &[u8], mut depth: usize) -> Option<(&mut V, &mut V)> {
+        let internal = match self {
+            Node::Internal(internal) => internal,
+            // A leaf holds a single key
+            Node::Empty | Node::Leaf(_) => return None,
+        };
+        internal.invalidate_caches();
+        let header = internal.header;
+        if header.partial_len != 0 {
+            let stored = min(MAX_PREFIX_LEN, header.partial_len);
+            if header.check_prefix(a, depth) != stored || header.check_prefix(b, depth) != stored {
+                return None;
+            }
+            depth += header.partial_len;
+        }
+
+        let (c_a, c_b) = (*a.get(depth)?, *b.get(depth)?);
+        if c_a == c_b {
+            return internal.find_child_mut(c_a)?.pair_mut(a, b, depth + 1);
+        }
+        internal.split_pair_mut(a, b, depth)
+    }
+
+    /// Returns the value stored at `key` below this node, invalidating the hashes on the path
+    fn value_mut(&mut self, key: &[u8], mut depth: usize) -> Option<&mut V> {
+        let mut node = self;
+        loop {
+            match node {
+                Node::Empty => return None,
+                Node::Leaf(leaf) if leaf.matches(key) => return Some(&mut leaf.value),
+                Node::Leaf(_) => return None,
+                Node::Internal(internal) => {
+                    internal.invalidate_caches();
+                    let header = internal.header;
+                    if header.partial_len != 0 {
+                        if header.check_prefix(key, depth)
+                            != min(MAX_PREFIX_LEN, header.partial_len)
+                        {
+                            return None;
+                        }
+                        depth += header.partial_len;
+                    }
+                    node = internal.find_child_mut(*key.get(depth)?)?;
+                    depth += 1;
+                }
+            }
+        }
+    }
+}
+
+impl<V, K: LeafKey> ArtNodeInternal<V, K> {
+    /// Returns the values stored at `a` and `b`, which lead to different children of this node
+    /// at `depth`
+    fn split_pair_mut(&mut self, a: &[u8], b: &[u8], depth: usize) -> Option<(&mut V, &mut V)> {
+        let i = self.find_child_index(a[depth])?;
+        let j = self.find_child_index(b[depth])?;
+        let children: &mut [Node<V, K>] = match &mut self.inner {
+            ArtNodeInternalInner::Node4 { children, .. } => &mut children[..],
+            ArtNodeInternalInner::Node16 { children, .. } => &mut children[..],
+            ArtNodeInternalInner::Node32 { children, .. } => &mut children[..],
+            ArtNodeInternalInner::Node48 { children, .. } => &mut children[..],
+            ArtNodeInternalInner::Node256 { children } => &mut children[..],
+        };
+        let (child_a, child_b) = if i < j {
+            let (low, high) = children.split_at_mut(j);
+            (&mut low[i], &mut high[0])
+        } else {
+            let (low, high) = children.split_at_mut(i);
+            (&mut high[0], &mut low[j])
+        };
+        Some((
+            child_a.value_mut(a, depth + 1)?,
+            child_b.value_mut(b, depth + 1)?,
+        ))
+    }
+}
@@ -0,0 +1,123 @@
+//! Events for the structural decisions of the tree, emitted through the `tracing` crate. They
+//! compile to nothing without the `tracing` feature.
+//!
+//! Node resizes, prefix splits and detached subtrees are `DEBUG` events carrying the key bytes
+//! above the node as `path`. Bulk loads run in an `INFO` span named `bulk_load`, with the phase
+//! and the number of entries it handled.
+
+#[cfg(feature = "tracing")]
+use std::fmt;
+
+use super::{ArtNodeLeaf, LeafKey, Node, NodeKind};
+
+/// The key bytes leading to a node, printed as an escaped byte string
+#[cfg(feature = "tracing")]
+struct Path<'a>(&'a [u8]);
+
+#[cfg(feature = "tracing")]
+impl fmt::Debug for Path<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("b\"")?;
+        for &byte in self.0 {
+            write!(f, "{}", std::ascii::escape_default(byte))?;
+        }
+        f.write_str("\"")
+    }
+}
+
+/// Returns the first `depth` bytes of the key, or the whole key if it is shorter
+#[cfg(feature = "tracing")]
+fn path(key: &[u8], depth: usize) -> Path<'_> {
+    Path(&key[..depth.min(key.len())])
+}
+
+/// An internal node on the path of `key` at `depth` grows out of its node type
+#[cfg(feature = "tracing")]
+pub(super) fn node_upgrade(key: &[u8], depth: usize, from: NodeKind) {
+    tracing::debug!(path = ?path(key, depth), ?from, "node upgrade");
+}
+
+/// An internal node at `depth` shrinks into a smaller node type after `deleted` was removed
+/// below it
+#[cfg(feature = "tracing")]
+pub(super) fn node_downgrade<V, K: LeafKey>(
+    deleted: Option<&ArtNodeLeaf<V, K>>,
+    depth: usize,
+    from: NodeKind,
+) {
+    let key = deleted.map_or(&[][..], |leaf| leaf.key());
+    tracing::debug!(path = ?path(key, depth), ?from, "node downgrade");
+}
+
+/// The compressed path of `prefix_len` bytes of the node at `depth` on the path of `key` is
+/// split after `at` bytes. The event's `path` includes the `at` bytes the key shares with it.
+#[cfg(feature = "tracing")]
+pub(super) fn prefix_split(key: &[u8], depth: usize, prefix_len: usize, at: usize) {
+    tracing::debug!(path = ?path(key, depth + at), prefix_len, at, "prefix split");
+}
+
+/// A subtree at `depth` is detached as a whole
+#[cfg(feature = "tracing")]
+pub(super) fn subtree_detach<V, K: LeafKey>(node: &Node<V, K>, depth: usize) {
+    if let (Node::Internal(internal), Some(leaf)) = (node, node.minimum()) {
+        tracing::debug!(
+            path = ?path(leaf.key(), depth),
+            kind = ?internal.node_kind(),
+            children = internal.header.num_children,
+            "subtree detach"
+        );
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+#[inline(always)]
+pub(super) fn node_upgrade(_key: &[u8], _depth: usize, _from: NodeKind) {}
+
+#[cfg(not(feature = "tracing"))]
+#[inline(always)]
+pub(super) fn node_downgrade<V, K: LeafKey>(
+    _deleted: Option<&ArtNodeLeaf<V, K>>,
+    _depth: usize,
+    _from: NodeKind,
+) {
+}
+
+#[cfg(not(feature = "tracing"))]
+#[inline(always)]
+pub(super) fn prefix_split(_key: &[u8], _depth: usize, _prefix_len: usize, _at: usize) {}
+
+#[cfg(not(feature = "tracing"))]
+#[inline(always)]
+pub(super) fn subtree_detach<V, K: LeafKey>(_node: &Node<V, K>, _depth: usize) {}
+
+/// The span of a phase of a bulk load, entered until it is dropped
+pub(crate) struct BulkLoad {
+    #[cfg(feature = "tracing")]
+    span: tracing::span::EnteredSpan,
+}
+
+impl BulkLoad {
+    #[cfg(feature = "tracing")]
+    pub(crate) fn enter(phase: &'static str) -> Self {
+        let span = tracing::info_span!("bulk_load", phase, entries = tracing::field::Empty);
+        BulkLoad {
+            span: span.entered(),
+        }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    #[inline(always)]
+    pub(crate) fn enter(_phase: &'static str) -> Self {
+        BulkLoad {}
+    }
+
+    /// Records the number of entries the phase handled
+    #[cfg(feature = "tracing")]
+    pub(crate) fn entries(&self, entries: usize) {
+        self.span.record("entries", entries);
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    #[inline(always)]
+    pub(crate) fn entries(&self, _entries: usize) {}
+}
@@ -0,0 +1,82 @@
+use super::{ArtTree, LeafKey};
+
+/// A transaction over an `ArtTree` that records how to undo each of its mutations.
+///
+/// Created by [`ArtTree::begin`]. Mutations are applied to the tree immediately; dropping the
+/// transaction without calling [`commit`](Txn::commit) rolls them back.
+pub struct Txn<'a, V, K: LeafKey = Box<[u8]>> {
+    tree: &'a mut ArtTree<V, K>,
+    undo: Vec<(Box<[u8]>, Option<V>)>,
+}
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Starts a transaction whose mutations can be rolled back as a whole.
+    pub fn begin(&mut self) -> Txn<'_, V, K> {
+        Txn {
+            tree: self,
+            undo: Vec::new(),
+        }
+    }
+}
+
+impl<'a, V, K: LeafKey> Txn<'a, V, K> {
+    /// Returns a reference to the value stored at the given key, including uncommitted changes
+    pub fn get(&self, key: &[u8]) -> Option<&V> {
+        self.tree.get(key)
+    }
+
+    /// Returns true if a value is stored at the given key, including uncommitted changes
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.tree.contains_key(key)
+    }
+
+    /// Returns a mutable reference to the value stored at the given key. The current value is
+    /// cloned first so that it can be restored on rollback.
+    pub fn get_mut(&mut self, key: &[u8]) -> Option<&mut V>
+    where
+        V: Clone,
+    {
+        let old_value = self.tree.get(key)?.clone();
+        self.undo.push((key.into(), Some(old_value)));
+        self.tree.get_mut(key)
+    }
+
+    /// Inserts a value and returns the previous one, like [`ArtTree::insert`].
+    pub fn insert(&mut self, key: &[u8], value: V) -> Option<V>
+    where
+        V: Clone,
+    {
+        let old_value = self.tree.insert(key, value);
+        self.undo.push((key.into(), old_value.clone()));
+        old_value
+    }
+
+    /// Deletes the value stored at the given key and returns it, like [`ArtTree::delete`].
+    pub fn delete(&mut self, key: &[u8]) -> Option<V>
+    where
+        V: Clone,
+    {
+        let old_value = self.tree.delete(key)?;
+        self.undo.push((key.into(), Some(old_value.clone())));
+        Some(old_value)
+    }
+
+    /// Keeps the mutations made through the transaction.
+    pub fn commit(mut self) {
+        self.undo.clear();
+    }
+
+    /// Restores the tree to its state before the transaction.
+    pub fn rollback(self) {}
+}
+
+impl<'a, V, K: LeafKey> Drop for Txn<'a, V, K> {
+    fn drop(&mut self) {
+        while let Some((key, old_value)) = self.undo.pop() {
+            match old_value {
+                Some(value) => self.tree.insert(&key, value),
+                None => self.tree.delete(&key),
+            };
+        }
+    }
+}
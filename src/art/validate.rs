@@ -0,0 +1,292 @@
+use std::cmp::min;
+use std::fmt;
+
+use super::{ArtNodeInternal, ArtNodeInternalInner, ArtTree, LeafKey, Node, MAX_PREFIX_LEN};
+
+/// A broken structural invariant, found by [`ArtTree::check_invariants`] or by the checks of
+/// the `validate` feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvariantViolation {
+    /// Key bytes leading from the root to the offending node
+    pub path: Vec<u8>,
+    /// One line per node from the root down to the offending node
+    pub nodes: Vec<String>,
+    /// What is wrong with the offending node
+    pub message: String,
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at path ", self.message)?;
+        for byte in &self.path {
+            write!(f, "{:02x}", byte)?;
+        }
+        for (depth, node) in self.nodes.iter().enumerate() {
+            write!(f, "\n{:width$}{}", "", node, width = 2 * depth)?;
+        }
+        Ok(())
+    }
+}
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Checks the structural invariants of every node: the number of children and their layout
+    /// in the node, the compressed paths against the keys of the leaves below, and the number
+    /// of entries. Returns the first violation found.
+    pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        let mut checker = Checker::default();
+        let entries = checker.check_subtree(&self.root, 0, None)?;
+        if entries != self.size {
+            return Err(checker.violation(format!(
+                "the tree holds {} entries but counts {}",
+                entries, self.size
+            )));
+        }
+        Ok(())
+    }
+
+    /// With the `validate` feature, checks the nodes on the path towards `key` after a mutation
+    /// of it and panics on a violation
+    #[cfg(feature = "validate")]
+    pub(super) fn validate_path(&self, key: &[u8]) {
+        if let Err(violation) = Checker::default().check_path(&self.root, key) {
+            panic!("ArtTree invariant violated: {}", violation);
+        }
+    }
+
+    #[cfg(not(feature = "validate"))]
+    #[inline(always)]
+    pub(super) fn validate_path(&self, _key: &[u8]) {}
+}
+
+#[derive(Default)]
+struct Checker {
+    /// Key bytes shared by every key below the current node
+    path: Vec<u8>,
+    /// Descriptions of the nodes from the root to the current node
+    nodes: Vec<String>,
+}
+
+impl Checker {
+    fn violation(&self, message: String) -> InvariantViolation {
+        InvariantViolation {
+            path: self.path.clone(),
+            nodes: self.nodes.clone(),
+            message,
+        }
+    }
+
+    /// Checks every node of the subtree and returns its number of leaves
+    fn check_subtree<V, K: LeafKey>(
+        &mut self,
+        node: &Node<V, K>,
+        depth: usize,
+        key: Option<u8>,
+    ) -> Result<u64, InvariantViolation> {
+        self.nodes.push(describe(node, key));
+        let entries = match node {
+            Node::Empty => 0,
+            Node::Leaf(_) => {
+                self.check_node(node, depth)?;
+                1
+            }
+            Node::Internal(internal) => {
+                let path_len = self.path.len();
+                self.check_node(node, depth)?;
+                let child_depth = depth + internal.header.partial_len + 1;
+                let mut entries = 0;
+                for (c, child) in internal.keyed_children() {
+                    self.path.push(c);
+                    entries += self.check_subtree(child, child_depth, Some(c))?;
+                    self.path.pop();
+                }
+                self.path.truncate(path_len);
+                entries
+            }
+        };
+        self.nodes.pop();
+        Ok(entries)
+    }
+
+    /// Checks the nodes from the root towards `key`, as far as the key leads
+    #[cfg(feature = "validate")]
+    fn check_path<V, K: LeafKey>(
+        &mut self,
+        root: &Node<V, K>,
+        key: &[u8],
+    ) -> Result<(), InvariantViolation> {
+        let mut node = root;
+        let mut depth = 0;
+        let mut c = None;
+        loop {
+            self.nodes.push(describe(node, c));
+            self.check_node(node, depth)?;
+            let internal = match node {
+                Node::Internal(internal) => internal,
+                Node::Empty | Node::Leaf(_) => return Ok(()),
+            };
+            depth += internal.header.partial_len;
+            let byte = match key.get(depth) {
+                Some(&byte) if key[..depth] == self.path[..] => byte,
+                _ => return Ok(()),
+            };
+            node = match internal.find_child(byte) {
+                Some(child) => child,
+                None => return Ok(()),
+            };
+            self.path.push(byte);
+            c = Some(byte);
+            depth += 1;
+        }
+    }
+
+    /// Checks a single node at `depth` below the current path. The compressed path of an
+    /// internal node is appended to the path.
+    fn check_node<V, K: LeafKey>(
+        &mut self,
+        node: &Node<V, K>,
+        depth: usize,
+    ) -> Result<(), InvariantViolation> {
+        let internal = match node {
+            Node::Empty => return Ok(()),
+            Node::Leaf(leaf) if leaf.key().starts_with(&self.path) => return Ok(()),
+            Node::Leaf(_) => {
+                return Err(self.violation("the leaf key does not start with its path".into()))
+            }
+            Node::Internal(internal) => internal,
+        };
+        if let Err(message) = internal.check_layout() {
+            return Err(self.violation(message));
+        }
+
+        // The minimum and maximum leaves bound the keys below, they have to agree on the path
+        let header = &internal.header;
+        let path_end = depth + header.partial_len;
+        let (min_key, max_key) = match (internal.minimum(), internal.maximum()) {
+            (Some(min), Some(max)) => (min.key(), max.key()),
+            _ => return Err(self.violation("the node has no leaves".into())),
+        };
+        if min_key.len() <= path_end || max_key.len() <= path_end {
+            return Err(self.violation(format!(
+                "a key below ends within the compressed path of length {}",
+                header.partial_len
+            )));
+        }
+        if !min_key.starts_with(&self.path) || min_key[..path_end] != max_key[..path_end] {
+            return Err(self.violation("the keys below do not share the path".into()));
+        }
+        let stored = min(header.partial_len, MAX_PREFIX_LEN);
+        if header.partial[..stored] != min_key[depth..depth + stored] {
+            return Err(self.violation("the stored prefix differs from the keys below".into()));
+        }
+        for (c, child) in internal.keyed_children() {
+            let key = child.minimum().map(|leaf| leaf.key());
+            if key.and_then(|key| key.get(path_end)) != Some(&c) {
+                return Err(self.violation(format!(
+                    "the keys below child {:02x} continue with another byte",
+                    c
+                )));
+            }
+        }
+        self.path.extend_from_slice(&min_key[depth..path_end]);
+        Ok(())
+    }
+}
+
+impl<V, K> ArtNodeInternal<V, K> {
+    /// Checks the number of children and how they are laid out in the node
+    fn check_layout(&self) -> Result<(), String> {
+        let n = self.header.num_children as usize;
+        let capacity = self.node_kind().capacity();
+        if n < 2 || n > capacity {
+            return Err(format!(
+                "{:?} with {} children, expected 2 to {}",
+                self.node_kind(),
+                n,
+                capacity
+            ));
+        }
+
+        let occupied = match &self.inner {
+            ArtNodeInternalInner::Node4 { keys, children } => check_sorted(keys, &children[..], n)?,
+            ArtNodeInternalInner::Node16 { keys, children } => {
+                check_sorted(keys, &children[..], n)?
+            }
+            ArtNodeInternalInner::Node32 { keys, children } => {
+                check_sorted(keys, &children[..], n)?
+            }
+            ArtNodeInternalInner::Node48 { keys, children } => {
+                let mut referenced = [false; 48];
+                for (c, &idx) in keys.iter().enumerate().filter(|&(_, &idx)| idx != 0) {
+                    let slot = referenced.get_mut(idx as usize - 1).ok_or_else(|| {
+                        format!("key {:02x} refers to child slot {} of 48", c, idx)
+                    })?;
+                    if *slot {
+                        return Err(format!("key {:02x} refers to a shared child slot", c));
+                    }
+                    if children[idx as usize - 1].is_empty() {
+                        return Err(format!("key {:02x} refers to an empty child slot", c));
+                    }
+                    *slot = true;
+                }
+                let orphans = children
+                    .iter()
+                    .zip(referenced.iter())
+                    .filter(|&(child, &referenced)| !child.is_empty() && !referenced)
+                    .count();
+                if orphans > 0 {
+                    return Err(format!("{} children are not referenced by a key", orphans));
+                }
+                referenced.iter().filter(|&&referenced| referenced).count()
+            }
+            ArtNodeInternalInner::Node256 { children } => {
+                children.iter().filter(|child| !child.is_empty()).count()
+            }
+        };
+        if occupied != n {
+            return Err(format!(
+                "{} children are stored but {} are counted",
+                occupied, n
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Checks that the first `n` keys of a Node4, Node16 or Node32 ascend and refer to children, and that
+/// the remaining slots are empty. Returns the number of children.
+fn check_sorted<V, K>(keys: &[u8], children: &[Node<V, K>], n: usize) -> Result<usize, String> {
+    if let Some(i) = (1..n).find(|&i| keys[i - 1] >= keys[i]) {
+        return Err(format!(
+            "keys {:02x} and {:02x} are out of order",
+            keys[i - 1],
+            keys[i]
+        ));
+    }
+    if let Some(i) = (0..n).find(|&i| children[i].is_empty()) {
+        return Err(format!("key {:02x} refers to an empty child", keys[i]));
+    }
+    Ok(children.iter().filter(|child| !child.is_empty()).count())
+}
+
+fn describe<V, K: LeafKey>(node: &Node<V, K>, key: Option<u8>) -> String {
+    let label = key.map_or_else(String::new, |c| format!("{:02x}: ", c));
+    match node {
+        Node::Empty => format!("{}Empty", label),
+        Node::Leaf(leaf) => format!("{}Leaf {}", label, hex(leaf.key())),
+        Node::Internal(internal) => {
+            let header = &internal.header;
+            format!(
+                "{}{:?} prefix={} partial_len={} children={}",
+                label,
+                internal.node_kind(),
+                hex(&header.partial[..min(header.partial_len, MAX_PREFIX_LEN)]),
+                header.partial_len,
+                header.num_children
+            )
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
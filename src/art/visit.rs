@@ -0,0 +1,122 @@
+use std::cmp::min;
+
+use super::{ArtNodeInternal, ArtNodeInternalInner, ArtTree, LeafKey, Node, MAX_PREFIX_LEN};
+
+/// The type of an internal node, by the number of children it has room for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+    Node4,
+    Node16,
+    Node32,
+    Node48,
+    Node256,
+}
+
+impl NodeKind {
+    /// Returns the maximum number of children of the node type
+    pub fn capacity(self) -> usize {
+        match self {
+            NodeKind::Node4 => 4,
+            NodeKind::Node16 => 16,
+            NodeKind::Node32 => 32,
+            NodeKind::Node48 => 48,
+            NodeKind::Node256 => 256,
+        }
+    }
+}
+
+/// The header of an internal node, as seen by a [`Visitor`].
+#[derive(Debug, Clone, Copy)]
+pub struct NodeHeader<'a> {
+    depth: usize,
+    prefix_len: usize,
+    prefix: &'a [u8],
+    num_children: usize,
+}
+
+impl<'a> NodeHeader<'a> {
+    /// Returns the number of key bytes consumed above the node
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the length of the compressed path of the node
+    pub fn prefix_len(&self) -> usize {
+        self.prefix_len
+    }
+
+    /// Returns the stored bytes of the compressed path. Paths longer than the inline prefix
+    /// buffer only keep their first bytes, the rest is recovered from the leaves on lookups.
+    pub fn prefix(&self) -> &'a [u8] {
+        self.prefix
+    }
+
+    /// Returns the number of children of the node
+    pub fn num_children(&self) -> usize {
+        self.num_children
+    }
+}
+
+/// Callbacks invoked on a depth-first walk over the nodes of a tree, see [`ArtTree::accept`].
+///
+/// Children are visited in ascending key order, between the `visit_internal` and
+/// `leave_internal` calls of their parent.
+pub trait Visitor<V> {
+    /// Called when entering an internal node, before its children
+    fn visit_internal(&mut self, _header: &NodeHeader<'_>, _kind: NodeKind) {}
+
+    /// Called after all children of an internal node were visited
+    fn leave_internal(&mut self, _header: &NodeHeader<'_>, _kind: NodeKind) {}
+
+    /// Called for every leaf
+    fn visit_leaf(&mut self, _key: &[u8], _value: &V) {}
+}
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Walks the nodes of the tree depth-first, calling the visitor for every internal node and
+    /// leaf.
+    pub fn accept<T: Visitor<V> + ?Sized>(&self, visitor: &mut T) {
+        self.root.accept(visitor, 0);
+    }
+}
+
+impl<V, K: LeafKey> Node<V, K> {
+    fn accept<T: Visitor<V> + ?Sized>(&self, visitor: &mut T, depth: usize) {
+        match self {
+            Node::Empty => {}
+            Node::Leaf(leaf) => visitor.visit_leaf(leaf.key(), &leaf.value),
+            Node::Internal(internal) => {
+                let header = internal.visitor_header(depth);
+                let kind = internal.node_kind();
+                visitor.visit_internal(&header, kind);
+                let child_depth = depth + header.prefix_len + 1;
+                for child in internal.children_from(0) {
+                    child.accept(visitor, child_depth);
+                }
+                visitor.leave_internal(&header, kind);
+            }
+        }
+    }
+}
+
+impl<V, K> ArtNodeInternal<V, K> {
+    pub(super) fn node_kind(&self) -> NodeKind {
+        match self.inner {
+            ArtNodeInternalInner::Node4 { .. } => NodeKind::Node4,
+            ArtNodeInternalInner::Node16 { .. } => NodeKind::Node16,
+            ArtNodeInternalInner::Node32 { .. } => NodeKind::Node32,
+            ArtNodeInternalInner::Node48 { .. } => NodeKind::Node48,
+            ArtNodeInternalInner::Node256 { .. } => NodeKind::Node256,
+        }
+    }
+
+    fn visitor_header(&self, depth: usize) -> NodeHeader<'_> {
+        let header = &self.header;
+        NodeHeader {
+            depth,
+            prefix_len: header.partial_len,
+            prefix: &header.partial[..min(header.partial_len, MAX_PREFIX_LEN)],
+            num_children: header.num_children as usize,
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::fmt;
+
+use crate::art::{self, ArtTree};
+
+/// Map between byte keys and values whose bytes are unique too, using two Adaptive Radix Trees
+///
+/// Every key maps to one value and every value to one key, looked up with
+/// [`get_by_key`](Self::get_by_key) and [`get_by_value`](Self::get_by_value). Both directions
+/// are updated together, so inserting a pair replaces the pairs holding its key or its value.
+/// Keys and values are encoded to be prefix-free like the keys of a
+/// [`StringArtMap`](crate::string_art_map::StringArtMap), so any byte strings can be used.
+#[derive(Clone)]
+pub struct ArtBiMap<V> {
+    /// Encoded key to the key and the value
+    by_key: ArtTree<(Box<[u8]>, V)>,
+    /// Encoded value to the encoded key
+    by_value: ArtTree<Box<[u8]>>,
+}
+
+/// Pairs replaced by [`ArtBiMap::insert`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Overwritten<V> {
+    /// Neither the key nor the value were in the map
+    Neither,
+    /// The key was mapped to another value, the old pair is returned
+    Key(Box<[u8]>, V),
+    /// The value was mapped from another key, the old pair is returned
+    Value(Box<[u8]>, V),
+    /// Both the key and the value were in different pairs, returned in this order
+    Both((Box<[u8]>, V), (Box<[u8]>, V)),
+    /// The same pair was in the map already
+    Pair(Box<[u8]>, V),
+}
+
+fn encode(bytes: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    art::encode_prefix_free(bytes, &mut encoded);
+    encoded
+}
+
+impl<V: AsRef<[u8]>> ArtBiMap<V> {
+    pub fn new() -> Self {
+        Self {
+            by_key: ArtTree::new(),
+            by_value: ArtTree::new(),
+        }
+    }
+
+    /// Returns the number of pairs in the map
+    pub fn len(&self) -> usize {
+        self.by_key.len()
+    }
+
+    /// Returns true if the map contains no pairs
+    pub fn is_empty(&self) -> bool {
+        self.by_key.is_empty()
+    }
+
+    /// Inserts the pair, removing the pairs that held its key or its value before, and returns
+    /// them.
+    pub fn insert(&mut self, key: &[u8], value: V) -> Overwritten<V> {
+        let encoded_key = encode(key);
+        let encoded_value = encode(value.as_ref());
+        let by_key = self.remove_encoded_key(&encoded_key);
+        let by_value = self
+            .by_value
+            .get(&encoded_value)
+            .cloned()
+            .and_then(|other| self.remove_encoded_key(&other));
+
+        self.by_value
+            .insert(&encoded_value, encoded_key.clone().into());
+        self.by_key.insert(&encoded_key, (key.into(), value));
+        match (by_key, by_value) {
+            (None, None) => Overwritten::Neither,
+            (Some((key, old)), None) => {
+                if old.as_ref() == self.by_key.get(&encoded_key).unwrap().1.as_ref() {
+                    Overwritten::Pair(key, old)
+                } else {
+                    Overwritten::Key(key, old)
+                }
+            }
+            (None, Some((key, old))) => Overwritten::Value(key, old),
+            (Some(by_key), Some(by_value)) => Overwritten::Both(by_key, by_value),
+        }
+    }
+
+    /// Returns the value the key maps to
+    pub fn get_by_key(&self, key: &[u8]) -> Option<&V> {
+        self.by_key.get(&encode(key)).map(|(_, value)| value)
+    }
+
+    /// Returns the key that maps to the value
+    pub fn get_by_value(&self, value: &[u8]) -> Option<&[u8]> {
+        let encoded_key = self.by_value.get(&encode(value))?;
+        self.by_key.get(encoded_key).map(|(key, _)| &key[..])
+    }
+
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.by_key.contains_key(&encode(key))
+    }
+
+    pub fn contains_value(&self, value: &[u8]) -> bool {
+        self.by_value.contains_key(&encode(value))
+    }
+
+    /// Removes the pair holding the key and returns it
+    pub fn remove_by_key(&mut self, key: &[u8]) -> Option<(Box<[u8]>, V)> {
+        self.remove_encoded_key(&encode(key))
+    }
+
+    /// Removes the pair holding the value and returns it
+    pub fn remove_by_value(&mut self, value: &[u8]) -> Option<(Box<[u8]>, V)> {
+        let encoded_key = self.by_value.get(&encode(value))?.clone();
+        self.remove_encoded_key(&encoded_key)
+    }
+
+    /// Returns an iterator over the pairs in ascending key order
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &V)> + '_ {
+        self.by_key
+            .entries()
+            .map(|(_, (key, value))| (&key[..], value))
+    }
+
+    /// Returns an iterator over the pairs in ascending order of the value bytes
+    pub fn iter_by_value(&self) -> impl Iterator<Item = (&[u8], &V)> + '_ {
+        self.by_value.entries().map(move |(_, encoded_key)| {
+            let (key, value) = self.by_key.get(encoded_key).unwrap();
+            (&key[..], value)
+        })
+    }
+
+    fn remove_encoded_key(&mut self, encoded_key: &[u8]) -> Option<(Box<[u8]>, V)> {
+        let (key, value) = self.by_key.delete(encoded_key)?;
+        self.by_value.delete(&encode(value.as_ref()));
+        Some((key, value))
+    }
+}
+
+impl<V: AsRef<[u8]>> Default for ArtBiMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: AsRef<[u8]> + fmt::Debug> fmt::Debug for ArtBiMap<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
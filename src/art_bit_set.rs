@@ -0,0 +1,185 @@
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::Bound;
+
+use crate::art::ArtTreeFixed;
+
+const BLOCK_BITS: u64 = 64;
+
+/// Set of u64 integers using an Adaptive Radix Tree of 64-bit blocks
+///
+/// Members are grouped by `x / 64` into blocks, stored as one leaf holding a bitmap of the
+/// members of the block. Clustered members share leaves and the common prefixes of their
+/// blocks, while gaps between clusters take no space, so the set suits sparse and clustered
+/// ids that are too spread for a `Vec<bool>`.
+#[derive(Clone, Default)]
+pub struct ArtBitSet {
+    /// Big-endian block index to the bitmap of the block, never zero
+    blocks: ArtTreeFixed<8, u64>,
+    len: usize,
+}
+
+fn split(x: u64) -> ([u8; 8], u64) {
+    ((x / BLOCK_BITS).to_be_bytes(), 1 << (x % BLOCK_BITS))
+}
+
+fn block_start(key: &[u8]) -> u64 {
+    let mut block = [0; 8];
+    block.copy_from_slice(key);
+    u64::from_be_bytes(block) * BLOCK_BITS
+}
+
+/// Returns the members of a block in ascending order
+fn block_members(key: &[u8], mut bits: u64) -> impl Iterator<Item = u64> {
+    let start = block_start(key);
+    std::iter::from_fn(move || {
+        if bits == 0 {
+            return None;
+        }
+        let bit = bits.trailing_zeros() as u64;
+        bits &= bits - 1;
+        Some(start + bit)
+    })
+}
+
+impl ArtBitSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of members
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the set has no members
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Adds `x` to the set and returns true if it was not a member already
+    pub fn insert(&mut self, x: u64) -> bool {
+        let (key, bit) = split(x);
+        let bits = self.blocks.get_or_insert_with(&key, || 0);
+        let added = *bits & bit == 0;
+        *bits |= bit;
+        self.len += added as usize;
+        added
+    }
+
+    pub fn contains(&self, x: u64) -> bool {
+        let (key, bit) = split(x);
+        self.blocks.get(&key).is_some_and(|bits| bits & bit != 0)
+    }
+
+    /// Removes `x` from the set and returns true if it was a member
+    pub fn remove(&mut self, x: u64) -> bool {
+        let (key, bit) = split(x);
+        let bits = match self.blocks.get_mut(&key) {
+            Some(bits) if *bits & bit != 0 => bits,
+            _ => return false,
+        };
+        *bits &= !bit;
+        if *bits == 0 {
+            self.blocks.delete(&key);
+        }
+        self.len -= 1;
+        true
+    }
+
+    /// Returns the smallest member
+    pub fn first(&self) -> Option<u64> {
+        let (key, bits) = self.blocks.minimum()?;
+        Some(block_start(key) + bits.trailing_zeros() as u64)
+    }
+
+    /// Returns the largest member
+    pub fn last(&self) -> Option<u64> {
+        let (key, bits) = self.blocks.maximum()?;
+        Some(block_start(key) + (BLOCK_BITS - 1 - bits.leading_zeros() as u64))
+    }
+
+    /// Returns the smallest member greater than `x`.
+    ///
+    /// The rest of the block of `x` is checked first, then a single descent finds the next
+    /// block, however many non-members lie in between.
+    pub fn next_set_after(&self, x: u64) -> Option<u64> {
+        let (key, bit) = split(x);
+        // Members of the block of `x` above it
+        let above = !(bit | (bit - 1));
+        if let Some(bits) = self.blocks.get(&key).map(|bits| bits & above) {
+            if bits != 0 {
+                return Some(block_start(&key) + bits.trailing_zeros() as u64);
+            }
+        }
+        let (next, bits) = self
+            .blocks
+            .range((Bound::Excluded(&key[..]), Bound::Unbounded))
+            .next()?;
+        Some(block_start(next) + bits.trailing_zeros() as u64)
+    }
+
+    /// Returns an iterator over the members in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        self.blocks
+            .entries()
+            .flat_map(|(key, &bits)| block_members(key, bits))
+    }
+
+    /// Returns the set of the members of either set
+    pub fn union(&self, other: &Self) -> Self {
+        let blocks = self.blocks.union_with(&other.blocks, |_, a, b| a | b);
+        Self::with_blocks(blocks)
+    }
+
+    /// Returns the set of the members of both sets.
+    ///
+    /// Only the blocks present in both sets are combined, the blocks of either set lying
+    /// between two blocks of the other are skipped.
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut blocks = self.blocks.intersection(&other.blocks);
+        for (key, bits) in blocks.entries_mut() {
+            *bits &= other.blocks.get(key).unwrap();
+        }
+        blocks.retain(|_, bits| *bits != 0);
+        Self::with_blocks(blocks)
+    }
+
+    fn with_blocks(blocks: ArtTreeFixed<8, u64>) -> Self {
+        let len = blocks
+            .entries()
+            .map(|(_, bits)| bits.count_ones() as usize)
+            .sum();
+        Self { blocks, len }
+    }
+}
+
+impl PartialEq for ArtBitSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.blocks.entries().eq(other.blocks.entries())
+    }
+}
+
+impl Eq for ArtBitSet {}
+
+impl FromIterator<u64> for ArtBitSet {
+    fn from_iter<I: IntoIterator<Item = u64>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<u64> for ArtBitSet {
+    fn extend<I: IntoIterator<Item = u64>>(&mut self, iter: I) {
+        for x in iter {
+            self.insert(x);
+        }
+    }
+}
+
+impl fmt::Debug for ArtBitSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,156 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::art::{self, ArtTree};
+
+/// Function extracting the indexed key of an item, see [`ArtIndex::new`]
+pub type KeyFn<T> = dyn Fn(&T) -> Vec<u8> + Send + Sync;
+
+/// Secondary index mapping the keys of the items of an external collection (e.g. a `Vec<T>`)
+/// to their positions in it, using an Adaptive Radix Tree
+///
+/// The index does not own the items. The collection calls [`insert_item`](Self::insert_item)
+/// and [`remove_item`](Self::remove_item) as it changes, or [`rebuild`](Self::rebuild) after
+/// bulk changes. The extracted keys are encoded to be prefix-free like the keys of a
+/// [`StringArtMap`](crate::string_art_map::StringArtMap), so any byte strings can be indexed.
+/// Several items may have the same key, their positions are kept in ascending order.
+#[derive(Clone)]
+pub struct ArtIndex<T> {
+    tree: ArtTree<Vec<usize>>,
+    key_fn: Arc<KeyFn<T>>,
+    len: usize,
+}
+
+impl<T> ArtIndex<T> {
+    /// Creates an empty index over the keys `key_fn` extracts from the items
+    pub fn new<K, F>(key_fn: F) -> Self
+    where
+        K: AsRef<[u8]>,
+        F: Fn(&T) -> K + Send + Sync + 'static,
+    {
+        Self {
+            tree: ArtTree::new(),
+            key_fn: Arc::new(move |item: &T| key_fn(item).as_ref().to_vec()),
+            len: 0,
+        }
+    }
+
+    /// Creates an index over `items`, indexed by their positions in the slice
+    pub fn with_items<K, F>(items: &[T], key_fn: F) -> Self
+    where
+        K: AsRef<[u8]>,
+        F: Fn(&T) -> K + Send + Sync + 'static,
+    {
+        let mut index = Self::new(key_fn);
+        index.rebuild(items);
+        index
+    }
+
+    /// Returns the number of indexed items
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if no item is indexed
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Indexes `item` at `position`.
+    pub fn insert_item(&mut self, position: usize, item: &T) {
+        let positions = self.tree.get_or_insert_with(&self.encode(item), Vec::new);
+        if let Err(i) = positions.binary_search(&position) {
+            positions.insert(i, position);
+            self.len += 1;
+        }
+    }
+
+    /// Removes `item` at `position` from the index and returns whether it was indexed there.
+    /// The item has to have the key it was indexed with.
+    pub fn remove_item(&mut self, position: usize, item: &T) -> bool {
+        let key = self.encode(item);
+        let positions = match self.tree.get_mut(&key) {
+            Some(positions) => positions,
+            None => return false,
+        };
+        let i = match positions.binary_search(&position) {
+            Ok(i) => i,
+            Err(_) => return false,
+        };
+
+        positions.remove(i);
+        if positions.is_empty() {
+            self.tree.delete(&key);
+        }
+        self.len -= 1;
+        true
+    }
+
+    /// Updates the index for `items.swap_remove(position)`, to be called before removing the
+    /// item: the item at `position` is removed and the last item is moved there.
+    pub fn swap_remove_item(&mut self, items: &[T], position: usize) {
+        let last = items.len() - 1;
+        self.remove_item(position, &items[position]);
+        if position != last {
+            self.remove_item(last, &items[last]);
+            self.insert_item(position, &items[last]);
+        }
+    }
+
+    /// Replaces the contents of the index with `items`, indexed by their positions in the slice
+    pub fn rebuild(&mut self, items: &[T]) {
+        self.tree = ArtTree::new();
+        self.len = 0;
+        // Positions are inserted in ascending order, so they only have to be appended
+        for (position, item) in items.iter().enumerate() {
+            self.tree
+                .get_or_insert_with(&self.encode(item), Vec::new)
+                .push(position);
+            self.len += 1;
+        }
+    }
+
+    /// Returns the positions of the items with the given key in ascending order, or an empty
+    /// slice if there are none
+    pub fn positions(&self, key: &[u8]) -> &[usize] {
+        let mut encoded = Vec::new();
+        art::encode_prefix_free(key, &mut encoded);
+        self.tree.get(&encoded).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the position of the first item with the given key
+    pub fn first_position(&self, key: &[u8]) -> Option<usize> {
+        self.positions(key).first().copied()
+    }
+
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        !self.positions(key).is_empty()
+    }
+
+    /// Returns the positions of the items whose keys start with `prefix`, in ascending key
+    /// order and in ascending order for the same key
+    pub fn positions_with_prefix(&self, prefix: &[u8]) -> impl Iterator<Item = usize> + '_ {
+        let mut encoded = Vec::new();
+        art::encode_prefix_free(prefix, &mut encoded);
+        // Without the terminator the encoded prefix is a prefix of the encoded keys
+        encoded.truncate(encoded.len() - 2);
+        self.tree
+            .scan_prefix(&encoded)
+            .flat_map(|(_, positions)| positions.iter().copied())
+    }
+
+    fn encode(&self, item: &T) -> Vec<u8> {
+        let mut key = Vec::new();
+        art::encode_prefix_free(&(self.key_fn)(item), &mut key);
+        key
+    }
+}
+
+impl<T> fmt::Debug for ArtIndex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArtIndex")
+            .field("keys", &self.tree.len())
+            .field("len", &self.len)
+            .finish()
+    }
+}
@@ -0,0 +1,114 @@
+use crate::art::{self, ArtTree};
+
+/// Map from disjoint half-open byte-key intervals `[start, end)` to values using an Adaptive Radix
+/// Tree
+///
+/// Intervals are stored by their start key, so finding the interval covering a point is a single
+/// predecessor search. The tree holds the starts in a prefix-free encoding that keeps their
+/// order, so that adjacent intervals such as `[a, aa)` and `[aa, b)` can be stored.
+#[derive(Clone, Debug)]
+pub struct ArtIntervalMap<V> {
+    /// Intervals keyed by their encoded start
+    tree: ArtTree<Interval<V>>,
+}
+
+#[derive(Clone, Debug)]
+struct Interval<V> {
+    start: Box<[u8]>,
+    /// Exclusive end
+    end: Box<[u8]>,
+    value: V,
+}
+
+impl<V> Interval<V> {
+    fn as_tuple(&self) -> (&[u8], &[u8], &V) {
+        (&self.start, &self.end, &self.value)
+    }
+}
+
+fn encode(key: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    art::encode_prefix_free(key, &mut encoded);
+    encoded
+}
+
+impl<V> ArtIntervalMap<V> {
+    pub fn new() -> Self {
+        Self {
+            tree: ArtTree::new(),
+        }
+    }
+
+    /// Returns the number of intervals in the map
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns true if the map contains no intervals
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Inserts the interval `[start, end)` with the given value.
+    ///
+    /// The value is handed back if the interval is empty or overlaps an interval already in the
+    /// map.
+    pub fn insert(&mut self, start: &[u8], end: &[u8], value: V) -> Result<(), V> {
+        if start >= end || self.overlaps(start, end).next().is_some() {
+            return Err(value);
+        }
+        let interval = Interval {
+            start: start.into(),
+            end: end.into(),
+            value,
+        };
+        self.tree.insert(&encode(start), interval);
+        Ok(())
+    }
+
+    /// Removes the interval starting at the given key and returns its end and value.
+    pub fn remove(&mut self, start: &[u8]) -> Option<(Box<[u8]>, V)> {
+        self.tree
+            .delete(&encode(start))
+            .map(|interval| (interval.end, interval.value))
+    }
+
+    /// Returns the interval covering the given point as `(start, end, value)`.
+    pub fn lookup(&self, point: &[u8]) -> Option<(&[u8], &[u8], &V)> {
+        let (_, interval) = self.tree.floor(&encode(point))?;
+        if point < &interval.end[..] {
+            Some(interval.as_tuple())
+        } else {
+            None
+        }
+    }
+
+    /// Returns an iterator over the intervals overlapping `[start, end)` in ascending order.
+    pub fn overlaps<'a>(
+        &'a self,
+        start: &'a [u8],
+        end: &'a [u8],
+    ) -> impl Iterator<Item = (&'a [u8], &'a [u8], &'a V)> + 'a {
+        // Only the interval starting right before `start` can reach into the range from the left
+        let (from, to) = (encode(start), encode(end));
+        let preceding = self
+            .tree
+            .floor(&from)
+            .filter(|(key, interval)| *key < &from[..] && start < &interval.end[..]);
+        preceding
+            .into_iter()
+            .chain(self.tree.range(&from[..]..&to[..]))
+            .map(|(_, interval)| interval.as_tuple())
+    }
+
+    /// Returns an iterator over all intervals in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &[u8], &V)> + '_ {
+        self.tree.entries().map(|(_, interval)| interval.as_tuple())
+    }
+}
+
+impl<V> Default for ArtIntervalMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
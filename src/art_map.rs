@@ -0,0 +1,658 @@
+use std::fmt;
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+
+use crate::art::{self, ArtTree, LeafKey};
+
+/// Encodes keys into bytes whose lexicographic order matches the order of the keys.
+///
+/// The encoding has to be prefix-free: no encoded key may be a prefix of another one.
+pub trait KeyEncoder<K> {
+    /// Byte representation of an encoded key
+    type Encoded: AsRef<[u8]>;
+
+    /// Returns the order-preserving bytes of the key.
+    fn encode(key: &K) -> Self::Encoded;
+}
+
+/// A `KeyEncoder` whose encoding can be reversed, which is needed by the parts of the map API
+/// that hand out keys (iteration, `minimum`, `pop_first`, ...).
+pub trait KeyDecoder<K>: KeyEncoder<K> {
+    /// Reads a key back from its encoded bytes.
+    fn decode(bytes: &[u8]) -> K;
+}
+
+/// Map indexed by keys of type `K`, stored in an Adaptive Radix Tree in the encoding of `E`
+///
+/// The leaves hold the encoded keys as `L`, e.g. `[u8; N]` for an encoding of a fixed length.
+pub struct ArtMap<K, V, E, L = Box<[u8]>> {
+    tree: ArtTree<V, L>,
+    _key: PhantomData<(K, E)>,
+}
+
+impl<K, V: Clone, E, L: LeafKey + Clone> Clone for ArtMap<K, V, E, L> {
+    fn clone(&self) -> Self {
+        Self {
+            tree: self.tree.clone(),
+            _key: PhantomData,
+        }
+    }
+}
+
+impl<K, V: fmt::Debug, E, L: LeafKey + fmt::Debug> fmt::Debug for ArtMap<K, V, E, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArtMap").field("tree", &self.tree).finish()
+    }
+}
+
+impl<K, V, E: KeyEncoder<K>, L: LeafKey> ArtMap<K, V, E, L> {
+    pub fn new() -> Self {
+        Self {
+            tree: ArtTree::default(),
+            _key: PhantomData,
+        }
+    }
+
+    /// Returns the number of elements in the map
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns true if the map contains no elements
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Returns true if the map contains a value for the given key
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.tree.contains_key(E::encode(key).as_ref())
+    }
+
+    /// Returns a reference to the value stored at the given key if it exists
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.tree.get(E::encode(key).as_ref())
+    }
+
+    /// Returns a mutable reference to the value stored at the given key if it exists
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.tree.get_mut(E::encode(key).as_ref())
+    }
+
+    /// Inserts the given value at the given key and returns the previous value stored at the key if
+    /// such exists.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.tree.insert(E::encode(&key).as_ref(), value)
+    }
+
+    /// Deletes and returns the value stored at the given key.
+    pub fn delete(&mut self, key: K) -> Option<V> {
+        self.tree.delete(E::encode(&key).as_ref())
+    }
+
+    /// Moves the value stored at `old` to `new`, replacing any value stored at `new`. Returns
+    /// false if `old` is not present.
+    pub fn rekey(&mut self, old: &K, new: &K) -> bool {
+        self.tree
+            .rekey(E::encode(old).as_ref(), E::encode(new).as_ref())
+    }
+
+    /// Moves the value stored at `old` to `new` like [`rekey`](Self::rekey), e.g. to change the
+    /// deadline of a pending timer. See [`reschedule_first`](Self::reschedule_first) for the
+    /// next timer.
+    pub fn reschedule(&mut self, old: &K, new: &K) -> bool {
+        self.rekey(old, new)
+    }
+
+    /// Exchanges the values stored at the keys `a` and `b`. Returns false and leaves the map
+    /// unchanged unless both keys are present.
+    pub fn swap(&mut self, a: &K, b: &K) -> bool {
+        self.tree.swap(E::encode(a).as_ref(), E::encode(b).as_ref())
+    }
+
+    /// Removes every element whose key is less than `key` and returns the number of removed
+    /// elements. Subtrees entirely below the key are detached as a whole.
+    pub fn remove_below(&mut self, key: &K) -> usize {
+        self.tree.remove_below(E::encode(key).as_ref())
+    }
+
+    /// Returns an iterator over the values of the map in ascending key order
+    pub fn values(&self) -> Values<'_, K, V, E, L> {
+        Values {
+            inner: self.tree.entries(),
+            _key: PhantomData,
+        }
+    }
+
+    /// Returns the entry of the given key for in-place manipulation
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, E, L> {
+        let encoded = E::encode(&key);
+        if self.tree.contains_key(encoded.as_ref()) {
+            Entry::Occupied(OccupiedEntry {
+                tree: &mut self.tree,
+                key,
+                encoded,
+            })
+        } else {
+            Entry::Vacant(VacantEntry {
+                tree: &mut self.tree,
+                key,
+                encoded,
+            })
+        }
+    }
+}
+
+impl<K, V, E: KeyDecoder<K>, L: LeafKey> ArtMap<K, V, E, L> {
+    /// Returns the key and a reference to the value of the minimum element in the map
+    pub fn minimum(&self) -> Option<(K, &V)> {
+        self.tree.minimum().map(|(k, v)| (E::decode(k.as_ref()), v))
+    }
+
+    /// Returns the key and a reference to the value of the maximum element in the map
+    pub fn maximum(&self) -> Option<(K, &V)> {
+        self.tree.maximum().map(|(k, v)| (E::decode(k.as_ref()), v))
+    }
+
+    /// Returns the key and a reference to the value of the minimum element in the map
+    pub fn minimum_mut(&mut self) -> Option<(K, &mut V)> {
+        self.tree
+            .minimum_mut()
+            .map(|(k, v)| (E::decode(k.as_ref()), v))
+    }
+
+    /// Returns the key and a reference to the value of the maximum element in the map
+    pub fn maximum_mut(&mut self) -> Option<(K, &mut V)> {
+        self.tree
+            .maximum_mut()
+            .map(|(k, v)| (E::decode(k.as_ref()), v))
+    }
+
+    /// Returns the element with the greatest key less than or equal to `key`
+    pub fn floor(&self, key: &K) -> Option<(K, &V)> {
+        self.tree
+            .floor(E::encode(key).as_ref())
+            .map(|(k, v)| (E::decode(k), v))
+    }
+
+    /// Returns the element with the least key greater than or equal to `key`
+    pub fn ceiling(&self, key: &K) -> Option<(K, &V)> {
+        self.range((Bound::Included(key), Bound::Unbounded)).next()
+    }
+
+    /// Returns the key and a mutable reference to the value of the element following `key`
+    pub fn next_after_mut(&mut self, key: &K) -> Option<(K, &mut V)> {
+        self.tree
+            .next_after_mut(E::encode(key).as_ref())
+            .map(|(k, v)| (E::decode(k), v))
+    }
+
+    /// Returns the key and a mutable reference to the value of the element preceding `key`
+    pub fn prev_before_mut(&mut self, key: &K) -> Option<(K, &mut V)> {
+        self.tree
+            .prev_before_mut(E::encode(key).as_ref())
+            .map(|(k, v)| (E::decode(k), v))
+    }
+
+    /// Returns an iterator over the key-value pairs of the map in ascending key order
+    pub fn iter(&self) -> Iter<'_, K, V, E, L> {
+        Iter {
+            inner: self.tree.entries(),
+            _key: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the key-value pairs of the map in ascending key order, with
+    /// mutable references to the values
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V, E, L> {
+        IterMut {
+            inner: self.tree.entries_mut(),
+            _key: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the key-value pairs whose keys fall in the given range, in
+    /// ascending key order
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, V, E, L> {
+        let start = range.start_bound().map(E::encode);
+        let end = range.end_bound().map(E::encode);
+        Range {
+            inner: self.tree.range((
+                start.as_ref().map(|key| key.as_ref()),
+                end.as_ref().map(|key| key.as_ref()),
+            )),
+            _key: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the keys of the map in ascending order
+    pub fn keys(&self) -> Keys<'_, K, V, E, L> {
+        Keys { inner: self.iter() }
+    }
+
+    /// Keeps only the elements for which `keep` returns true
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(K, &mut V) -> bool,
+    {
+        self.tree
+            .retain_mut(|key, value| keep(E::decode(key), value))
+    }
+
+    /// Removes all elements from the map and returns them in ascending key order
+    pub fn drain(&mut self) -> IntoIter<K, V, E, L> {
+        IntoIter {
+            inner: self.tree.drain(),
+            _key: PhantomData,
+        }
+    }
+
+    /// Removes the elements whose keys fall in the given range and returns them in ascending key
+    /// order
+    pub fn drain_range<R: RangeBounds<K>>(&mut self, range: R) -> IntoIter<K, V, E, L> {
+        let start = range.start_bound().map(E::encode);
+        let end = range.end_bound().map(E::encode);
+        IntoIter {
+            inner: self.tree.drain_range((
+                start.as_ref().map(|key| key.as_ref()),
+                end.as_ref().map(|key| key.as_ref()),
+            )),
+            _key: PhantomData,
+        }
+    }
+
+    /// Returns the minimal key-value pair of the map without removing it
+    pub fn peek_first(&self) -> Option<(K, &V)> {
+        self.minimum()
+    }
+
+    /// Returns the maximal key-value pair of the map without removing it
+    pub fn peek_last(&self) -> Option<(K, &V)> {
+        self.maximum()
+    }
+
+    /// Removes and returns the minimal key-value pair from the map
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        self.tree
+            .pop_first()
+            .map(|(k, v)| (E::decode(k.as_ref()), v))
+    }
+
+    /// Removes and returns the maximal key-value pair from the map
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        self.tree
+            .pop_last()
+            .map(|(k, v)| (E::decode(k.as_ref()), v))
+    }
+
+    /// Moves the minimal element to `new`, replacing any value stored at `new`, and returns the
+    /// key it had. Reuses the leaf of the element, unlike `pop_first` followed by `insert`.
+    pub fn reschedule_first(&mut self, new: &K) -> Option<K> {
+        self.tree
+            .reschedule_first(E::encode(new).as_ref())
+            .map(|k| E::decode(k.as_ref()))
+    }
+
+    /// Removes and returns the minimal key-value pair from the map if `pred` approves of it
+    pub fn pop_first_if<F>(&mut self, pred: F) -> Option<(K, V)>
+    where
+        F: FnOnce(&K, &V) -> bool,
+    {
+        self.tree
+            .pop_first_if(|k, v| pred(&E::decode(k), v))
+            .map(|(k, v)| (E::decode(k.as_ref()), v))
+    }
+
+    /// Removes and returns the maximal key-value pair from the map if `pred` approves of it
+    pub fn pop_last_if<F>(&mut self, pred: F) -> Option<(K, V)>
+    where
+        F: FnOnce(&K, &V) -> bool,
+    {
+        self.tree
+            .pop_last_if(|k, v| pred(&E::decode(k), v))
+            .map(|(k, v)| (E::decode(k.as_ref()), v))
+    }
+}
+
+impl<K, V, E: KeyEncoder<K>, L: LeafKey> Default for ArtMap<K, V, E, L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, E: KeyEncoder<K>, L: LeafKey> FromIterator<(K, V)> for ArtMap<K, V, E, L> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K, V, E: KeyEncoder<K>, L: LeafKey> Extend<(K, V)> for ArtMap<K, V, E, L> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.tree.reserve(iter.size_hint().0);
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K, V, E: KeyDecoder<K>, L: LeafKey> IntoIterator for ArtMap<K, V, E, L> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V, E, L>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            inner: self.tree.into_iter(),
+            _key: PhantomData,
+        }
+    }
+}
+
+impl<'a, K, V, E: KeyDecoder<K>, L: LeafKey> IntoIterator for &'a ArtMap<K, V, E, L> {
+    type Item = (K, &'a V);
+    type IntoIter = Iter<'a, K, V, E, L>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, E: KeyDecoder<K>, L: LeafKey> IntoIterator for &'a mut ArtMap<K, V, E, L> {
+    type Item = (K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V, E, L>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// Serializes the map as a map from keys to values, in ascending key order.
+#[cfg(feature = "serde")]
+impl<K, V, E, L: LeafKey> serde::Serialize for ArtMap<K, V, E, L>
+where
+    K: serde::Serialize,
+    V: serde::Serialize,
+    E: KeyDecoder<K>,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, K, V, E, L: LeafKey> serde::Deserialize<'de> for ArtMap<K, V, E, L>
+where
+    K: serde::Deserialize<'de>,
+    V: serde::Deserialize<'de>,
+    E: KeyEncoder<K>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MapVisitor<K, V, E, L>(PhantomData<(K, V, E, L)>);
+
+        impl<'de, K, V, E, L> serde::de::Visitor<'de> for MapVisitor<K, V, E, L>
+        where
+            K: serde::Deserialize<'de>,
+            V: serde::Deserialize<'de>,
+            E: KeyEncoder<K>,
+            L: LeafKey,
+        {
+            type Value = ArtMap<K, V, E, L>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a map")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut access: A,
+            ) -> Result<Self::Value, A::Error> {
+                let mut map = ArtMap::new();
+                while let Some((key, value)) = access.next_entry()? {
+                    map.insert(key, value);
+                }
+                Ok(map)
+            }
+        }
+
+        deserializer.deserialize_map(MapVisitor(PhantomData))
+    }
+}
+
+/// A view into a single key of an `ArtMap`, which is either occupied or vacant.
+///
+/// Created by [`ArtMap::entry`].
+pub enum Entry<'a, K, V, E: KeyEncoder<K>, L = Box<[u8]>> {
+    Occupied(OccupiedEntry<'a, K, V, E, L>),
+    Vacant(VacantEntry<'a, K, V, E, L>),
+}
+
+/// A view into a key stored in an `ArtMap`.
+pub struct OccupiedEntry<'a, K, V, E: KeyEncoder<K>, L = Box<[u8]>> {
+    tree: &'a mut ArtTree<V, L>,
+    key: K,
+    encoded: E::Encoded,
+}
+
+/// A view into a key missing from an `ArtMap`.
+pub struct VacantEntry<'a, K, V, E: KeyEncoder<K>, L = Box<[u8]>> {
+    tree: &'a mut ArtTree<V, L>,
+    key: K,
+    encoded: E::Encoded,
+}
+
+impl<'a, K, V, E: KeyEncoder<K>, L: LeafKey> Entry<'a, K, V, E, L> {
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Returns the value of the entry, inserting `default` first if the entry is vacant.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    /// Returns the value of the entry, inserting the result of `default` first if the entry is
+    /// vacant.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Returns the value of the entry, inserting `V::default()` first if the entry is vacant.
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /// Calls `f` on the value if the entry is occupied.
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K, V, E: KeyEncoder<K>, L: LeafKey> OccupiedEntry<'a, K, V, E, L> {
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Returns a reference to the value of the entry.
+    pub fn get(&self) -> &V {
+        self.tree.get(self.encoded.as_ref()).unwrap()
+    }
+
+    /// Returns a mutable reference to the value of the entry.
+    pub fn get_mut(&mut self) -> &mut V {
+        self.tree.get_mut(self.encoded.as_ref()).unwrap()
+    }
+
+    /// Converts the entry into a mutable reference to its value.
+    pub fn into_mut(self) -> &'a mut V {
+        self.tree.get_mut(self.encoded.as_ref()).unwrap()
+    }
+
+    /// Replaces the value of the entry and returns the old value.
+    pub fn insert(&mut self, value: V) -> V {
+        self.tree.insert(self.encoded.as_ref(), value).unwrap()
+    }
+
+    /// Removes the entry from the map and returns its value.
+    pub fn remove(self) -> V {
+        self.tree.delete(self.encoded.as_ref()).unwrap()
+    }
+}
+
+impl<'a, K, V, E: KeyEncoder<K>, L: LeafKey> VacantEntry<'a, K, V, E, L> {
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Takes ownership of the key of the entry.
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Inserts the value at the key of the entry and returns a mutable reference to it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.tree
+            .get_or_insert_with(self.encoded.as_ref(), || value)
+    }
+}
+
+/// An iterator over the entries of an `ArtMap` in ascending key order.
+///
+/// Created by [`ArtMap::iter`].
+pub struct Iter<'a, K, V, E, L = Box<[u8]>> {
+    inner: art::Iter<'a, V, L>,
+    _key: PhantomData<(K, E)>,
+}
+
+impl<'a, K, V, E: KeyDecoder<K>, L: LeafKey> Iterator for Iter<'a, K, V, E, L> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, v)| (E::decode(k), v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V, E: KeyDecoder<K>, L: LeafKey> ExactSizeIterator for Iter<'a, K, V, E, L> {}
+
+/// A mutable iterator over the entries of an `ArtMap` in ascending key order.
+///
+/// Created by [`ArtMap::iter_mut`].
+pub struct IterMut<'a, K, V, E, L = Box<[u8]>> {
+    inner: art::IterMut<'a, V, L>,
+    _key: PhantomData<(K, E)>,
+}
+
+impl<'a, K, V, E: KeyDecoder<K>, L: LeafKey> Iterator for IterMut<'a, K, V, E, L> {
+    type Item = (K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, v)| (E::decode(k), v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V, E: KeyDecoder<K>, L: LeafKey> ExactSizeIterator for IterMut<'a, K, V, E, L> {}
+
+/// An owning iterator over the entries of an `ArtMap` in ascending key order.
+///
+/// Created by the `IntoIterator` implementation of `ArtMap`.
+pub struct IntoIter<K, V, E, L = Box<[u8]>> {
+    inner: art::IntoIter<V, L>,
+    _key: PhantomData<(K, E)>,
+}
+
+impl<K, V, E: KeyDecoder<K>, L: LeafKey> Iterator for IntoIter<K, V, E, L> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, v)| (E::decode(k.as_ref()), v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V, E: KeyDecoder<K>, L: LeafKey> ExactSizeIterator for IntoIter<K, V, E, L> {}
+
+/// An iterator over a key range of an `ArtMap` in ascending key order.
+///
+/// Created by [`ArtMap::range`].
+pub struct Range<'a, K, V, E, L = Box<[u8]>> {
+    inner: art::Range<'a, V, L>,
+    _key: PhantomData<(K, E)>,
+}
+
+impl<'a, K, V, E: KeyDecoder<K>, L: LeafKey> Iterator for Range<'a, K, V, E, L> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, v)| (E::decode(k), v))
+    }
+}
+
+/// An iterator over the keys of an `ArtMap` in ascending order.
+///
+/// Created by [`ArtMap::keys`].
+pub struct Keys<'a, K, V, E, L = Box<[u8]>> {
+    inner: Iter<'a, K, V, E, L>,
+}
+
+impl<'a, K, V, E: KeyDecoder<K>, L: LeafKey> Iterator for Keys<'a, K, V, E, L> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V, E: KeyDecoder<K>, L: LeafKey> ExactSizeIterator for Keys<'a, K, V, E, L> {}
+
+/// An iterator over the values of an `ArtMap` in ascending key order.
+///
+/// Created by [`ArtMap::values`].
+pub struct Values<'a, K, V, E, L = Box<[u8]>> {
+    inner: art::Iter<'a, V, L>,
+    _key: PhantomData<(K, E)>,
+}
+
+impl<'a, K, V, E, L: LeafKey> Iterator for Values<'a, K, V, E, L> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, v)| v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V, E, L: LeafKey> ExactSizeIterator for Values<'a, K, V, E, L> {}
@@ -0,0 +1,110 @@
+use crate::art::{self, ArtTree};
+
+/// Map from byte keys to one or more values using an Adaptive Radix Tree
+///
+/// The values stored under a key are kept in insertion order. A key is removed from the map as soon
+/// as its last value is removed. Any keys can be stored, also keys that are prefixes of each
+/// other: the tree holds them in a prefix-free encoding, next to the keys as they were inserted.
+#[derive(Clone, Debug)]
+pub struct ArtMultiMap<V> {
+    tree: ArtTree<(Box<[u8]>, Vec<V>)>,
+    len: usize,
+}
+
+fn encode(key: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    art::encode_prefix_free(key, &mut encoded);
+    encoded
+}
+
+impl<V> ArtMultiMap<V> {
+    pub fn new() -> Self {
+        Self {
+            tree: ArtTree::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the total number of values stored in the map
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the map contains no values
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns true if at least one value is stored at the given key
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.tree.contains_key(&encode(key))
+    }
+
+    /// Appends the given value to the values stored at the given key.
+    pub fn insert(&mut self, key: &[u8], value: V) {
+        let encoded = encode(key);
+        match self.tree.get_mut(&encoded) {
+            Some((_, values)) => values.push(value),
+            None => {
+                self.tree.insert(&encoded, (key.into(), vec![value]));
+            }
+        }
+        self.len += 1;
+    }
+
+    /// Returns the values stored at the given key in insertion order, or an empty slice if there
+    /// are none.
+    pub fn get_all(&self, key: &[u8]) -> &[V] {
+        self.tree
+            .get(&encode(key))
+            .map_or(&[], |(_, values)| values.as_slice())
+    }
+
+    /// Removes the first occurrence of the given value from the values stored at the given key and
+    /// returns whether it was found. The key itself is removed along with its last value.
+    pub fn remove_value(&mut self, key: &[u8], value: &V) -> bool
+    where
+        V: PartialEq,
+    {
+        let encoded = encode(key);
+        let values = match self.tree.get_mut(&encoded) {
+            Some((_, values)) => values,
+            None => return false,
+        };
+        let idx = match values.iter().position(|v| v == value) {
+            Some(idx) => idx,
+            None => return false,
+        };
+
+        values.remove(idx);
+        if values.is_empty() {
+            self.tree.delete(&encoded);
+        }
+        self.len -= 1;
+        true
+    }
+
+    /// Removes the given key and returns all values that were stored at it.
+    pub fn remove_all(&mut self, key: &[u8]) -> Vec<V> {
+        let values = self
+            .tree
+            .delete(&encode(key))
+            .map_or_else(Vec::new, |(_, values)| values);
+        self.len -= values.len();
+        values
+    }
+
+    /// Returns an iterator over the key-value pairs of the map in ascending key order, yielding the
+    /// key once for each of its values.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &V)> + '_ {
+        self.tree
+            .entries()
+            .flat_map(|(_, (key, values))| values.iter().map(move |value| (&key[..], value)))
+    }
+}
+
+impl<V> Default for ArtMultiMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt;
+use std::mem;
+
+use crate::art::ArtTree;
+
+/// Bytes charged per entry for the recency index of a [`BoundedArtTree`], on top of the key
+/// length: a tick and a boxed key, doubled for the slack of half-full B-tree nodes.
+const RECENCY_ENTRY_BYTES: usize = 2 * mem::size_of::<(u64, Box<[u8]>)>();
+
+/// The choice of an eviction policy when an insert takes a [`BoundedArtTree`] over its budget.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Eviction {
+    /// Evict the entry that was inserted or read the longest time ago
+    LeastRecentlyUsed,
+    /// Evict the entry with the smallest key
+    Minimum,
+    /// Evict the entry with the given key. Naming a missing key, or the key being inserted,
+    /// rejects the insert.
+    Key(Vec<u8>),
+    /// Undo the insert, keeping what was evicted for it so far
+    Reject,
+}
+
+/// Callback choosing what to evict, called with the tree, which already holds the new entry,
+/// and the key being inserted.
+pub type EvictionPolicy<V> = Box<dyn FnMut(&BoundedArtTree<V>, &[u8]) -> Eviction>;
+
+/// An insert rejected by the eviction policy of a [`BoundedArtTree`], returning the value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rejected<V>(pub V);
+
+impl<V> fmt::Display for Rejected<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "insert rejected by the memory budget")
+    }
+}
+
+impl<V: fmt::Debug> error::Error for Rejected<V> {}
+
+/// Map indexed by byte keys using an Adaptive Radix Tree that stays within a byte budget
+///
+/// The memory of the tree is tracked on every insert and delete with
+/// [`ArtTree::path_memory`], so it matches [`ArtTree::memory_stats`], plus an estimate for the
+/// index of the entries by their last use. When an insert takes the total over the budget, the
+/// eviction policy is asked which entry to evict, again and again until the total fits or the
+/// policy rejects the insert. Without a policy the least recently used entries are evicted.
+///
+/// Only the bytes of the tree are counted, not memory owned by the values.
+pub struct BoundedArtTree<V> {
+    /// The values with the tick of their last use
+    tree: ArtTree<(u64, V)>,
+    /// The keys by the tick of their last use
+    recency: BTreeMap<u64, Box<[u8]>>,
+    clock: u64,
+    budget: usize,
+    bytes: usize,
+    evictions: u64,
+    policy: Option<EvictionPolicy<V>>,
+}
+
+impl<V> BoundedArtTree<V> {
+    /// Creates an empty tree of at most `budget` bytes, evicting the least recently used
+    /// entries.
+    pub fn new(budget: usize) -> Self {
+        Self {
+            tree: ArtTree::new(),
+            recency: BTreeMap::new(),
+            clock: 0,
+            budget,
+            bytes: 0,
+            evictions: 0,
+            policy: None,
+        }
+    }
+
+    /// Creates an empty tree of at most `budget` bytes, asking `policy` what to evict.
+    pub fn with_policy<F>(budget: usize, policy: F) -> Self
+    where
+        F: FnMut(&BoundedArtTree<V>, &[u8]) -> Eviction + 'static,
+    {
+        let mut tree = Self::new(budget);
+        tree.policy = Some(Box::new(policy));
+        tree
+    }
+
+    /// Returns the number of entries
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Returns the byte budget
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// Returns the bytes counted against the budget
+    pub fn memory_bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Returns the number of entries evicted so far
+    pub fn evictions(&self) -> u64 {
+        self.evictions
+    }
+
+    /// Inserts the given value at the given key, evicting entries to make room for it, and
+    /// returns the previous value. Replacing a value takes no extra room.
+    pub fn insert(&mut self, key: &[u8], value: V) -> Result<Option<V>, Rejected<V>> {
+        self.clock += 1;
+        let tick = self.clock;
+        if let Some((used, current)) = self.tree.get_mut(key) {
+            let last_used = mem::replace(used, tick);
+            let old = mem::replace(current, value);
+            self.recency.remove(&last_used);
+            self.recency.insert(tick, key.into());
+            return Ok(Some(old));
+        }
+
+        let before = self.tree.path_memory(key);
+        self.tree.insert(key, (tick, value));
+        self.recency.insert(tick, key.into());
+        self.bytes += self.tree.path_memory(key) - before + recency_bytes(key);
+        while self.bytes > self.budget {
+            let victim = match self.choose_eviction(key) {
+                Eviction::LeastRecentlyUsed => self
+                    .recency
+                    .values()
+                    .find(|victim| &victim[..] != key)
+                    .map(|victim| victim.to_vec()),
+                Eviction::Minimum => self
+                    .tree
+                    .entries()
+                    .map(|(victim, _)| victim)
+                    .find(|&victim| victim != key)
+                    .map(|victim| victim.to_vec()),
+                Eviction::Key(victim) => {
+                    Some(victim).filter(|victim| victim != key && self.tree.contains_key(victim))
+                }
+                Eviction::Reject => None,
+            };
+            match victim {
+                Some(victim) => {
+                    self.remove(&victim);
+                    self.evictions += 1;
+                }
+                None => return Err(Rejected(self.remove(key).unwrap())),
+            }
+        }
+        Ok(None)
+    }
+
+    fn choose_eviction(&mut self, key: &[u8]) -> Eviction {
+        match self.policy.take() {
+            Some(mut policy) => {
+                let eviction = policy(self, key);
+                self.policy = Some(policy);
+                eviction
+            }
+            None => Eviction::LeastRecentlyUsed,
+        }
+    }
+
+    /// Deletes the value stored at the given key and returns it
+    pub fn delete(&mut self, key: &[u8]) -> Option<V> {
+        self.remove(key)
+    }
+
+    fn remove(&mut self, key: &[u8]) -> Option<V> {
+        let before = self.tree.path_memory(key);
+        let (last_used, value) = self.tree.delete(key)?;
+        self.recency.remove(&last_used);
+        self.bytes -= before - self.tree.path_memory(key) + recency_bytes(key);
+        Some(value)
+    }
+
+    /// Returns a reference to the value stored at the given key, marking the entry as used
+    pub fn get(&mut self, key: &[u8]) -> Option<&V> {
+        self.clock += 1;
+        let (used, value) = self.tree.get_mut(key)?;
+        let last_used = mem::replace(used, self.clock);
+        let key = self.recency.remove(&last_used).unwrap();
+        self.recency.insert(self.clock, key);
+        Some(value)
+    }
+
+    /// Returns a reference to the value stored at the given key without marking it as used
+    pub fn peek(&self, key: &[u8]) -> Option<&V> {
+        self.tree.get(key).map(|(_, value)| value)
+    }
+
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.tree.contains_key(key)
+    }
+
+    /// Returns the keys from the least to the most recently used
+    pub fn keys_by_use(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        self.recency.values().map(|key| &key[..])
+    }
+
+    /// Returns an iterator over the key-value pairs in ascending key order, without marking
+    /// them as used
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &V)> + '_ {
+        self.tree.entries().map(|(key, (_, value))| (key, value))
+    }
+}
+
+fn recency_bytes(key: &[u8]) -> usize {
+    RECENCY_ENTRY_BYTES + key.len()
+}
+
+impl<V: fmt::Debug> fmt::Debug for BoundedArtTree<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BoundedArtTree")
+            .field("budget", &self.budget)
+            .field("bytes", &self.bytes)
+            .field("tree", &self.tree)
+            .finish()
+    }
+}
@@ -0,0 +1,233 @@
+use std::borrow::Cow;
+
+use crate::art::ArtTree;
+
+/// The size from which [`CompressedArtTree::new`] compresses values
+pub const DEFAULT_THRESHOLD: usize = 128;
+
+/// Compresses the values of a [`CompressedArtTree`].
+pub trait ValueCodec {
+    /// Returns the compressed bytes of `value`.
+    fn compress(&self, value: &[u8]) -> Vec<u8>;
+
+    /// Returns the value of `len` bytes that `compress` turned into `compressed`.
+    fn decompress(&self, compressed: &[u8], len: usize) -> Vec<u8>;
+}
+
+/// LZ4 block compression, fast with a moderate ratio
+#[cfg(feature = "lz4")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Lz4;
+
+#[cfg(feature = "lz4")]
+impl ValueCodec for Lz4 {
+    fn compress(&self, value: &[u8]) -> Vec<u8> {
+        lz4_flex::block::compress(value)
+    }
+
+    fn decompress(&self, compressed: &[u8], len: usize) -> Vec<u8> {
+        lz4_flex::block::decompress(compressed, len).expect("a value compressed with LZ4")
+    }
+}
+
+/// Zstandard compression, slower than LZ4 with a better ratio
+#[cfg(feature = "zstd")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Zstd {
+    /// The compression level from 1 to 22, or 0 for the default level of the library
+    pub level: i32,
+}
+
+#[cfg(feature = "zstd")]
+impl ValueCodec for Zstd {
+    fn compress(&self, value: &[u8]) -> Vec<u8> {
+        zstd::bulk::compress(value, self.level).expect("a valid Zstandard compression level")
+    }
+
+    fn decompress(&self, compressed: &[u8], len: usize) -> Vec<u8> {
+        zstd::bulk::decompress(compressed, len).expect("a value compressed with Zstandard")
+    }
+}
+
+/// The sizes of the values stored in a [`CompressedArtTree`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompressionStats {
+    /// Number of values stored as they are, being smaller than the threshold or not shrinking
+    /// when compressed
+    pub plain_values: usize,
+    /// Total size of the values stored as they are
+    pub plain_bytes: usize,
+    /// Number of values stored compressed
+    pub compressed_values: usize,
+    /// Total size of the compressed values before compression
+    pub original_bytes: usize,
+    /// Total size of the compressed values as stored
+    pub compressed_bytes: usize,
+}
+
+impl CompressionStats {
+    /// Returns the total size of the values divided by the size they are stored in, 1 for an
+    /// empty tree.
+    pub fn ratio(&self) -> f64 {
+        let stored = self.plain_bytes + self.compressed_bytes;
+        if stored == 0 {
+            1.0
+        } else {
+            (self.plain_bytes + self.original_bytes) as f64 / stored as f64
+        }
+    }
+
+    fn add(&mut self, stored: &Stored) {
+        match stored {
+            Stored::Plain(value) => {
+                self.plain_values += 1;
+                self.plain_bytes += value.len();
+            }
+            Stored::Compressed { len, bytes } => {
+                self.compressed_values += 1;
+                self.original_bytes += len;
+                self.compressed_bytes += bytes.len();
+            }
+        }
+    }
+
+    fn remove(&mut self, stored: &Stored) {
+        match stored {
+            Stored::Plain(value) => {
+                self.plain_values -= 1;
+                self.plain_bytes -= value.len();
+            }
+            Stored::Compressed { len, bytes } => {
+                self.compressed_values -= 1;
+                self.original_bytes -= len;
+                self.compressed_bytes -= bytes.len();
+            }
+        }
+    }
+}
+
+/// A value in the leaf of a [`CompressedArtTree`]
+#[derive(Debug, Clone)]
+enum Stored {
+    Plain(Box<[u8]>),
+    Compressed { len: usize, bytes: Box<[u8]> },
+}
+
+/// Map from byte keys to byte values using an Adaptive Radix Tree, storing large values
+/// compressed
+///
+/// Values of at least [`threshold`](Self::threshold) bytes are compressed with the codec when
+/// they are inserted and decompressed whenever they are read, so that lookups return the values
+/// as they were inserted. Values that do not shrink are stored as they are.
+#[derive(Debug, Clone)]
+pub struct CompressedArtTree<C> {
+    tree: ArtTree<Stored>,
+    codec: C,
+    threshold: usize,
+    stats: CompressionStats,
+}
+
+impl<C: ValueCodec> CompressedArtTree<C> {
+    /// Creates an empty map compressing values of at least [`DEFAULT_THRESHOLD`] bytes
+    pub fn new(codec: C) -> Self {
+        Self::with_threshold(codec, DEFAULT_THRESHOLD)
+    }
+
+    /// Creates an empty map compressing values of at least `threshold` bytes
+    pub fn with_threshold(codec: C, threshold: usize) -> Self {
+        Self {
+            tree: ArtTree::new(),
+            codec,
+            threshold,
+            stats: CompressionStats::default(),
+        }
+    }
+
+    pub fn codec(&self) -> &C {
+        &self.codec
+    }
+
+    /// Returns the size from which values are compressed
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Returns the sizes of the stored values
+    pub fn stats(&self) -> CompressionStats {
+        self.stats
+    }
+
+    /// Returns the number of elements in the map
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns true if the map contains no elements
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Returns true if the map contains a value for the key
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.tree.contains_key(key)
+    }
+
+    /// Returns the value stored at the key, decompressing it if it was compressed
+    pub fn get(&self, key: &[u8]) -> Option<Cow<'_, [u8]>> {
+        self.tree.get(key).map(|stored| self.decode(stored))
+    }
+
+    /// Inserts the value at the key and returns the previous value
+    pub fn insert(&mut self, key: &[u8], value: &[u8]) -> Option<Vec<u8>> {
+        let stored = self.encode(value);
+        self.stats.add(&stored);
+        let old = self.tree.insert(key, stored)?;
+        self.stats.remove(&old);
+        Some(self.decode(&old).into_owned())
+    }
+
+    /// Deletes and returns the value stored at the key
+    pub fn delete(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+        let old = self.tree.delete(key)?;
+        self.stats.remove(&old);
+        Some(self.decode(&old).into_owned())
+    }
+
+    /// Returns an iterator over the entries in ascending key order, decompressing the values as
+    /// they are reached
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], Cow<'_, [u8]>)> + '_ {
+        self.tree
+            .entries()
+            .map(move |(key, stored)| (key, self.decode(stored)))
+    }
+
+    /// Returns an iterator over the entries whose keys start with the prefix
+    pub fn scan_prefix<'a>(
+        &'a self,
+        prefix: &[u8],
+    ) -> impl Iterator<Item = (&'a [u8], Cow<'a, [u8]>)> + 'a {
+        self.tree
+            .scan_prefix(prefix)
+            .map(move |(key, stored)| (key, self.decode(stored)))
+    }
+
+    fn encode(&self, value: &[u8]) -> Stored {
+        if value.len() >= self.threshold {
+            let compressed = self.codec.compress(value);
+            if compressed.len() < value.len() {
+                return Stored::Compressed {
+                    len: value.len(),
+                    bytes: compressed.into_boxed_slice(),
+                };
+            }
+        }
+        Stored::Plain(value.into())
+    }
+
+    fn decode<'a>(&self, stored: &'a Stored) -> Cow<'a, [u8]> {
+        match stored {
+            Stored::Plain(value) => Cow::Borrowed(value),
+            Stored::Compressed { len, bytes } => Cow::Owned(self.codec.decompress(bytes, *len)),
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::{Bound, RangeBounds};
+
+use crate::u64_art_map::U64ArtMap;
+
+const CHUNK_BITS: u64 = 64;
+
+/// Values of up to 64 consecutive keys, stored in one leaf
+#[derive(Clone)]
+struct Chunk<V> {
+    /// Bitmap of the keys of the chunk present in the map, never zero
+    bits: u64,
+    /// Values of the present keys in ascending key order
+    values: Vec<V>,
+}
+
+impl<V> Chunk<V> {
+    /// Returns the index in `values` of the key at `offset` in the chunk, and whether it is
+    /// present
+    fn position(&self, offset: u64) -> (usize, bool) {
+        let bit = 1 << offset;
+        (
+            (self.bits & (bit - 1)).count_ones() as usize,
+            self.bits & bit != 0,
+        )
+    }
+
+    fn entries(&self, start: u64) -> impl Iterator<Item = (u64, &V)> {
+        let mut bits = self.bits;
+        self.values.iter().map(move |value| {
+            let offset = bits.trailing_zeros() as u64;
+            bits &= bits - 1;
+            (start + offset, value)
+        })
+    }
+
+    fn entries_mut(&mut self, start: u64) -> impl Iterator<Item = (u64, &mut V)> {
+        let mut bits = self.bits;
+        self.values.iter_mut().map(move |value| {
+            let offset = bits.trailing_zeros() as u64;
+            bits &= bits - 1;
+            (start + offset, value)
+        })
+    }
+}
+
+/// Map indexed by u64-keys that come in dense runs, using an Adaptive Radix Tree of chunks
+///
+/// Unlike a [`U64ArtMap`] storing one leaf per key, keys are grouped by `key / 64` into chunks
+/// like the members of an [`ArtBitSet`](crate::art_bit_set::ArtBitSet). A chunk is a single
+/// leaf holding a bitmap of its keys and their values in key order, so a run of consecutive
+/// keys takes one leaf and one allocation per 64 keys, and the tree is 64 times smaller. Sparse
+/// keys take a chunk each, which costs a little more than a leaf of a `U64ArtMap`.
+#[derive(Clone)]
+pub struct DenseU64ArtMap<V> {
+    chunks: U64ArtMap<Chunk<V>>,
+    len: usize,
+}
+
+fn split(key: u64) -> (u64, u64) {
+    (key / CHUNK_BITS, key % CHUNK_BITS)
+}
+
+impl<V> DenseU64ArtMap<V> {
+    pub fn new() -> Self {
+        Self {
+            chunks: U64ArtMap::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of elements in the map
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the map contains no elements
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of chunks, i.e. the leaves of the tree
+    pub fn chunks(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn contains_key(&self, key: u64) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn get(&self, key: u64) -> Option<&V> {
+        let (index, offset) = split(key);
+        let chunk = self.chunks.get(&index)?;
+        match chunk.position(offset) {
+            (i, true) => Some(&chunk.values[i]),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: u64) -> Option<&mut V> {
+        let (index, offset) = split(key);
+        let chunk = self.chunks.get_mut(&index)?;
+        match chunk.position(offset) {
+            (i, true) => Some(&mut chunk.values[i]),
+            _ => None,
+        }
+    }
+
+    /// Inserts a value into the map and returns the previous value of the key
+    pub fn insert(&mut self, key: u64, value: V) -> Option<V> {
+        let (index, offset) = split(key);
+        let chunk = self.chunks.entry(index).or_insert_with(|| Chunk {
+            bits: 0,
+            values: Vec::new(),
+        });
+        match chunk.position(offset) {
+            (i, true) => Some(std::mem::replace(&mut chunk.values[i], value)),
+            (i, false) => {
+                chunk.bits |= 1 << offset;
+                chunk.values.insert(i, value);
+                self.len += 1;
+                None
+            }
+        }
+    }
+
+    /// Removes the value of the key from the map and returns it
+    pub fn delete(&mut self, key: u64) -> Option<V> {
+        let (index, offset) = split(key);
+        let chunk = self.chunks.get_mut(&index)?;
+        let i = match chunk.position(offset) {
+            (i, true) => i,
+            _ => return None,
+        };
+        chunk.bits &= !(1 << offset);
+        let value = chunk.values.remove(i);
+        if chunk.bits == 0 {
+            self.chunks.delete(index);
+        }
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Returns an iterator over the key-value pairs of the map in ascending key order
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &V)> + '_ {
+        self.chunks
+            .iter()
+            .flat_map(|(index, chunk)| chunk.entries(index * CHUNK_BITS))
+    }
+
+    /// Returns an iterator over the entries of the map in ascending key order, with mutable
+    /// references to the values
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (u64, &mut V)> + '_ {
+        self.chunks
+            .iter_mut()
+            .flat_map(|(index, chunk)| chunk.entries_mut(index * CHUNK_BITS))
+    }
+
+    /// Returns an iterator over the key-value pairs whose keys fall in the given range, in
+    /// ascending key order. Only the chunks overlapping the range are visited.
+    pub fn range<R: RangeBounds<u64>>(&self, range: R) -> impl Iterator<Item = (u64, &V)> + '_ {
+        let bounds = (range.start_bound().cloned(), range.end_bound().cloned());
+        let start = match bounds.0 {
+            Bound::Included(key) | Bound::Excluded(key) => Bound::Included(split(key).0),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let end = match bounds.1 {
+            Bound::Included(key) | Bound::Excluded(key) => Bound::Included(split(key).0),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        self.chunks
+            .range((start, end))
+            .flat_map(|(index, chunk)| chunk.entries(index * CHUNK_BITS))
+            .skip_while(move |(key, _)| !bounds.contains(key))
+            .take_while(move |(key, _)| bounds.contains(key))
+    }
+
+    /// Returns the entry with the minimum key
+    pub fn first(&self) -> Option<(u64, &V)> {
+        let (index, chunk) = self.chunks.minimum()?;
+        chunk.entries(index * CHUNK_BITS).next()
+    }
+
+    /// Returns the entry with the maximum key
+    pub fn last(&self) -> Option<(u64, &V)> {
+        let (index, chunk) = self.chunks.maximum()?;
+        chunk.entries(index * CHUNK_BITS).last()
+    }
+}
+
+impl<V> Default for DenseU64ArtMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> FromIterator<(u64, V)> for DenseU64ArtMap<V> {
+    fn from_iter<I: IntoIterator<Item = (u64, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<V> Extend<(u64, V)> for DenseU64ArtMap<V> {
+    fn extend<I: IntoIterator<Item = (u64, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<V: fmt::Debug> fmt::Debug for DenseU64ArtMap<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
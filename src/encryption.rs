@@ -0,0 +1,242 @@
+//! Authenticated encryption of [snapshots](crate::snapshot) and [flat trees](crate::flat_art),
+//! so that they can be stored where others can read or modify them.
+//!
+//! The data is sealed with XChaCha20-Poly1305 under a user-supplied 256-bit key, in blocks of up
+//! to 64 KiB that are written and read as they fill, so that streams stay bounded in memory. A
+//! sealed stream starts with a random 24-byte nonce. Every block follows as its ciphertext
+//! length as a little-endian `u32`, a byte that is 1 for the last block and 0 otherwise, and the
+//! ciphertext including the 16-byte tag. The nonce of a block is the stream nonce with the block
+//! number xored into its last 8 bytes, and the associated data is the header of the format,
+//! which stays readable, followed by the last-block byte. A wrong key, a modified header or
+//! block, reordered or dropped blocks and a stream cut off before its last block all fail to
+//! decrypt.
+//!
+//! An encrypted flat tree cannot be queried in place. [`flat_art::decrypt`] turns it back into
+//! a buffer that [`FlatArt::new`] opens, once, after which lookups read the decrypted buffer
+//! without copying.
+//!
+//! [`flat_art::decrypt`]: crate::flat_art::decrypt
+//! [`FlatArt::new`]: crate::flat_art::FlatArt::new
+
+use std::cmp::min;
+use std::error;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use chacha20poly1305::aead::{AeadCore, AeadInPlace, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+
+/// The maximum number of plaintext bytes in a block
+const BLOCK_LEN: usize = 64 * 1024;
+const TAG_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// A 256-bit key sealing and opening encrypted data
+#[derive(Clone)]
+pub struct EncryptionKey(Key);
+
+impl EncryptionKey {
+    pub fn new(bytes: [u8; 32]) -> Self {
+        EncryptionKey(bytes.into())
+    }
+
+    /// Generates a random key with the random number generator of the operating system
+    pub fn generate() -> Self {
+        EncryptionKey(XChaCha20Poly1305::generate_key(&mut OsRng))
+    }
+
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new(&self.0)
+    }
+}
+
+/// Does not print the key
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+/// Encrypted data that failed to decrypt, because the key is wrong or the data was modified or
+/// cut off.
+///
+/// [`OpenReader`] returns it wrapped in an `io::Error` of the kind `InvalidData`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecryptionError;
+
+impl fmt::Display for DecryptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("decryption failed: the key is wrong or the data was modified")
+    }
+}
+
+impl error::Error for DecryptionError {}
+
+impl DecryptionError {
+    /// Returns true if `err` wraps a `DecryptionError`
+    pub(crate) fn is_cause_of(err: &io::Error) -> bool {
+        err.get_ref()
+            .is_some_and(|cause| cause.is::<DecryptionError>())
+    }
+}
+
+/// Returns the nonce of the block with the given number
+fn block_nonce(nonce: &XNonce, index: u64) -> XNonce {
+    let mut nonce = *nonce;
+    for (byte, counter) in nonce[NONCE_LEN - 8..].iter_mut().zip(&index.to_le_bytes()) {
+        *byte ^= counter;
+    }
+    nonce
+}
+
+/// Encrypts the bytes written to it into a sealed stream, see the
+/// [module documentation](self).
+///
+/// The stream has to be completed with [`finish`](Self::finish), which seals the last block.
+/// `flush` only flushes the underlying writer, the bytes of a block that is not full yet are
+/// held back until it is.
+pub struct SealWriter<W: Write> {
+    out: W,
+    cipher: XChaCha20Poly1305,
+    nonce: XNonce,
+    /// The context followed by the last-block byte
+    associated: Vec<u8>,
+    block: Vec<u8>,
+    index: u64,
+}
+
+impl<W: Write> SealWriter<W> {
+    /// Writes the nonce of a new stream to `out`. The `context`, e.g. the header of the file,
+    /// has to be passed to [`OpenReader::new`] unchanged to decrypt the stream.
+    pub fn new(mut out: W, key: &EncryptionKey, context: &[u8]) -> io::Result<Self> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        out.write_all(&nonce)?;
+        let mut associated = context.to_vec();
+        associated.push(0);
+        Ok(Self {
+            out,
+            cipher: key.cipher(),
+            nonce,
+            associated,
+            block: Vec::with_capacity(BLOCK_LEN + TAG_LEN),
+            index: 0,
+        })
+    }
+
+    /// Seals the last block, flushes the stream and returns the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.seal_block(true)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    fn seal_block(&mut self, last: bool) -> io::Result<()> {
+        *self.associated.last_mut().unwrap() = last as u8;
+        self.cipher
+            .encrypt_in_place(
+                &block_nonce(&self.nonce, self.index),
+                &self.associated,
+                &mut self.block,
+            )
+            .map_err(|_| io::Error::other("encryption failed"))?;
+        self.out
+            .write_all(&(self.block.len() as u32).to_le_bytes())?;
+        self.out.write_all(&[last as u8])?;
+        self.out.write_all(&self.block)?;
+        self.block.clear();
+        self.index += 1;
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for SealWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.block.len() == BLOCK_LEN {
+            self.seal_block(false)?;
+        }
+        let len = min(buf.len(), BLOCK_LEN - self.block.len());
+        self.block.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Decrypts a stream sealed by a [`SealWriter`], see the [module documentation](self).
+///
+/// The bytes of a block are only returned once the whole block was authenticated. A block that
+/// fails to decrypt is reported as an `io::Error` of the kind `InvalidData` wrapping a
+/// [`DecryptionError`], a stream ending before its last block as one of the kind
+/// `UnexpectedEof`.
+pub struct OpenReader<R: Read> {
+    input: R,
+    cipher: XChaCha20Poly1305,
+    nonce: XNonce,
+    /// The context followed by the last-block byte
+    associated: Vec<u8>,
+    block: Vec<u8>,
+    pos: usize,
+    index: u64,
+    done: bool,
+}
+
+impl<R: Read> OpenReader<R> {
+    /// Reads the nonce of the stream from `input`. The `context` has to be the one the stream
+    /// was sealed with.
+    pub fn new(mut input: R, key: &EncryptionKey, context: &[u8]) -> io::Result<Self> {
+        let mut nonce = XNonce::default();
+        input.read_exact(&mut nonce)?;
+        let mut associated = context.to_vec();
+        associated.push(0);
+        Ok(Self {
+            input,
+            cipher: key.cipher(),
+            nonce,
+            associated,
+            block: Vec::new(),
+            pos: 0,
+            index: 0,
+            done: false,
+        })
+    }
+
+    fn open_block(&mut self) -> io::Result<()> {
+        let mut header = [0u8; 5];
+        self.input.read_exact(&mut header)?;
+        let len = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        if len > BLOCK_LEN + TAG_LEN || header[4] > 1 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, DecryptionError));
+        }
+        self.block.resize(len, 0);
+        self.input.read_exact(&mut self.block)?;
+        *self.associated.last_mut().unwrap() = header[4];
+        self.cipher
+            .decrypt_in_place(
+                &block_nonce(&self.nonce, self.index),
+                &self.associated,
+                &mut self.block,
+            )
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, DecryptionError))?;
+        self.pos = 0;
+        self.index += 1;
+        self.done = header[4] == 1;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for OpenReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.block.len() {
+            if self.done {
+                return Ok(0);
+            }
+            self.open_block()?;
+        }
+        let len = min(buf.len(), self.block.len() - self.pos);
+        buf[..len].copy_from_slice(&self.block[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
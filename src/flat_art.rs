@@ -0,0 +1,844 @@
+//! A read-only tree stored in a flat buffer, queried in place without deserializing it.
+//!
+//! [`FlatArtBuilder`] (or [`ArtTree::to_flat`]) writes the keys and values into a `Vec<u8>`,
+//! and [`FlatArt`] looks them up directly in the bytes, e.g. of a file embedded with
+//! `include_bytes!`. Values are written with [`EncodeFlat`] and read back with [`DecodeFlat`],
+//! which can borrow from the buffer.
+//!
+//! The buffer starts with a 16-byte header: the magic `ARTF`, the format version and the
+//! [`FlatOptions`] flags as little-endian `u16`, and the number of entries and the offset of
+//! the root node as little-endian `u32`. The nodes follow, every one written after its
+//! children:
+//!
+//! - a leaf: the tag `0`, the key and value lengths as `u32`, the key and the value. A
+//!   front-coded leaf stores only the part of the key after the path leading to it;
+//! - an inner node: the tag `1`, the length of its compressed path as `u32`, the number of
+//!   children as `u16`, the path, the sorted key bytes of the children and their offsets as
+//!   `u32`.
+//!
+//! With the `lz4` feature, everything after the header can be compressed, see [`decompress`].
+//! With the `encryption` feature, it can be sealed with a key, see `encrypt`, after compressing
+//! it.
+//!
+//! Nothing is aligned, so the buffer can be read from any address. A buffer that passes the
+//! header check of [`FlatArt::new`] but is corrupted otherwise makes the queries panic.
+
+use std::borrow::Cow;
+use std::convert::TryInto;
+use std::error;
+use std::fmt;
+#[cfg(feature = "encryption")]
+use std::io::{Read, Write};
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+use std::str;
+
+use crate::art::{ArtTree, BuildSink, BulkLoad, LeafKey, SortedPath};
+#[cfg(feature = "encryption")]
+use crate::encryption::{EncryptionKey, OpenReader, SealWriter};
+
+const MAGIC: &[u8; 4] = b"ARTF";
+const VERSION: u16 = 1;
+const HEADER_LEN: usize = 16;
+const FRONT_CODED: u16 = 1;
+const COMPRESSED: u16 = 2;
+const ENCRYPTED: u16 = 4;
+const LEAF: u8 = 0;
+const INNER: u8 = 1;
+
+/// Writes a value into a flat buffer, see [`FlatArtBuilder`].
+pub trait EncodeFlat {
+    /// Appends the encoded value to `out`.
+    fn encode_flat(&self, out: &mut Vec<u8>);
+}
+
+/// Reads a value written by [`EncodeFlat`] back from a flat buffer, borrowing from it where
+/// possible (e.g. `&'a str` for a `String`).
+pub trait DecodeFlat<'a>: Sized {
+    /// Reads the value from exactly the bytes written by `encode_flat`, returning `None` if they
+    /// do not hold a value of this type.
+    fn try_decode_flat(bytes: &'a [u8]) -> Option<Self>;
+
+    /// Reads the value like [`try_decode_flat`](Self::try_decode_flat).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bytes do not hold a value of this type.
+    fn decode_flat(bytes: &'a [u8]) -> Self {
+        Self::try_decode_flat(bytes).expect("a flat value of another type")
+    }
+}
+
+impl EncodeFlat for [u8] {
+    fn encode_flat(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self);
+    }
+}
+
+impl EncodeFlat for Vec<u8> {
+    fn encode_flat(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self);
+    }
+}
+
+impl EncodeFlat for str {
+    fn encode_flat(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self.as_bytes());
+    }
+}
+
+impl EncodeFlat for String {
+    fn encode_flat(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self.as_bytes());
+    }
+}
+
+impl<T: EncodeFlat + ?Sized> EncodeFlat for &T {
+    fn encode_flat(&self, out: &mut Vec<u8>) {
+        (**self).encode_flat(out)
+    }
+}
+
+impl EncodeFlat for () {
+    fn encode_flat(&self, _out: &mut Vec<u8>) {}
+}
+
+impl<'a> DecodeFlat<'a> for &'a [u8] {
+    fn try_decode_flat(bytes: &'a [u8]) -> Option<Self> {
+        Some(bytes)
+    }
+}
+
+impl<'a> DecodeFlat<'a> for &'a str {
+    fn try_decode_flat(bytes: &'a [u8]) -> Option<Self> {
+        str::from_utf8(bytes).ok()
+    }
+}
+
+/// Copies the bytes out of the buffer
+impl<'a> DecodeFlat<'a> for Vec<u8> {
+    fn try_decode_flat(bytes: &'a [u8]) -> Option<Self> {
+        Some(bytes.to_vec())
+    }
+}
+
+/// Copies the string out of the buffer
+impl<'a> DecodeFlat<'a> for String {
+    fn try_decode_flat(bytes: &'a [u8]) -> Option<Self> {
+        <&str>::try_decode_flat(bytes).map(str::to_owned)
+    }
+}
+
+impl<'a> DecodeFlat<'a> for () {
+    fn try_decode_flat(bytes: &'a [u8]) -> Option<Self> {
+        if bytes.is_empty() {
+            Some(())
+        } else {
+            None
+        }
+    }
+}
+
+macro_rules! impl_flat_num {
+    ($($t:ty),*) => {
+        $(
+            /// Stored in little-endian byte order
+            impl EncodeFlat for $t {
+                fn encode_flat(&self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_le_bytes());
+                }
+            }
+
+            impl<'a> DecodeFlat<'a> for $t {
+                fn try_decode_flat(bytes: &'a [u8]) -> Option<Self> {
+                    bytes.try_into().ok().map(<$t>::from_le_bytes)
+                }
+            }
+        )*
+    };
+}
+
+impl_flat_num!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+/// How [`FlatArtBuilder`] writes a buffer, trading the cost of reading it for its size.
+///
+/// The default is a buffer whose keys are borrowed from it as they are.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FlatOptions {
+    front_coded: bool,
+    #[cfg(feature = "lz4")]
+    compressed: bool,
+}
+
+impl FlatOptions {
+    /// Stores the keys of the leaves without the path leading to them, which the inner nodes
+    /// already hold. The buffer is still queried in place, but iterators assemble the keys
+    /// instead of borrowing them from the buffer.
+    pub fn front_coded(mut self, front_coded: bool) -> Self {
+        self.front_coded = front_coded;
+        self
+    }
+
+    /// Compresses everything after the header with LZ4. The buffer has to be inflated with
+    /// [`decompress`] before it can be opened.
+    #[cfg(feature = "lz4")]
+    pub fn compressed(mut self, compressed: bool) -> Self {
+        self.compressed = compressed;
+        self
+    }
+
+    fn flags(&self) -> u16 {
+        let mut flags = 0;
+        if self.front_coded {
+            flags |= FRONT_CODED;
+        }
+        #[cfg(feature = "lz4")]
+        if self.compressed {
+            flags |= COMPRESSED;
+        }
+        flags
+    }
+}
+
+/// Writes entries pushed in ascending key order into the flat format read by [`FlatArt`].
+///
+/// Like [`ArtBuilder`](crate::art::ArtBuilder), only the rightmost path of the tree is kept in
+/// memory: every other node is written to the buffer as soon as no later key can be added
+/// below it.
+///
+/// Like in an `ArtTree`, no key may be a prefix of another one.
+#[derive(Debug, Default, Clone)]
+pub struct FlatArtBuilder {
+    /// Open nodes, whose children are the offsets of closed ones, and the encoded value of
+    /// the last entry
+    path: SortedPath<Vec<u8>, u32>,
+    writer: FlatWriter,
+}
+
+/// The buffer written so far, with room for the header
+#[derive(Debug, Clone)]
+struct FlatWriter {
+    out: Vec<u8>,
+    options: FlatOptions,
+}
+
+impl Default for FlatWriter {
+    fn default() -> Self {
+        Self {
+            out: vec![0; HEADER_LEN],
+            options: FlatOptions::default(),
+        }
+    }
+}
+
+impl FlatArtBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a builder writing the buffer as configured by `options`
+    pub fn with_options(options: FlatOptions) -> Self {
+        Self {
+            writer: FlatWriter {
+                options,
+                ..FlatWriter::default()
+            },
+            ..Self::default()
+        }
+    }
+
+    /// Adds an entry.
+    ///
+    /// # Panics
+    ///
+    /// If `key` is not greater than the key pushed before it, or the key before it is a prefix
+    /// of it.
+    pub fn push<V: EncodeFlat + ?Sized>(&mut self, key: &[u8], value: &V) {
+        self.path.check_order(key);
+        let mut encoded = Vec::new();
+        value.encode_flat(&mut encoded);
+        self.path.push(&mut self.writer, key, encoded);
+    }
+
+    /// Returns the number of entries pushed
+    pub fn len(&self) -> usize {
+        self.path.len
+    }
+
+    /// Returns true if no entry was pushed
+    pub fn is_empty(&self) -> bool {
+        self.path.len == 0
+    }
+
+    /// Writes the rest of the entries and returns the buffer for [`FlatArt::new`].
+    pub fn finish(self) -> Vec<u8> {
+        let Self {
+            path, mut writer, ..
+        } = self;
+        let len = path.len;
+        let phase = BulkLoad::enter("finish_flat");
+        phase.entries(len);
+        let root = path.finish(&mut writer).unwrap_or(0);
+        let FlatWriter { mut out, options } = writer;
+        out[..4].copy_from_slice(MAGIC);
+        out[4..6].copy_from_slice(&VERSION.to_le_bytes());
+        out[6..8].copy_from_slice(&options.flags().to_le_bytes());
+        out[8..12].copy_from_slice(&to_u32(len).to_le_bytes());
+        out[12..16].copy_from_slice(&root.to_le_bytes());
+        #[cfg(feature = "lz4")]
+        if options.compressed {
+            let body = lz4_flex::block::compress_prepend_size(&out[HEADER_LEN..]);
+            out.truncate(HEADER_LEN);
+            out.extend_from_slice(&body);
+        }
+        out
+    }
+}
+
+impl BuildSink<Vec<u8>, u32> for FlatWriter {
+    fn leaf(&mut self, value: Vec<u8>, mut key: &[u8], depth: usize) -> u32 {
+        if self.options.front_coded {
+            key = &key[depth..];
+        }
+        let out = &mut self.out;
+        let offset = to_u32(out.len());
+        out.push(LEAF);
+        out.extend_from_slice(&to_u32(key.len()).to_le_bytes());
+        out.extend_from_slice(&to_u32(value.len()).to_le_bytes());
+        out.extend_from_slice(key);
+        out.extend_from_slice(&value);
+        offset
+    }
+
+    fn inner(&mut self, path: &[u8], children: Vec<(u8, u32)>) -> u32 {
+        let out = &mut self.out;
+        let offset = to_u32(out.len());
+        out.push(INNER);
+        out.extend_from_slice(&to_u32(path.len()).to_le_bytes());
+        out.extend_from_slice(&(children.len() as u16).to_le_bytes());
+        out.extend_from_slice(path);
+        out.extend(children.iter().map(|&(c, _)| c));
+        for (_, child) in children {
+            out.extend_from_slice(&child.to_le_bytes());
+        }
+        offset
+    }
+}
+
+fn to_u32(n: usize) -> u32 {
+    n.try_into()
+        .expect("a flat tree is limited to 4 GiB and keys and values of 4 GiB")
+}
+
+impl<V: EncodeFlat, K: LeafKey> ArtTree<V, K> {
+    /// Writes the entries of the tree into a buffer for [`FlatArt::new`].
+    pub fn to_flat(&self) -> Vec<u8> {
+        self.to_flat_with(FlatOptions::default())
+    }
+
+    /// Writes the entries of the tree into a buffer laid out as configured by `options`.
+    pub fn to_flat_with(&self, options: FlatOptions) -> Vec<u8> {
+        let mut builder = FlatArtBuilder::with_options(options);
+        for (key, value) in self.entries() {
+            builder.push(key, value);
+        }
+        builder.finish()
+    }
+}
+
+/// A buffer rejected by [`FlatArt::new`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlatError {
+    /// The buffer is shorter than the header
+    TooShort,
+    /// The buffer does not start with the magic bytes of the format
+    BadMagic,
+    /// The buffer was written in another version of the format
+    UnsupportedVersion(u16),
+    /// The buffer was written with options this version does not know
+    UnsupportedFlags(u16),
+    /// The buffer is compressed and has to be inflated with [`decompress`] first
+    Compressed,
+    /// The compressed part of the buffer is damaged
+    Corrupt,
+    /// The root offset points outside the buffer
+    BadRoot,
+    /// The buffer is encrypted and has to be decrypted with `decrypt` first
+    Encrypted,
+    /// The buffer failed to decrypt, because the key is wrong or the buffer was modified
+    Decryption,
+}
+
+impl fmt::Display for FlatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlatError::TooShort => f.write_str("the buffer is shorter than the header"),
+            FlatError::BadMagic => f.write_str("the buffer does not hold a flat tree"),
+            FlatError::UnsupportedVersion(version) => {
+                write!(f, "unsupported flat tree format version {}", version)
+            }
+            FlatError::UnsupportedFlags(flags) => {
+                write!(f, "unsupported flat tree options {:#06x}", flags)
+            }
+            FlatError::Compressed => f.write_str("the buffer is compressed"),
+            FlatError::Corrupt => f.write_str("the compressed buffer is damaged"),
+            FlatError::BadRoot => f.write_str("the root node is outside the buffer"),
+            FlatError::Encrypted => f.write_str("the buffer is encrypted"),
+            FlatError::Decryption => {
+                f.write_str("the key is wrong or the encrypted buffer was modified")
+            }
+        }
+    }
+}
+
+impl error::Error for FlatError {}
+
+/// Checks the header of a buffer and returns its flags
+fn read_header(buf: &[u8]) -> Result<u16, FlatError> {
+    if buf.len() < HEADER_LEN {
+        return Err(FlatError::TooShort);
+    }
+    if &buf[..4] != MAGIC {
+        return Err(FlatError::BadMagic);
+    }
+    let version = read_u16(buf, 4);
+    if version != VERSION {
+        return Err(FlatError::UnsupportedVersion(version));
+    }
+    let flags = read_u16(buf, 6);
+    if flags & !(FRONT_CODED | COMPRESSED | ENCRYPTED) != 0 {
+        return Err(FlatError::UnsupportedFlags(flags));
+    }
+    Ok(flags)
+}
+
+/// Inflates a buffer written with [`FlatOptions::compressed`] into one that [`FlatArt::new`]
+/// opens. Other buffers are copied as they are.
+#[cfg(feature = "lz4")]
+pub fn decompress(buf: &[u8]) -> Result<Vec<u8>, FlatError> {
+    let flags = read_header(buf)?;
+    if flags & ENCRYPTED != 0 {
+        return Err(FlatError::Encrypted);
+    }
+    if flags & COMPRESSED == 0 {
+        return Ok(buf.to_vec());
+    }
+    let body = lz4_flex::block::decompress_size_prepended(&buf[HEADER_LEN..])
+        .map_err(|_| FlatError::Corrupt)?;
+    let mut out = Vec::with_capacity(HEADER_LEN + body.len());
+    out.extend_from_slice(&buf[..HEADER_LEN]);
+    out[6..8].copy_from_slice(&(flags & !COMPRESSED).to_le_bytes());
+    out.extend_from_slice(&body);
+    Ok(out)
+}
+
+/// Seals everything after the header of a buffer with `key`. The header stays readable and is
+/// authenticated along with the rest, see the `encryption` module. The buffer has to be
+/// decrypted with [`decrypt`] before it can be opened or inflated.
+#[cfg(feature = "encryption")]
+pub fn encrypt(buf: &[u8], key: &EncryptionKey) -> Result<Vec<u8>, FlatError> {
+    let flags = read_header(buf)?;
+    if flags & ENCRYPTED != 0 {
+        return Err(FlatError::Encrypted);
+    }
+    let mut header = buf[..HEADER_LEN].to_vec();
+    header[6..8].copy_from_slice(&(flags | ENCRYPTED).to_le_bytes());
+    let mut out = header.clone();
+    // Writing into a vector does not fail
+    let mut sealed = SealWriter::new(&mut out, key, &header).unwrap();
+    sealed.write_all(&buf[HEADER_LEN..]).unwrap();
+    sealed.finish().unwrap();
+    Ok(out)
+}
+
+/// Opens a buffer sealed by [`encrypt`] with the same key, returning one that [`FlatArt::new`]
+/// opens, or [`decompress`] inflates if it was compressed. Other buffers are copied as they are.
+#[cfg(feature = "encryption")]
+pub fn decrypt(buf: &[u8], key: &EncryptionKey) -> Result<Vec<u8>, FlatError> {
+    let flags = read_header(buf)?;
+    if flags & ENCRYPTED == 0 {
+        return Ok(buf.to_vec());
+    }
+    let header = &buf[..HEADER_LEN];
+    let mut out = header.to_vec();
+    out[6..8].copy_from_slice(&(flags & !ENCRYPTED).to_le_bytes());
+    OpenReader::new(&buf[HEADER_LEN..], key, header)
+        .and_then(|mut body| body.read_to_end(&mut out))
+        .map_err(|_| FlatError::Decryption)?;
+    Ok(out)
+}
+
+/// A read-only view of a tree written by [`FlatArtBuilder`], reading the keys and values
+/// directly from the buffer.
+pub struct FlatArt<'a, V> {
+    buf: &'a [u8],
+    len: usize,
+    root: usize,
+    front_coded: bool,
+    _value: PhantomData<fn() -> V>,
+}
+
+impl<V> Clone for FlatArt<'_, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<V> Copy for FlatArt<'_, V> {}
+
+impl<V> fmt::Debug for FlatArt<'_, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FlatArt")
+            .field("len", &self.len)
+            .field("bytes", &self.buf.len())
+            .field("front_coded", &self.front_coded)
+            .finish()
+    }
+}
+
+/// A node read from the buffer
+enum FlatNode<'a> {
+    Leaf {
+        key: &'a [u8],
+        value: &'a [u8],
+    },
+    Inner {
+        path: &'a [u8],
+        keys: &'a [u8],
+        children: &'a [u8],
+    },
+}
+
+impl FlatNode<'_> {
+    fn child(&self, i: usize) -> usize {
+        match self {
+            FlatNode::Inner { children, .. } => read_u32(children, 4 * i) as usize,
+            FlatNode::Leaf { .. } => unreachable!(),
+        }
+    }
+}
+
+fn read_u16(buf: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes(buf[pos..pos + 2].try_into().unwrap())
+}
+
+fn read_u32(buf: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes(buf[pos..pos + 4].try_into().unwrap())
+}
+
+impl<'a, V: DecodeFlat<'a>> FlatArt<'a, V> {
+    /// Opens a buffer written by [`FlatArtBuilder::finish`]. Only the header is checked, so this
+    /// takes constant time.
+    pub fn new(buf: &'a [u8]) -> Result<Self, FlatError> {
+        let flags = read_header(buf)?;
+        if flags & ENCRYPTED != 0 {
+            return Err(FlatError::Encrypted);
+        }
+        if flags & COMPRESSED != 0 {
+            return Err(FlatError::Compressed);
+        }
+        let len = read_u32(buf, 8) as usize;
+        let root = read_u32(buf, 12) as usize;
+        if len > 0 && (root < HEADER_LEN || root >= buf.len()) {
+            return Err(FlatError::BadRoot);
+        }
+        Ok(FlatArt {
+            buf,
+            len,
+            root,
+            front_coded: flags & FRONT_CODED != 0,
+            _value: PhantomData,
+        })
+    }
+
+    /// Returns the number of entries
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the tree holds no entries
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns true if a value is stored at the given key
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.find(key).is_some()
+    }
+
+    /// Returns the value stored at the given key
+    pub fn get(&self, key: &[u8]) -> Option<V> {
+        self.find(key).map(V::decode_flat)
+    }
+
+    fn find(&self, key: &[u8]) -> Option<&'a [u8]> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut offset = self.root;
+        let mut depth = 0;
+        loop {
+            let node = self.node(offset);
+            match node {
+                FlatNode::Leaf { key: stored, value } => {
+                    return if self.key_rest(stored, depth) == &key[depth..] {
+                        Some(value)
+                    } else {
+                        None
+                    };
+                }
+                FlatNode::Inner { path, keys, .. } => {
+                    if !key[depth..].starts_with(path) {
+                        return None;
+                    }
+                    depth += path.len();
+                    let i = keys.binary_search(key.get(depth)?).ok()?;
+                    offset = node.child(i);
+                    depth += 1;
+                }
+            }
+        }
+    }
+
+    /// Iterates over all entries in key order
+    pub fn iter(&self) -> FlatIter<'a, V> {
+        let mut iter = self.iter_from(&[], Bound::Unbounded);
+        if !self.is_empty() {
+            iter.stack.push((self.root, 0, 0));
+        }
+        iter
+    }
+
+    /// Iterates over the entries whose keys start with `prefix`, in key order
+    pub fn scan_prefix(&self, prefix: &[u8]) -> FlatIter<'a, V> {
+        let mut iter = self.iter_from(prefix, Bound::Unbounded);
+        if self.is_empty() {
+            return iter;
+        }
+        let mut offset = self.root;
+        let mut depth = 0;
+        loop {
+            let node = self.node(offset);
+            match node {
+                FlatNode::Leaf { key, .. } => {
+                    if self.key_rest(key, depth).starts_with(&prefix[depth..]) {
+                        iter.stack.push((offset, 0, depth));
+                    }
+                    return iter;
+                }
+                FlatNode::Inner { path, keys, .. } => {
+                    // The subtree matches once the prefix ends within the path
+                    let rest = &prefix[depth..];
+                    if rest.len() <= path.len() {
+                        if path.starts_with(rest) {
+                            iter.stack.push((offset, 0, depth));
+                        }
+                        return iter;
+                    }
+                    if !rest.starts_with(path) {
+                        return iter;
+                    }
+                    depth += path.len();
+                    match keys.binary_search(&prefix[depth]) {
+                        Ok(i) => offset = node.child(i),
+                        Err(_) => return iter,
+                    }
+                    depth += 1;
+                }
+            }
+        }
+    }
+
+    /// Iterates over the entries whose keys lie within `range`, in key order
+    pub fn range<'r, R>(&self, range: R) -> FlatIter<'a, V>
+    where
+        R: RangeBounds<&'r [u8]>,
+    {
+        let end = match range.end_bound() {
+            Bound::Included(key) => Bound::Included(key.to_vec()),
+            Bound::Excluded(key) => Bound::Excluded(key.to_vec()),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let (start, inclusive) = match range.start_bound() {
+            Bound::Included(key) => (*key, true),
+            Bound::Excluded(key) => (*key, false),
+            Bound::Unbounded => (&[][..], true),
+        };
+        let mut iter = self.iter_from(start, end);
+        if self.is_empty() {
+            return iter;
+        }
+
+        // Descend towards the start, leaving the parents to continue after the child taken
+        let mut offset = self.root;
+        let mut depth = 0;
+        loop {
+            let node = self.node(offset);
+            match node {
+                FlatNode::Leaf { key, .. } => {
+                    let (key, start) = (self.key_rest(key, depth), &start[depth..]);
+                    if key > start || (inclusive && key == start) {
+                        iter.stack.push((offset, 0, depth));
+                    }
+                    return iter;
+                }
+                FlatNode::Inner { path, keys, .. } => {
+                    let node_depth = depth;
+                    let rest = &start[depth..];
+                    let common = rest.len().min(path.len());
+                    if rest[..common] != path[..common] {
+                        // The subtree lies entirely before or after the start
+                        if path[..common] > rest[..common] {
+                            iter.stack.push((offset, 0, node_depth));
+                        }
+                        return iter;
+                    }
+                    depth += path.len();
+                    let c = match start.get(depth) {
+                        Some(&c) => c,
+                        None => {
+                            // Every key below is longer than the start, so greater
+                            iter.stack.push((offset, 0, node_depth));
+                            return iter;
+                        }
+                    };
+                    match keys.binary_search(&c) {
+                        Ok(i) => {
+                            iter.stack.push((offset, i + 1, node_depth));
+                            offset = node.child(i);
+                            depth += 1;
+                        }
+                        Err(i) => {
+                            iter.stack.push((offset, i, node_depth));
+                            return iter;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn node(&self, offset: usize) -> FlatNode<'a> {
+        read_node(self.buf, offset)
+    }
+
+    /// Returns the part of the key stored in a leaf at `depth` that follows the path leading to
+    /// the leaf
+    fn key_rest<'k>(&self, stored: &'k [u8], depth: usize) -> &'k [u8] {
+        if self.front_coded {
+            stored
+        } else {
+            &stored[depth..]
+        }
+    }
+
+    /// Returns an iterator without nodes to visit yet. Front-coded keys are assembled on top
+    /// of `path`, which the paths of the nodes pushed first have to start with.
+    fn iter_from(&self, path: &[u8], end: Bound<Vec<u8>>) -> FlatIter<'a, V> {
+        FlatIter {
+            buf: self.buf,
+            stack: Vec::new(),
+            end,
+            front_coded: self.front_coded,
+            key: if self.front_coded {
+                path.to_vec()
+            } else {
+                Vec::new()
+            },
+            _value: PhantomData,
+        }
+    }
+}
+
+fn read_node(buf: &[u8], offset: usize) -> FlatNode<'_> {
+    match buf[offset] {
+        LEAF => {
+            let key_len = read_u32(buf, offset + 1) as usize;
+            let value_len = read_u32(buf, offset + 5) as usize;
+            let key_start = offset + 9;
+            let value_start = key_start + key_len;
+            FlatNode::Leaf {
+                key: &buf[key_start..value_start],
+                value: &buf[value_start..value_start + value_len],
+            }
+        }
+        INNER => {
+            let path_len = read_u32(buf, offset + 1) as usize;
+            let n = u16::from_le_bytes(buf[offset + 5..offset + 7].try_into().unwrap()) as usize;
+            let path_start = offset + 7;
+            let keys_start = path_start + path_len;
+            let children_start = keys_start + n;
+            FlatNode::Inner {
+                path: &buf[path_start..keys_start],
+                keys: &buf[keys_start..children_start],
+                children: &buf[children_start..children_start + 4 * n],
+            }
+        }
+        tag => panic!("unknown flat node tag {} at offset {}", tag, offset),
+    }
+}
+
+/// Iterator over entries of a [`FlatArt`], created by [`FlatArt::iter`],
+/// [`FlatArt::scan_prefix`] and [`FlatArt::range`].
+///
+/// The keys are borrowed from the buffer, or assembled if the buffer is front-coded.
+pub struct FlatIter<'a, V> {
+    buf: &'a [u8],
+    /// Nodes to continue with, with the index of their next child and the length of the path
+    /// leading to them
+    stack: Vec<(usize, usize, usize)>,
+    end: Bound<Vec<u8>>,
+    front_coded: bool,
+    /// The path to the current node of a front-coded buffer
+    key: Vec<u8>,
+    _value: PhantomData<fn() -> V>,
+}
+
+impl<'a, V: DecodeFlat<'a>> Iterator for FlatIter<'a, V> {
+    type Item = (Cow<'a, [u8]>, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let frame = self.stack.last_mut()?;
+            let (offset, depth) = (frame.0, frame.2);
+            let node = read_node(self.buf, offset);
+            match node {
+                FlatNode::Leaf { key, value } => {
+                    self.stack.pop();
+                    let key = if self.front_coded {
+                        self.key.truncate(depth);
+                        self.key.extend_from_slice(key);
+                        Cow::Owned(self.key.clone())
+                    } else {
+                        Cow::Borrowed(key)
+                    };
+                    let before_end = match &self.end {
+                        Bound::Included(end) => *key <= end[..],
+                        Bound::Excluded(end) => *key < end[..],
+                        Bound::Unbounded => true,
+                    };
+                    if !before_end {
+                        self.stack.clear();
+                        return None;
+                    }
+                    return Some((key, V::decode_flat(value)));
+                }
+                FlatNode::Inner { path, keys, .. } => {
+                    let next = frame.1;
+                    if next == keys.len() {
+                        self.stack.pop();
+                        continue;
+                    }
+                    frame.1 += 1;
+                    if self.front_coded {
+                        self.key.truncate(depth);
+                        self.key.extend_from_slice(path);
+                        self.key.push(keys[next]);
+                    }
+                    let child = node.child(next);
+                    self.stack.push((child, 0, depth + path.len() + 1));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use crate::art::LeafKey;
+use crate::art_map::{self, ArtMap, KeyDecoder, KeyEncoder};
+
+/// Fixed-width unsigned integer usable as an `IntArtMap` key.
+///
+/// Keys are stored big-endian, so that the byte order of the tree matches the numeric order.
+pub trait PrimIntKey: Copy {
+    /// Big-endian byte representation of the integer, also stored inline in the leaves
+    type Bytes: LeafKey;
+
+    /// Returns the big-endian bytes of the integer.
+    fn to_key_bytes(self) -> Self::Bytes;
+
+    /// Reads an integer back from its big-endian bytes.
+    fn from_key_bytes(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_prim_int_key {
+    ($($int:ty),*) => {
+        $(
+            impl PrimIntKey for $int {
+                type Bytes = [u8; std::mem::size_of::<$int>()];
+
+                fn to_key_bytes(self) -> Self::Bytes {
+                    self.to_be_bytes()
+                }
+
+                fn from_key_bytes(bytes: &[u8]) -> Self {
+                    let mut key_bytes = [0; std::mem::size_of::<$int>()];
+                    key_bytes.copy_from_slice(bytes);
+                    <$int>::from_be_bytes(key_bytes)
+                }
+            }
+        )*
+    };
+}
+
+impl_prim_int_key!(u8, u16, u32, u64, u128);
+
+/// Key encoder storing `PrimIntKey` integers big-endian
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BigEndian;
+
+impl<K: PrimIntKey> KeyEncoder<K> for BigEndian {
+    type Encoded = K::Bytes;
+
+    fn encode(key: &K) -> Self::Encoded {
+        key.to_key_bytes()
+    }
+}
+
+impl<K: PrimIntKey> KeyDecoder<K> for BigEndian {
+    fn decode(bytes: &[u8]) -> K {
+        K::from_key_bytes(bytes)
+    }
+}
+
+/// Map indexed by fixed-width integer keys using an Adaptive Radix Tree
+///
+/// The leaves store the keys inline, as in an [`ArtTreeFixed`](crate::art::ArtTreeFixed).
+pub type IntArtMap<K, V> = ArtMap<K, V, BigEndian, <K as PrimIntKey>::Bytes>;
+
+pub type Entry<'a, K, V> = art_map::Entry<'a, K, V, BigEndian, <K as PrimIntKey>::Bytes>;
+pub type OccupiedEntry<'a, K, V> =
+    art_map::OccupiedEntry<'a, K, V, BigEndian, <K as PrimIntKey>::Bytes>;
+pub type VacantEntry<'a, K, V> =
+    art_map::VacantEntry<'a, K, V, BigEndian, <K as PrimIntKey>::Bytes>;
+pub type Iter<'a, K, V> = art_map::Iter<'a, K, V, BigEndian, <K as PrimIntKey>::Bytes>;
+pub type IterMut<'a, K, V> = art_map::IterMut<'a, K, V, BigEndian, <K as PrimIntKey>::Bytes>;
+pub type IntoIter<K, V> = art_map::IntoIter<K, V, BigEndian, <K as PrimIntKey>::Bytes>;
+pub type Range<'a, K, V> = art_map::Range<'a, K, V, BigEndian, <K as PrimIntKey>::Bytes>;
+pub type Keys<'a, K, V> = art_map::Keys<'a, K, V, BigEndian, <K as PrimIntKey>::Bytes>;
+pub type Values<'a, K, V> = art_map::Values<'a, K, V, BigEndian, <K as PrimIntKey>::Bytes>;
+
+impl<K: PrimIntKey, V> IntArtMap<K, V> {
+    /// Returns an iterator over the elements with keys in `start..end`, e.g. the entries of a
+    /// time window when the keys are timestamps
+    pub fn window(&self, start: K, end: K) -> Range<'_, K, V> {
+        self.range(start..end)
+    }
+
+    /// Removes every element whose key is less than `cutoff` and returns the number of removed
+    /// elements, detaching whole subtrees instead of deleting the keys one by one
+    pub fn purge_older_than(&mut self, cutoff: K) -> usize {
+        self.remove_below(&cutoff)
+    }
+}
+
+/// Map indexed by u16-keys using an Adaptive Radix Tree
+pub type U16ArtMap<V> = IntArtMap<u16, V>;
+
+/// Map indexed by u32-keys using an Adaptive Radix Tree
+pub type U32ArtMap<V> = IntArtMap<u32, V>;
+
+/// Map indexed by u128-keys using an Adaptive Radix Tree
+pub type U128ArtMap<V> = IntArtMap<u128, V>;
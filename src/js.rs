@@ -0,0 +1,110 @@
+//! JavaScript bindings built with `wasm-bindgen`, e.g. by `wasm-pack build -- --features js`.
+//!
+//! The bindings are a thin layer over [`ArtTree`] for browser applications such as
+//! autocompletion or routing tables: keys and values are `Uint8Array`s, which JavaScript code
+//! fills with `TextEncoder` or its own serialization.
+
+use js_sys::{Array, Uint8Array};
+use wasm_bindgen::prelude::*;
+
+use crate::art::{self, ArtTree};
+
+/// The key as it was set and the value
+type Entry = (Box<[u8]>, Box<[u8]>);
+
+/// Map from `Uint8Array` keys to `Uint8Array` values, ordered by key
+///
+/// Keys are made prefix-free like those of a
+/// [`StringArtMap`](crate::string_art_map::StringArtMap), so any key may be a prefix of another
+/// one. Values are copied in and out of the WebAssembly memory.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct JsArtMap {
+    tree: ArtTree<Entry>,
+}
+
+#[wasm_bindgen]
+impl JsArtMap {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of entries in the map
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns true if the map contains a value for the key
+    pub fn has(&self, key: &[u8]) -> bool {
+        self.tree.contains_key(&encode(key))
+    }
+
+    /// Returns a copy of the value stored at the key, `undefined` if there is none
+    pub fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.tree.get(&encode(key)).map(|(_, value)| value.to_vec())
+    }
+
+    /// Stores a copy of the value at the key, returning true if it replaced another value
+    pub fn set(&mut self, key: &[u8], value: &[u8]) -> bool {
+        self.tree
+            .insert(&encode(key), (key.into(), value.into()))
+            .is_some()
+    }
+
+    /// Deletes the value stored at the key, returning true if there was one
+    pub fn delete(&mut self, key: &[u8]) -> bool {
+        self.tree.delete(&encode(key)).is_some()
+    }
+
+    /// Deletes all entries
+    pub fn clear(&mut self) {
+        self.tree = ArtTree::new();
+    }
+
+    /// Returns the `[key, value]` pairs of the keys starting with `prefix` in ascending key
+    /// order, at most `limit` of them if it is given.
+    #[wasm_bindgen(js_name = prefixScan)]
+    pub fn prefix_scan(&self, prefix: &[u8], limit: Option<u32>) -> Array {
+        self.scan(prefix, limit)
+            .map(|(key, value)| {
+                let pair = Array::new();
+                pair.push(&Uint8Array::from(key).into());
+                pair.push(&Uint8Array::from(value).into());
+                JsValue::from(pair)
+            })
+            .collect()
+    }
+
+    /// Returns the keys starting with `prefix` in ascending order, at most `limit` of them if it
+    /// is given.
+    #[wasm_bindgen(js_name = prefixKeys)]
+    pub fn prefix_keys(&self, prefix: &[u8], limit: Option<u32>) -> Array {
+        self.scan(prefix, limit)
+            .map(|(key, _)| JsValue::from(Uint8Array::from(key)))
+            .collect()
+    }
+}
+
+impl JsArtMap {
+    fn scan<'a>(
+        &'a self,
+        prefix: &[u8],
+        limit: Option<u32>,
+    ) -> impl Iterator<Item = (&'a [u8], &'a [u8])> + 'a {
+        let mut encoded = encode(prefix);
+        // Without the terminator the encoded prefix is a prefix of the encoded keys
+        encoded.truncate(encoded.len() - 2);
+        self.tree
+            .scan_prefix(&encoded)
+            .map(|(_, (key, value))| (&key[..], &value[..]))
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
+    }
+}
+
+fn encode(key: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    art::encode_prefix_free(key, &mut encoded);
+    encoded
+}
@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::marker::PhantomData;
+
+use crate::art::{self, ArtTree};
+use crate::int_art_map::PrimIntKey;
+
+/// Counts the k-mers of DNA sequences, packed 2 bits per base into integers of type `K`
+///
+/// Bases are encoded as `A = 0`, `C = 1`, `G = 2`, `T = 3` with the first base in the most
+/// significant position, so the numeric order of packed k-mers is their lexicographic order.
+/// Each k-mer is stored under its `ceil(k / 4)` low-order big-endian bytes.
+#[derive(Clone, Debug)]
+pub struct KmerCounter<K = u64> {
+    tree: ArtTree<u64>,
+    k: usize,
+    _key: PhantomData<K>,
+}
+
+impl<K> KmerCounter<K>
+where
+    K: PrimIntKey + Into<u128> + TryFrom<u128>,
+{
+    /// Creates an empty counter of k-mers of length `k`.
+    ///
+    /// Panics if `k` is zero or the k-mers do not fit into `K`.
+    pub fn new(k: usize) -> Self {
+        assert!(
+            k > 0 && 2 * k <= 8 * std::mem::size_of::<K>(),
+            "k = {} does not fit the key type",
+            k
+        );
+        Self {
+            tree: ArtTree::new(),
+            k,
+            _key: PhantomData,
+        }
+    }
+
+    /// Returns the length of the counted k-mers
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Returns the number of distinct k-mers counted
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns true if no k-mer was counted
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    fn key_width(&self) -> usize {
+        self.k.div_ceil(4)
+    }
+
+    fn to_key(&self, kmer: K) -> [u8; 16] {
+        kmer.into().to_be_bytes()
+    }
+
+    fn from_key(key: &[u8]) -> K {
+        let mut bytes = [0; 16];
+        bytes[16 - key.len()..].copy_from_slice(key);
+        match K::try_from(u128::from_be_bytes(bytes)) {
+            Ok(kmer) => kmer,
+            Err(_) => unreachable!("stored k-mers fit the key type"),
+        }
+    }
+
+    /// Packs a sequence of exactly `k` bases, or returns `None` if it has another length or
+    /// contains anything but `ACGT` (in either case).
+    pub fn encode(&self, bases: &[u8]) -> Option<K> {
+        if bases.len() != self.k {
+            return None;
+        }
+        let mut packed = 0u128;
+        for &base in bases {
+            packed = packed << 2 | u128::from(base_code(base)?);
+        }
+        K::try_from(packed).ok()
+    }
+
+    /// Unpacks a k-mer into its bases.
+    pub fn decode(&self, kmer: K) -> Vec<u8> {
+        let packed: u128 = kmer.into();
+        (0..self.k)
+            .rev()
+            .map(|i| b"ACGT"[(packed >> (2 * i)) as usize & 3])
+            .collect()
+    }
+
+    /// Adds one occurrence of the packed k-mer and returns its new count.
+    pub fn increment(&mut self, kmer: K) -> u64 {
+        let key = self.to_key(kmer);
+        self.tree.increment(&key[16 - self.key_width()..], 1)
+    }
+
+    /// Counts every k-mer of the sequence, skipping the windows that contain bases other than
+    /// `ACGT`.
+    pub fn add_sequence(&mut self, sequence: &[u8]) {
+        let mask = if self.k == 64 {
+            u128::MAX
+        } else {
+            (1u128 << (2 * self.k)) - 1
+        };
+        let mut packed = 0u128;
+        let mut valid = 0;
+        for &base in sequence {
+            match base_code(base) {
+                Some(code) => {
+                    packed = (packed << 2 | u128::from(code)) & mask;
+                    valid += 1;
+                }
+                None => valid = 0,
+            }
+            if valid >= self.k {
+                let key = packed.to_be_bytes();
+                self.tree.increment(&key[16 - self.key_width()..], 1);
+            }
+        }
+    }
+
+    /// Returns the number of occurrences of the packed k-mer
+    pub fn count(&self, kmer: K) -> u64 {
+        let key = self.to_key(kmer);
+        self.tree
+            .get(&key[16 - self.key_width()..])
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Returns an iterator over the k-mers and their counts in ascending k-mer order
+    pub fn iter(&self) -> Iter<'_, K> {
+        Iter {
+            inner: self.tree.entries(),
+            _key: PhantomData,
+        }
+    }
+
+    /// Returns the k-mer spectrum: for every occurrence count, the number of distinct k-mers
+    /// seen that many times.
+    pub fn histogram(&self) -> BTreeMap<u64, usize> {
+        let mut histogram = BTreeMap::new();
+        for (_, &count) in self.tree.entries() {
+            *histogram.entry(count).or_insert(0) += 1;
+        }
+        histogram
+    }
+}
+
+fn base_code(base: u8) -> Option<u8> {
+    match base {
+        b'A' | b'a' => Some(0),
+        b'C' | b'c' => Some(1),
+        b'G' | b'g' => Some(2),
+        b'T' | b't' => Some(3),
+        _ => None,
+    }
+}
+
+/// An iterator over the k-mers of a `KmerCounter` and their counts.
+///
+/// Created by [`KmerCounter::iter`].
+pub struct Iter<'a, K> {
+    inner: art::Iter<'a, u64>,
+    _key: PhantomData<K>,
+}
+
+impl<'a, K> Iterator for Iter<'a, K>
+where
+    K: PrimIntKey + Into<u128> + TryFrom<u128>,
+{
+    type Item = (K, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(key, &count)| (KmerCounter::<K>::from_key(key), count))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K> ExactSizeIterator for Iter<'a, K> where K: PrimIntKey + Into<u128> + TryFrom<u128> {}
@@ -0,0 +1,352 @@
+//! An index whose subtrees live in external storage and are loaded on demand.
+//!
+//! A [`LazyArt`] keeps only a directory in memory: an `ArtTree` mapping the key prefix of every
+//! subtree to an opaque handle. The subtrees themselves are [flat buffers](crate::flat_art),
+//! which a user-provided [`SubtreeStore`] loads by their handle, e.g. from a memory-mapped file
+//! or an object store. Loaded subtrees are queried in place and kept in a cache bounded in
+//! bytes, so that lookups in the hot set of subtrees do not go to the store.
+//!
+//! [`LazyArtBuilder`] splits entries pushed in ascending key order into subtrees by key prefix,
+//! holding only the subtree being written in memory.
+
+use std::cmp::min;
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::hash::Hash;
+use std::mem;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::art::{ArtBuilder, ArtTree};
+#[cfg(feature = "lz4")]
+use crate::flat_art::decompress;
+use crate::flat_art::{DecodeFlat, EncodeFlat, FlatArt, FlatArtBuilder, FlatError, FlatOptions};
+
+/// External storage holding the subtrees of a [`LazyArt`].
+pub trait SubtreeStore {
+    /// Opaque reference to a stored subtree, e.g. a file offset or an object name
+    type Handle: Clone + Eq + Hash;
+    type Error;
+
+    /// Returns the buffer that was stored for `handle`.
+    fn load(&self, handle: &Self::Handle) -> Result<Vec<u8>, Self::Error>;
+}
+
+/// A subtree that could not be loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadError<E> {
+    /// The store failed to load the subtree
+    Store(E),
+    /// The loaded buffer is not a flat tree
+    Flat(FlatError),
+}
+
+impl<E: fmt::Display> fmt::Display for LoadError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Store(err) => write!(f, "failed to load a subtree: {}", err),
+            LoadError::Flat(err) => write!(f, "failed to open a subtree: {}", err),
+        }
+    }
+}
+
+impl<E: error::Error + 'static> error::Error for LoadError<E> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            LoadError::Store(err) => Some(err),
+            LoadError::Flat(err) => Some(err),
+        }
+    }
+}
+
+/// Counters of the subtree cache of a [`LazyArt`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups served by a cached subtree
+    pub hits: u64,
+    /// Lookups that loaded their subtree from the store
+    pub misses: u64,
+    /// Subtrees dropped from the cache to make room for others
+    pub evictions: u64,
+    /// Number of subtrees in the cache
+    pub subtrees: usize,
+    /// Total size of the subtrees in the cache
+    pub bytes: usize,
+}
+
+/// A loaded subtree, shared with the cache of the [`LazyArt`] it was loaded by.
+#[derive(Clone)]
+pub struct Subtree {
+    buf: Arc<[u8]>,
+}
+
+impl Subtree {
+    /// Returns a view of the subtree, whose values can borrow from it
+    pub fn flat<'a, V: DecodeFlat<'a>>(&'a self) -> FlatArt<'a, V> {
+        FlatArt::new(&self.buf).expect("the subtree was checked when it was loaded")
+    }
+
+    /// Returns the size of the subtree buffer
+    pub fn byte_len(&self) -> usize {
+        self.buf.len()
+    }
+}
+
+impl fmt::Debug for Subtree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subtree")
+            .field("bytes", &self.buf.len())
+            .finish()
+    }
+}
+
+/// A read-only index of flat subtrees loaded from a [`SubtreeStore`] on demand.
+///
+/// The directory maps key prefixes, none of which may be a prefix of another one, to the
+/// handles of the subtrees holding the keys that start with them. The subtrees hold the whole
+/// keys. Lookups take `&self`, the cache is shared behind a lock that is not held while a
+/// subtree is loaded.
+pub struct LazyArt<S: SubtreeStore> {
+    directory: ArtTree<S::Handle>,
+    store: S,
+    cache: Mutex<Cache<S::Handle>>,
+}
+
+/// Loaded subtrees with the tick of their last use, evicted least recently used first
+struct Cache<H> {
+    subtrees: HashMap<H, (Subtree, u64)>,
+    clock: u64,
+    capacity: usize,
+    stats: CacheStats,
+}
+
+impl<H: Clone + Eq + Hash> Cache<H> {
+    fn get(&mut self, handle: &H) -> Option<Subtree> {
+        self.clock += 1;
+        let (subtree, used) = self.subtrees.get_mut(handle)?;
+        *used = self.clock;
+        self.stats.hits += 1;
+        Some(subtree.clone())
+    }
+
+    /// Caches a loaded subtree, unless it is larger than the whole cache. Returns the cached
+    /// subtree, which is another copy if the subtree was loaded concurrently.
+    fn insert(&mut self, handle: &H, subtree: Subtree) -> Subtree {
+        if let Some((cached, _)) = self.subtrees.get(handle) {
+            return cached.clone();
+        }
+        let len = subtree.byte_len();
+        if len > self.capacity {
+            return subtree;
+        }
+        while self.stats.bytes + len > self.capacity {
+            let oldest = self
+                .subtrees
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(handle, _)| handle.clone())
+                .unwrap();
+            let (evicted, _) = self.subtrees.remove(&oldest).unwrap();
+            self.stats.bytes -= evicted.byte_len();
+            self.stats.subtrees -= 1;
+            self.stats.evictions += 1;
+        }
+        self.stats.bytes += len;
+        self.stats.subtrees += 1;
+        self.subtrees
+            .insert(handle.clone(), (subtree.clone(), self.clock));
+        subtree
+    }
+}
+
+impl<S: SubtreeStore> LazyArt<S> {
+    /// Creates an index over the subtrees of `directory`, caching up to `cache_bytes` bytes of
+    /// loaded subtrees.
+    pub fn new(directory: ArtTree<S::Handle>, store: S, cache_bytes: usize) -> Self {
+        Self {
+            directory,
+            store,
+            cache: Mutex::new(Cache {
+                subtrees: HashMap::new(),
+                clock: 0,
+                capacity: cache_bytes,
+                stats: CacheStats::default(),
+            }),
+        }
+    }
+
+    /// Returns the directory mapping key prefixes to subtree handles
+    pub fn directory(&self) -> &ArtTree<S::Handle> {
+        &self.directory
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Returns the subtree that holds `key` if it is in the index, loading it if it is not
+    /// cached. Returns `None` if no prefix of the directory covers `key`.
+    pub fn subtree(&self, key: &[u8]) -> Result<Option<Subtree>, LoadError<S::Error>> {
+        // The prefixes are prefix-free, so one that `key` starts with is the greatest one not
+        // greater than `key`
+        let handle = match self.directory.floor(key) {
+            Some((prefix, handle)) if key.starts_with(prefix) => handle,
+            _ => return Ok(None),
+        };
+        {
+            let mut cache = self.lock_cache();
+            if let Some(subtree) = cache.get(handle) {
+                return Ok(Some(subtree));
+            }
+            cache.stats.misses += 1;
+        }
+
+        let buf = self.store.load(handle).map_err(LoadError::Store)?;
+        #[cfg(feature = "lz4")]
+        let buf = match FlatArt::<()>::new(&buf) {
+            Err(FlatError::Compressed) => decompress(&buf).map_err(LoadError::Flat)?,
+            _ => buf,
+        };
+        FlatArt::<()>::new(&buf).map_err(LoadError::Flat)?;
+        let subtree = Subtree { buf: buf.into() };
+        Ok(Some(self.lock_cache().insert(handle, subtree)))
+    }
+
+    /// Returns the value of `key`, loading its subtree if it is not cached. Values borrowing
+    /// from the subtree are read through [`LazyArt::subtree`] instead.
+    pub fn get<V>(&self, key: &[u8]) -> Result<Option<V>, LoadError<S::Error>>
+    where
+        V: for<'a> DecodeFlat<'a>,
+    {
+        Ok(self
+            .subtree(key)?
+            .and_then(|subtree| subtree.flat::<V>().get(key)))
+    }
+
+    pub fn contains_key(&self, key: &[u8]) -> Result<bool, LoadError<S::Error>> {
+        Ok(self
+            .subtree(key)?
+            .is_some_and(|subtree| subtree.flat::<()>().contains_key(key)))
+    }
+
+    pub fn cache_stats(&self) -> CacheStats {
+        self.lock_cache().stats
+    }
+
+    /// Drops all cached subtrees, the counters are kept
+    pub fn clear_cache(&self) {
+        let mut cache = self.lock_cache();
+        cache.subtrees.clear();
+        cache.stats.subtrees = 0;
+        cache.stats.bytes = 0;
+    }
+
+    fn lock_cache(&self) -> MutexGuard<'_, Cache<S::Handle>> {
+        // The cache is consistent between statements, so a panic elsewhere does not poison it
+        self.cache.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl<S: SubtreeStore> fmt::Debug for LazyArt<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazyArt")
+            .field("subtrees", &self.directory.len())
+            .field("cache", &self.cache_stats())
+            .finish()
+    }
+}
+
+/// Splits entries pushed in ascending key order into flat subtrees by the first `prefix_len`
+/// bytes of their keys (a shorter key is a prefix of its own), and builds the directory of a
+/// [`LazyArt`] over them.
+///
+/// Every finished subtree is passed to the `write` function with its prefix, which stores it
+/// and returns its handle. Only the subtree being written is held in memory.
+pub struct LazyArtBuilder<H, F> {
+    prefix_len: usize,
+    options: FlatOptions,
+    prefix: Vec<u8>,
+    subtree: FlatArtBuilder,
+    directory: ArtBuilder<H>,
+    write: F,
+}
+
+impl<H, E, F> LazyArtBuilder<H, F>
+where
+    F: FnMut(&[u8], Vec<u8>) -> Result<H, E>,
+{
+    pub fn new(prefix_len: usize, write: F) -> Self {
+        Self::with_options(prefix_len, FlatOptions::default(), write)
+    }
+
+    /// Creates a builder writing the subtrees as configured by `options`
+    pub fn with_options(prefix_len: usize, options: FlatOptions, write: F) -> Self {
+        Self {
+            prefix_len,
+            options,
+            prefix: Vec::new(),
+            subtree: FlatArtBuilder::with_options(options),
+            directory: ArtBuilder::new(),
+            write,
+        }
+    }
+
+    /// Adds an entry, first writing the current subtree if the key starts another one.
+    ///
+    /// # Panics
+    ///
+    /// If `key` is not greater than the key pushed before it, or the key before it is a prefix
+    /// of it.
+    pub fn push<V: EncodeFlat + ?Sized>(&mut self, key: &[u8], value: &V) -> Result<(), E> {
+        let prefix = &key[..min(key.len(), self.prefix_len)];
+        if !self.subtree.is_empty() && prefix != &self.prefix[..] {
+            assert!(
+                self.prefix[..] < *prefix,
+                "keys have to be pushed in ascending order"
+            );
+            assert!(
+                !prefix.starts_with(&self.prefix),
+                "a key is a prefix of another key"
+            );
+            self.write_subtree()?;
+        }
+        if self.subtree.is_empty() {
+            self.prefix.clear();
+            self.prefix.extend_from_slice(prefix);
+        }
+        self.subtree.push(key, value);
+        Ok(())
+    }
+
+    /// Returns the number of subtrees written so far
+    pub fn subtrees(&self) -> usize {
+        self.directory.len()
+    }
+
+    /// Writes the last subtree and returns the directory for [`LazyArt::new`].
+    pub fn finish(mut self) -> Result<ArtTree<H>, E> {
+        if !self.subtree.is_empty() {
+            self.write_subtree()?;
+        }
+        Ok(self.directory.finish())
+    }
+
+    fn write_subtree(&mut self) -> Result<(), E> {
+        let subtree = mem::replace(
+            &mut self.subtree,
+            FlatArtBuilder::with_options(self.options),
+        );
+        let handle = (self.write)(&self.prefix, subtree.finish())?;
+        self.directory.push(&self.prefix, handle);
+        Ok(())
+    }
+}
+
+impl<H, F> fmt::Debug for LazyArtBuilder<H, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazyArtBuilder")
+            .field("prefix_len", &self.prefix_len)
+            .field("subtrees", &self.directory.len())
+            .finish()
+    }
+}
@@ -1,2 +1,32 @@
 pub mod art;
+pub mod art_bi_map;
+pub mod art_bit_set;
+pub mod art_index;
+pub mod art_interval_map;
+pub mod art_map;
+pub mod art_multi_map;
+pub mod bounded_art_tree;
+pub mod compressed_art_tree;
+pub mod dense_art_map;
+#[cfg(feature = "encryption")]
+pub mod encryption;
+pub mod flat_art;
+pub mod int_art_map;
+#[cfg(feature = "js")]
+pub mod js;
+pub mod kmer_counter;
+pub mod lazy_art;
+pub mod lww_art_map;
+pub mod normalized_art_tree;
+pub mod scheduler;
+pub mod shared_art_map;
+pub mod snapshot;
+pub mod string_art_map;
+pub mod succinct_art;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+pub mod tombstone_art_tree;
 pub mod u64_art_map;
+pub mod versioned_art_tree;
+
+mod simd;
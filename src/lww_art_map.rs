@@ -0,0 +1,126 @@
+use crate::art::ArtTree;
+
+/// Tag of a write to an [`LwwArtMap`], ordered by timestamp and then by node id
+///
+/// The timestamp is usually a hybrid logical clock. Every write of a node has to get a new tag,
+/// so that the node id makes the tags of concurrent writes of different nodes distinct.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LwwTag {
+    pub timestamp: u64,
+    pub node: u64,
+}
+
+impl LwwTag {
+    pub fn new(timestamp: u64, node: u64) -> Self {
+        Self { timestamp, node }
+    }
+}
+
+/// Last-writer-wins register map indexed by byte keys using an Adaptive Radix Tree
+///
+/// Every key holds the value or the deletion with the greatest [`LwwTag`] written to it, by
+/// this replica or a remote one. Writes are applied in any order and any number of times with
+/// the same result, so replicas that exchanged all their writes, or merged their full states
+/// with [`merge`](Self::merge), hold the same entries. Deletions are kept as tombstones to win
+/// against older writes arriving later.
+#[derive(Clone, Debug)]
+pub struct LwwArtMap<V> {
+    /// `None` marks a deletion
+    tree: ArtTree<(LwwTag, Option<V>)>,
+    live: usize,
+}
+
+impl<V> LwwArtMap<V> {
+    pub fn new() -> Self {
+        Self {
+            tree: ArtTree::new(),
+            live: 0,
+        }
+    }
+
+    /// Returns the number of keys holding a value
+    pub fn len(&self) -> usize {
+        self.live
+    }
+
+    /// Returns true if no key holds a value, there may be deletions
+    pub fn is_empty(&self) -> bool {
+        self.live == 0
+    }
+
+    /// Writes the value at the key if `tag` is greater than the tag of the key, and returns
+    /// whether it was written.
+    pub fn insert(&mut self, key: &[u8], value: V, tag: LwwTag) -> bool {
+        self.merge_remote(key, Some(value), tag)
+    }
+
+    /// Deletes the value of the key if `tag` is greater than the tag of the key, and returns
+    /// whether the deletion was written. Keys that were never written get a tombstone too.
+    pub fn delete(&mut self, key: &[u8], tag: LwwTag) -> bool {
+        self.merge_remote(key, None, tag)
+    }
+
+    /// Applies a write received from another replica, `None` being a deletion, if `tag` is
+    /// greater than the tag of the key. Returns whether the write was applied.
+    pub fn merge_remote(&mut self, key: &[u8], value: Option<V>, tag: LwwTag) -> bool {
+        let live = value.is_some() as usize;
+        match self.tree.get_mut(key) {
+            Some(current) if current.0 >= tag => false,
+            Some(current) => {
+                self.live = self.live + live - current.1.is_some() as usize;
+                *current = (tag, value);
+                true
+            }
+            None => {
+                self.live += live;
+                self.tree.insert(key, (tag, value));
+                true
+            }
+        }
+    }
+
+    /// Merges the full state of another replica into this one. Merging is commutative,
+    /// associative and idempotent.
+    pub fn merge(&mut self, other: &Self)
+    where
+        V: Clone,
+    {
+        for (key, (tag, value)) in other.tree.entries() {
+            self.merge_remote(key, value.clone(), *tag);
+        }
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&V> {
+        self.tree.get(key).and_then(|(_, value)| value.as_ref())
+    }
+
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns the tag of the latest write of the key, a value or a deletion
+    pub fn tag(&self, key: &[u8]) -> Option<LwwTag> {
+        self.tree.get(key).map(|(tag, _)| *tag)
+    }
+
+    /// Returns an iterator over the keys holding values in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &V)> + '_ {
+        self.tree
+            .entries()
+            .filter_map(|(key, (_, value))| value.as_ref().map(|value| (key, value)))
+    }
+
+    /// Returns an iterator over the latest writes of all keys in ascending key order, with
+    /// `None` for deletions, e.g. to send the state to another replica
+    pub fn entries(&self) -> impl Iterator<Item = (&[u8], Option<&V>, LwwTag)> + '_ {
+        self.tree
+            .entries()
+            .map(|(key, (tag, value))| (key, value.as_ref(), *tag))
+    }
+}
+
+impl<V> Default for LwwArtMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,142 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+use std::sync::Arc;
+
+use crate::art::{ArtTree, Iter, Prefix, Range};
+
+/// Function normalizing a key, see [`NormalizedArtTree::new`]
+pub type NormalizeFn = dyn for<'a> Fn(&'a [u8]) -> Cow<'a, [u8]> + Send + Sync;
+
+/// Map indexed by byte keys using an Adaptive Radix Tree, normalizing every key it is given
+///
+/// The normalization function is set once when the map is created and applied to the keys of
+/// inserts, lookups and deletes, and to prefixes and range bounds, so that keys differing only
+/// in what the function removes refer to the same entry. The tree stores the normalized keys,
+/// which is what iteration yields.
+///
+/// Prefixes are normalized like whole keys. That suits functions working on every byte on its
+/// own, such as lowercasing or stripping separators, while trimming a prefix can widen a scan.
+#[derive(Clone)]
+pub struct NormalizedArtTree<V> {
+    tree: ArtTree<V>,
+    normalize: Arc<NormalizeFn>,
+}
+
+impl<V> NormalizedArtTree<V> {
+    /// Creates an empty map normalizing keys with `normalize`, which returns a borrowed key if
+    /// it is already normal.
+    pub fn new<F>(normalize: F) -> Self
+    where
+        F: for<'a> Fn(&'a [u8]) -> Cow<'a, [u8]> + Send + Sync + 'static,
+    {
+        Self {
+            tree: ArtTree::new(),
+            normalize: Arc::new(normalize),
+        }
+    }
+
+    /// Creates an empty map in which keys differing only in ASCII case refer to the same entry
+    pub fn ascii_case_insensitive() -> Self {
+        Self::new(|key| {
+            if key.iter().any(u8::is_ascii_uppercase) {
+                Cow::Owned(key.to_ascii_lowercase())
+            } else {
+                Cow::Borrowed(key)
+            }
+        })
+    }
+
+    /// Returns the key as the map stores it
+    pub fn normalize<'a>(&self, key: &'a [u8]) -> Cow<'a, [u8]> {
+        (self.normalize)(key)
+    }
+
+    /// Returns the tree holding the normalized keys
+    pub fn tree(&self) -> &ArtTree<V> {
+        &self.tree
+    }
+
+    pub fn into_inner(self) -> ArtTree<V> {
+        self.tree
+    }
+
+    /// Returns the number of elements in the map
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns true if the map contains no elements
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Returns true if the map contains a value for the normalized key
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.tree.contains_key(&self.normalize(key))
+    }
+
+    /// Returns a reference to the value stored at the normalized key
+    pub fn get(&self, key: &[u8]) -> Option<&V> {
+        self.tree.get(&self.normalize(key))
+    }
+
+    /// Returns a mutable reference to the value stored at the normalized key
+    pub fn get_mut(&mut self, key: &[u8]) -> Option<&mut V> {
+        let key = (self.normalize)(key);
+        self.tree.get_mut(&key)
+    }
+
+    /// Inserts the given value at the normalized key and returns the previous value
+    pub fn insert(&mut self, key: &[u8], value: V) -> Option<V> {
+        let key = (self.normalize)(key);
+        self.tree.insert(&key, value)
+    }
+
+    /// Deletes and returns the value stored at the normalized key
+    pub fn delete(&mut self, key: &[u8]) -> Option<V> {
+        let key = (self.normalize)(key);
+        self.tree.delete(&key)
+    }
+
+    /// Returns an iterator over the normalized keys and their values in ascending key order
+    pub fn iter(&self) -> Iter<'_, V> {
+        self.tree.entries()
+    }
+
+    /// Returns an iterator over the entries whose keys start with the normalized prefix
+    pub fn scan_prefix(&self, prefix: &[u8]) -> Prefix<'_, V> {
+        self.tree.scan_prefix(&self.normalize(prefix))
+    }
+
+    /// Returns an iterator over the entries whose keys fall between the normalized bounds of the
+    /// range, in ascending key order.
+    pub fn range<'r, R>(&self, range: R) -> Range<'_, V>
+    where
+        R: RangeBounds<&'r [u8]>,
+    {
+        let normalize = |bound: Bound<&&'r [u8]>| match bound {
+            Bound::Included(key) => Bound::Included(self.normalize(key)),
+            Bound::Excluded(key) => Bound::Excluded(self.normalize(key)),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let (start, end) = (normalize(range.start_bound()), normalize(range.end_bound()));
+        self.tree.range((as_slice(&start), as_slice(&end)))
+    }
+}
+
+fn as_slice<'a>(bound: &'a Bound<Cow<'_, [u8]>>) -> Bound<&'a [u8]> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key),
+        Bound::Excluded(key) => Bound::Excluded(key),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+impl<V: fmt::Debug> fmt::Debug for NormalizedArtTree<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NormalizedArtTree")
+            .field("tree", &self.tree)
+            .finish()
+    }
+}
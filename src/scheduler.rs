@@ -0,0 +1,153 @@
+use std::fmt;
+
+use crate::int_art_map::{self, U128ArtMap};
+
+/// Timer queue keyed by u64 deadlines using an Adaptive Radix Tree
+///
+/// Every scheduled value gets a [`TimerHandle`], through which it is cancelled or moved to
+/// another deadline. Values due at the same deadline expire in the order they were scheduled.
+/// [`expire_until`](Self::expire_until) removes all due values at once, detaching whole
+/// subtrees of them like [`ArtTree::drain_range`](crate::art::ArtTree::drain_range).
+#[derive(Clone)]
+pub struct Scheduler<V> {
+    /// Deadline in the high and sequence number in the low 64 bits, to the value
+    timers: U128ArtMap<V>,
+    next_seq: u64,
+}
+
+/// Identifies a value scheduled in a [`Scheduler`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimerHandle(u128);
+
+impl TimerHandle {
+    fn new(deadline: u64, seq: u64) -> Self {
+        TimerHandle((deadline as u128) << 64 | seq as u128)
+    }
+
+    /// Returns the deadline the value is scheduled at
+    pub fn deadline(&self) -> u64 {
+        (self.0 >> 64) as u64
+    }
+}
+
+impl<V> Scheduler<V> {
+    pub fn new() -> Self {
+        Self {
+            timers: U128ArtMap::new(),
+            next_seq: 0,
+        }
+    }
+
+    /// Returns the number of scheduled values
+    pub fn len(&self) -> usize {
+        self.timers.len()
+    }
+
+    /// Returns true if no value is scheduled
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+
+    /// Schedules the value at the deadline `at` and returns its handle
+    pub fn schedule(&mut self, at: u64, value: V) -> TimerHandle {
+        let handle = self.next_handle(at);
+        self.timers.insert(handle.0, value);
+        handle
+    }
+
+    /// Removes the scheduled value and returns it, or `None` if it expired or was cancelled
+    /// already.
+    pub fn cancel(&mut self, handle: TimerHandle) -> Option<V> {
+        self.timers.delete(handle.0)
+    }
+
+    /// Moves the scheduled value to the deadline `at` and returns its new handle, or `None` if
+    /// it expired or was cancelled already. The value is not moved in memory, and expires after
+    /// the values scheduled at the same deadline before.
+    pub fn reschedule(&mut self, handle: TimerHandle, at: u64) -> Option<TimerHandle> {
+        if !self.timers.contains_key(&handle.0) {
+            return None;
+        }
+        let new = self.next_handle(at);
+        self.timers.rekey(&handle.0, &new.0);
+        Some(new)
+    }
+
+    pub fn get(&self, handle: TimerHandle) -> Option<&V> {
+        self.timers.get(&handle.0)
+    }
+
+    pub fn get_mut(&mut self, handle: TimerHandle) -> Option<&mut V> {
+        self.timers.get_mut(&handle.0)
+    }
+
+    /// Returns the handle and the value that expire next
+    pub fn peek(&self) -> Option<(TimerHandle, &V)> {
+        self.timers
+            .peek_first()
+            .map(|(key, value)| (TimerHandle(key), value))
+    }
+
+    /// Returns the earliest deadline of the scheduled values
+    pub fn next_deadline(&self) -> Option<u64> {
+        self.peek().map(|(handle, _)| handle.deadline())
+    }
+
+    /// Removes the values whose deadline is at or before `now` and returns them in the order
+    /// they expire.
+    ///
+    /// The values are removed when `expire_until` is called, not as the iterator is consumed.
+    /// Subtrees of due values are detached as a whole, only the path towards `now` is
+    /// descended.
+    pub fn expire_until(&mut self, now: u64) -> Expired<V> {
+        let last = TimerHandle::new(now, u64::MAX);
+        Expired {
+            inner: self.timers.drain_range(..=last.0),
+        }
+    }
+
+    fn next_handle(&mut self, at: u64) -> TimerHandle {
+        let handle = TimerHandle::new(at, self.next_seq);
+        self.next_seq += 1;
+        handle
+    }
+}
+
+impl<V> Default for Scheduler<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: fmt::Debug> fmt::Debug for Scheduler<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.timers
+                    .iter()
+                    .map(|(key, value)| (TimerHandle(key), value)),
+            )
+            .finish()
+    }
+}
+
+/// Iterator over the values removed by [`Scheduler::expire_until`], with their handles
+pub struct Expired<V> {
+    inner: int_art_map::IntoIter<u128, V>,
+}
+
+impl<V> Iterator for Expired<V> {
+    type Item = (TimerHandle, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(key, value)| (TimerHandle(key), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<V> ExactSizeIterator for Expired<V> {}
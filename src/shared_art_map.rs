@@ -0,0 +1,209 @@
+use std::fmt;
+use std::ops::RangeBounds;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::art::ArtTree;
+
+/// An [`ArtTree`] shared between threads behind a read-write lock
+///
+/// Clones share the same tree. Lookups take the read lock and writes the write lock, each for
+/// the duration of the call only: values are cloned out or passed to a closure, and iteration
+/// runs inside a closure as well, so that no guard or iterator outlives the call.
+/// [`read`](Self::read) and [`write`](Self::write) hand out the guards for the rest of the
+/// `ArtTree` API.
+///
+/// The closures run while the lock is held, so they must not use the map or a clone of it: a
+/// write from a closure under the read lock deadlocks the thread, as does any access from one
+/// under the write lock, and with writers waiting even a read under the read lock may. To write while going over elements, take them out first with
+/// [`collect_prefix`](Self::collect_prefix) or [`collect_range`](Self::collect_range).
+///
+/// The methods panic if another thread panicked while holding the write lock, since the tree
+/// may have been left half-modified.
+pub struct SharedArtMap<V> {
+    tree: Arc<RwLock<ArtTree<V>>>,
+}
+
+impl<V> SharedArtMap<V> {
+    pub fn new() -> Self {
+        Self::from_tree(ArtTree::new())
+    }
+
+    /// Shares an existing tree, keeping its settings
+    pub fn from_tree(tree: ArtTree<V>) -> Self {
+        Self {
+            tree: Arc::new(RwLock::new(tree)),
+        }
+    }
+
+    /// Takes the read lock, blocking while a thread writes.
+    ///
+    /// The map cannot be written through this or any clone, on this thread too, before the
+    /// guard is dropped, so keep it, and the iterators borrowing from it, short-lived.
+    pub fn read(&self) -> RwLockReadGuard<'_, ArtTree<V>> {
+        self.tree.read().unwrap()
+    }
+
+    /// Takes the write lock, blocking while other threads read or write.
+    pub fn write(&self) -> RwLockWriteGuard<'_, ArtTree<V>> {
+        self.tree.write().unwrap()
+    }
+
+    /// Calls `f` with the tree under the read lock
+    ///
+    /// `f` must not use this map or a clone of it, which may deadlock.
+    pub fn with_read<R>(&self, f: impl FnOnce(&ArtTree<V>) -> R) -> R {
+        f(&self.read())
+    }
+
+    /// Calls `f` with the tree under the write lock
+    ///
+    /// `f` must not use this map or a clone of it, which deadlocks.
+    pub fn with_write<R>(&self, f: impl FnOnce(&mut ArtTree<V>) -> R) -> R {
+        f(&mut self.write())
+    }
+
+    /// Returns the number of elements in the map
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Returns true if the map contains no elements
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Returns true if the map contains a value for the key
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.read().contains_key(key)
+    }
+
+    /// Calls `f` with the value stored at the key under the read lock and returns its result
+    ///
+    /// `f` must not use this map or a clone of it, which may deadlock.
+    pub fn get_with<R>(&self, key: &[u8], f: impl FnOnce(&V) -> R) -> Option<R> {
+        self.read().get(key).map(f)
+    }
+
+    /// Calls `f` with the value stored at the key under the write lock and returns its result
+    ///
+    /// `f` must not use this map or a clone of it, which deadlocks.
+    pub fn update<R>(&self, key: &[u8], f: impl FnOnce(&mut V) -> R) -> Option<R> {
+        self.write().get_mut(key).map(f)
+    }
+
+    /// Inserts the value at the key and returns the previous value
+    pub fn insert(&self, key: &[u8], value: V) -> Option<V> {
+        self.write().insert(key, value)
+    }
+
+    /// Deletes and returns the value stored at the key
+    pub fn delete(&self, key: &[u8]) -> Option<V> {
+        self.write().delete(key)
+    }
+
+    /// Keeps only the elements for which `keep` returns true, under a single write lock
+    ///
+    /// `keep` must not use this map or a clone of it, which deadlocks.
+    pub fn retain(&self, keep: impl FnMut(&[u8], &V) -> bool) {
+        self.write().retain(keep)
+    }
+
+    /// Calls `f` with every element in ascending key order under the read lock
+    ///
+    /// `f` must not use this map or a clone of it, which may deadlock. Use
+    /// [`collect_prefix`](Self::collect_prefix) with an empty prefix to write while going over
+    /// the elements.
+    pub fn for_each(&self, mut f: impl FnMut(&[u8], &V)) {
+        for (key, value) in self.read().entries() {
+            f(key, value);
+        }
+    }
+
+    /// Calls `f` with every element whose key starts with `prefix`, in ascending key order,
+    /// under the read lock
+    ///
+    /// `f` must not use this map or a clone of it, which may deadlock. Use
+    /// [`collect_prefix`](Self::collect_prefix) to write while going over the elements.
+    pub fn for_each_prefix(&self, prefix: &[u8], mut f: impl FnMut(&[u8], &V)) {
+        for (key, value) in self.read().scan_prefix(prefix) {
+            f(key, value);
+        }
+    }
+
+    /// Calls `f` with every element whose key falls in the given range, in ascending key
+    /// order, under the read lock
+    ///
+    /// `f` must not use this map or a clone of it, which may deadlock. Use
+    /// [`collect_range`](Self::collect_range) to write while going over the elements.
+    pub fn for_each_range<'r, R>(&self, range: R, mut f: impl FnMut(&[u8], &V))
+    where
+        R: RangeBounds<&'r [u8]>,
+    {
+        for (key, value) in self.read().range(range) {
+            f(key, value);
+        }
+    }
+}
+
+impl<V: Clone> SharedArtMap<V> {
+    /// Returns a clone of the value stored at the key
+    pub fn get(&self, key: &[u8]) -> Option<V> {
+        self.get_with(key, V::clone)
+    }
+
+    /// Returns clones of the elements whose keys start with `prefix`, in ascending key order,
+    /// to iterate over without holding the lock
+    pub fn collect_prefix(&self, prefix: &[u8]) -> Vec<(Vec<u8>, V)> {
+        let mut entries = Vec::new();
+        self.for_each_prefix(prefix, |key, value| {
+            entries.push((key.to_vec(), value.clone()))
+        });
+        entries
+    }
+
+    /// Returns clones of the elements whose keys fall in the given range, in ascending key
+    /// order, to iterate over without holding the lock
+    pub fn collect_range<'r, R>(&self, range: R) -> Vec<(Vec<u8>, V)>
+    where
+        R: RangeBounds<&'r [u8]>,
+    {
+        let mut entries = Vec::new();
+        self.for_each_range(range, |key, value| {
+            entries.push((key.to_vec(), value.clone()))
+        });
+        entries
+    }
+}
+
+/// Shares the tree with the clone
+impl<V> Clone for SharedArtMap<V> {
+    fn clone(&self) -> Self {
+        Self {
+            tree: Arc::clone(&self.tree),
+        }
+    }
+}
+
+impl<V> Default for SharedArtMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> From<ArtTree<V>> for SharedArtMap<V> {
+    fn from(tree: ArtTree<V>) -> Self {
+        Self::from_tree(tree)
+    }
+}
+
+impl<V: fmt::Debug> fmt::Debug for SharedArtMap<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.tree.try_read() {
+            Ok(tree) => f
+                .debug_struct("SharedArtMap")
+                .field("tree", &*tree)
+                .finish(),
+            Err(_) => f.write_str("SharedArtMap(<locked>)"),
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Key search over the sorted key arrays of Node16 and Node32.
+//!
+//! The SSE2 path is only compiled when the `simd` feature is enabled and the target supports it.
+//! Every other target (including `wasm32-unknown-unknown`) uses the portable scalar search, so the
+//! tree never depends on platform intrinsics being available.
+
+/// Returns the index of `c` among the first `num_children` keys of a Node16.
+#[cfg(all(
+    feature = "simd",
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_feature = "sse2"
+))]
+pub(crate) fn find_key_16(keys: &[u8; 16], num_children: usize, c: u8) -> Option<usize> {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::*;
+
+    // SAFETY: SSE2 is statically enabled for this target (checked by the cfg above) and the
+    // unaligned load reads exactly the 16 bytes of `keys`.
+    let bitfield = unsafe {
+        let needle = _mm_set1_epi8(c as i8);
+        let haystack = _mm_loadu_si128(keys.as_ptr() as *const __m128i);
+        _mm_movemask_epi8(_mm_cmpeq_epi8(needle, haystack)) as u32
+    };
+    let mask = (1u32 << num_children) - 1;
+    match bitfield & mask {
+        0 => None,
+        hits => Some(hits.trailing_zeros() as usize),
+    }
+}
+
+/// Returns the index of `c` among the first `num_children` keys of a Node16.
+#[cfg(not(all(
+    feature = "simd",
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_feature = "sse2"
+)))]
+pub(crate) fn find_key_16(keys: &[u8; 16], num_children: usize, c: u8) -> Option<usize> {
+    find_key_portable(&keys[..num_children], c)
+}
+
+/// Returns the index of `c` among the first `num_children` keys of a Node32.
+#[cfg(all(
+    feature = "simd",
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_feature = "sse2"
+))]
+pub(crate) fn find_key_32(keys: &[u8; 32], num_children: usize, c: u8) -> Option<usize> {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::*;
+
+    // SAFETY: SSE2 is statically enabled for this target (checked by the cfg above) and the two
+    // unaligned loads read exactly the 32 bytes of `keys`.
+    let bitfield = unsafe {
+        let needle = _mm_set1_epi8(c as i8);
+        let low = _mm_loadu_si128(keys.as_ptr() as *const __m128i);
+        let high = _mm_loadu_si128(keys.as_ptr().add(16) as *const __m128i);
+        let low = _mm_movemask_epi8(_mm_cmpeq_epi8(needle, low)) as u64;
+        let high = _mm_movemask_epi8(_mm_cmpeq_epi8(needle, high)) as u64;
+        low | high << 16
+    };
+    let mask = (1u64 << num_children) - 1;
+    match bitfield & mask {
+        0 => None,
+        hits => Some(hits.trailing_zeros() as usize),
+    }
+}
+
+/// Returns the index of `c` among the first `num_children` keys of a Node32.
+#[cfg(not(all(
+    feature = "simd",
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_feature = "sse2"
+)))]
+pub(crate) fn find_key_32(keys: &[u8; 32], num_children: usize, c: u8) -> Option<usize> {
+    find_key_portable(&keys[..num_children], c)
+}
+
+/// Scalar fallback used on targets without a vectorised search.
+pub(crate) fn find_key_portable(keys: &[u8], c: u8) -> Option<usize> {
+    keys.iter().position(|&key| key == c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_key_16_matches_portable_search() {
+        let mut keys = [0u8; 16];
+        for (i, key) in keys.iter_mut().enumerate() {
+            *key = (i * 7) as u8;
+        }
+
+        for num_children in 0..=16 {
+            for c in 0..=255u8 {
+                assert_eq!(
+                    find_key_16(&keys, num_children, c),
+                    find_key_portable(&keys[..num_children], c)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn find_key_32_matches_portable_search() {
+        let mut keys = [0u8; 32];
+        for (i, key) in keys.iter_mut().enumerate() {
+            *key = (i * 7) as u8;
+        }
+
+        for num_children in 0..=32 {
+            for c in 0..=255u8 {
+                assert_eq!(
+                    find_key_32(&keys, num_children, c),
+                    find_key_portable(&keys[..num_children], c)
+                );
+            }
+        }
+    }
+}
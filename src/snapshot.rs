@@ -0,0 +1,444 @@
+//! A stream format for saving a tree to any `io::Write` and loading it back from any `io::Read`.
+//!
+//! [`SnapshotWriter`] (or [`ArtTree::write_snapshot`]) writes the entries in a single pass in
+//! ascending key order, keeping only the last key and one encoded value in memory.
+//! [`SnapshotReader`] (or [`ArtTree::read_snapshot`]) reads them back the same way, and
+//! `read_snapshot` feeds them to an [`ArtBuilder`], which only keeps the rightmost path of the
+//! tree open. Neither side materializes the entries or the serialized bytes, so a tree is saved
+//! and loaded without doubling its memory. Values are written with [`EncodeFlat`] and read back
+//! with [`DecodeFlat`], like in a flat tree.
+//!
+//! The stream starts with an 8-byte header: the magic `ARTS` and the format version and flags
+//! as little-endian `u16`. Every entry follows as a record of three little-endian `u32`: the
+//! number of leading bytes its key shares with the key before it, the length of the rest of the
+//! key and the length of the value, followed by the rest of the key and the value. The stream
+//! ends with the marker `0xffff_ffff` in place of the shared length and the number of entries
+//! as a little-endian `u64`, so that a truncated stream is detected.
+//!
+//! With the `encryption` feature, [`SnapshotWriter::encrypted`] seals everything after the
+//! header, which then carries the flag `1`, with a user-supplied key, see the `encryption`
+//! module. Such a stream is only read by [`SnapshotReader::encrypted`] with the same key, and a
+//! reader given a key refuses streams that are not encrypted.
+//!
+//! Writers and readers issue small writes and reads for every entry, so files should be wrapped
+//! in a `BufWriter` or `BufReader`.
+
+use std::convert::TryInto;
+use std::error;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::art::{ArtBuilder, ArtTree, BulkLoad, LeafKey};
+#[cfg(feature = "encryption")]
+use crate::encryption::{DecryptionError, EncryptionKey, OpenReader, SealWriter};
+use crate::flat_art::{DecodeFlat, EncodeFlat};
+
+const MAGIC: &[u8; 4] = b"ARTS";
+const VERSION: u16 = 1;
+const ENCRYPTED: u16 = 1;
+const END: u32 = u32::MAX;
+
+fn header(flags: u16) -> [u8; 8] {
+    let mut header = [0u8; 8];
+    header[..4].copy_from_slice(MAGIC);
+    header[4..6].copy_from_slice(&VERSION.to_le_bytes());
+    header[6..].copy_from_slice(&flags.to_le_bytes());
+    header
+}
+
+/// The stream after the header
+enum Output<W: Write> {
+    Plain(W),
+    #[cfg(feature = "encryption")]
+    Sealed(SealWriter<W>),
+}
+
+impl<W: Write> Output<W> {
+    fn finish(self) -> io::Result<W> {
+        match self {
+            Output::Plain(mut out) => {
+                out.flush()?;
+                Ok(out)
+            }
+            #[cfg(feature = "encryption")]
+            Output::Sealed(out) => out.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for Output<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Plain(out) => out.write(buf),
+            #[cfg(feature = "encryption")]
+            Output::Sealed(out) => out.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Plain(out) => out.flush(),
+            #[cfg(feature = "encryption")]
+            Output::Sealed(out) => out.flush(),
+        }
+    }
+}
+
+/// Writes entries pushed in ascending key order into a snapshot stream, see the
+/// [module documentation](self).
+pub struct SnapshotWriter<W: Write> {
+    out: Output<W>,
+    last_key: Vec<u8>,
+    value: Vec<u8>,
+    len: u64,
+}
+
+impl<W: Write> SnapshotWriter<W> {
+    /// Writes the header of the stream to `out`.
+    pub fn new(mut out: W) -> io::Result<Self> {
+        out.write_all(&header(0))?;
+        Ok(Self::with_output(Output::Plain(out)))
+    }
+
+    /// Writes the header of the stream to `out`, and seals the entries written after it with
+    /// `key`.
+    #[cfg(feature = "encryption")]
+    pub fn encrypted(mut out: W, key: &EncryptionKey) -> io::Result<Self> {
+        let header = header(ENCRYPTED);
+        out.write_all(&header)?;
+        let out = SealWriter::new(out, key, &header)?;
+        Ok(Self::with_output(Output::Sealed(out)))
+    }
+
+    fn with_output(out: Output<W>) -> Self {
+        Self {
+            out,
+            last_key: Vec::new(),
+            value: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Writes an entry.
+    ///
+    /// # Panics
+    ///
+    /// If `key` is not greater than the key pushed before it, or the key before it is a prefix
+    /// of it.
+    pub fn push<V: EncodeFlat + ?Sized>(&mut self, key: &[u8], value: &V) -> io::Result<()> {
+        if self.len > 0 {
+            assert!(
+                self.last_key[..] < *key,
+                "keys have to be pushed in ascending order"
+            );
+            assert!(
+                !key.starts_with(&self.last_key),
+                "a key is a prefix of another key"
+            );
+        }
+        let shared = self
+            .last_key
+            .iter()
+            .zip(key)
+            .take_while(|(a, b)| a == b)
+            .count();
+        self.value.clear();
+        value.encode_flat(&mut self.value);
+
+        let suffix = &key[shared..];
+        self.out.write_all(&to_u32(shared).to_le_bytes())?;
+        self.out.write_all(&to_u32(suffix.len()).to_le_bytes())?;
+        self.out
+            .write_all(&to_u32(self.value.len()).to_le_bytes())?;
+        self.out.write_all(suffix)?;
+        self.out.write_all(&self.value)?;
+
+        self.last_key.truncate(shared);
+        self.last_key.extend_from_slice(suffix);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Returns the number of entries written
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns true if no entry was written
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Writes the end of the stream, flushes it and returns the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.write_all(&END.to_le_bytes())?;
+        self.out.write_all(&self.len.to_le_bytes())?;
+        self.out.finish()
+    }
+}
+
+fn to_u32(n: usize) -> u32 {
+    n.try_into()
+        .expect("a snapshot is limited to keys and values of 4 GiB")
+}
+
+/// A stream rejected by [`SnapshotReader`].
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// Reading the stream failed, or it ended early
+    Io(io::Error),
+    /// The stream does not start with the magic bytes of the format
+    BadMagic,
+    /// The stream was written in another version of the format
+    UnsupportedVersion(u16),
+    /// The stream was written with options this version does not know
+    UnsupportedFlags(u16),
+    /// The keys are out of order, a value does not decode, or the number of entries does not
+    /// match the end of the stream
+    Corrupt,
+    /// The stream is encrypted and has to be read with [`SnapshotReader::encrypted`]
+    Encrypted,
+    /// The stream was expected to be encrypted, but is not
+    Unencrypted,
+    /// The stream failed to decrypt, because the key is wrong or the stream was modified
+    Decryption,
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(err) => write!(f, "reading the snapshot failed: {}", err),
+            SnapshotError::BadMagic => f.write_str("the stream does not hold a snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot format version {}", version)
+            }
+            SnapshotError::UnsupportedFlags(flags) => {
+                write!(f, "unsupported snapshot options {:#06x}", flags)
+            }
+            SnapshotError::Corrupt => f.write_str("the snapshot is damaged"),
+            SnapshotError::Encrypted => f.write_str("the snapshot is encrypted"),
+            SnapshotError::Unencrypted => f.write_str("the snapshot is not encrypted"),
+            SnapshotError::Decryption => {
+                f.write_str("the key is wrong or the encrypted snapshot was modified")
+            }
+        }
+    }
+}
+
+impl error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            SnapshotError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(err: io::Error) -> Self {
+        #[cfg(feature = "encryption")]
+        if DecryptionError::is_cause_of(&err) {
+            return SnapshotError::Decryption;
+        }
+        SnapshotError::Io(err)
+    }
+}
+
+/// The stream after the header
+enum Input<R: Read> {
+    Plain(R),
+    #[cfg(feature = "encryption")]
+    Sealed(OpenReader<R>),
+}
+
+impl<R: Read> Read for Input<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Input::Plain(input) => input.read(buf),
+            #[cfg(feature = "encryption")]
+            Input::Sealed(input) => input.read(buf),
+        }
+    }
+}
+
+/// Reads the entries of a snapshot stream one by one, see the [module documentation](self).
+pub struct SnapshotReader<R: Read> {
+    input: Input<R>,
+    key: Vec<u8>,
+    value: Vec<u8>,
+    len: u64,
+    done: bool,
+}
+
+impl<R: Read> SnapshotReader<R> {
+    /// Reads and checks the header of the stream.
+    pub fn new(mut input: R) -> Result<Self, SnapshotError> {
+        if read_header(&mut input)? & ENCRYPTED != 0 {
+            return Err(SnapshotError::Encrypted);
+        }
+        Ok(Self::with_input(Input::Plain(input)))
+    }
+
+    /// Reads and checks the header of a stream written by [`SnapshotWriter::encrypted`], and
+    /// opens the entries after it with `key`.
+    #[cfg(feature = "encryption")]
+    pub fn encrypted(mut input: R, key: &EncryptionKey) -> Result<Self, SnapshotError> {
+        let flags = read_header(&mut input)?;
+        if flags & ENCRYPTED == 0 {
+            return Err(SnapshotError::Unencrypted);
+        }
+        let input = OpenReader::new(input, key, &header(flags))?;
+        Ok(Self::with_input(Input::Sealed(input)))
+    }
+
+    fn with_input(input: Input<R>) -> Self {
+        Self {
+            input,
+            key: Vec::new(),
+            value: Vec::new(),
+            len: 0,
+            done: false,
+        }
+    }
+
+    /// Reads the next entry, returning `None` at the end of the stream. The key and a borrowing
+    /// value point into buffers reused for the next entry.
+    pub fn read_entry<'a, V: DecodeFlat<'a>>(
+        &'a mut self,
+    ) -> Result<Option<(&'a [u8], V)>, SnapshotError> {
+        if self.done {
+            return Ok(None);
+        }
+        let shared = self.read_u32()?;
+        if shared == END {
+            let mut len = [0u8; 8];
+            self.input.read_exact(&mut len)?;
+            if u64::from_le_bytes(len) != self.len {
+                return Err(SnapshotError::Corrupt);
+            }
+            self.done = true;
+            return Ok(None);
+        }
+        let shared = shared as usize;
+        let suffix_len = self.read_u32()? as u64;
+        let value_len = self.read_u32()? as u64;
+
+        // Every key has to branch off the key before it to the right
+        let first = self.len == 0;
+        if (first && shared != 0) || (!first && shared >= self.key.len()) {
+            return Err(SnapshotError::Corrupt);
+        }
+        let branch = self.key.get(shared).copied();
+        self.key.truncate(shared);
+        read_exactly(&mut self.input, suffix_len, &mut self.key)?;
+        if let Some(branch) = branch {
+            if self.key.len() == shared || self.key[shared] <= branch {
+                return Err(SnapshotError::Corrupt);
+            }
+        }
+        self.value.clear();
+        read_exactly(&mut self.input, value_len, &mut self.value)?;
+        let value = V::try_decode_flat(&self.value).ok_or(SnapshotError::Corrupt)?;
+        self.len += 1;
+        Ok(Some((&self.key, value)))
+    }
+
+    /// Returns the number of entries read
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns true if no entry was read
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn read_u32(&mut self) -> io::Result<u32> {
+        let mut bytes = [0u8; 4];
+        self.input.read_exact(&mut bytes)?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+}
+
+/// Reads and checks the header of a stream, returning its flags
+fn read_header(input: &mut impl Read) -> Result<u16, SnapshotError> {
+    let mut header = [0u8; 8];
+    input.read_exact(&mut header)?;
+    if &header[..4] != MAGIC {
+        return Err(SnapshotError::BadMagic);
+    }
+    let version = u16::from_le_bytes([header[4], header[5]]);
+    if version != VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+    let flags = u16::from_le_bytes([header[6], header[7]]);
+    if flags & !ENCRYPTED != 0 {
+        return Err(SnapshotError::UnsupportedFlags(flags));
+    }
+    Ok(flags)
+}
+
+/// Appends exactly `len` bytes to `out`, growing it only as the bytes arrive so that a damaged
+/// length does not allocate up front.
+fn read_exactly(input: &mut impl Read, len: u64, out: &mut Vec<u8>) -> io::Result<()> {
+    let read = input.take(len).read_to_end(out)?;
+    if read as u64 != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+
+impl<V: EncodeFlat, K: LeafKey> ArtTree<V, K> {
+    /// Writes the entries of the tree to `out` in a single pass, see the
+    /// [`snapshot`](crate::snapshot) module. Returns the flushed writer.
+    pub fn write_snapshot<W: Write>(&self, out: W) -> io::Result<W> {
+        self.write_entries(SnapshotWriter::new(out)?)
+    }
+
+    /// Writes the entries of the tree to `out` in a single pass like
+    /// [`write_snapshot`](Self::write_snapshot), sealed with `key`.
+    #[cfg(feature = "encryption")]
+    pub fn write_snapshot_encrypted<W: Write>(&self, out: W, key: &EncryptionKey) -> io::Result<W> {
+        self.write_entries(SnapshotWriter::encrypted(out, key)?)
+    }
+
+    fn write_entries<W: Write>(&self, mut writer: SnapshotWriter<W>) -> io::Result<W> {
+        let phase = BulkLoad::enter("write_snapshot");
+        phase.entries(self.len());
+        for (key, value) in self.entries() {
+            writer.push(key, value)?;
+        }
+        writer.finish()
+    }
+}
+
+impl<V, K: LeafKey> ArtTree<V, K>
+where
+    V: for<'a> DecodeFlat<'a>,
+{
+    /// Builds a tree from a stream written by [`write_snapshot`](Self::write_snapshot) or a
+    /// [`SnapshotWriter`], reading it in a single pass.
+    pub fn read_snapshot<R: Read>(input: R) -> Result<Self, SnapshotError> {
+        Self::read_entries(SnapshotReader::new(input)?)
+    }
+
+    /// Builds a tree from a stream written by
+    /// [`write_snapshot_encrypted`](Self::write_snapshot_encrypted) with the same key.
+    #[cfg(feature = "encryption")]
+    pub fn read_snapshot_encrypted<R: Read>(
+        input: R,
+        key: &EncryptionKey,
+    ) -> Result<Self, SnapshotError> {
+        Self::read_entries(SnapshotReader::encrypted(input, key)?)
+    }
+
+    fn read_entries<R: Read>(mut reader: SnapshotReader<R>) -> Result<Self, SnapshotError> {
+        let phase = BulkLoad::enter("read_snapshot");
+        let mut builder = ArtBuilder::default();
+        while let Some((key, value)) = reader.read_entry()? {
+            builder.push(key, value);
+        }
+        phase.entries(builder.len());
+        Ok(builder.finish())
+    }
+}
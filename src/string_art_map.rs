@@ -0,0 +1,263 @@
+use std::fmt;
+use std::iter::FromIterator;
+use std::sync::Arc;
+
+use crate::art::{self, ArtTree};
+
+/// Function turning a string into a binary-comparable sort key, see
+/// [`StringArtMap::with_sort_key`]
+pub type SortKeyFn = dyn Fn(&str) -> Vec<u8> + Send + Sync;
+
+/// How keys are turned into bytes before encoding
+#[derive(Clone)]
+enum KeyMode {
+    Exact,
+    CaseInsensitive,
+    SortKey(Arc<SortKeyFn>),
+}
+
+impl fmt::Debug for KeyMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyMode::Exact => f.write_str("Exact"),
+            KeyMode::CaseInsensitive => f.write_str("CaseInsensitive"),
+            KeyMode::SortKey(_) => f.write_str("SortKey"),
+        }
+    }
+}
+
+/// Map indexed by string keys using an Adaptive Radix Tree
+///
+/// Keys are made binary-comparable and prefix-free as described in part IV.B of the ART paper:
+/// the UTF-8 bytes are terminated by `00 00`, with every `00` byte in the key escaped as
+/// `00 ff`. A case-insensitive map additionally lowercases the keys before encoding them, and a
+/// map created with [`with_sort_key`](StringArtMap::with_sort_key) encodes the sort keys of
+/// a collation instead. Either way the originally inserted key is kept for iteration.
+#[derive(Clone, Debug)]
+pub struct StringArtMap<V> {
+    tree: ArtTree<(String, V)>,
+    mode: KeyMode,
+}
+
+impl<V> StringArtMap<V> {
+    pub fn new() -> Self {
+        Self {
+            tree: ArtTree::new(),
+            mode: KeyMode::Exact,
+        }
+    }
+
+    /// Creates an empty map in which keys differing only in case refer to the same entry
+    pub fn case_insensitive() -> Self {
+        Self {
+            tree: ArtTree::new(),
+            mode: KeyMode::CaseInsensitive,
+        }
+    }
+
+    /// Creates an empty map ordered by the sort keys returned by `sort_key`, e.g. the sort keys
+    /// of an ICU collator (`ucol_getSortKey`, or `Collator::write_sort_key_to` in ICU4X), so that
+    /// iteration follows a locale-aware collation. Keys with equal sort keys refer to the same
+    /// entry.
+    pub fn with_sort_key<F>(sort_key: F) -> Self
+    where
+        F: Fn(&str) -> Vec<u8> + Send + Sync + 'static,
+    {
+        Self {
+            tree: ArtTree::new(),
+            mode: KeyMode::SortKey(Arc::new(sort_key)),
+        }
+    }
+
+    /// Returns true if keys are compared without regard to case
+    pub fn is_case_insensitive(&self) -> bool {
+        matches!(self.mode, KeyMode::CaseInsensitive)
+    }
+
+    fn encode(&self, key: &str) -> Vec<u8> {
+        let folded;
+        let key = match &self.mode {
+            KeyMode::Exact => key.as_bytes(),
+            KeyMode::CaseInsensitive => {
+                folded = key.to_lowercase().into_bytes();
+                &folded
+            }
+            KeyMode::SortKey(sort_key) => {
+                folded = sort_key(key);
+                &folded
+            }
+        };
+        let mut bytes = Vec::new();
+        art::encode_prefix_free(key, &mut bytes);
+        bytes
+    }
+
+    /// Returns the number of elements in the map
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns true if the map contains no elements
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Returns true if the map contains a value for the given key
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.tree.contains_key(&self.encode(key))
+    }
+
+    /// Returns a reference to the value stored at the given key if it exists
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.get_key_value(key).map(|(_, value)| value)
+    }
+
+    /// Returns the key as it was inserted and a reference to the value stored at the given key
+    pub fn get_key_value(&self, key: &str) -> Option<(&str, &V)> {
+        self.tree
+            .get(&self.encode(key))
+            .map(|(key, value)| (key.as_str(), value))
+    }
+
+    /// Returns a mutable reference to the value stored at the given key if it exists
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        let encoded = self.encode(key);
+        self.tree.get_mut(&encoded).map(|(_, value)| value)
+    }
+
+    /// Inserts the given value at the given key and returns the previous value stored at the key if
+    /// such exists. An existing entry keeps the key it was first inserted with.
+    pub fn insert(&mut self, key: &str, value: V) -> Option<V> {
+        let encoded = self.encode(key);
+        let mut value = Some(value);
+        let (_, current) = self
+            .tree
+            .get_or_insert_with(&encoded, || (key.to_string(), value.take().unwrap()));
+        value.map(|value| std::mem::replace(current, value))
+    }
+
+    /// Deletes and returns the value stored at the given key.
+    pub fn delete(&mut self, key: &str) -> Option<V> {
+        self.tree.delete(&self.encode(key)).map(|(_, value)| value)
+    }
+
+    /// Moves the value stored at `old` to `new`, replacing any value stored at `new`. Returns
+    /// false if `old` is not present.
+    ///
+    /// The entry takes `new` as its key even if both keys refer to the same entry, e.g. when
+    /// changing the case of a key in a case-insensitive map.
+    pub fn rekey(&mut self, old: &str, new: &str) -> bool {
+        match self.delete(old) {
+            Some(value) => {
+                let encoded = self.encode(new);
+                self.tree.insert(&encoded, (new.to_string(), value));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns an iterator over the key-value pairs of the map in the order of the encoded keys
+    pub fn iter(&self) -> Iter<'_, V> {
+        Iter {
+            inner: self.tree.entries(),
+        }
+    }
+
+    /// Returns an iterator over the key-value pairs of the map in the order of the encoded keys,
+    /// with mutable references to the values
+    pub fn iter_mut(&mut self) -> IterMut<'_, V> {
+        IterMut {
+            inner: self.tree.entries_mut(),
+        }
+    }
+
+    /// Returns an iterator over the keys of the map, as they were inserted
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /// Returns an iterator over the values of the map
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+}
+
+impl<V> Default for StringArtMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> FromIterator<(String, V)> for StringArtMap<V> {
+    fn from_iter<I: IntoIterator<Item = (String, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<V> Extend<(String, V)> for StringArtMap<V> {
+    fn extend<I: IntoIterator<Item = (String, V)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.tree.reserve(iter.size_hint().0);
+        for (key, value) in iter {
+            self.insert(&key, value);
+        }
+    }
+}
+
+impl<'a, V> IntoIterator for &'a StringArtMap<V> {
+    type Item = (&'a str, &'a V);
+    type IntoIter = Iter<'a, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the entries of a `StringArtMap`.
+///
+/// Created by [`StringArtMap::iter`].
+pub struct Iter<'a, V> {
+    inner: art::Iter<'a, (String, V)>,
+}
+
+impl<'a, V> Iterator for Iter<'a, V> {
+    type Item = (&'a str, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(_, (key, value))| (key.as_str(), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, V> ExactSizeIterator for Iter<'a, V> {}
+
+/// A mutable iterator over the entries of a `StringArtMap`.
+///
+/// Created by [`StringArtMap::iter_mut`].
+pub struct IterMut<'a, V> {
+    inner: art::IterMut<'a, (String, V)>,
+}
+
+impl<'a, V> Iterator for IterMut<'a, V> {
+    type Item = (&'a str, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(_, (key, value))| (key.as_str(), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, V> ExactSizeIterator for IterMut<'a, V> {}
@@ -0,0 +1,472 @@
+//! A read-only map compiled into a succinct trie, for trees that are built once and then only
+//! queried.
+//!
+//! [`FrozenArtTree::to_succinct`] (or [`ArtTree::into_succinct`]) encodes the keys as a byte
+//! trie in the LOUDS-sparse layout: the nodes are numbered breadth-first and every edge is
+//! stored as its label byte plus two bits, one marking the first edge of a node and one
+//! telling whether the edge leads to another node or to a leaf. Children and values are found
+//! by counting set bits with a small rank index instead of following pointers. The trie is cut
+//! off as soon as a subtree holds a single key, and the rest of that key is stored as its tail
+//! in one shared byte array.
+//!
+//! Keys take little more than the bytes of their distinct prefixes and tails, where an
+//! `ArtTree` allocates a header and a child array sized for its type for every internal node,
+//! and every leaf and key separately. For hashed or path-like keys the trie takes a third to a
+//! quarter of the memory of the tree it is compiled from, see [`SuccinctArt::memory_bytes`].
+//! The price is that nothing can be changed, and lookups count bits at every byte of the key
+//! instead of jumping over compressed paths.
+
+use std::collections::VecDeque;
+use std::convert::TryInto;
+use std::mem;
+use std::ops::{Bound, Range, RangeBounds};
+
+use crate::art::{ArtTree, FrozenArtTree, LeafKey};
+
+/// Bits per block of the rank index
+const BLOCK_BITS: usize = 512;
+const BLOCK_WORDS: usize = BLOCK_BITS / 64;
+
+/// A bit vector that counts the set bits before a position in constant time
+#[derive(Debug, Clone, Default)]
+struct RankBits {
+    words: Vec<u64>,
+    /// Number of set bits before every block of `BLOCK_BITS` bits
+    blocks: Vec<u32>,
+    len: usize,
+    ones: usize,
+}
+
+impl RankBits {
+    fn push(&mut self, bit: bool) {
+        if self.len.is_multiple_of(64) {
+            self.words.push(0);
+        }
+        if bit {
+            *self.words.last_mut().unwrap() |= 1 << (self.len % 64);
+            self.ones += 1;
+        }
+        self.len += 1;
+    }
+
+    /// Builds the rank index once every bit is pushed
+    fn finish(&mut self) {
+        self.words.shrink_to_fit();
+        let mut ones = 0;
+        self.blocks = self
+            .words
+            .chunks(BLOCK_WORDS)
+            .map(|block| {
+                let before = to_u32(ones);
+                ones += block.iter().map(|w| w.count_ones() as usize).sum::<usize>();
+                before
+            })
+            .collect();
+    }
+
+    fn get(&self, pos: usize) -> bool {
+        self.words[pos / 64] & (1 << (pos % 64)) != 0
+    }
+
+    /// Returns the number of set bits before `pos`
+    fn rank1(&self, pos: usize) -> usize {
+        let word = pos / 64;
+        let block = word / BLOCK_WORDS;
+        let mut ones = self.blocks.get(block).map_or(self.ones, |&n| n as usize);
+        for w in &self.words[block * BLOCK_WORDS..word] {
+            ones += w.count_ones() as usize;
+        }
+        if !pos.is_multiple_of(64) {
+            ones += (self.words[word] & ((1 << (pos % 64)) - 1)).count_ones() as usize;
+        }
+        ones
+    }
+
+    /// Returns the position of the set bit with index `n`, counting from 0
+    fn select1(&self, n: usize) -> usize {
+        // The last block with at most n set bits before it holds the bit
+        let block = self.blocks.partition_point(|&ones| ones as usize <= n) - 1;
+        let mut rest = n - self.blocks[block] as usize;
+        for (i, &w) in self.words[block * BLOCK_WORDS..].iter().enumerate() {
+            let ones = w.count_ones() as usize;
+            if rest < ones {
+                let mut w = w;
+                for _ in 0..rest {
+                    w &= w - 1;
+                }
+                return (block * BLOCK_WORDS + i) * 64 + w.trailing_zeros() as usize;
+            }
+            rest -= ones;
+        }
+        unreachable!("select1 past the last set bit")
+    }
+
+    fn bytes(&self) -> usize {
+        mem::size_of_val(&self.words[..]) + mem::size_of_val(&self.blocks[..])
+    }
+}
+
+fn to_u32(n: usize) -> u32 {
+    n.try_into()
+        .expect("a succinct tree is limited to 4 GiB of tails and 4G edges")
+}
+
+/// A read-only map from byte keys to values, encoded as a succinct trie.
+///
+/// Created by [`FrozenArtTree::to_succinct`] and [`ArtTree::into_succinct`], see the
+/// [module documentation](self) for the layout.
+#[derive(Debug, Clone)]
+pub struct SuccinctArt<V> {
+    /// The label bytes of the edges, breadth-first and sorted within each node
+    labels: Vec<u8>,
+    /// Set for the edges leading to another node, clear for those leading to a leaf
+    has_child: RankBits,
+    /// Set for the first edge of every node
+    louds: RankBits,
+    /// The rests of the keys after the edges leading to their leaves, breadth-first
+    tails: Vec<u8>,
+    /// The end of the tail of every leaf in `tails`
+    tail_ends: Vec<u32>,
+    /// The values of the leaves, breadth-first. Without edges, the only key is empty.
+    values: Vec<V>,
+}
+
+impl<V> SuccinctArt<V> {
+    /// Encodes the keys, which have to be in ascending order, with their values.
+    fn build<Key: AsRef<[u8]>>(keys: &[Key], values: Vec<V>) -> Self {
+        let mut values: Vec<Option<V>> = values.into_iter().map(Some).collect();
+        let mut art = SuccinctArt {
+            labels: Vec::new(),
+            has_child: RankBits::default(),
+            louds: RankBits::default(),
+            tails: Vec::new(),
+            tail_ends: Vec::new(),
+            values: Vec::with_capacity(values.len()),
+        };
+        if keys.len() == 1 && keys[0].as_ref().is_empty() {
+            art.values.push(values[0].take().unwrap());
+            art.tail_ends.push(0);
+            return art;
+        }
+
+        // Every group of keys sharing their first `depth` bytes becomes a node
+        let mut groups = VecDeque::new();
+        if !keys.is_empty() {
+            groups.push_back((0..keys.len(), 0));
+        }
+        while let Some((group, depth)) = groups.pop_front() {
+            let mut first = true;
+            let mut start = group.start;
+            while start < group.end {
+                let label = *keys[start]
+                    .as_ref()
+                    .get(depth)
+                    .expect("a key is a prefix of another key");
+                let end = start
+                    + keys[start..group.end]
+                        .iter()
+                        .take_while(|key| key.as_ref().get(depth) == Some(&label))
+                        .count();
+                art.labels.push(label);
+                art.louds.push(first);
+                art.has_child.push(end - start > 1);
+                if end - start > 1 {
+                    groups.push_back((start..end, depth + 1));
+                } else {
+                    art.tails
+                        .extend_from_slice(&keys[start].as_ref()[depth + 1..]);
+                    art.tail_ends.push(to_u32(art.tails.len()));
+                    art.values.push(values[start].take().unwrap());
+                }
+                first = false;
+                start = end;
+            }
+        }
+        art.labels.shrink_to_fit();
+        art.has_child.finish();
+        art.louds.finish();
+        art.tails.shrink_to_fit();
+        art
+    }
+
+    /// Returns the number of entries
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns true if the map holds no entries
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns true if a value is stored at the given key
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns a reference to the value stored at the given key
+    pub fn get(&self, key: &[u8]) -> Option<&V> {
+        if self.labels.is_empty() {
+            return self.values.first().filter(|_| key.is_empty());
+        }
+        let mut node = 0;
+        let mut depth = 0;
+        loop {
+            let e = self.find_edge(node, *key.get(depth)?)?;
+            depth += 1;
+            if self.has_child.get(e) {
+                node = self.child(e);
+            } else {
+                let leaf = self.leaf(e);
+                return if self.tail(leaf) == &key[depth..] {
+                    Some(&self.values[leaf])
+                } else {
+                    None
+                };
+            }
+        }
+    }
+
+    /// Returns the bytes allocated by the encoding, including the values but not memory owned
+    /// by them, to compare with [`MemoryStats::total_bytes`](crate::art::MemoryStats::total_bytes).
+    pub fn memory_bytes(&self) -> usize {
+        self.labels.len()
+            + self.has_child.bytes()
+            + self.louds.bytes()
+            + self.tails.len()
+            + mem::size_of_val(&self.tail_ends[..])
+            + mem::size_of_val(&self.values[..])
+    }
+
+    /// Iterates over all entries in key order
+    pub fn iter(&self) -> SuccinctIter<'_, V> {
+        self.range(..)
+    }
+
+    /// Iterates over the entries whose keys start with `prefix`, in key order
+    pub fn scan_prefix(&self, prefix: &[u8]) -> SuccinctIter<'_, V> {
+        let mut iter = self.iter_from(prefix, Bound::Unbounded);
+        if self.labels.is_empty() {
+            if prefix.is_empty() {
+                iter.empty_key = self.values.first();
+            }
+            return iter;
+        }
+        let mut node = 0;
+        let mut depth = 0;
+        loop {
+            let edges = self.edges(node);
+            let c = match prefix.get(depth) {
+                Some(&c) => c,
+                None => {
+                    iter.stack.push((edges, depth));
+                    return iter;
+                }
+            };
+            let e = match self.find_edge(node, c) {
+                Some(e) => e,
+                None => return iter,
+            };
+            if self.has_child.get(e) {
+                node = self.child(e);
+                depth += 1;
+            } else {
+                if self.tail(self.leaf(e)).starts_with(&prefix[depth + 1..]) {
+                    iter.stack.push((e..e + 1, depth));
+                }
+                return iter;
+            }
+        }
+    }
+
+    /// Iterates over the entries whose keys lie within `range`, in key order
+    pub fn range<'r, R>(&self, range: R) -> SuccinctIter<'_, V>
+    where
+        R: RangeBounds<&'r [u8]>,
+    {
+        let end = match range.end_bound() {
+            Bound::Included(key) => Bound::Included(key.to_vec()),
+            Bound::Excluded(key) => Bound::Excluded(key.to_vec()),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let (start, inclusive) = match range.start_bound() {
+            Bound::Included(key) => (*key, true),
+            Bound::Excluded(key) => (*key, false),
+            Bound::Unbounded => (&[][..], true),
+        };
+        let mut iter = self.iter_from(start, end);
+        if self.labels.is_empty() {
+            if start.is_empty() && inclusive {
+                iter.empty_key = self.values.first();
+            }
+            return iter;
+        }
+
+        // Descend towards the start, leaving the parents to continue after the edge taken
+        let mut node = 0;
+        let mut depth = 0;
+        loop {
+            let edges = self.edges(node);
+            let c = match start.get(depth) {
+                Some(&c) => c,
+                None => {
+                    // Every key below is longer than the start, so greater
+                    iter.stack.push((edges, depth));
+                    return iter;
+                }
+            };
+            let e = match self.labels[edges.clone()].binary_search(&c) {
+                Ok(i) => edges.start + i,
+                Err(i) => {
+                    iter.stack.push((edges.start + i..edges.end, depth));
+                    return iter;
+                }
+            };
+            if self.has_child.get(e) {
+                iter.stack.push((e + 1..edges.end, depth));
+                node = self.child(e);
+                depth += 1;
+            } else {
+                let (tail, rest) = (self.tail(self.leaf(e)), &start[depth + 1..]);
+                let from = if tail > rest || (inclusive && tail == rest) {
+                    e
+                } else {
+                    e + 1
+                };
+                iter.stack.push((from..edges.end, depth));
+                return iter;
+            }
+        }
+    }
+
+    fn iter_from(&self, path: &[u8], end: Bound<Vec<u8>>) -> SuccinctIter<'_, V> {
+        SuccinctIter {
+            art: self,
+            stack: Vec::new(),
+            end,
+            key: path.to_vec(),
+            empty_key: None,
+        }
+    }
+
+    /// Returns the edges of the node with the given breadth-first number
+    fn edges(&self, node: usize) -> Range<usize> {
+        let start = self.louds.select1(node);
+        let end = if node + 1 < self.louds.ones {
+            self.louds.select1(node + 1)
+        } else {
+            self.labels.len()
+        };
+        start..end
+    }
+
+    fn find_edge(&self, node: usize, label: u8) -> Option<usize> {
+        let edges = self.edges(node);
+        let i = self.labels[edges.clone()].binary_search(&label).ok()?;
+        Some(edges.start + i)
+    }
+
+    /// Returns the node an edge with a child leads to. The root is node 0 and every edge with
+    /// a child adds the next node breadth-first.
+    fn child(&self, edge: usize) -> usize {
+        self.has_child.rank1(edge) + 1
+    }
+
+    /// Returns the leaf an edge without a child leads to
+    fn leaf(&self, edge: usize) -> usize {
+        edge - self.has_child.rank1(edge)
+    }
+
+    fn tail(&self, leaf: usize) -> &[u8] {
+        let start = match leaf {
+            0 => 0,
+            _ => self.tail_ends[leaf - 1] as usize,
+        };
+        &self.tails[start..self.tail_ends[leaf] as usize]
+    }
+}
+
+/// Iterator over entries of a [`SuccinctArt`], created by [`SuccinctArt::iter`],
+/// [`SuccinctArt::scan_prefix`] and [`SuccinctArt::range`].
+///
+/// The trie stores no whole keys, so every key is assembled into a new `Vec`.
+pub struct SuccinctIter<'a, V> {
+    art: &'a SuccinctArt<V>,
+    /// Edges left to visit in the nodes on the path, with the length of the path leading to
+    /// each node
+    stack: Vec<(Range<usize>, usize)>,
+    end: Bound<Vec<u8>>,
+    /// The path to the current node
+    key: Vec<u8>,
+    /// The value of the empty key, the only key of a map without edges
+    empty_key: Option<&'a V>,
+}
+
+impl<V> SuccinctIter<'_, V> {
+    fn before_end(&self, key: &[u8]) -> bool {
+        match &self.end {
+            Bound::Included(end) => key <= &end[..],
+            Bound::Excluded(end) => key < &end[..],
+            Bound::Unbounded => true,
+        }
+    }
+}
+
+impl<'a, V> Iterator for SuccinctIter<'a, V> {
+    type Item = (Vec<u8>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(value) = self.empty_key.take() {
+            return if self.before_end(&[]) {
+                Some((Vec::new(), value))
+            } else {
+                None
+            };
+        }
+        let art = self.art;
+        loop {
+            let (edges, depth) = self.stack.last_mut()?;
+            let e = match edges.next() {
+                Some(e) => e,
+                None => {
+                    self.stack.pop();
+                    continue;
+                }
+            };
+            let depth = *depth;
+            self.key.truncate(depth);
+            self.key.push(art.labels[e]);
+            if art.has_child.get(e) {
+                self.stack.push((art.edges(art.child(e)), depth + 1));
+                continue;
+            }
+            let leaf = art.leaf(e);
+            self.key.extend_from_slice(art.tail(leaf));
+            if !self.before_end(&self.key) {
+                self.stack.clear();
+                return None;
+            }
+            return Some((self.key.clone(), &art.values[leaf]));
+        }
+    }
+}
+
+impl<V: Clone, K: LeafKey> FrozenArtTree<V, K> {
+    /// Compiles the entries into a [`SuccinctArt`], cloning the values. The handle is left as
+    /// it is, so it can be dropped once the compiled map takes over.
+    pub fn to_succinct(&self) -> SuccinctArt<V> {
+        let (keys, values): (Vec<&[u8]>, Vec<V>) = self
+            .entries()
+            .map(|(key, value)| (key, value.clone()))
+            .unzip();
+        SuccinctArt::build(&keys, values)
+    }
+}
+
+impl<V, K: LeafKey> ArtTree<V, K> {
+    /// Compiles the entries into a [`SuccinctArt`], moving the values.
+    pub fn into_succinct(self) -> SuccinctArt<V> {
+        let (keys, values): (Vec<K>, Vec<V>) = self.into_iter().unzip();
+        SuccinctArt::build(&keys, values)
+    }
+}
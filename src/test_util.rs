@@ -0,0 +1,282 @@
+//! Differential testing of `ArtTree` against a `BTreeMap`, enabled by the `test-util` feature.
+//!
+//! Operations are recorded into an [`OpLog`], either by running them through a [`Recorder`] or
+//! by generating them deterministically from a seed. A log can be replayed against a fresh tree
+//! and a reference map, and a failing log can be minimized to a few operations that still
+//! reproduce the mismatch.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::art::ArtTree;
+
+/// An operation applied to both the tree and the reference map.
+///
+/// Keys must be prefix-free, as for `ArtTree` itself. Generated logs use 8-byte keys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op {
+    Insert(Vec<u8>, u64),
+    Get(Vec<u8>),
+    Delete(Vec<u8>),
+    PopFirst,
+    PopLast,
+    /// Entries with keys in `start..end`
+    Range(Vec<u8>, Vec<u8>),
+}
+
+/// The result of an operation, compared between the tree and the reference map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Value(Option<u64>),
+    Entry(Option<(Vec<u8>, u64)>),
+    Entries(Vec<(Vec<u8>, u64)>),
+}
+
+/// The first point at which the tree and the reference map disagree during a replay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// Index of the operation in the log
+    pub index: usize,
+    pub op: Op,
+    /// What the reference map returned, or the state it was left in
+    pub expected: String,
+    /// What the tree returned, or the state it was left in
+    pub actual: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "operation {} ({:?}): expected {}, got {}",
+            self.index, self.op, self.expected, self.actual
+        )
+    }
+}
+
+/// A sequence of operations that can be replayed deterministically.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpLog {
+    pub ops: Vec<Op>,
+}
+
+impl OpLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generates `len` random operations from the given seed. The same seed always yields the
+    /// same log.
+    ///
+    /// Keys are drawn mostly from a small key space, so that lookups and deletes hit existing
+    /// keys, and otherwise from all 8-byte keys.
+    pub fn generate(seed: u64, len: usize) -> Self {
+        let mut rng = SplitMix64(seed);
+        fn key(rng: &mut SplitMix64) -> Vec<u8> {
+            let key = match rng.next() % 4 {
+                0 => rng.next(),
+                _ => rng.next() % 1024,
+            };
+            key.to_be_bytes().to_vec()
+        }
+
+        let ops = (0..len)
+            .map(|_| match rng.next() % 16 {
+                0..=6 => Op::Insert(key(&mut rng), rng.next()),
+                7..=9 => Op::Get(key(&mut rng)),
+                10..=12 => Op::Delete(key(&mut rng)),
+                13 => Op::PopFirst,
+                14 => Op::PopLast,
+                _ => {
+                    let mut bounds = [key(&mut rng), key(&mut rng)];
+                    bounds.sort();
+                    let [start, end] = bounds;
+                    Op::Range(start, end)
+                }
+            })
+            .collect();
+        OpLog { ops }
+    }
+
+    pub fn push(&mut self, op: Op) {
+        self.ops.push(op);
+    }
+
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Applies the operations to a fresh tree and a fresh `BTreeMap`, comparing the outcome of
+    /// every operation and the length, minimum and maximum of both after it. A panic in the tree
+    /// is reported as a mismatch too.
+    pub fn replay(&self) -> Result<(), Mismatch> {
+        let mut tree = ArtTree::new();
+        let mut model = BTreeMap::new();
+        for (index, op) in self.ops.iter().enumerate() {
+            let mismatch = |expected: &dyn fmt::Debug, actual: &dyn fmt::Debug| Mismatch {
+                index,
+                op: op.clone(),
+                expected: format!("{:?}", expected),
+                actual: format!("{:?}", actual),
+            };
+
+            let expected = apply_model(&mut model, op);
+            let expected_state = (
+                model.len(),
+                model.iter().next().map(|(key, _)| key.clone()),
+                model.iter().next_back().map(|(key, _)| key.clone()),
+            );
+            let actual = panic::catch_unwind(AssertUnwindSafe(|| {
+                let outcome = apply(&mut tree, op);
+                let state = (
+                    tree.len(),
+                    tree.minimum().map(|(key, _)| key.to_vec()),
+                    tree.maximum().map(|(key, _)| key.to_vec()),
+                );
+                (outcome, state)
+            }));
+            match actual {
+                Ok((actual, _)) if actual != expected => return Err(mismatch(&expected, &actual)),
+                Ok((_, actual_state)) if actual_state != expected_state => {
+                    return Err(mismatch(&expected_state, &actual_state))
+                }
+                Ok(_) => {}
+                Err(payload) => {
+                    let message = payload
+                        .downcast_ref::<&str>()
+                        .copied()
+                        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                        .unwrap_or("non-string payload");
+                    return Err(mismatch(&expected, &format_args!("panic: {}", message)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a smaller log that still fails to replay, or `None` if this log replays fine.
+    ///
+    /// Operations after the first mismatch are dropped, then ever smaller chunks of operations
+    /// are removed as long as the replay keeps failing.
+    pub fn minimize(&self) -> Option<OpLog> {
+        let index = self.replay().err()?.index;
+        let mut ops = self.ops[..=index].to_vec();
+
+        let mut chunk = ops.len() / 2;
+        while chunk > 0 {
+            let mut removed = false;
+            let mut start = 0;
+            while start < ops.len() {
+                let end = (start + chunk).min(ops.len());
+                let candidate = OpLog {
+                    ops: ops[..start].iter().chain(&ops[end..]).cloned().collect(),
+                };
+                if candidate.replay().is_err() {
+                    ops = candidate.ops;
+                    removed = true;
+                } else {
+                    start = end;
+                }
+            }
+            if !removed {
+                chunk /= 2;
+            }
+        }
+        Some(OpLog { ops })
+    }
+}
+
+/// An `ArtTree` that records every operation applied through it.
+#[derive(Debug, Clone, Default)]
+pub struct Recorder {
+    tree: ArtTree<u64>,
+    log: OpLog,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies the operation to the tree and appends it to the log
+    pub fn apply(&mut self, op: Op) -> Outcome {
+        let outcome = apply(&mut self.tree, &op);
+        self.log.push(op);
+        outcome
+    }
+
+    pub fn tree(&self) -> &ArtTree<u64> {
+        &self.tree
+    }
+
+    pub fn log(&self) -> &OpLog {
+        &self.log
+    }
+
+    pub fn into_log(self) -> OpLog {
+        self.log
+    }
+}
+
+fn apply(tree: &mut ArtTree<u64>, op: &Op) -> Outcome {
+    match op {
+        Op::Insert(key, value) => Outcome::Value(tree.insert(key, *value)),
+        Op::Get(key) => Outcome::Value(tree.get(key).copied()),
+        Op::Delete(key) => Outcome::Value(tree.delete(key)),
+        Op::PopFirst => Outcome::Entry(tree.pop_first().map(|(key, value)| (key.into(), value))),
+        Op::PopLast => Outcome::Entry(tree.pop_last().map(|(key, value)| (key.into(), value))),
+        Op::Range(start, end) => Outcome::Entries(
+            tree.range(&start[..]..&end[..])
+                .map(|(key, value)| (key.to_vec(), *value))
+                .collect(),
+        ),
+    }
+}
+
+fn apply_model(model: &mut BTreeMap<Vec<u8>, u64>, op: &Op) -> Outcome {
+    match op {
+        Op::Insert(key, value) => Outcome::Value(model.insert(key.clone(), *value)),
+        Op::Get(key) => Outcome::Value(model.get(key).copied()),
+        Op::Delete(key) => Outcome::Value(model.remove(key)),
+        Op::PopFirst => {
+            let key = model.keys().next().cloned();
+            Outcome::Entry(key.map(|key| {
+                let value = model.remove(&key).unwrap();
+                (key, value)
+            }))
+        }
+        Op::PopLast => {
+            let key = model.keys().next_back().cloned();
+            Outcome::Entry(key.map(|key| {
+                let value = model.remove(&key).unwrap();
+                (key, value)
+            }))
+        }
+        // `BTreeMap::range` panics on inverted ranges, which are empty
+        Op::Range(start, end) if start > end => Outcome::Entries(Vec::new()),
+        Op::Range(start, end) => Outcome::Entries(
+            model
+                .range(start.clone()..end.clone())
+                .map(|(key, value)| (key.clone(), *value))
+                .collect(),
+        ),
+    }
+}
+
+/// Small deterministic generator, so that logs do not depend on the `rand` version
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut x = self.0;
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        x ^ (x >> 31)
+    }
+}
@@ -0,0 +1,144 @@
+use crate::art::ArtTree;
+use crate::versioned_art_tree::GcStats;
+
+/// Map indexed by byte keys using an Adaptive Radix Tree, where deleted keys leave tombstones
+///
+/// A deleted key stays in the tree as a tombstone tagged with the version of the deletion, so
+/// that a replication layer can find and propagate deletes with
+/// [`iter_tombstones`](Self::iter_tombstones). Reads and [`iter`](Self::iter) skip tombstones,
+/// [`entries`](Self::entries) includes them. Tombstones are removed by inserting their key
+/// again or by [`purge_tombstones`](Self::purge_tombstones).
+#[derive(Clone, Debug)]
+pub struct TombstoneArtTree<V> {
+    /// `None` marks a tombstone
+    tree: ArtTree<(u64, Option<V>)>,
+    live: usize,
+    version: u64,
+}
+
+impl<V> TombstoneArtTree<V> {
+    pub fn new() -> Self {
+        Self {
+            tree: ArtTree::new(),
+            live: 0,
+            version: 0,
+        }
+    }
+
+    /// Returns the version of the latest mutation, or 0 if the tree was never modified.
+    pub fn current_version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the number of live keys
+    pub fn len(&self) -> usize {
+        self.live
+    }
+
+    /// Returns true if there are no live keys, there may be tombstones
+    pub fn is_empty(&self) -> bool {
+        self.live == 0
+    }
+
+    /// Returns the number of tombstones
+    pub fn tombstones(&self) -> usize {
+        self.tree.len() - self.live
+    }
+
+    /// Inserts the given value at the given key, replacing a tombstone, and returns the previous
+    /// live value.
+    pub fn insert(&mut self, key: &[u8], value: V) -> Option<V> {
+        self.version += 1;
+        let old = self.tree.insert(key, (self.version, Some(value)));
+        let old_value = old.and_then(|(_, value)| value);
+        if old_value.is_none() {
+            self.live += 1;
+        }
+        old_value
+    }
+
+    /// Replaces the value stored at the given key with a tombstone and returns it. Returns `None`
+    /// and leaves the tree unchanged if the key is not live.
+    pub fn delete(&mut self, key: &[u8]) -> Option<V> {
+        let (version, value) = self.tree.get_mut(key)?;
+        let value = value.take()?;
+        self.version += 1;
+        *version = self.version;
+        self.live -= 1;
+        Some(value)
+    }
+
+    /// Returns a reference to the live value stored at the given key
+    pub fn get(&self, key: &[u8]) -> Option<&V> {
+        self.tree.get(key).and_then(|(_, value)| value.as_ref())
+    }
+
+    pub fn get_mut(&mut self, key: &[u8]) -> Option<&mut V> {
+        self.tree.get_mut(key).and_then(|(_, value)| value.as_mut())
+    }
+
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns the version of the deletion if the key holds a tombstone
+    pub fn tombstone(&self, key: &[u8]) -> Option<u64> {
+        match self.tree.get(key) {
+            Some((version, None)) => Some(*version),
+            _ => None,
+        }
+    }
+
+    /// Returns an iterator over the live key-value pairs in ascending key order
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &V)> + '_ {
+        self.tree
+            .entries()
+            .filter_map(|(key, (_, value))| value.as_ref().map(|value| (key, value)))
+    }
+
+    /// Returns an iterator over the keys in ascending order with their live values, or `None`
+    /// for tombstones
+    pub fn entries(&self) -> impl Iterator<Item = (&[u8], Option<&V>)> + '_ {
+        self.tree
+            .entries()
+            .map(|(key, (_, value))| (key, value.as_ref()))
+    }
+
+    /// Returns an iterator over the keys holding tombstones in ascending order, with the
+    /// versions of their deletions
+    pub fn iter_tombstones(&self) -> impl Iterator<Item = (&[u8], u64)> + '_ {
+        self.tree
+            .entries()
+            .filter_map(|(key, (version, value))| match value {
+                Some(_) => None,
+                None => Some((key, *version)),
+            })
+    }
+
+    /// Removes every tombstone from the tree and returns the number of removed tombstones
+    pub fn purge_tombstones(&mut self) -> usize {
+        let before = self.tree.len();
+        self.tree.retain(|_, (_, value)| value.is_some());
+        before - self.tree.len()
+    }
+
+    /// Removes the tombstones of deletions up to version `horizon`, e.g. once every replica has
+    /// seen them, and returns the number of removed tombstones and the bytes they took. Later
+    /// tombstones are kept.
+    pub fn gc(&mut self, horizon: u64) -> GcStats {
+        let bytes_before = self.tree.memory_stats().total_bytes();
+        let before = self.tree.len();
+        self.tree
+            .retain(|_, (version, value)| value.is_some() || *version > horizon);
+        GcStats {
+            entries: before - self.tree.len(),
+            bytes: bytes_before - self.tree.memory_stats().total_bytes(),
+        }
+    }
+}
+
+impl<V> Default for TombstoneArtTree<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,188 @@
+use std::mem;
+
+use crate::art::ArtTree;
+
+/// Versions or tombstones discarded by a garbage collection pass, see [`VersionedArtTree::gc`]
+/// and [`TombstoneArtTree::gc`](crate::tombstone_art_tree::TombstoneArtTree::gc)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcStats {
+    /// Number of discarded versions or tombstones
+    pub entries: usize,
+    /// Estimated bytes freed, not counting memory owned by the values
+    pub bytes: usize,
+}
+
+/// Multi-version map indexed by byte keys using an Adaptive Radix Tree
+///
+/// Every `insert` and `delete` is tagged with a new, monotonically increasing version number.
+/// Reads can be made as of any version that has not been garbage collected with `gc`, which makes
+/// the tree usable as the storage layer of snapshot-isolated transactions.
+#[derive(Clone, Debug)]
+pub struct VersionedArtTree<V> {
+    /// Per-key history in ascending version order. `None` marks a deletion.
+    tree: ArtTree<Vec<(u64, Option<V>)>>,
+    version: u64,
+    /// Maximum number of versions kept per key, see `with_history_limit`
+    history_limit: Option<usize>,
+}
+
+impl<V> VersionedArtTree<V> {
+    pub fn new() -> Self {
+        Self {
+            tree: ArtTree::new(),
+            version: 0,
+            history_limit: None,
+        }
+    }
+
+    /// Creates a tree keeping only the latest `limit` versions of every key, deletions included.
+    /// Older versions are discarded as new ones are written, so reads at versions before the
+    /// retained ones are no longer accurate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit` is zero.
+    pub fn with_history_limit(limit: usize) -> Self {
+        assert!(
+            limit > 0,
+            "the history of a key has to keep at least one version"
+        );
+        Self {
+            history_limit: Some(limit),
+            ..Self::new()
+        }
+    }
+
+    /// Returns the version of the latest mutation, or 0 if the tree was never modified.
+    pub fn current_version(&self) -> u64 {
+        self.version
+    }
+
+    /// Inserts the given value at the given key and returns the version the write was tagged with.
+    pub fn insert(&mut self, key: &[u8], value: V) -> u64 {
+        self.version += 1;
+        let entry = (self.version, Some(value));
+        match self.tree.get_mut(key) {
+            Some(history) => {
+                history.push(entry);
+                prune(history, self.history_limit);
+            }
+            None => {
+                self.tree.insert(key, vec![entry]);
+            }
+        }
+        self.version
+    }
+
+    /// Deletes the value stored at the given key and returns the version the deletion was tagged
+    /// with, or `None` if the key is not present in the latest version.
+    pub fn delete(&mut self, key: &[u8]) -> Option<u64> {
+        let history = self.tree.get_mut(key)?;
+        if let Some((_, None)) | None = history.last() {
+            return None;
+        }
+
+        self.version += 1;
+        history.push((self.version, None));
+        prune(history, self.history_limit);
+        Some(self.version)
+    }
+
+    /// Returns a reference to the latest value stored at the given key.
+    pub fn get(&self, key: &[u8]) -> Option<&V> {
+        self.get_at(key, self.version)
+    }
+
+    /// Returns a reference to the value stored at the given key as of the given version.
+    pub fn get_at(&self, key: &[u8], version: u64) -> Option<&V> {
+        self.tree
+            .get(key)
+            .and_then(|history| visible_at(history, version))
+    }
+
+    /// Returns the `n`th latest version of the key, counting from 0 for the latest, with the
+    /// value written by it or `None` for a deletion. Returns `None` if the key has fewer
+    /// retained versions.
+    pub fn get_version(&self, key: &[u8], n: usize) -> Option<(u64, Option<&V>)> {
+        let history = self.tree.get(key)?;
+        let idx = history.len().checked_sub(n + 1)?;
+        let (version, value) = &history[idx];
+        Some((*version, value.as_ref()))
+    }
+
+    /// Returns the retained versions of the key in ascending version order, with the values
+    /// written by them or `None` for deletions.
+    pub fn history(&self, key: &[u8]) -> impl Iterator<Item = (u64, Option<&V>)> + '_ {
+        self.tree
+            .get(key)
+            .into_iter()
+            .flatten()
+            .map(|(version, value)| (*version, value.as_ref()))
+    }
+
+    /// Returns an iterator over the key-value pairs visible at the given version, in ascending key
+    /// order.
+    pub fn iter_at(&self, version: u64) -> impl Iterator<Item = (&[u8], &V)> + '_ {
+        self.tree
+            .entries()
+            .filter_map(move |(key, history)| visible_at(history, version).map(|v| (key, v)))
+    }
+
+    /// Discards the history that is not needed to read at `before_version` or any later version,
+    /// and returns the number of discarded versions and the bytes they took. Reads at earlier
+    /// versions are no longer accurate afterwards.
+    ///
+    /// Keys whose whole history is discarded are removed from the tree, shrinking the nodes that
+    /// held them.
+    pub fn gc(&mut self, before_version: u64) -> GcStats {
+        let bytes_before = self.allocated_bytes();
+        let mut removed = 0;
+        self.tree.retain_mut(|_, history| {
+            // The newest version at or before the horizon is still visible at the horizon and has
+            // to be kept, unless it is a deletion.
+            let mut first_kept = history.partition_point(|(v, _)| *v <= before_version);
+            if first_kept > 0 && history[first_kept - 1].1.is_some() {
+                first_kept -= 1;
+            }
+            if first_kept > 0 {
+                history.drain(..first_kept);
+                history.shrink_to_fit();
+                removed += first_kept;
+            }
+            !history.is_empty()
+        });
+        GcStats {
+            entries: removed,
+            bytes: bytes_before - self.allocated_bytes(),
+        }
+    }
+
+    /// Returns the bytes allocated for the tree and the histories, not counting memory owned by
+    /// the values
+    fn allocated_bytes(&self) -> usize {
+        let histories: usize = self
+            .tree
+            .entries()
+            .map(|(_, history)| history.capacity())
+            .sum();
+        self.tree.memory_stats().total_bytes() + histories * mem::size_of::<(u64, Option<V>)>()
+    }
+}
+
+impl<V> Default for VersionedArtTree<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Discards the oldest versions of the history beyond the limit
+fn prune<V>(history: &mut Vec<(u64, Option<V>)>, limit: Option<usize>) {
+    if let Some(excess) = limit.and_then(|limit| history.len().checked_sub(limit)) {
+        history.drain(..excess);
+    }
+}
+
+fn visible_at<V>(history: &[(u64, Option<V>)], version: u64) -> Option<&V> {
+    let idx = history.partition_point(|(v, _)| *v <= version);
+    history[..idx].last().and_then(|(_, value)| value.as_ref())
+}
@@ -61,7 +61,7 @@ fn art_minmax_with_four_unique_elements_works() {
 }
 
 fn insert_kv<V>(data: &mut ArtTree<V>, key_list: [u8; 4], value: V) -> Option<V> {
-    return data.insert(&key_list, value);
+    data.insert(&key_list, value)
 }
 
 #[test]
@@ -104,7 +104,9 @@ fn art_iterator_works() {
 fn art_delete_works() {
     let mut ds = ArtTree::new();
 
-    let edge_cases = vec![1, 3, 4, 5, 15, 16, 17, 47, 48, 49, 255, 256, 257, 3000];
+    let edge_cases = vec![
+        1, 3, 4, 5, 15, 16, 17, 31, 32, 33, 47, 48, 49, 255, 256, 257, 3000,
+    ];
 
     for case in edge_cases {
         for _ in 0..3 {
@@ -231,3 +233,2963 @@ fn art_pop_last_twice_works() {
 fn kv_pair_eq(left: (Box<[u8]>, u32), right: (&[u8], u32)) -> bool {
     left.1 == right.1 && left.0.iter().zip(right.0).all(|(k1, k2)| *k1 == *k2)
 }
+
+#[cfg(feature = "bytes")]
+#[test]
+fn art_bytes_keys_share_the_source_buffer() {
+    use bytes::Bytes;
+
+    let frame = Bytes::from_static(&[9, 9, 1, 2, 3, 9, 1, 2, 4, 9]);
+    let mut ds = ArtTree::<u32, Bytes>::default();
+
+    assert!(ds.insert_key(frame.slice(2..5), 17).is_none());
+    assert!(ds.insert_key(frame.slice(6..9), 18).is_none());
+    assert!(ds.insert(&[1, 3, 3], 19).is_none());
+    assert_eq!(ds.insert_key(frame.slice(2..5), 20), Some(17));
+
+    assert_eq!(*ds.get_mut(&frame.slice(6..9)).unwrap(), 18);
+    let (min_key, min_value) = ds.minimum().unwrap();
+    assert_eq!(min_key.as_ptr(), frame[2..].as_ptr());
+    assert_eq!(*min_value, 20);
+
+    let (max_key, max_value) = ds.pop_last().unwrap();
+    assert_eq!(max_key, Bytes::from_static(&[1, 3, 3]));
+    assert_eq!(max_value, 19);
+}
+
+#[test]
+fn art_entries_are_sorted_and_match_get() {
+    let mut ds = ArtTree::<u32>::new();
+    let mut expected = std::collections::BTreeMap::new();
+    for i in 0..3000u32 {
+        let key = *make_interesting_key(i * 7);
+        ds.insert(&key, i);
+        expected.insert(key.to_vec(), i);
+    }
+
+    let entries: Vec<_> = ds.entries().map(|(k, v)| (k.to_vec(), *v)).collect();
+    let expected: Vec<_> = expected.into_iter().collect();
+    assert_eq!(ds.entries().len(), expected.len());
+    assert_eq!(entries, expected);
+
+    for (key, value) in &expected {
+        assert_eq!(ds.get(key), Some(value));
+    }
+    assert!(ds.get(&[255, 255, 255, 255]).is_none());
+}
+
+#[test]
+fn art_range_and_floor_match_btree() {
+    use rand::Rng;
+    use std::ops::Bound;
+
+    let mut rng = rand::thread_rng();
+    let mut ds = ArtTree::<u32>::new();
+    let mut expected = std::collections::BTreeMap::new();
+    for i in 0..2000u32 {
+        let key = *make_interesting_key(rng.gen_range(0..100_000));
+        ds.insert(&key, i);
+        expected.insert(key.to_vec(), i);
+    }
+
+    for _ in 0..500 {
+        let mut bound = || -> Vec<u8> {
+            let len = rng.gen_range(0..=5);
+            (0..len).map(|_| rng.gen_range(0..60)).collect()
+        };
+        let (start, end) = (bound(), bound());
+        if start > end {
+            continue;
+        }
+
+        let actual: Vec<_> = ds
+            .range(start.as_slice()..end.as_slice())
+            .map(|(k, v)| (k.to_vec(), *v))
+            .collect();
+        let wanted: Vec<_> = expected
+            .range(start.clone()..end.clone())
+            .map(|(k, v)| (k.clone(), *v))
+            .collect();
+        assert_eq!(actual, wanted, "range {:?}..{:?}", start, end);
+
+        let actual: Vec<_> = ds
+            .range((
+                Bound::Excluded(start.as_slice()),
+                Bound::Included(end.as_slice()),
+            ))
+            .map(|(k, _)| k.to_vec())
+            .collect();
+        let wanted: Vec<_> = expected
+            .range((Bound::Excluded(start.clone()), Bound::Included(end.clone())))
+            .map(|(k, _)| k.clone())
+            .collect();
+        assert_eq!(actual, wanted, "range ({:?}, {:?}]", start, end);
+
+        assert_eq!(
+            ds.floor(&start).map(|(k, v)| (k.to_vec(), *v)),
+            expected
+                .range(..=start.clone())
+                .next_back()
+                .map(|(k, v)| (k.clone(), *v)),
+            "floor {:?}",
+            start
+        );
+    }
+}
+
+#[test]
+fn art_split_at_first_prefix_byte_keeps_old_keys() {
+    let mut ds = ArtTree::<u32>::new();
+    ds.insert(&[1, 2, 3, 4], 1);
+    ds.insert(&[1, 2, 3, 5], 2);
+    ds.insert(&[7, 2, 3, 4], 3);
+
+    assert_eq!(ds.get(&[1, 2, 3, 4]), Some(&1));
+    assert_eq!(ds.get(&[1, 2, 3, 5]), Some(&2));
+    assert_eq!(ds.get(&[7, 2, 3, 4]), Some(&3));
+}
+
+#[test]
+fn art_increment_counts_keys() {
+    let mut ds = ArtTree::<u64>::new();
+    for i in 0..1000u32 {
+        let key = make_interesting_key(i);
+        ds.increment(key.as_ref(), 2);
+    }
+
+    let mut expected = std::collections::BTreeMap::new();
+    for i in 0..1000u32 {
+        *expected
+            .entry(make_interesting_key(i).to_vec())
+            .or_insert(0) += 2;
+    }
+    let counts: Vec<_> = ds.entries().map(|(k, v)| (k.to_vec(), *v)).collect();
+    assert_eq!(counts, expected.into_iter().collect::<Vec<_>>());
+
+    assert_eq!(ds.increment(&[0, 0, 0, 0], 5), 7);
+    assert_eq!(ds.increment(&[255, 0, 0, 0], 5), 5);
+}
+
+#[test]
+fn art_increment_clones_the_old_value_only_for_observers() {
+    use std::cell::Cell;
+    use std::ops::Add;
+    use std::sync::Arc;
+
+    thread_local! {
+        static CLONES: Cell<usize> = const { Cell::new(0) };
+    }
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Counted(u64);
+
+    impl Clone for Counted {
+        fn clone(&self) -> Self {
+            CLONES.with(|clones| clones.set(clones.get() + 1));
+            Counted(self.0)
+        }
+    }
+
+    impl Add for Counted {
+        type Output = Counted;
+
+        fn add(self, other: Counted) -> Counted {
+            Counted(self.0 + other.0)
+        }
+    }
+
+    struct Quiet;
+
+    impl MutationObserver<Counted> for Quiet {}
+
+    let mut ds = ArtTree::new();
+    ds.increment(b"k", Counted(1));
+    CLONES.with(|clones| clones.set(0));
+    // Only the returned value is cloned
+    assert_eq!(ds.increment(b"k", Counted(2)), Counted(3));
+    assert_eq!(CLONES.with(Cell::get), 1);
+
+    ds.add_observer(Arc::new(Quiet));
+    assert_eq!(ds.increment(b"k", Counted(4)), Counted(7));
+    assert_eq!(CLONES.with(Cell::get), 3);
+}
+
+#[test]
+fn art_get_or_insert_with_only_inserts_missing_keys() {
+    let mut ds = ArtTree::<String>::new();
+    ds.get_or_insert_with(&[1, 2, 3], || "first".to_string());
+    ds.get_or_insert_with(&[1, 2, 3], || unreachable!())
+        .push('!');
+    ds.get_or_insert_with(&[1, 2, 4], || "second".to_string());
+
+    assert_eq!(ds.get(&[1, 2, 3]).unwrap(), "first!");
+    assert_eq!(ds.get(&[1, 2, 4]).unwrap(), "second");
+    assert_eq!(ds.entries().len(), 2);
+}
+
+#[test]
+fn art_merge_folds_operands() {
+    let mut ds = ArtTree::<Vec<u32>>::with_merge_operator(|_key, current, mut operand| {
+        let mut merged = current.unwrap_or_default();
+        merged.append(&mut operand);
+        merged
+    });
+    ds.merge(&[1, 2, 3], vec![1]);
+    ds.merge(&[1, 2, 4], vec![2]);
+    ds.merge(&[1, 2, 3], vec![3, 4]);
+
+    assert_eq!(ds.get(&[1, 2, 3]), Some(&vec![1, 3, 4]));
+    assert_eq!(ds.get(&[1, 2, 4]), Some(&vec![2]));
+    assert_eq!(ds.entries().len(), 2);
+
+    let mut plain = ArtTree::<u32>::new();
+    plain.merge(&[1], DUMMY_VALUE);
+    plain.merge(&[1], DUMMY_VALUE_2);
+    assert_eq!(plain.get(&[1]), Some(&DUMMY_VALUE_2));
+}
+
+#[test]
+fn art_merge_reports_one_update_to_observers() {
+    use std::sync::Arc;
+
+    let journal = Arc::new(Journal::new());
+    let mut ds = ArtTree::<u32>::with_merge_operator(|_key, current, operand| {
+        current.unwrap_or(0) + operand
+    });
+    ds.add_observer(journal.clone());
+    ds.merge(b"k", 1);
+    ds.merge(b"k", 2);
+
+    let ops: Vec<_> = journal
+        .journal_since(0)
+        .map(|entry| (entry.op, entry.value))
+        .collect();
+    assert_eq!(
+        ops,
+        vec![(JournalOp::Insert, Some(1)), (JournalOp::Replace, Some(3))]
+    );
+}
+
+#[test]
+fn art_merge_removes_the_key_when_the_operator_panics() {
+    use std::cell::Cell;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::rc::Rc;
+
+    struct Tracked(u32, Rc<Cell<usize>>);
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            self.1.set(self.1.get() + 1);
+        }
+    }
+
+    let drops = Rc::new(Cell::new(0));
+    let mut ds = ArtTree::with_merge_operator(|_key, current: Option<Tracked>, operand| {
+        assert!(operand.0 != 0, "zero operand");
+        Tracked(current.map_or(0, |c| c.0) + operand.0, operand.1.clone())
+    });
+    for key in 0..20u8 {
+        ds.merge(&[key, 0], Tracked(1, drops.clone()));
+    }
+    drops.set(0);
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        ds.merge(&[7, 0], Tracked(0, drops.clone()));
+    }));
+    assert!(result.is_err());
+    // The old value and the operand were dropped once each while unwinding
+    assert_eq!(drops.get(), 2);
+    assert_eq!(ds.len(), 19);
+    assert!(ds.get(&[7, 0]).is_none());
+    assert_eq!(ds.get(&[8, 0]).map(|v| v.0), Some(1));
+    assert_eq!(ds.check_invariants(), Ok(()));
+    drop(ds);
+    assert_eq!(drops.get(), 21);
+}
+
+#[test]
+fn art_insert_with_policy_handles_present_keys() {
+    let mut ds = ArtTree::<u32>::new();
+    assert_eq!(
+        ds.insert_with_policy(&[1], 10, DuplicatePolicy::Error),
+        Ok(InsertOutcome::Inserted)
+    );
+    assert_eq!(
+        ds.insert_with_policy(&[1], 11, DuplicatePolicy::Error),
+        Err(InsertError::DuplicateKey(11))
+    );
+    assert_eq!(
+        ds.insert_with_policy(&[1], 12, DuplicatePolicy::KeepExisting),
+        Ok(InsertOutcome::Kept(12))
+    );
+    assert_eq!(ds.get(&[1]), Some(&10));
+    assert_eq!(
+        ds.insert_with_policy(&[1], 13, DuplicatePolicy::Replace),
+        Ok(InsertOutcome::Replaced(10))
+    );
+    assert_eq!(
+        ds.insert_with_policy(&[1], 2, DuplicatePolicy::MergeWith(|_, a, b| a + b)),
+        Ok(InsertOutcome::Merged)
+    );
+    assert_eq!(ds.get(&[1]), Some(&15));
+    assert_eq!(ds.len(), 1);
+
+    // The policy of the tree applies to try_insert only
+    ds.set_duplicate_policy(DuplicatePolicy::Error);
+    assert_eq!(ds.try_insert(&[1], 16), Err(InsertError::DuplicateKey(16)));
+    assert_eq!(ds.try_insert(&[2], 20), Ok(InsertOutcome::Inserted));
+    assert_eq!(ds.insert(&[1], 17), Some(15));
+    let clone = ds.clone();
+    assert!(matches!(clone.duplicate_policy(), DuplicatePolicy::Error));
+    assert_eq!(ds.len(), 2);
+}
+
+#[test]
+fn art_max_key_len_rejects_long_keys() {
+    let mut ds = ArtTree::<u32>::with_max_key_len(4);
+    assert_eq!(ds.max_key_len(), 4);
+    assert_eq!(ds.try_insert(b"four", 1), Ok(InsertOutcome::Inserted));
+    assert_eq!(ds.try_insert(b"fives", 2), Err(InsertError::KeyTooLong(2)));
+    assert_eq!(
+        ds.insert_with_policy(b"fives", 3, DuplicatePolicy::Replace),
+        Err(InsertError::KeyTooLong(3))
+    );
+    assert_eq!(
+        ds.try_insert_alloc(b"fives", 4),
+        Err(InsertError::KeyTooLong(4))
+    );
+    assert_eq!(ds.len(), 1);
+
+    // Lowering the limit keeps the keys in the tree
+    ds.set_max_key_len(2);
+    assert_eq!(ds.get(b"four"), Some(&1));
+    assert_eq!(ds.delete(b"four"), Some(1));
+    assert_eq!(ds.clone().max_key_len(), 2);
+}
+
+#[test]
+#[should_panic(expected = "the key of 5 bytes is longer than the maximum of 4")]
+fn art_max_key_len_panics_on_insert() {
+    let mut ds = ArtTree::<u32>::with_max_key_len(4);
+    ds.insert(b"fives", 1);
+}
+
+#[test]
+fn art_max_key_len_keeps_entries_on_failed_rekey() {
+    let mut ds = ArtTree::<u32>::with_max_key_len(4);
+    ds.insert(b"key", 1);
+    let rekey =
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| ds.rekey(b"key", b"fives")));
+    assert!(rekey.is_err());
+    assert_eq!(ds.get(b"key"), Some(&1));
+}
+
+#[test]
+fn art_observer_sees_inserts_replaces_and_deletes() {
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Log(Mutex<Vec<String>>);
+
+    impl MutationObserver<u32> for Log {
+        fn on_insert(&self, key: &[u8], value: &u32) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("insert {:?} {}", key, value));
+        }
+
+        fn on_replace(&self, key: &[u8], old_value: &u32, new_value: &u32) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("replace {:?} {} {}", key, old_value, new_value));
+        }
+
+        fn on_delete(&self, key: &[u8], value: &u32) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("delete {:?} {}", key, value));
+        }
+    }
+
+    let log = Arc::new(Log::default());
+    let mut ds = ArtTree::<u32>::new();
+    ds.add_observer(log.clone());
+
+    ds.insert(&[1, 2], DUMMY_VALUE);
+    ds.insert(&[1, 2], DUMMY_VALUE_2);
+    ds.increment(&[1, 3], 1);
+    ds.increment(&[1, 3], 1);
+    ds.get_or_insert_with(&[1, 2], || unreachable!());
+    ds.delete(&[1, 4]);
+    ds.delete(&[1, 2]);
+    ds.pop_first();
+
+    assert_eq!(
+        *log.0.lock().unwrap(),
+        vec![
+            "insert [1, 2] 17",
+            "replace [1, 2] 17 18",
+            "insert [1, 3] 1",
+            "replace [1, 3] 1 2",
+            "delete [1, 2] 18",
+            "delete [1, 3] 2",
+        ]
+    );
+}
+
+#[test]
+fn art_journal_replays_mutations_on_a_follower() {
+    use std::sync::Arc;
+
+    let journal = Arc::new(Journal::new());
+    let mut leader = ArtTree::<u32>::new();
+    leader.add_observer(journal.clone());
+    let mut follower = ArtTree::<u32>::new();
+
+    leader.insert(&[1, 2], DUMMY_VALUE);
+    leader.insert(&[1, 2], DUMMY_VALUE_2);
+    leader.insert(&[1, 3], 7);
+    leader.delete(&[1, 4]);
+    let entries: Vec<_> = journal.journal_since(0).collect();
+    assert_eq!(
+        entries
+            .iter()
+            .map(|entry| (entry.seq, entry.op, entry.value))
+            .collect::<Vec<_>>(),
+        vec![
+            (1, JournalOp::Insert, Some(DUMMY_VALUE)),
+            (2, JournalOp::Replace, Some(DUMMY_VALUE_2)),
+            (3, JournalOp::Insert, Some(7)),
+        ]
+    );
+    for entry in &entries {
+        entry.apply_to(&mut follower);
+    }
+    let mut applied = journal.last_seq();
+
+    for i in 0..100u8 {
+        leader.insert(&[2, i], i as u32);
+    }
+    leader.delete(&[1, 2]);
+    leader.drain_range(&[2, 10][..]..&[2, 20][..]);
+    assert_eq!(journal.truncate_journal(applied), 3);
+    for entry in journal.journal_since(applied) {
+        assert!(entry.seq > applied);
+        entry.apply_to(&mut follower);
+        applied = entry.seq;
+    }
+    assert_eq!(applied, 3 + 100 + 1 + 10);
+    assert_eq!(journal.len(), 111);
+    assert!(follower.entries().eq(leader.entries()));
+    assert_eq!(journal.journal_since(applied).count(), 0);
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn art_metrics_count_operations() {
+    let mut ds = ArtTree::<u32>::new();
+    for i in 0..17u8 {
+        ds.insert(&[1, 2, i], DUMMY_VALUE);
+    }
+    ds.insert(&[1, 3, 0], DUMMY_VALUE);
+    assert_eq!(ds.get(&[1, 2, 0]), Some(&DUMMY_VALUE));
+    assert_eq!(ds.get(&[1, 2, 200]), None);
+    for i in 0..14u8 {
+        ds.delete(&[1, 2, i]);
+    }
+
+    let metrics = ds.metrics();
+    assert_eq!(metrics.inserts, 18);
+    assert_eq!(metrics.lookups, 2);
+    // Node4 -> Node16 -> Node32, and back down to Node4 through Node16
+    assert_eq!(metrics.node_upgrades, 2);
+    assert_eq!(metrics.node_downgrades, 2);
+    assert_eq!(metrics.prefix_splits, 1);
+    assert_eq!(metrics.lookup_depth_total, 5);
+    assert_eq!(metrics.average_lookup_depth(), 2.5);
+}
+
+#[cfg(feature = "profile")]
+#[test]
+fn art_profile_attributes_work_to_operations() {
+    let mut ds = ArtTree::<u32>::new();
+    take_profile();
+    ds.insert(&[1, 2, 3], DUMMY_VALUE);
+    let (_, work) = profile(|| ds.insert(&[1, 2, 4], DUMMY_VALUE));
+    // The root leaf is split into a Node4 with the prefix [1, 2]
+    assert_eq!(work.inserts, 1);
+    assert_eq!(work.nodes_visited, 1);
+    assert_eq!(work.node_conversions, 0);
+
+    let (value, work) = profile(|| ds.get(&[1, 2, 4]).copied());
+    assert_eq!(value, Some(DUMMY_VALUE));
+    assert_eq!(work.lookups, 1);
+    assert_eq!(work.nodes_visited, 2);
+    assert_eq!(work.prefix_bytes_compared, 2);
+    assert_eq!(work.average_depth(), 2.0);
+
+    let (_, work) = profile(|| {
+        for i in 5..8 {
+            ds.insert(&[1, 2, i], DUMMY_VALUE);
+        }
+    });
+    // Every new child is added to the Node4 directly, the fifth one grows it into a Node16
+    assert_eq!(work.inserts, 3);
+    assert_eq!(work.nodes_visited, 3);
+    assert_eq!(work.prefix_bytes_compared, 6);
+    assert_eq!(work.node_conversions, 1);
+    assert_eq!(work.leaf_peeks, 0);
+
+    // Nested profiles also count towards the enclosing one
+    let (_, outer) = profile(|| {
+        let (_, inner) = profile(|| ds.get(&[1, 3, 0]));
+        assert_eq!(inner.prefix_bytes_compared, 2);
+        ds.get(&[1, 2, 5]);
+    });
+    assert_eq!(outer.lookups, 2);
+    // The thread's profile kept accumulating around the calls to `profile`
+    let total = take_profile();
+    assert_eq!((total.lookups, total.inserts), (3, 5));
+}
+
+#[test]
+fn art_explain_traces_the_descent() {
+    let mut ds = ArtTree::<u32>::new();
+    assert_eq!(ds.explain(b"a").end, ExplainEnd::EmptyTree);
+    for i in 0..5u8 {
+        ds.insert(&[1, 2, 3, i, 0], DUMMY_VALUE);
+    }
+    ds.insert(&[1, 2, 3, 0, 1], DUMMY_VALUE);
+
+    let trace = ds.explain(&[1, 2, 3, 0, 1]);
+    assert!(trace.found());
+    assert_eq!(
+        trace.steps,
+        [
+            ExplainStep {
+                kind: NodeKind::Node16,
+                depth: 0,
+                num_children: 5,
+                prefix_len: 3,
+                matched_prefix: vec![1, 2, 3],
+                child_byte: Some(0),
+            },
+            ExplainStep {
+                kind: NodeKind::Node4,
+                depth: 4,
+                num_children: 2,
+                prefix_len: 0,
+                matched_prefix: vec![],
+                child_byte: Some(1),
+            },
+        ]
+    );
+
+    let trace = ds.explain(&[1, 2, 7, 0]);
+    assert_eq!(trace.steps[0].matched_prefix, [1, 2]);
+    assert_eq!(trace.steps[0].child_byte, None);
+    assert_eq!(
+        trace.end,
+        ExplainEnd::PrefixMismatch {
+            offset: 2,
+            expected: 3,
+            found: Some(7),
+        }
+    );
+    assert_eq!(
+        ds.explain(&[1, 2]).end,
+        ExplainEnd::PrefixMismatch {
+            offset: 2,
+            expected: 3,
+            found: None,
+        }
+    );
+    assert_eq!(ds.explain(&[1, 2, 3]).end, ExplainEnd::KeyExhausted);
+    assert_eq!(ds.explain(&[1, 2, 3, 9]).end, ExplainEnd::NoChild(9));
+    assert_eq!(
+        ds.explain(&[1, 2, 3, 4, 7]).end,
+        ExplainEnd::LeafMismatch {
+            leaf_key: vec![1, 2, 3, 4, 0],
+            offset: 4,
+        }
+    );
+    assert_eq!(
+        ds.explain(&[1, 2, 3, 9]).to_string(),
+        "Node16 with 5 children at depth 0, matched prefix [1, 2, 3] of 3 bytes\n\
+         not found: no child for 0x09"
+    );
+}
+
+#[test]
+fn art_entries_mut_visits_every_node_type_in_order() {
+    let mut ds = ArtTree::<u32>::new();
+    let mut expected = Vec::new();
+    // 5, 17, 33, 49 and 256 children exercise each node type
+    for (prefix, count) in [(0u8, 5u32), (1, 17), (2, 33), (3, 49), (4, 256)].iter() {
+        for i in (0..*count).rev() {
+            ds.insert(&[*prefix, i as u8], i);
+        }
+        expected.extend((0..*count).map(|i| vec![*prefix, i as u8]));
+    }
+
+    for (key, value) in ds.entries_mut() {
+        *value = key[0] as u32 * 1000 + key[1] as u32;
+    }
+    let keys: Vec<_> = ds.entries().map(|(k, _)| k.to_vec()).collect();
+    assert_eq!(keys, expected);
+    assert!(ds
+        .entries()
+        .all(|(k, v)| *v == k[0] as u32 * 1000 + k[1] as u32));
+    assert_eq!(ds.entries_mut().len(), expected.len());
+}
+
+#[test]
+fn art_entry_api() {
+    let mut ds = ArtTree::<u32>::new();
+    *ds.entry(&[1, 2, 3]).or_insert(DUMMY_VALUE) += 1;
+    *ds.entry(&[1, 2, 3]).or_insert(DUMMY_VALUE) += 1;
+    ds.entry(&[1, 2, 4]).or_default();
+
+    assert_eq!(ds.get(&[1, 2, 3]), Some(&(DUMMY_VALUE + 2)));
+    assert_eq!(ds.get(&[1, 2, 4]), Some(&0));
+
+    match ds.entry(&[1, 2, 3]) {
+        Entry::Occupied(mut entry) => {
+            assert_eq!(entry.key(), &[1, 2, 3]);
+            assert_eq!(entry.insert(DUMMY_VALUE_2), DUMMY_VALUE + 2);
+            assert_eq!(*entry.get(), DUMMY_VALUE_2);
+            assert_eq!(entry.remove(), DUMMY_VALUE_2);
+        }
+        Entry::Vacant(_) => panic!("key should be occupied"),
+    }
+    assert!(matches!(ds.entry(&[1, 2, 3]), Entry::Vacant(_)));
+    assert_eq!(ds.len(), 1);
+}
+
+#[test]
+fn art_into_iter_moves_entries_out_in_order() {
+    let mut ds = ArtTree::<String>::new();
+    let mut expected = Vec::new();
+    for i in 0..2000u32 {
+        let key = make_interesting_key(i * 7919);
+        ds.insert(key.as_ref(), i.to_string());
+        expected.push((key.to_vec(), i.to_string()));
+    }
+    expected.sort();
+    expected.dedup_by(|a, b| a.0 == b.0);
+
+    let entries: Vec<_> = ds.entries().map(|(k, v)| (k.to_vec(), v.clone())).collect();
+    let owned: Vec<_> = ds.into_iter().map(|(k, v)| (k.to_vec(), v)).collect();
+    assert_eq!(owned, entries);
+    assert_eq!(owned.len(), expected.len());
+}
+
+#[test]
+fn art_to_vec_and_into_sorted_vec() {
+    let mut ds = ArtTree::<u32>::new();
+    for i in (0..300u32).rev() {
+        ds.insert(&(i * 13).to_be_bytes(), i);
+    }
+
+    let borrowed = ds.to_vec();
+    assert_eq!(borrowed.len(), 300);
+    assert!(borrowed.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    assert_eq!(&*borrowed[1].0, &13u32.to_be_bytes());
+    assert_eq!(ds.into_sorted_vec(), borrowed);
+}
+
+#[test]
+fn art_set_operations_match_btree() {
+    use rand::Rng;
+    use std::collections::BTreeMap;
+
+    let mut rng = rand::thread_rng();
+    let mut make = |count: usize, spread: u32| {
+        let mut tree = ArtTree::<u32>::new();
+        let mut btree = BTreeMap::new();
+        for _ in 0..count {
+            let key = rng.gen_range(0..spread).to_be_bytes();
+            let value = rng.gen::<u32>() >> 2;
+            tree.insert(&key, value);
+            btree.insert(key.to_vec(), value);
+        }
+        (tree, btree)
+    };
+    let (a, a_btree) = make(2000, 10_000);
+    let (b, b_btree) = make(500, 100_000);
+
+    let union: Vec<_> = a.union_with(&b, |_, x, y| x + y).into_sorted_vec();
+    let mut expected = a_btree.clone();
+    for (k, v) in &b_btree {
+        *expected.entry(k.clone()).or_insert(0) += v;
+    }
+    assert_eq!(
+        union
+            .into_iter()
+            .map(|(k, v)| (k.to_vec(), v))
+            .collect::<Vec<_>>(),
+        expected.into_iter().collect::<Vec<_>>()
+    );
+
+    let intersection: Vec<_> = a.intersection(&b).into_sorted_vec();
+    let expected: Vec<_> = a_btree
+        .iter()
+        .filter(|(k, _)| b_btree.contains_key(*k))
+        .map(|(k, v)| (k.clone(), *v))
+        .collect();
+    assert_eq!(
+        intersection
+            .into_iter()
+            .map(|(k, v)| (k.to_vec(), v))
+            .collect::<Vec<_>>(),
+        expected
+    );
+
+    let difference: Vec<_> = a.difference(&b).into_sorted_vec();
+    let expected: Vec<_> = a_btree
+        .iter()
+        .filter(|(k, _)| !b_btree.contains_key(*k))
+        .map(|(k, v)| (k.clone(), *v))
+        .collect();
+    assert_eq!(
+        difference
+            .into_iter()
+            .map(|(k, v)| (k.to_vec(), v))
+            .collect::<Vec<_>>(),
+        expected
+    );
+}
+
+#[test]
+fn art_set_operations_combine_subtrees_and_keep_the_settings() {
+    use std::collections::BTreeMap;
+
+    // Long shared prefixes that diverge at different depths, and subtrees only in one tree
+    let key = |tenant: &str, i: u32| format!("{}/object-{:05}\0", tenant, i).into_bytes();
+    let mut a = ArtTree::<u32>::with_node_sizing(NodeSizing::FAST);
+    a.enable_bloom_filter(10);
+    let mut b = ArtTree::<u32>::new();
+    let (mut a_btree, mut b_btree) = (BTreeMap::new(), BTreeMap::new());
+    for i in 0..600u32 {
+        for (tenant, tree, btree) in [
+            ("tenant-aaaaaaaaaaaa", &mut a, &mut a_btree),
+            ("tenant-aaaaaaaaaaab", &mut b, &mut b_btree),
+        ] {
+            if i % 3 != 0 {
+                tree.insert(&key(tenant, i), i);
+                btree.insert(key(tenant, i), i);
+            }
+        }
+        let (tree, btree) = if i % 2 == 0 {
+            (&mut a, &mut a_btree)
+        } else {
+            (&mut b, &mut b_btree)
+        };
+        tree.insert(&key("shared", i / 2), i);
+        btree.insert(key("shared", i / 2), i);
+    }
+    for (tree, btree) in [(&mut a, &mut a_btree), (&mut b, &mut b_btree)] {
+        tree.insert(b"tenant-b\0", 7);
+        btree.insert(b"tenant-b\0".to_vec(), 7);
+    }
+
+    let entries = |tree: &ArtTree<u32>| {
+        assert_eq!(tree.check_invariants(), Ok(()));
+        assert_eq!(tree.len(), tree.entries().count());
+        tree.entries()
+            .map(|(k, &v)| (k.to_vec(), v))
+            .collect::<Vec<_>>()
+    };
+    let union = a.union_with(&b, |_, x, y| x * 1000 + y);
+    let mut expected = a_btree.clone();
+    for (k, v) in &b_btree {
+        expected
+            .entry(k.clone())
+            .and_modify(|x| *x = *x * 1000 + v)
+            .or_insert(*v);
+    }
+    assert_eq!(entries(&union), expected.into_iter().collect::<Vec<_>>());
+    assert_eq!(union.node_sizing(), NodeSizing::FAST);
+    assert!(union.has_bloom_filter());
+    assert_eq!(union.get(&key("tenant-aaaaaaaaaaab", 1)), Some(&1));
+    #[cfg(feature = "merkle")]
+    {
+        // Subtrees cloned with a shortened path carry no stale hashes
+        let mut rebuilt = ArtTree::new();
+        for (k, &v) in union.entries() {
+            rebuilt.insert(k, v);
+        }
+        assert_eq!(union.root_hash(), rebuilt.root_hash());
+    }
+
+    let intersection = a.intersection(&b);
+    let expected: Vec<_> = a_btree
+        .iter()
+        .filter(|(k, _)| b_btree.contains_key(*k))
+        .map(|(k, v)| (k.clone(), *v))
+        .collect();
+    assert_eq!(entries(&intersection), expected);
+    assert_eq!(intersection.node_sizing(), NodeSizing::FAST);
+
+    let difference = a.difference(&b);
+    let expected: Vec<_> = a_btree
+        .iter()
+        .filter(|(k, _)| !b_btree.contains_key(*k))
+        .map(|(k, v)| (k.clone(), *v))
+        .collect();
+    assert_eq!(entries(&difference), expected);
+    assert!(difference.has_bloom_filter());
+    assert_eq!(entries(&b.difference(&b)), vec![]);
+    assert_eq!(entries(&a.intersection(&ArtTree::<()>::new())), vec![]);
+}
+
+#[test]
+fn art_subtree_matches_prefix_filter() {
+    let mut ds = ArtTree::<usize>::new();
+    let tenants: [&[u8]; 4] = [b"tenant-a/", b"tenant-b/", b"tenant-bb/", b"t/"];
+    for (t, tenant) in tenants.iter().enumerate() {
+        for i in 0..(50 * (t + 1)) {
+            let mut key = tenant.to_vec();
+            key.extend_from_slice(format!("some/long/shared/path/{:05}", i).as_bytes());
+            key.push(0);
+            ds.insert(&key, i);
+        }
+    }
+
+    let prefixes: [&[u8]; 8] = [
+        b"tenant-b/",
+        b"tenant-b",
+        b"tenant-",
+        b"tenant-a/some/long/shared/path/0004",
+        b"t",
+        b"",
+        b"x",
+        b"tenant-c",
+    ];
+    for prefix in prefixes.iter() {
+        let sub = ds.subtree(prefix);
+        let expected: Vec<_> = ds
+            .entries()
+            .filter(|(k, _)| k.starts_with(prefix))
+            .map(|(k, v)| (k.to_vec(), *v))
+            .collect();
+        let actual: Vec<_> = sub.entries().map(|(k, v)| (k.to_vec(), *v)).collect();
+        assert_eq!(actual, expected);
+        assert_eq!(sub.len(), expected.len());
+        for (key, value) in &expected {
+            assert_eq!(sub.get(key), Some(value));
+        }
+        assert_eq!(sub.get(b"tenant-z/none\0"), None);
+    }
+
+    let mut sub = ds.subtree(b"tenant-a/");
+    sub.insert(b"tenant-a/extra\0", 7);
+    assert_eq!(sub.get(b"tenant-a/extra\0"), Some(&7));
+    assert_eq!(ds.get(b"tenant-a/extra\0"), None);
+}
+
+#[cfg(feature = "merkle")]
+#[test]
+fn art_root_hash_tracks_contents() {
+    let mut a = ArtTree::<u32>::new();
+    let mut b = ArtTree::<u32>::new();
+    assert_eq!(a.root_hash(), b.root_hash());
+
+    // Same contents reached through different histories and node types
+    for i in 0..300u32 {
+        a.insert(&make_interesting_key(i)[..], i);
+    }
+    for i in (0..600u32).rev() {
+        b.insert(&make_interesting_key(i)[..], i);
+    }
+    assert_ne!(a.root_hash(), b.root_hash());
+    for i in 300..600u32 {
+        b.delete(&make_interesting_key(i)[..]);
+    }
+    assert_eq!(a.root_hash(), b.root_hash());
+
+    let before = a.root_hash();
+    let key = make_interesting_key(17);
+    *a.get_mut(&key[..]).unwrap() += 1;
+    assert_ne!(a.root_hash(), before);
+    a.entry(&key[..]).and_modify(|v| *v -= 1);
+    assert_eq!(a.root_hash(), before);
+
+    for (_, value) in a.entries_mut() {
+        *value += 1;
+    }
+    assert_ne!(a.root_hash(), before);
+    a.retain_mut(|_, value| {
+        *value -= 1;
+        true
+    });
+    assert_eq!(a.root_hash(), before);
+
+    // Removing entries in place drops the hashes along their paths
+    a.retain(|_, &value| value % 7 != 0);
+    for i in (0..300u32).filter(|i| i % 7 == 0) {
+        b.delete(&make_interesting_key(i)[..]);
+    }
+    assert_eq!(a.root_hash(), b.root_hash());
+}
+
+#[cfg(feature = "merkle")]
+#[test]
+fn art_subtree_hash_matches_subtree_root_hash() {
+    let mut ds = ArtTree::<u32>::new();
+    for i in 0..2000u32 {
+        ds.insert(&make_interesting_key(i * 7)[..], i);
+    }
+    for prefix in [&[][..], &[0], &[0, 0], &[1, 2], &[0, 0, 1], &[9, 9, 9, 9]].iter() {
+        let sub = ds.subtree(prefix);
+        let expected = if sub.is_empty() {
+            None
+        } else {
+            Some(sub.root_hash())
+        };
+        assert_eq!(ds.subtree_hash(prefix), expected);
+    }
+    assert_eq!(ds.subtree_hash(&[]), Some(ds.root_hash()));
+}
+
+#[cfg(feature = "merkle")]
+#[test]
+fn art_sync_diff_finds_changed_keys() {
+    struct CountingOracle<'a> {
+        tree: &'a mut ArtTree<u32>,
+        calls: usize,
+    }
+
+    impl<'a> HashOracle for CountingOracle<'a> {
+        fn subtree_hash(&mut self, prefix: &[u8]) -> Option<[u8; 32]> {
+            self.calls += 1;
+            self.tree.subtree_hash(prefix)
+        }
+
+        fn children_hashes(&mut self, prefix: &[u8]) -> Vec<(u8, [u8; 32])> {
+            self.calls += 1;
+            self.tree.children_hashes(prefix)
+        }
+
+        fn entry_hashes(&mut self, prefix: &[u8]) -> Vec<(Vec<u8>, [u8; 32])> {
+            self.calls += 1;
+            self.tree.entry_hashes(prefix)
+        }
+    }
+
+    let mut local = ArtTree::<u32>::new();
+    for i in 0..20_000u32 {
+        local.insert(&(i * 3).to_be_bytes(), i);
+    }
+    let mut remote = local.clone();
+    assert_eq!(local.sync_diff(&mut remote), SyncDiff::default());
+
+    local.insert(&7u32.to_be_bytes(), 7);
+    local.insert(&50_000u32.to_be_bytes(), 1);
+    remote.delete(&9_000u32.to_be_bytes());
+    remote.insert(&(3 * 12_345u32).to_be_bytes(), 0);
+    remote.insert(&u32::MAX.to_be_bytes(), 0);
+
+    let mut oracle = CountingOracle {
+        tree: &mut remote,
+        calls: 0,
+    };
+    let diff = local.sync_diff(&mut oracle);
+    assert!(oracle.calls < 40, "{} oracle calls", oracle.calls);
+    assert_eq!(
+        diff,
+        SyncDiff {
+            local_only: vec![
+                7u32.to_be_bytes().to_vec(),
+                9_000u32.to_be_bytes().to_vec(),
+                50_000u32.to_be_bytes().to_vec(),
+            ],
+            remote_only: vec![u32::MAX.to_be_bytes().to_vec()],
+            differing: vec![(3 * 12_345u32).to_be_bytes().to_vec()],
+        }
+    );
+
+    let empty = ArtTree::<u32>::new();
+    let diff = empty.sync_diff(&mut remote);
+    assert_eq!(diff.remote_only.len(), remote.len());
+}
+
+#[test]
+fn art_changes_since_mark_clean() {
+    let mut tree = ArtTree::<u32>::new();
+    for i in 0..1000u32 {
+        tree.insert(&i.to_be_bytes(), i);
+    }
+    assert_eq!(tree.changes().count(), 0);
+
+    tree.track_changes();
+    assert!(tree.is_tracking_changes());
+    assert_eq!(tree.changes().count(), 0);
+
+    tree.insert(&5u32.to_be_bytes(), 50);
+    tree.insert(&2000u32.to_be_bytes(), 2000);
+    tree.delete(&7u32.to_be_bytes());
+    *tree.get_mut(&9u32.to_be_bytes()).unwrap() += 1;
+    tree.insert(&3000u32.to_be_bytes(), 3000);
+    tree.delete(&3000u32.to_be_bytes());
+    tree.delete(&4000u32.to_be_bytes());
+    assert!(tree.get_mut(&5000u32.to_be_bytes()).is_none());
+
+    let key = |i: u32| i.to_be_bytes();
+    assert_eq!(
+        tree.changes().collect::<Vec<_>>(),
+        vec![
+            Delta::Upsert(&key(5), &50),
+            Delta::Delete(&key(7)),
+            Delta::Upsert(&key(9), &10),
+            Delta::Upsert(&key(2000), &2000),
+            Delta::Delete(&key(3000)),
+        ]
+    );
+
+    tree.mark_clean();
+    assert_eq!(tree.changes().count(), 0);
+
+    tree.pop_first();
+    for (_, value) in tree.entries_mut().take(2) {
+        *value += 1;
+    }
+    assert_eq!(
+        tree.changes().collect::<Vec<_>>(),
+        vec![
+            Delta::Delete(&key(0)),
+            Delta::Upsert(&key(1), &2),
+            Delta::Upsert(&key(2), &3),
+        ]
+    );
+
+    tree.mark_clean();
+    tree.drain();
+    assert_eq!(tree.changes().len(), 999);
+    assert!(tree
+        .changes()
+        .all(|delta| matches!(delta, Delta::Delete(_))));
+}
+
+#[test]
+fn art_retain_changes_only_the_removed_paths() {
+    let mut tree = ArtTree::<u32>::new();
+    for i in 0..1000u32 {
+        tree.insert(&i.to_be_bytes(), i);
+    }
+    tree.track_changes();
+    tree.retain(|key, _| key[2] != 1 && key[3] % 10 != 0);
+    assert_eq!(tree.len(), 1000 - 256 - 76);
+    assert!(tree
+        .changes()
+        .all(|delta| matches!(delta, Delta::Delete(key) if key[2] == 1 || key[3] % 10 == 0)));
+    assert_eq!(tree.changes().len(), 256 + 76);
+    assert_eq!(tree.check_invariants(), Ok(()));
+    assert!(tree
+        .entries()
+        .all(|(key, &value)| key == value.to_be_bytes() && key[2] != 1 && key[3] % 10 != 0));
+
+    // Keeping a single child of the root merges it into the child
+    tree.retain(|key, _| key[2] == 0);
+    assert_eq!(root_kind(&tree), "Node256");
+    assert_eq!(tree.get(&5u32.to_be_bytes()), Some(&5));
+
+    tree.mark_clean();
+    tree.retain_mut(|key, value| {
+        *value += 1;
+        key[3] != 5
+    });
+    assert_eq!(tree.changes().len(), 230);
+    assert_eq!(tree.get(&1u32.to_be_bytes()), Some(&2));
+    assert_eq!(tree.get(&5u32.to_be_bytes()), None);
+    assert_eq!(tree.check_invariants(), Ok(()));
+}
+
+#[test]
+fn art_txn_commit_and_rollback() {
+    let mut tree = ArtTree::<u32>::new();
+    for i in 0..100u32 {
+        tree.insert(&i.to_be_bytes(), i);
+    }
+    let before = tree.to_vec();
+
+    let mut txn = tree.begin();
+    txn.insert(&5u32.to_be_bytes(), 500);
+    txn.insert(&5u32.to_be_bytes(), 501);
+    txn.insert(&1000u32.to_be_bytes(), 1000);
+    assert_eq!(txn.delete(&7u32.to_be_bytes()), Some(7));
+    assert_eq!(txn.delete(&7u32.to_be_bytes()), None);
+    *txn.get_mut(&9u32.to_be_bytes()).unwrap() = 90;
+    assert_eq!(txn.get(&5u32.to_be_bytes()), Some(&501));
+    assert!(!txn.contains_key(&7u32.to_be_bytes()));
+    txn.rollback();
+    assert_eq!(tree.to_vec(), before);
+
+    {
+        let mut txn = tree.begin();
+        txn.delete(&0u32.to_be_bytes());
+    }
+    assert_eq!(tree.to_vec(), before);
+
+    let mut txn = tree.begin();
+    txn.insert(&1000u32.to_be_bytes(), 1000);
+    txn.delete(&0u32.to_be_bytes());
+    txn.commit();
+    assert_eq!(tree.len(), 100);
+    assert_eq!(tree.get(&1000u32.to_be_bytes()), Some(&1000));
+    assert!(!tree.contains_key(&0u32.to_be_bytes()));
+}
+
+#[test]
+fn art_scan_prefix_matches_filter() {
+    let mut tree = ArtTree::<u32>::new();
+    for i in 0..5000u32 {
+        tree.insert(&(i * 7).to_be_bytes(), i);
+    }
+    for prefix in [
+        &[][..],
+        &[0],
+        &[0, 0],
+        &[0, 0, 0x1b],
+        &[0, 0, 0x1b, 0x5d],
+        &[1],
+    ] {
+        let expected: Vec<_> = tree
+            .entries()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .collect();
+        assert_eq!(tree.scan_prefix(prefix).collect::<Vec<_>>(), expected);
+        assert_eq!(
+            tree.keys_with_prefix(prefix).collect::<Vec<_>>(),
+            expected.iter().map(|(key, _)| *key).collect::<Vec<_>>()
+        );
+    }
+}
+
+#[test]
+fn art_frozen_tree_is_shared_across_threads() {
+    let mut tree = ArtTree::<u32>::new();
+    for i in 0..1000u32 {
+        tree.insert(&i.to_be_bytes(), i);
+    }
+    let frozen = tree.freeze();
+
+    let handles: Vec<_> = (0..4u32)
+        .map(|t| {
+            let frozen = frozen.clone();
+            std::thread::spawn(move || {
+                let start = (t * 250).to_be_bytes();
+                let end = ((t + 1) * 250).to_be_bytes();
+                let sum: u32 = frozen.range(&start[..]..&end[..]).map(|(_, v)| *v).sum();
+                assert_eq!(frozen.get(&start), Some(&(t * 250)));
+                sum
+            })
+        })
+        .collect();
+    let total: u32 = handles.into_iter().map(|h| h.join().unwrap()).sum();
+    assert_eq!(total, (0..1000).sum());
+
+    assert_eq!(frozen.len(), 1000);
+    assert_eq!(frozen.scan_prefix(&[0, 0, 3]).count(), 232);
+    assert_eq!((&frozen).into_iter().count(), 1000);
+    let tree = frozen.try_unfreeze().unwrap();
+    assert_eq!(tree.len(), 1000);
+}
+
+#[test]
+fn art_diff_yields_changed_entries() {
+    let key = |i: u32| i.to_be_bytes();
+    let old: ArtTree<u32> = {
+        let mut tree = ArtTree::new();
+        for i in 0..1_000u32 {
+            tree.insert(&key(i), i);
+        }
+        tree
+    };
+    let mut new = old.clone();
+    new.delete(&key(3));
+    new.insert(&key(500), 0);
+    new.insert(&key(500), 500);
+    new.insert(&key(700), 1);
+    new.insert(&[0xff, 0xff], 2);
+
+    let diff: Vec<_> = old.diff(&new).collect();
+    assert_eq!(
+        diff,
+        vec![
+            DiffEntry::Removed(&key(3)[..], &3),
+            DiffEntry::Changed(&key(700)[..], &700, &1),
+            DiffEntry::Added(&[0xff, 0xff][..], &2),
+        ]
+    );
+    assert_eq!(new.diff(&old).count(), 3);
+    assert_eq!(old.diff(&old.clone()).count(), 0);
+
+    let (old, new) = (old.freeze(), new.freeze());
+    assert_eq!(old.diff(&old.clone()).count(), 0);
+    assert_eq!(old.diff(&new).count(), 3);
+}
+
+#[cfg(feature = "merkle")]
+#[test]
+fn art_diff_by_hash_matches_diff() {
+    let key = |i: u32| format!("{}/{:x}\0", i % 5, i * 7919).into_bytes();
+    let mut old = ArtTree::<u32>::new();
+    for i in 0..3_000u32 {
+        old.insert(&key(i), i);
+    }
+    let mut new = old.clone();
+    assert_eq!(old.diff_by_hash(&new).count(), 0);
+
+    for i in (0..3_000u32).step_by(97) {
+        new.delete(&key(i));
+    }
+    for i in (0..3_000u32).step_by(389) {
+        new.insert(&key(i), 0);
+    }
+    // New keys splitting compressed paths, and branches found in only one tree
+    new.insert(b"1/long/path/a\0", 1);
+    new.insert(b"1/long/path/b\0", 2);
+    new.insert(b"9\0", 3);
+    old.insert(b"8\0", 4);
+
+    for (a, b) in [(&old, &new), (&new, &old)] {
+        let expected: Vec<_> = a.diff(b).collect();
+        assert!(expected.len() > 30);
+        assert_eq!(a.diff_by_hash(b).collect::<Vec<_>>(), expected);
+    }
+    let empty = ArtTree::new();
+    assert_eq!(old.diff_by_hash(&empty).count(), old.len());
+    assert_eq!(empty.diff_by_hash(&new).count(), new.len());
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn art_sample_is_roughly_uniform() {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    let mut rng = StdRng::seed_from_u64(7);
+    let tree = ArtTree::<u32>::new();
+    assert!(tree.sample(&mut rng, 10).is_empty());
+
+    let mut tree = ArtTree::<u32>::new();
+    for i in 0..4096u32 {
+        tree.insert(&(i * 3).to_be_bytes(), i);
+    }
+    let samples = tree.sample(&mut rng, 8000);
+    assert_eq!(samples.len(), 8000);
+
+    let mut quarters = [0; 4];
+    for (key, value) in samples {
+        assert_eq!(tree.get(key), Some(value));
+        quarters[(*value / 1024) as usize] += 1;
+    }
+    for count in quarters.iter() {
+        assert!((1600..2400).contains(count), "{:?}", quarters);
+    }
+}
+
+#[test]
+fn art_estimate_count_prefix_bounds_the_true_count() {
+    let mut tree = ArtTree::<u32>::new();
+    for i in 0..200_000u32 {
+        tree.insert(&(i * 3).to_be_bytes(), i);
+    }
+
+    let estimate = tree.estimate_count_prefix(&[1]);
+    assert_eq!(estimate.lower, 0);
+    assert!(estimate.is_exact());
+
+    let estimate = tree.estimate_count_prefix(&[0, 0, 2, 7]);
+    assert!(estimate.is_exact());
+    assert_eq!(estimate.estimate, tree.scan_prefix(&[0, 0, 2, 7]).count());
+
+    for prefix in [&[][..], &[0], &[0, 1], &[0, 9]] {
+        let count = tree.scan_prefix(prefix).count();
+        let estimate = tree.estimate_count_prefix(prefix);
+        assert!(estimate.lower <= count && count <= estimate.upper);
+        let error = (estimate.estimate as f64 - count as f64).abs() / count as f64;
+        assert!(error < 0.2, "{:?} for {} entries", estimate, count);
+    }
+}
+
+#[test]
+fn art_first_and_last_entry() {
+    let mut tree = ArtTree::<u32>::new();
+    assert!(tree.first_entry().is_none());
+    assert!(tree.last_entry().is_none());
+    for i in 10..20u32 {
+        tree.insert(&i.to_be_bytes(), i);
+    }
+
+    let mut first = tree.first_entry().unwrap();
+    assert_eq!(first.key(), &10u32.to_be_bytes());
+    *first.get_mut() += 100;
+    assert_eq!(tree.get(&10u32.to_be_bytes()), Some(&110));
+
+    // Consume the head only while it is due
+    while let Some(entry) = tree.first_entry() {
+        if *entry.get() > 12 && *entry.get() < 100 {
+            break;
+        }
+        entry.remove();
+    }
+    assert_eq!(tree.minimum().map(|(_, v)| *v), Some(13));
+
+    let mut last = tree.last_entry().unwrap();
+    assert_eq!(last.insert(0), 19);
+    assert_eq!(last.remove(), 0);
+    assert_eq!(tree.maximum().map(|(_, v)| *v), Some(18));
+    assert_eq!(tree.len(), 6);
+}
+
+#[test]
+fn art_min_and_max_under_prefix() {
+    let mut tree = ArtTree::<u64>::new();
+    for stream in 0..50u32 {
+        for seq in 0..=stream * 7 {
+            let mut key = stream.to_be_bytes().to_vec();
+            key.extend_from_slice(&seq.to_be_bytes());
+            tree.insert(&key, u64::from(stream) << 32 | u64::from(seq));
+        }
+    }
+    for stream in 0..50u32 {
+        let prefix = stream.to_be_bytes();
+        let (key, _) = tree.min_prefix(&prefix).unwrap();
+        assert_eq!(&key[4..], &0u32.to_be_bytes());
+        let (key, value) = tree.max_prefix(&prefix).unwrap();
+        assert_eq!(&key[4..], &(stream * 7).to_be_bytes());
+        assert_eq!(*value, u64::from(stream) << 32 | u64::from(stream * 7));
+    }
+    assert!(tree.min_prefix(&50u32.to_be_bytes()).is_none());
+    assert_eq!(
+        tree.max_prefix(&[0, 0, 0, 3, 0, 0, 0, 1]).unwrap().0,
+        &[0, 0, 0, 3, 0, 0, 0, 1]
+    );
+    assert_eq!(tree.min_prefix(&[]).unwrap().0, &[0; 8]);
+}
+
+#[test]
+fn art_range_mut_matches_btree() {
+    use rand::Rng;
+    use std::ops::Bound;
+
+    let mut rng = rand::thread_rng();
+    let mut ds = ArtTree::<u32>::new();
+    let mut expected = std::collections::BTreeMap::new();
+    // Fan-outs that produce every node type
+    for (a, fan_out) in [3u8, 10, 40, 200].iter().enumerate() {
+        for b in 0..*fan_out {
+            let key = [a as u8 * 2, b.wrapping_mul(7), 1];
+            ds.insert(&key, 0);
+            expected.insert(key.to_vec(), 0);
+        }
+    }
+
+    for round in 0..300 {
+        let mut bound = || -> Vec<u8> {
+            let len = rng.gen_range(0..=3);
+            (0..len)
+                .map(|i| {
+                    if i == 0 {
+                        rng.gen_range(0..8)
+                    } else {
+                        rng.gen()
+                    }
+                })
+                .collect()
+        };
+        let (start, end) = (bound(), bound());
+        if start > end {
+            continue;
+        }
+
+        let bounds = if round % 2 == 0 {
+            (Bound::Included(start.clone()), Bound::Excluded(end.clone()))
+        } else {
+            (Bound::Excluded(start.clone()), Bound::Included(end.clone()))
+        };
+        let mut visited = Vec::new();
+        for (key, value) in ds.range_mut((
+            bounds.0.as_ref().map(|key| key.as_slice()),
+            bounds.1.as_ref().map(|key| key.as_slice()),
+        )) {
+            *value += 1;
+            visited.push(key.to_vec());
+        }
+        let mut wanted = Vec::new();
+        for (key, value) in expected.range_mut(bounds) {
+            *value += 1;
+            wanted.push(key.clone());
+        }
+        assert_eq!(visited, wanted, "range {:?}..{:?}", start, end);
+    }
+
+    let actual: Vec<_> = ds.entries().map(|(k, v)| (k.to_vec(), *v)).collect();
+    let wanted: Vec<_> = expected.into_iter().collect();
+    assert_eq!(actual, wanted);
+}
+
+#[test]
+fn art_groups_by_prefix() {
+    let mut tree = ArtTree::<u32>::new();
+    let tenants = [0u32, 1, 7, 255, 256, u32::MAX];
+    for (t, &tenant) in tenants.iter().enumerate() {
+        for i in 0..(t as u32 * 10 + 1) {
+            let mut key = tenant.to_be_bytes().to_vec();
+            key.extend_from_slice(&i.to_be_bytes());
+            tree.insert(&key, i);
+        }
+    }
+
+    let groups: Vec<_> = tree
+        .groups_by_prefix(4)
+        .map(|(prefix, entries)| (prefix, entries.map(|(_, v)| *v).sum::<u32>()))
+        .collect();
+    let expected: Vec<_> = tenants
+        .iter()
+        .enumerate()
+        .map(|(t, tenant)| (tenant.to_be_bytes().to_vec(), (0..=t as u32 * 10).sum()))
+        .collect();
+    assert_eq!(groups, expected);
+
+    assert_eq!(tree.groups_by_prefix(0).count(), 1);
+    assert_eq!(tree.groups_by_prefix(3).count(), 3);
+    assert_eq!(tree.groups_by_prefix(8).count(), tree.len());
+    assert_eq!(ArtTree::<u32>::new().groups_by_prefix(4).count(), 0);
+}
+
+#[test]
+fn art_sort_by_key_bytes_is_stable() {
+    use rand::Rng;
+
+    let mut rng = rand::thread_rng();
+    let items: Vec<(Vec<u8>, usize)> = (0..5000)
+        .map(|i| {
+            let len = rng.gen_range(0..4);
+            ((0..len).map(|_| rng.gen_range(0..3)).collect(), i)
+        })
+        .collect();
+
+    let sorted = sort_by_key_bytes(items.clone(), |(key, _)| key.clone());
+    let mut expected = items;
+    expected.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(sorted, expected);
+
+    let words = sort_by_key_bytes(vec!["pear", "apple", "app", "", "apple"], |w| w.as_bytes());
+    assert_eq!(words, vec!["", "app", "apple", "apple", "pear"]);
+}
+
+#[test]
+fn art_remove_below_matches_btree() {
+    use rand::Rng;
+
+    let mut rng = rand::thread_rng();
+    for _ in 0..50 {
+        let mut ds = ArtTree::<u32>::new();
+        let mut expected = std::collections::BTreeMap::new();
+        for i in 0..rng.gen_range(0..3000u32) {
+            let mut key = make_interesting_key(rng.gen_range(0..100_000)).to_vec();
+            // Long shared paths exercise prefixes beyond MAX_PREFIX_LEN
+            if i % 3 == 0 {
+                key.splice(1..1, [200u8; 12].iter().copied());
+            }
+            ds.insert(&key, i);
+            expected.insert(key, i);
+        }
+
+        let len = rng.gen_range(0..=16);
+        let bound: Vec<u8> = (0..len)
+            .map(|i| match i {
+                0 => rng.gen_range(0..10),
+                1..=12 if rng.gen_bool(0.9) => 200,
+                _ => rng.gen_range(0..50),
+            })
+            .collect();
+        let kept = expected.split_off(&bound);
+        assert_eq!(ds.remove_below(&bound), expected.len());
+        assert_eq!(ds.len(), kept.len());
+        let actual: Vec<_> = ds.entries().map(|(k, v)| (k.to_vec(), *v)).collect();
+        assert_eq!(actual, kept.clone().into_iter().collect::<Vec<_>>());
+        for (key, value) in &kept {
+            assert_eq!(ds.get(key), Some(value));
+        }
+        for key in expected.keys() {
+            assert!(ds.get(key).is_none());
+        }
+        for key in kept.keys() {
+            assert!(ds.delete(key).is_some());
+        }
+        assert!(ds.is_empty());
+    }
+}
+
+#[test]
+fn art_bloom_filter_keeps_lookups_exact() {
+    let mut ds = ArtTree::<u32>::new();
+    for i in 0..500u32 {
+        ds.insert(&i.to_be_bytes(), i);
+    }
+    ds.enable_bloom_filter(10);
+    assert!(ds.has_bloom_filter());
+
+    // Enough inserts and deletes to trigger rebuilds of the filter
+    for i in 500..5_000u32 {
+        ds.insert(&i.to_be_bytes(), i);
+    }
+    for i in (0..5_000u32).filter(|i| i % 3 == 0) {
+        assert_eq!(ds.delete(&i.to_be_bytes()), Some(i));
+    }
+    ds.remove_below(&100u32.to_be_bytes());
+    for i in 0..10_000u32 {
+        let expected = if (100..5_000).contains(&i) && i % 3 != 0 {
+            Some(i)
+        } else {
+            None
+        };
+        assert_eq!(ds.get(&i.to_be_bytes()).copied(), expected);
+        assert_eq!(ds.get_mut(&i.to_be_bytes()).copied(), expected);
+    }
+
+    ds.drain();
+    ds.insert(b"key", 1);
+    assert!(ds.contains_key(b"key"));
+    ds.disable_bloom_filter();
+    assert!(!ds.has_bloom_filter());
+    assert!(ds.contains_key(b"key"));
+}
+
+#[test]
+fn art_content_hash_ignores_shape_and_history() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::Hasher;
+
+    fn digest(tree: &ArtTree<u32>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        tree.content_hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let mut left = ArtTree::<u32>::new();
+    let mut right = ArtTree::<u32>::new();
+    for i in 0..1_000u32 {
+        left.insert(&i.to_be_bytes(), i);
+    }
+    for i in (0..2_000u32).rev() {
+        right.insert(&i.to_be_bytes(), i);
+    }
+    for i in 1_000..2_000u32 {
+        right.delete(&i.to_be_bytes());
+    }
+    assert_eq!(digest(&left), digest(&right));
+
+    *right.get_mut(&7u32.to_be_bytes()).unwrap() = 8;
+    assert_ne!(digest(&left), digest(&right));
+
+    // The bytes fed to the hasher do not depend on the platform
+    #[derive(Default)]
+    struct Bytes(Vec<u8>);
+    impl Hasher for Bytes {
+        fn finish(&self) -> u64 {
+            0
+        }
+        fn write(&mut self, bytes: &[u8]) {
+            self.0.extend_from_slice(bytes);
+        }
+    }
+    let mut tree = ArtTree::<u32>::new();
+    tree.insert(&[1, 2], 0x0a0b_0c0d);
+    let mut bytes = Bytes::default();
+    tree.content_hash(&mut bytes);
+    assert_eq!(
+        bytes.0,
+        [1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 1, 2, 0x0d, 0x0c, 0x0b, 0x0a]
+    );
+}
+
+#[test]
+fn art_debug_print_renders_the_hierarchy() {
+    let mut ds = ArtTree::<u32>::new();
+    assert_eq!(ds.debug_print().to_string(), "Empty\n");
+
+    ds.insert(&[0, 1, 1], 1);
+    ds.insert(&[0, 2, 1], 2);
+    ds.insert(&[0, 2, 2], 3);
+    assert_eq!(
+        ds.debug_print().to_string(),
+        "Node4 prefix=00 children=2\n\
+         |-- 01: Leaf 000101 => 1\n\
+         `-- 02: Node4 prefix= children=2\n\
+         \x20   |-- 01: Leaf 000201 => 2\n\
+         \x20   `-- 02: Leaf 000202 => 3\n"
+    );
+    assert_eq!(
+        ds.debug_print().max_depth(0).to_string(),
+        "Node4 prefix=00 children=2\n`-- ... (3 entries)\n"
+    );
+}
+
+#[test]
+fn art_visitor_sees_every_node() {
+    #[derive(Default)]
+    struct Stats {
+        open: usize,
+        internal: usize,
+        slots: usize,
+        children: usize,
+        leaves: Vec<Vec<u8>>,
+        value_sum: u32,
+    }
+
+    impl Visitor<u32> for Stats {
+        fn visit_internal(&mut self, header: &NodeHeader<'_>, kind: NodeKind) {
+            assert!(header.num_children() >= 2 && header.num_children() <= kind.capacity());
+            assert!(header.prefix().len() <= header.prefix_len());
+            self.open += 1;
+            self.internal += 1;
+            self.slots += kind.capacity();
+            self.children += header.num_children();
+        }
+
+        fn leave_internal(&mut self, _header: &NodeHeader<'_>, _kind: NodeKind) {
+            self.open -= 1;
+        }
+
+        fn visit_leaf(&mut self, key: &[u8], value: &u32) {
+            assert!(self.open > 0);
+            self.leaves.push(key.to_vec());
+            self.value_sum += value;
+        }
+    }
+
+    let mut ds = ArtTree::<u32>::new();
+    let mut expected = std::collections::BTreeMap::new();
+    for i in 0..2_000u32 {
+        let key = make_interesting_key(i * 7919);
+        ds.insert(key.as_ref(), i % 10);
+        expected.insert(key.to_vec(), i % 10);
+    }
+
+    let mut stats = Stats::default();
+    ds.accept(&mut stats);
+    assert_eq!(stats.open, 0);
+    assert_eq!(stats.leaves, expected.keys().cloned().collect::<Vec<_>>());
+    assert_eq!(stats.value_sum, expected.values().sum());
+    // Every node but the root is the child of an internal node
+    assert_eq!(stats.children, stats.internal - 1 + stats.leaves.len());
+    assert!(stats.slots >= stats.children);
+}
+
+#[test]
+fn art_prefix_report_counts_compressed_paths() {
+    let mut ds = ArtTree::<u32>::new();
+    assert_eq!(ds.prefix_report().internal_nodes, 0);
+
+    let key = |i: u8, j: u8| {
+        let mut key = vec![i];
+        key.extend_from_slice(&[200; 12]);
+        key.extend_from_slice(&[j, 0]);
+        key
+    };
+    for i in 0..3 {
+        for j in 0..5 {
+            ds.insert(&key(i, j), DUMMY_VALUE);
+        }
+    }
+    ds.insert(&[9, 0], DUMMY_VALUE);
+
+    let report = ds.prefix_report();
+    assert_eq!(report.internal_nodes, 4);
+    assert_eq!(report.compressed_nodes, 3);
+    assert_eq!(report.compressed_bytes, 36);
+    assert!(report.bytes_saved > report.compressed_bytes);
+    assert_eq!(
+        report.partial_len_histogram.into_iter().collect::<Vec<_>>(),
+        vec![(0, 1), (12, 3)]
+    );
+    assert_eq!(report.overflowing_nodes, 3);
+    assert_eq!(report.entries_below_overflow, 15);
+    assert_eq!(ds.prefix_report().max_partial_len(), 12);
+
+    #[cfg(feature = "metrics")]
+    {
+        let peeks = ds.metrics().prefix_leaf_peeks;
+        ds.insert(&key(1, 7), DUMMY_VALUE);
+        assert_eq!(ds.metrics().prefix_leaf_peeks, peeks + 1);
+    }
+}
+
+#[test]
+fn art_clone_from_copies_contents() {
+    use rand::Rng;
+
+    let mut rng = rand::thread_rng();
+    let random_tree = |rng: &mut rand::rngs::ThreadRng| {
+        let mut ds = ArtTree::<String>::new();
+        for _ in 0..rng.gen_range(0..2_000) {
+            let key = make_interesting_key(rng.gen_range(0..20_000));
+            ds.insert(key.as_ref(), format!("{:?}", key));
+        }
+        ds
+    };
+
+    let mut target = random_tree(&mut rng);
+    for _ in 0..20 {
+        let source = random_tree(&mut rng);
+        target.clone_from(&source);
+        assert_eq!(target.len(), source.len());
+        assert!(target.entries().eq(source.entries()));
+
+        // The copy is independent of the source
+        if let Some((key, _)) = source.minimum() {
+            let key = key.to_vec();
+            target.delete(&key);
+            assert!(source.contains_key(&key));
+        }
+    }
+}
+
+#[test]
+fn art_with_capacity_and_reserve() {
+    let mut ds = ArtTree::<u32>::with_capacity(5_000);
+    ds.enable_bloom_filter(8);
+    for i in 0..5_000u32 {
+        ds.insert(&i.to_be_bytes(), i);
+    }
+    ds.reserve(20_000);
+    for i in 5_000..25_000u32 {
+        ds.insert(&i.to_be_bytes(), i);
+    }
+    assert_eq!(ds.len(), 25_000);
+    for i in 0..30_000u32 {
+        assert_eq!(ds.get(&i.to_be_bytes()).is_some(), i < 25_000);
+    }
+}
+
+#[test]
+fn art_pop_first_if_and_pop_last_if() {
+    let mut ds = ArtTree::<u32>::new();
+    assert_eq!(
+        ds.pop_first_if(|_, _| panic!("called on an empty tree")),
+        None
+    );
+    for i in 0..100u32 {
+        ds.insert(&i.to_be_bytes(), i * 10);
+    }
+
+    // Deadlines that have passed are popped, the rest stay
+    let now = 250;
+    let mut expired = Vec::new();
+    while let Some((_, deadline)) = ds.pop_first_if(|_, &deadline| deadline <= now) {
+        expired.push(deadline);
+    }
+    assert_eq!(expired, (0..=25).map(|i| i * 10).collect::<Vec<_>>());
+    assert_eq!(ds.len(), 74);
+
+    assert_eq!(ds.pop_last_if(|key, _| key[3] % 2 == 0), None);
+    let (key, value) = ds.pop_last_if(|key, _| key[3] == 99).unwrap();
+    assert_eq!((&key[..], value), (&99u32.to_be_bytes()[..], 990));
+    assert_eq!(ds.len(), 73);
+    assert_eq!(ds.maximum().map(|(_, v)| *v), Some(980));
+}
+
+#[test]
+fn art_drain_range_matches_btree() {
+    use rand::Rng;
+    use std::ops::Bound;
+
+    let mut rng = rand::thread_rng();
+    let random_bound = |rng: &mut rand::rngs::ThreadRng| {
+        let len = rng.gen_range(0..=16);
+        let key: Vec<u8> = (0..len)
+            .map(|i| match i {
+                0 => rng.gen_range(0..10),
+                1..=12 if rng.gen_bool(0.9) => 200,
+                _ => rng.gen_range(0..50),
+            })
+            .collect();
+        match rng.gen_range(0..3) {
+            0 => Bound::Unbounded,
+            1 => Bound::Included(key),
+            _ => Bound::Excluded(key),
+        }
+    };
+
+    for _ in 0..100 {
+        let mut ds = ArtTree::<u32>::new();
+        let mut expected = std::collections::BTreeMap::new();
+        for i in 0..rng.gen_range(0..3000u32) {
+            let mut key = make_interesting_key(rng.gen_range(0..100_000)).to_vec();
+            if i % 3 == 0 {
+                key.splice(1..1, [200u8; 12].iter().copied());
+            }
+            ds.insert(&key, i);
+            expected.insert(key, i);
+        }
+
+        let (start, end) = (random_bound(&mut rng), random_bound(&mut rng));
+        let range = (
+            start.as_ref().map(|key| &key[..]),
+            end.as_ref().map(|key| &key[..]),
+        );
+        let inverted = match (range.0, range.1) {
+            (Bound::Included(s), Bound::Included(e)) => s > e,
+            (Bound::Included(s), Bound::Excluded(e))
+            | (Bound::Excluded(s), Bound::Included(e))
+            | (Bound::Excluded(s), Bound::Excluded(e)) => s >= e,
+            _ => false,
+        };
+        let removed: Vec<_> = if inverted {
+            Vec::new()
+        } else {
+            expected
+                .range::<[u8], _>(range)
+                .map(|(k, _)| k.clone())
+                .collect()
+        };
+        let removed: Vec<_> = removed
+            .into_iter()
+            .map(|key| {
+                let value = expected.remove(&key).unwrap();
+                (key, value)
+            })
+            .collect();
+
+        let drained = ds.drain_range(range);
+        assert_eq!(drained.len(), removed.len());
+        let drained: Vec<_> = drained.map(|(k, v)| (k.to_vec(), v)).collect();
+        assert_eq!(drained, removed);
+        assert_eq!(ds.len(), expected.len());
+        let actual: Vec<_> = ds.entries().map(|(k, v)| (k.to_vec(), *v)).collect();
+        assert_eq!(actual, expected.clone().into_iter().collect::<Vec<_>>());
+        for (key, value) in &expected {
+            assert_eq!(ds.get(key), Some(value));
+        }
+    }
+}
+
+#[test]
+fn art_rekey_moves_non_clone_values() {
+    struct Handle(u64);
+    let key = |i: u64| i.to_be_bytes();
+
+    let mut ds = ArtTree::new();
+    for i in 0..1_000 {
+        ds.insert(&key(i), Handle(i));
+    }
+
+    for i in (0..1_000).step_by(2) {
+        assert!(ds.rekey(&key(i), &key(i + 10_000)));
+    }
+    assert!(!ds.rekey(&key(0), &key(1)));
+    assert_eq!(ds.len(), 1_000);
+    for i in 0..1_000 {
+        let moved = ds.get(&key(i + 10_000)).map(|h| h.0);
+        if i % 2 == 0 {
+            assert!(ds.get(&key(i)).is_none());
+            assert_eq!(moved, Some(i));
+        } else {
+            assert_eq!(ds.get(&key(i)).map(|h| h.0), Some(i));
+            assert_eq!(moved, None);
+        }
+    }
+
+    // Moving onto an existing key replaces its value
+    assert!(ds.rekey(&key(1), &key(3)));
+    assert_eq!(ds.get(&key(3)).map(|h| h.0), Some(1));
+    assert_eq!(ds.len(), 999);
+    assert!(ds.rekey(&key(3), &key(3)));
+    assert_eq!(ds.get(&key(3)).map(|h| h.0), Some(1));
+}
+
+#[test]
+fn art_swap_exchanges_values_in_place() {
+    let mut ds = ArtTree::new();
+    let mut expected = std::collections::BTreeMap::new();
+    for i in 0..2_000u32 {
+        let key = make_interesting_key(i);
+        ds.insert(&*key, i);
+        expected.insert(key.to_vec(), i);
+    }
+    let keys: Vec<_> = expected.keys().cloned().collect();
+
+    for (i, a) in keys.iter().enumerate() {
+        let b = &keys[(i * 7 + 3) % keys.len()];
+        assert!(ds.swap(a, b));
+        let value_a = expected[a];
+        let value_b = expected.insert(b.clone(), value_a).unwrap();
+        expected.insert(a.clone(), value_b);
+    }
+    assert_eq!(ds.len(), expected.len());
+    for (key, value) in &expected {
+        assert_eq!(ds.get(key), Some(value));
+    }
+
+    let missing = [255, 255, 255, 255];
+    assert!(!ds.swap(&keys[0], &missing));
+    assert!(!ds.swap(&missing, &keys[0]));
+    assert!(!ds.swap(&missing, &missing));
+    assert!(ds.swap(&keys[1], &keys[1]));
+    assert_eq!(ds.get(&keys[0]), expected.get(&keys[0]));
+    assert_eq!(ds.get(&keys[1]), expected.get(&keys[1]));
+}
+
+#[test]
+fn art_next_after_and_prev_before_mut_match_btree() {
+    use std::ops::Bound;
+
+    let mut ds = ArtTree::new();
+    let mut expected = std::collections::BTreeMap::new();
+    for i in (0..3_000u32).step_by(3) {
+        let key = make_interesting_key(i);
+        ds.insert(&*key, i);
+        expected.insert(key.to_vec(), i);
+    }
+
+    for i in 0..3_000u32 {
+        let key = make_interesting_key(i).to_vec();
+        let next = expected
+            .range::<[u8], _>((Bound::Excluded(&key[..]), Bound::Unbounded))
+            .next()
+            .map(|(k, v)| (k.clone(), *v));
+        let prev = expected
+            .range::<[u8], _>((Bound::Unbounded, Bound::Excluded(&key[..])))
+            .next_back()
+            .map(|(k, v)| (k.clone(), *v));
+        assert_eq!(ds.next_after_mut(&key).map(|(k, v)| (k.to_vec(), *v)), next);
+        assert_eq!(
+            ds.prev_before_mut(&key).map(|(k, v)| (k.to_vec(), *v)),
+            prev
+        );
+    }
+    for key in [vec![], vec![0], vec![255; 5]] {
+        let next = expected
+            .range::<[u8], _>((Bound::Excluded(&key[..]), Bound::Unbounded))
+            .next()
+            .map(|(_, v)| *v);
+        let prev = expected
+            .range::<[u8], _>((Bound::Unbounded, Bound::Excluded(&key[..])))
+            .next_back()
+            .map(|(_, v)| *v);
+        assert_eq!(ds.next_after_mut(&key).map(|(_, v)| *v), next);
+        assert_eq!(ds.prev_before_mut(&key).map(|(_, v)| *v), prev);
+    }
+
+    // Extend the previous run in place
+    let key = make_interesting_key(301);
+    let (prev_key, value) = ds.prev_before_mut(&*key).unwrap();
+    let prev_key = prev_key.to_vec();
+    *value += 1;
+    assert_eq!(ds.get(&prev_key), Some(&(expected[&prev_key] + 1)));
+}
+
+#[test]
+fn art_apply_to_prefix_only_touches_the_namespace() {
+    let key = |tenant: &str, i: u32| format!("tenant/{}/object/{:05}\0", tenant, i).into_bytes();
+    let mut tree = ArtTree::new();
+    for tenant in ["a", "ab", "b"] {
+        for i in 0..500 {
+            tree.insert(&key(tenant, i), i);
+        }
+    }
+
+    let mut visited = Vec::new();
+    tree.apply_to_prefix(b"tenant/a/", |key, value| {
+        visited.push(key.to_vec());
+        *value += 1_000;
+    });
+    assert_eq!(visited, (0..500).map(|i| key("a", i)).collect::<Vec<_>>());
+    for tenant in ["a", "ab", "b"] {
+        for i in 0..500 {
+            let expected = if tenant == "a" { i + 1_000 } else { i };
+            assert_eq!(tree.get(&key(tenant, i)), Some(&expected));
+        }
+    }
+
+    let mut count = 0;
+    tree.apply_to_prefix(b"tenant/a", |_, _| count += 1);
+    assert_eq!(count, 1_000);
+    tree.apply_to_prefix(b"tenant/c", |_, _| unreachable!());
+    tree.apply_to_prefix(&key("b", 7), |_, value| *value = 0);
+    assert_eq!(tree.get(&key("b", 7)), Some(&0));
+}
+
+#[cfg(feature = "merkle")]
+#[test]
+fn art_apply_to_prefix_updates_root_hash() {
+    let mut tree = ArtTree::new();
+    let mut expected = ArtTree::new();
+    for i in 0..5000u32 {
+        tree.insert(&(i * 7).to_be_bytes(), i);
+        let value = if (i * 7) >> 16 == 0 { i + 1 } else { i };
+        expected.insert(&(i * 7).to_be_bytes(), value);
+    }
+    tree.root_hash();
+    tree.apply_to_prefix(&[0, 0], |_, value| *value += 1);
+    assert_eq!(tree.root_hash(), expected.root_hash());
+}
+
+#[test]
+fn art_iter_prefix_mut_matches_scan_prefix() {
+    let mut tree = ArtTree::<(u32, bool)>::new();
+    for i in 0..5000u32 {
+        tree.insert(&(i * 7).to_be_bytes(), (i, false));
+    }
+    for prefix in [
+        &[0, 0, 0x1b][..],
+        &[0, 0, 0x1b, 0x5d],
+        &[0, 0, 0x1b, 0x5e],
+        &[1],
+    ] {
+        let expected: Vec<_> = tree
+            .scan_prefix(prefix)
+            .map(|(key, &(i, _))| (key.to_vec(), i))
+            .collect();
+        let mut tagged = Vec::new();
+        for (key, value) in tree.iter_prefix_mut(prefix) {
+            value.1 = true;
+            tagged.push((key.to_vec(), value.0));
+        }
+        assert_eq!(tagged, expected);
+        assert!(tree.scan_prefix(prefix).all(|(_, &(_, tag))| tag));
+    }
+    assert_eq!(tree.entries().filter(|(_, &(_, tag))| tag).count(), 36);
+
+    // The iterator is lazy, stopping early leaves the rest untouched
+    for (_, value) in tree.iter_prefix_mut(&[0, 0, 0x2a]).take(3) {
+        value.0 = 0;
+    }
+    let zeroed: Vec<_> = tree.scan_prefix(&[0, 0, 0x2a]).map(|(_, v)| v.0).collect();
+    assert_eq!(&zeroed[..3], &[0, 0, 0]);
+    assert!(zeroed[3..].iter().all(|&i| i != 0));
+}
+
+#[test]
+fn art_check_invariants_through_resizes() {
+    let mut tree = ArtTree::new();
+    assert_eq!(tree.check_invariants(), Ok(()));
+    for i in 0..20_000u32 {
+        tree.insert(&*make_interesting_key(i), i);
+        if i % 997 == 0 {
+            assert_eq!(tree.check_invariants(), Ok(()));
+        }
+    }
+    for i in (0..20_000u32).filter(|i| i % 3 != 0) {
+        tree.delete(&*make_interesting_key(i));
+        if i % 997 == 0 {
+            assert_eq!(tree.check_invariants(), Ok(()));
+        }
+    }
+    tree.drain_range(&[2u8, 2][..]..&[5u8][..]).for_each(drop);
+    tree.remove_below(&[1, 11]);
+    assert_eq!(tree.check_invariants(), Ok(()));
+
+    let mut long = ArtTree::new();
+    for i in 0..1_000u32 {
+        let key = format!("{:040}{:04}", 0, i);
+        long.insert(key.as_bytes(), i);
+    }
+    assert_eq!(long.check_invariants(), Ok(()));
+}
+
+#[test]
+fn art_tree_fixed_stores_uuid_keys() {
+    let mut tree = ArtTreeFixed::<16, u32>::default();
+    let mut expected = std::collections::BTreeMap::new();
+    let mut state = 0x9e37_79b9_7f4a_7c15u128;
+    for i in 0..5_000u32 {
+        state = state
+            .wrapping_mul(0x2360_ed05_1fc6_5da4_4385_df64_9fcc_f645)
+            .wrapping_add(1);
+        // Share the leading bytes between groups of keys, like time-ordered UUIDs
+        let uuid = (u128::from(i / 64) << 96 | state >> 32).to_be_bytes();
+        assert_eq!(tree.insert_key(uuid, i), expected.insert(uuid, i));
+    }
+    for uuid in expected.keys().step_by(3) {
+        assert_eq!(tree.delete(uuid), Some(expected[uuid]));
+    }
+    expected = expected
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| i % 3 != 0)
+        .map(|(_, entry)| entry)
+        .collect();
+
+    assert_eq!(tree.len(), expected.len());
+    assert_eq!(tree.check_invariants(), Ok(()));
+    let entries: Vec<_> = tree
+        .entries()
+        .map(|(key, &value)| (key.to_vec(), value))
+        .collect();
+    let expected: Vec<_> = expected
+        .iter()
+        .map(|(key, &value)| (key.to_vec(), value))
+        .collect();
+    assert_eq!(entries, expected);
+    assert_eq!(tree.get(&[0; 4]), None);
+}
+
+#[test]
+#[should_panic(expected = "a key of 3 bytes does not fit a tree of 4-byte keys")]
+fn art_tree_fixed_rejects_keys_of_another_length() {
+    let mut tree = ArtTreeFixed::<4, u32>::default();
+    tree.insert(&[1, 2, 3, 4], 1);
+    tree.insert(&[1, 2, 3], 2);
+}
+
+#[test]
+fn art_tree_inline_mixes_short_and_long_keys() {
+    let mut tree = ArtTreeInline::<u32>::default();
+    let mut expected = std::collections::BTreeMap::new();
+    for i in 0..3_000u32 {
+        // Terminated keys of 5 to 32 bytes, either side of the inline capacity
+        let mut key = vec![b'k'; 4 + (i as usize % 28)];
+        key.extend_from_slice(&i.to_be_bytes());
+        key.push(0);
+        assert_eq!(tree.insert(&key, i), expected.insert(key, i));
+    }
+    for key in expected.keys().step_by(4) {
+        assert_eq!(tree.delete(key), Some(expected[key]));
+    }
+    expected = expected
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| i % 4 != 0)
+        .map(|(_, entry)| entry)
+        .collect();
+
+    assert_eq!(tree.len(), expected.len());
+    assert_eq!(tree.check_invariants(), Ok(()));
+    for (key, value) in &expected {
+        assert_eq!(tree.get(key), Some(value));
+    }
+    let (min, _) = tree.minimum().unwrap();
+    assert_eq!(&min[..], &expected.keys().next().unwrap()[..]);
+    assert!(min.is_inline());
+
+    let (max, _) = tree.pop_last().unwrap();
+    let (expected_max, _) = expected.pop_last().unwrap();
+    assert_eq!(max.len() > INLINE_KEY_CAPACITY, !max.is_inline());
+    assert_eq!(Vec::from(max), expected_max);
+}
+
+#[test]
+fn inline_key_fits_short_keys_without_allocating() {
+    assert_eq!(std::mem::size_of::<InlineKey>(), 24);
+    let short = InlineKey::from(&[7; INLINE_KEY_CAPACITY][..]);
+    let long = InlineKey::from(&[7; INLINE_KEY_CAPACITY + 1][..]);
+    assert!(short.is_inline());
+    assert!(!long.is_inline());
+    assert!(short < long);
+    assert_eq!(InlineKey::from(&[][..]).as_bytes(), &[] as &[u8]);
+}
+
+#[test]
+fn art_tree_arena_interns_keys_into_shared_blocks() {
+    let mut tree = ArtTreeArena::<u32>::default();
+    let mut boxed = ArtTree::<u32>::new();
+    let mut expected = std::collections::BTreeMap::new();
+    for i in 0..20_000u32 {
+        let key = format!("tenants/{:04}/objects/{:08}\0", i % 97, i).into_bytes();
+        assert_eq!(tree.insert(&key, i), expected.insert(key.clone(), i));
+        boxed.insert(&key, i);
+    }
+    for key in expected.keys().step_by(5) {
+        assert_eq!(tree.delete(key), Some(expected[key]));
+        boxed.delete(key);
+    }
+    expected = expected
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| i % 5 != 0)
+        .map(|(_, entry)| entry)
+        .collect();
+
+    assert_eq!(tree.check_invariants(), Ok(()));
+    let entries: Vec<_> = tree
+        .entries()
+        .map(|(key, &value)| (key.to_vec(), value))
+        .collect();
+    let expected: Vec<_> = expected.into_iter().collect();
+    assert_eq!(entries, expected);
+
+    let stats = tree.memory_stats();
+    let boxed_stats = boxed.memory_stats();
+    assert_eq!(stats.leaves, expected.len());
+    assert_eq!(stats.key_bytes, boxed_stats.key_bytes);
+    assert_eq!(boxed_stats.key_allocations, expected.len());
+    // 20000 keys of 30 bytes fit in 10 blocks of 64 KiB
+    assert_eq!(stats.key_allocations, 10);
+    assert_eq!(stats.leaf_bytes, boxed_stats.leaf_bytes);
+
+    // A clone shares the blocks and keeps them alive
+    let clone = tree.clone();
+    drop(tree);
+    let entries: Vec<_> = clone
+        .entries()
+        .map(|(key, &value)| (key.to_vec(), value))
+        .collect();
+    assert_eq!(entries, expected);
+    assert_eq!(clone.memory_stats().key_allocations, 0);
+}
+
+#[test]
+fn art_tree_arena_shares_bytes_of_reinserted_keys() {
+    let mut tree = ArtTreeArena::<u32>::default();
+    tree.insert(b"counter\0", 1);
+    let key = tree.pop_first().unwrap().0;
+    assert_eq!(&key[..], b"counter\0");
+    tree.insert(b"counter\0", 2);
+    tree.delete(b"counter\0");
+    tree.insert(b"counter\0/extended\0", 3);
+
+    let stats = tree.memory_stats();
+    assert_eq!(stats.deduplicated_key_bytes, 16);
+    assert_eq!(stats.key_allocations, 1);
+    assert_eq!(tree.get(b"counter\0/extended\0"), Some(&3));
+    assert_eq!(ArenaKey::from_slice(b"counter\0"), key);
+}
+
+#[test]
+fn art_path_memory_tracks_memory_stats() {
+    let mut tree = ArtTree::<u32>::new();
+    let mut bytes = 0;
+    // Keys of varying length with long shared paths split leaves and compressed paths, and
+    // nodes grow and shrink through all types
+    let key = |i: u32| format!("{}/{}/{}\0", i % 5, "path".repeat(i as usize % 6), i % 300);
+    for i in 0..4_000u32 {
+        let key = key(i.wrapping_mul(7919) % 1_000);
+        let before = tree.path_memory(key.as_bytes());
+        if i % 3 == 2 || (2_000..3_000).contains(&i) {
+            tree.delete(key.as_bytes());
+            bytes -= before - tree.path_memory(key.as_bytes());
+        } else {
+            tree.insert(key.as_bytes(), i);
+            bytes += tree.path_memory(key.as_bytes()) - before;
+        }
+        assert_eq!(bytes, tree.memory_stats().total_bytes(), "step {}", i);
+    }
+    assert_eq!(ArtTree::<u32>::new().path_memory(b"missing"), 0);
+}
+
+#[test]
+fn art_nodes_are_allocated_at_the_size_of_their_type() {
+    // A root of each type, from Node4 to Node256
+    let trees: Vec<_> = [2u8, 10, 20, 40, 100]
+        .iter()
+        .map(|&children| {
+            let mut tree = ArtTree::new();
+            for i in 0..children {
+                tree.insert(&[i, 0], 0u32);
+            }
+            tree
+        })
+        .collect();
+    let kinds: Vec<_> = trees.iter().map(root_kind).collect();
+    assert_eq!(kinds, ["Node4", "Node16", "Node32", "Node48", "Node256"]);
+    let bytes: Vec<_> = trees
+        .iter()
+        .map(|tree| tree.memory_stats().internal_node_bytes)
+        .collect();
+    assert!(bytes.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+#[test]
+fn art_values_stay_in_place_through_structural_changes() {
+    let mut tree = ArtTree::<[u64; 64]>::new();
+    tree.insert(b"pinned\0", [7; 64]);
+    let address = tree.get(b"pinned\0").unwrap() as *const [u64; 64];
+
+    // Grow the nodes around the value up to a Node256, split its paths and shrink them again
+    for i in 0..6_400 {
+        tree.insert(&*make_interesting_key(i), [i as u64; 64]);
+        tree.insert(format!("pinned{}\0", i).as_bytes(), [0; 64]);
+    }
+    for i in 0..6_400 {
+        tree.delete(&*make_interesting_key(i));
+        tree.delete(format!("pinned{}\0", i).as_bytes());
+    }
+    assert_eq!(tree.get(b"pinned\0").unwrap() as *const _, address);
+
+    assert!(tree.rekey(b"pinned\0", b"moved\0"));
+    assert_eq!(tree.get(b"moved\0").unwrap() as *const _, address);
+    assert_eq!(tree.get(b"moved\0"), Some(&[7; 64]));
+    assert_eq!(tree.check_invariants(), Ok(()));
+}
+
+#[test]
+fn art_builder_matches_inserted_tree() {
+    let mut keys: Vec<Vec<u8>> = (0..20_000u32)
+        .map(|i| {
+            let mut key = make_interesting_key(i).to_vec();
+            key.extend_from_slice(format!("/a-long-shared-path/{}\0", i % 300).as_bytes());
+            key
+        })
+        .collect();
+    keys.sort();
+    keys.dedup();
+
+    let mut inserted = ArtTree::<usize>::new();
+    let mut builder = ArtBuilder::new();
+    for (i, key) in keys.iter().enumerate() {
+        inserted.insert(key, i);
+        builder.push(key, i);
+    }
+    assert_eq!(builder.len(), keys.len());
+    let built = builder.finish();
+
+    assert_eq!(built.check_invariants(), Ok(()));
+    assert_eq!(built.len(), inserted.len());
+    assert!(built.entries().eq(inserted.entries()));
+    assert_eq!(built.memory_stats(), inserted.memory_stats());
+    assert_eq!(built.get(&keys[1234]), Some(&1234));
+
+    let mut built = built;
+    built.insert(b"\x09late\0", 0);
+    assert_eq!(built.delete(&keys[0]), Some(0));
+    assert_eq!(built.check_invariants(), Ok(()));
+}
+
+#[test]
+fn art_builder_builds_arena_and_tiny_trees() {
+    let mut builder = ArtBuilder::<u32, ArenaKey>::default();
+    for i in 0..1_000u32 {
+        builder.push(format!("key{:04}\0", i).as_bytes(), i);
+    }
+    let tree = builder.finish();
+    assert_eq!(tree.check_invariants(), Ok(()));
+    assert_eq!(tree.get(b"key0999\0"), Some(&999));
+    assert_eq!(tree.memory_stats().key_allocations, 1);
+
+    assert!(ArtBuilder::<u32>::new().finish().is_empty());
+    let mut builder = ArtBuilder::new();
+    builder.push(b"only\0", 1u32);
+    let tree = builder.finish();
+    assert_eq!(tree.check_invariants(), Ok(()));
+    assert_eq!(tree.get(b"only\0"), Some(&1));
+}
+
+#[test]
+#[should_panic(expected = "keys have to be pushed in ascending order")]
+fn art_builder_rejects_unsorted_keys() {
+    let mut builder = ArtBuilder::new();
+    builder.push(b"b\0", 1u32);
+    builder.push(b"a\0", 2u32);
+}
+
+#[test]
+fn art_scopes_prefix_keys_of_nested_views() {
+    let mut tree = ArtTree::<u32>::new();
+    {
+        let mut tenant = tree.scoped_mut(b"acme/");
+        let mut users = tenant.scoped_mut(b"users/");
+        assert_eq!(users.prefix(), b"acme/users/");
+        assert_eq!(users.insert(b"ann\0", 1), None);
+        assert_eq!(users.insert(b"bob\0", 2), None);
+        *users.get_mut(b"bob\0").unwrap() += 10;
+        tenant.scoped_mut(b"orders/").insert(b"7\0", 7);
+        assert_eq!(tenant.insert(b"users/ann\0", 3), Some(1));
+    }
+    tree.insert(b"acme0\0", 0);
+    tree.insert(b"initech/users/ann\0", 4);
+
+    assert_eq!(tree.get(b"acme/users/bob\0"), Some(&12));
+    let tenant = tree.scoped(b"acme/");
+    let users = tenant.scoped(b"users/");
+    assert_eq!(users.get(b"ann\0"), Some(&3));
+    assert!(!users.contains_key(b"7\0"));
+    assert_eq!(users.len(), 2);
+    assert_eq!(tenant.len(), 3);
+    let entries: Vec<_> = users.entries().collect();
+    assert_eq!(entries, vec![(&b"ann\0"[..], &3), (&b"bob\0"[..], &12)]);
+    assert!(tree.scoped(b"globex/").is_empty());
+
+    let mut tenant = tree.scoped_mut(b"acme/");
+    for (_, value) in tenant.entries_mut() {
+        *value *= 2;
+    }
+    assert_eq!(tenant.as_scope().scoped(b"orders/").get(b"7\0"), Some(&14));
+    assert_eq!(tenant.delete(b"orders/7\0"), Some(14));
+    assert_eq!(tenant.clear(), 2);
+    assert_eq!(tree.len(), 2);
+    assert_eq!(tree.check_invariants(), Ok(()));
+
+    let mut top = ArtTree::<u32>::new();
+    top.insert(&[0xff, 0xff, 1], 1);
+    top.insert(&[0xff, 0xfe], 2);
+    assert_eq!(top.scoped_mut(&[0xff, 0xff]).clear(), 1);
+    assert_eq!(top.len(), 1);
+}
+
+/// Sum and maximum of the values, and the first key byte of every entry in key order
+#[cfg(feature = "aggregate")]
+struct Stats;
+
+#[cfg(feature = "aggregate")]
+impl Aggregate<u32> for Stats {
+    type Summary = (u64, u32, Vec<u8>);
+
+    fn empty(&self) -> Self::Summary {
+        (0, 0, Vec::new())
+    }
+
+    fn leaf(&self, key: &[u8], value: &u32) -> Self::Summary {
+        (*value as u64, *value, vec![key[0]])
+    }
+
+    fn combine(&self, left: &Self::Summary, right: &Self::Summary) -> Self::Summary {
+        let mut firsts = left.2.clone();
+        firsts.extend_from_slice(&right.2);
+        (left.0 + right.0, left.1.max(right.1), firsts)
+    }
+}
+
+#[cfg(feature = "aggregate")]
+fn check_prefix_aggregates(tree: &AnnotatedArtTree<u32, Stats>) {
+    let prefixes = [
+        &[][..],
+        &[0],
+        &[0, 0],
+        &[1, 1],
+        &[3, 13, 3],
+        &[9, 9, 9, 9],
+        &[7; 5],
+    ];
+    for prefix in prefixes {
+        let expected = tree
+            .scan_prefix(prefix)
+            .fold(Stats.empty(), |acc, (key, value)| {
+                Stats.combine(&acc, &Stats.leaf(key, value))
+            });
+        assert_eq!(tree.aggregate_prefix(prefix), expected, "{:?}", prefix);
+    }
+    assert_eq!(tree.summary(), tree.aggregate_prefix(&[]));
+}
+
+#[cfg(feature = "aggregate")]
+#[test]
+fn art_aggregates_follow_mutations() {
+    let mut tree = AnnotatedArtTree::new(Stats);
+    check_prefix_aggregates(&tree);
+    for i in 0..3000u32 {
+        tree.insert(&make_interesting_key(i)[..], i);
+    }
+    check_prefix_aggregates(&tree);
+
+    for i in (0..3000u32).step_by(3) {
+        tree.delete(&make_interesting_key(i)[..]);
+    }
+    *tree.get_mut(&make_interesting_key(1)[..]).unwrap() += 100_000;
+    check_prefix_aggregates(&tree);
+    assert_eq!(tree.summary().1, 100_001);
+
+    for (_, value) in tree.iter_prefix_mut(&[2]) {
+        *value = 0;
+    }
+    tree.scoped_mut(&[5]).clear();
+    check_prefix_aggregates(&tree);
+
+    // Another aggregate over the same nodes is computed without the cached summaries
+    let counted = AnnotatedArtTree::with_tree((*tree).clone(), Count);
+    assert_eq!(counted.summary(), tree.len());
+    assert_eq!(counted.aggregate_prefix(&[5]), 0);
+    assert_eq!(tree.aggregate_prefix(&[4]).0, {
+        tree.scan_prefix(&[4]).map(|(_, v)| *v as u64).sum::<u64>()
+    });
+}
+
+#[cfg(feature = "aggregate")]
+#[test]
+fn art_aggregates_recompute_only_changed_paths() {
+    use std::cell::Cell;
+
+    /// Counts the entries and the summaries computed for leaves
+    struct CountingLeaves(Cell<usize>);
+
+    impl Aggregate<u32> for CountingLeaves {
+        type Summary = usize;
+
+        fn empty(&self) -> usize {
+            0
+        }
+
+        fn leaf(&self, _key: &[u8], _value: &u32) -> usize {
+            self.0.set(self.0.get() + 1);
+            1
+        }
+
+        fn combine(&self, left: &usize, right: &usize) -> usize {
+            left + right
+        }
+    }
+
+    let mut tree = AnnotatedArtTree::new(CountingLeaves(Cell::new(0)));
+    for i in 0..10_000u32 {
+        tree.insert(&i.to_be_bytes(), i);
+    }
+    assert_eq!(tree.summary(), 10_000);
+    assert_eq!(tree.aggregate().0.replace(0), 10_000);
+    assert_eq!(tree.aggregate_prefix(&[0, 0, 7]), 256);
+    assert_eq!(tree.aggregate().0.get(), 0);
+
+    tree.delete(&1234u32.to_be_bytes());
+    assert_eq!(tree.summary(), 9_999);
+    // Only the leaves below the last changed node are summarized again
+    assert!(tree.aggregate().0.get() <= 256);
+}
+
+#[cfg(feature = "aggregate")]
+#[test]
+fn art_aggregate_range_matches_scan() {
+    use std::ops::Bound;
+
+    let mut tree = AnnotatedArtTree::new(Stats);
+    for i in 0..4000u32 {
+        tree.insert(&make_interesting_key(i * 3)[..], i);
+    }
+    let bounds = [
+        vec![],
+        vec![0],
+        vec![0, 0, 0, 0],
+        vec![1, 11, 11],
+        vec![2, 2, 2, 2],
+        vec![3, 13],
+        vec![5, 15, 45, 255],
+        vec![9, 19, 49, 255, 0],
+        vec![255],
+    ];
+    for start in &bounds {
+        for end in &bounds {
+            let ranges = [
+                (Bound::Included(&start[..]), Bound::Excluded(&end[..])),
+                (Bound::Excluded(&start[..]), Bound::Included(&end[..])),
+                (Bound::Included(&start[..]), Bound::Unbounded),
+                (Bound::Unbounded, Bound::Excluded(&end[..])),
+            ];
+            for range in ranges {
+                if start > end && range.0 != Bound::Unbounded && range.1 != Bound::Unbounded {
+                    continue;
+                }
+                let expected = tree.range(range).fold(Stats.empty(), |acc, (key, value)| {
+                    Stats.combine(&acc, &Stats.leaf(key, value))
+                });
+                assert_eq!(tree.aggregate_range(range), expected, "{:?}", range);
+            }
+        }
+    }
+    let all =
+        tree.aggregate_range::<(Bound<&[u8]>, Bound<&[u8]>)>((Bound::Unbounded, Bound::Unbounded));
+    assert_eq!(all, tree.summary());
+    assert_eq!(tree.aggregate_range(&[1][..]..&[1][..]), Stats.empty());
+}
+
+/// Returns the type of the root node as printed by `debug_print`
+fn root_kind(tree: &ArtTree<u32>) -> String {
+    let print = tree.debug_print().max_depth(0).to_string();
+    print.split(' ').next().unwrap().to_string()
+}
+
+// With the `adaptive` feature the resize points also depend on the accesses of the nodes
+#[cfg(not(feature = "adaptive"))]
+#[test]
+fn art_node_sizing_moves_the_resize_points() {
+    let sizing = NodeSizing::FAST;
+    let mut fast = ArtTree::with_node_sizing(sizing);
+    let mut default = ArtTree::new();
+    assert_eq!(fast.node_sizing(), sizing);
+    assert_eq!(default.node_sizing(), NodeSizing::default());
+    for i in 0..=sizing.grow32 as u8 {
+        fast.insert(&[i, 0], 0);
+        default.insert(&[i, 0], 0);
+    }
+    assert_eq!(root_kind(&fast), "Node48");
+    assert_eq!(root_kind(&default), "Node16");
+
+    // The fast sizing keeps the node until only a few children are left
+    for i in (sizing.shrink48 + 1)..=sizing.grow32 {
+        fast.delete(&[i as u8, 0]);
+    }
+    assert_eq!(root_kind(&fast), "Node48");
+    fast.delete(&[sizing.shrink48 as u8, 0]);
+    assert_eq!(root_kind(&fast), "Node32");
+    fast.delete(&[sizing.shrink32 as u8, 0]);
+    assert_eq!(root_kind(&fast), "Node16");
+
+    let mut compact = ArtTree::with_node_sizing(NodeSizing::COMPACT);
+    for i in 0..48 {
+        compact.insert(&[i, 0], 0);
+    }
+    compact.delete(&[47, 0]);
+    assert_eq!(root_kind(&compact), "Node48");
+    compact.insert(&[47, 0], 0);
+    compact.insert(&[48, 0], 0);
+    compact.delete(&[48, 0]);
+    assert_eq!(root_kind(&compact), "Node256");
+    compact.delete(&[47, 0]);
+    assert_eq!(root_kind(&compact), "Node48");
+}
+
+#[test]
+fn art_detach_range_rebuilds_nodes_with_the_tree_sizing() {
+    let mut tree = ArtTree::with_node_sizing(NodeSizing::FAST);
+    for i in 0..30u8 {
+        tree.insert(&[i, 0], i as u32);
+    }
+    // 20 children fit a Node32 with the default sizing but grow past it with the fast one
+    assert_eq!(tree.drain_range(&[20u8][..]..).count(), 10);
+    assert_eq!(root_kind(&tree), "Node48");
+    assert_eq!(tree.check_invariants(), Ok(()));
+}
+
+#[test]
+fn art_node32_sits_between_node16_and_node48() {
+    let mut tree = ArtTree::new();
+    let mut kinds = Vec::new();
+    // Insert out of order to shift the sorted keys
+    for i in (0..40u32).map(|i| i * 37 % 40) {
+        tree.insert(&[i as u8 * 3, 1], i);
+        kinds.push(root_kind(&tree));
+    }
+    assert_eq!(kinds[16], "Node32");
+    assert_eq!(kinds[31], "Node32");
+    assert_eq!(kinds[32], "Node48");
+    assert_eq!(tree.check_invariants(), Ok(()));
+    for i in 0..40u32 {
+        assert_eq!(tree.get(&[i as u8 * 3, 1]), Some(&i));
+        assert_eq!(tree.get(&[i as u8 * 3 + 1, 1]), None);
+    }
+    let keys: Vec<_> = tree.entries().map(|(k, _)| k[0]).collect();
+    assert_eq!(keys, (0..40).map(|i| i * 3).collect::<Vec<u8>>());
+    assert_eq!(tree.minimum().map(|(k, _)| k[0]), Some(0));
+    assert_eq!(tree.maximum().map(|(k, _)| k[0]), Some(117));
+
+    // Node48 shrinks into a Node32 at 24 children and that into a Node16 at 12
+    let mut kinds = Vec::new();
+    for i in 0..30u32 {
+        tree.delete(&[i as u8 * 3, 1]);
+        kinds.push((tree.len(), root_kind(&tree)));
+    }
+    assert!(kinds.contains(&(25, "Node48".to_string())));
+    assert!(kinds.contains(&(24, "Node32".to_string())));
+    assert!(kinds.contains(&(13, "Node32".to_string())));
+    assert!(kinds.contains(&(12, "Node16".to_string())));
+    assert_eq!(tree.check_invariants(), Ok(()));
+    let values: Vec<_> = tree.entries().map(|(_, &v)| v).collect();
+    assert_eq!(values, (30..40).collect::<Vec<u32>>());
+}
+
+#[test]
+fn art_node_sizing_keeps_invariants() {
+    let custom = NodeSizing {
+        grow4: 3,
+        grow16: 5,
+        grow32: 6,
+        grow48: 7,
+        shrink16: 2,
+        shrink32: 3,
+        shrink48: 4,
+        shrink256: 5,
+        ..NodeSizing::DEFAULT
+    };
+    for &sizing in [NodeSizing::COMPACT, NodeSizing::FAST, custom].iter() {
+        let mut tree = ArtTree::with_node_sizing(sizing);
+        let mut expected = std::collections::BTreeMap::new();
+        for i in 0..5_000u32 {
+            let key = make_interesting_key(i * 7919);
+            tree.insert(key.as_ref(), i);
+            expected.insert(key.to_vec(), i);
+        }
+        assert_eq!(tree.check_invariants(), Ok(()));
+        for i in (0..5_000u32).filter(|i| i % 3 != 0) {
+            let key = make_interesting_key(i * 7919);
+            assert_eq!(tree.delete(key.as_ref()), expected.remove(&key.to_vec()));
+        }
+        assert_eq!(tree.check_invariants(), Ok(()));
+        assert!(tree
+            .entries()
+            .map(|(k, &v)| (k.to_vec(), v))
+            .eq(expected.into_iter()));
+    }
+
+    // Changing the sizing of a populated tree applies to later resizes
+    let mut tree = ArtTree::new();
+    for i in 0..1_000u32 {
+        tree.insert(&*make_interesting_key(i), i);
+    }
+    tree.set_node_sizing(custom);
+    for i in 1_000..2_000u32 {
+        tree.insert(&*make_interesting_key(i), i);
+    }
+    for i in (0..2_000u32).step_by(2) {
+        tree.delete(&*make_interesting_key(i));
+    }
+    assert_eq!(tree.check_invariants(), Ok(()));
+    assert_eq!(tree.len(), 1_000);
+}
+
+#[test]
+#[should_panic(expected = "shrink16 is 4, expected 2 to 3")]
+fn art_node_sizing_rejects_thrashing_points() {
+    ArtTree::<u32>::with_node_sizing(NodeSizing {
+        shrink16: 4,
+        ..NodeSizing::DEFAULT
+    });
+}
+
+#[cfg(feature = "adaptive")]
+#[test]
+fn art_adaptive_sizing_keeps_hot_nodes_large() {
+    let sizing = NodeSizing {
+        hot_accesses: 16,
+        ..NodeSizing::DEFAULT
+    };
+    let mut tree = ArtTree::with_node_sizing(sizing);
+    for i in 0..40u8 {
+        tree.insert(&[i, 0], 0);
+    }
+    assert_eq!(root_kind(&tree), "Node48");
+    for _ in 0..100 {
+        assert_eq!(tree.get(&[0, 0]), Some(&0));
+    }
+
+    // The hot root keeps its type below the shrink point, cooling down with every delete
+    for i in (sizing.shrink48 as u8..40).rev() {
+        tree.delete(&[i, 0]);
+    }
+    assert_eq!(tree.len(), sizing.shrink48 as usize);
+    assert_eq!(root_kind(&tree), "Node48");
+    tree.delete(&[23, 0]);
+    tree.delete(&[22, 0]);
+    assert_eq!(root_kind(&tree), "Node48");
+    tree.delete(&[21, 0]);
+    assert_eq!(root_kind(&tree), "Node32");
+    assert_eq!(tree.check_invariants(), Ok(()));
+
+    // A cold tree shrinks at the shrink point
+    let mut cold = ArtTree::with_node_sizing(NodeSizing {
+        hot_accesses: 1_000,
+        ..NodeSizing::DEFAULT
+    });
+    for i in 0..40u8 {
+        cold.insert(&[i, 0], 0);
+    }
+    for i in (sizing.shrink48 as u8..40).rev() {
+        cold.delete(&[i, 0]);
+    }
+    assert_eq!(root_kind(&cold), "Node32");
+}
+
+#[cfg(feature = "adaptive")]
+#[test]
+fn art_adaptive_sizing_grows_only_hot_nodes_early() {
+    let sizing = NodeSizing {
+        hot_accesses: 100,
+        ..NodeSizing::FAST
+    };
+    let mut cold = ArtTree::with_node_sizing(sizing);
+    let mut hot = ArtTree::with_node_sizing(sizing);
+    for i in 0..4u8 {
+        cold.insert(&[i, 0], 0);
+        hot.insert(&[i, 0], 0);
+    }
+    for _ in 0..100 {
+        hot.get(&[0, 0]);
+    }
+    for i in 4..=sizing.grow32 as u8 {
+        cold.insert(&[i, 0], 0);
+        hot.insert(&[i, 0], 0);
+    }
+    assert_eq!(root_kind(&cold), "Node16");
+    assert_eq!(root_kind(&hot), "Node48");
+    for i in 0..=16u8 {
+        cold.insert(&[i, 0], 0);
+    }
+    assert_eq!(root_kind(&cold), "Node32");
+}
+
+#[test]
+fn art_optimize_rebuilds_nodes_into_their_ideal_types() {
+    let mut tree = ArtTree::new();
+    for i in 0..40u8 {
+        tree.insert(&[i, 0], u32::from(i));
+    }
+    for i in 25..40u8 {
+        tree.delete(&[i, 0]);
+    }
+    // Deleting down to 25 children keeps the Node48
+    assert_eq!(root_kind(&tree), "Node48");
+    let report = tree.optimize();
+    assert_eq!(root_kind(&tree), "Node32");
+    assert_eq!(report.before.node48, 1);
+    assert_eq!(report.after.node48, 0);
+    assert_eq!(report.after.node32, 1);
+    assert_eq!(report.after.children, 25);
+    assert_eq!(report.after, tree.node_stats());
+    assert_eq!(tree.optimize().before, report.after);
+
+    let key = |i: u32| i.wrapping_mul(2_654_435_761).to_be_bytes();
+    let mut tree = ArtTree::new();
+    for i in 0..20_000u32 {
+        tree.insert(&key(i), i);
+    }
+    for i in (0..20_000u32).filter(|i| i % 5 != 0) {
+        tree.delete(&key(i));
+    }
+    let expected: Vec<_> = tree.entries().map(|(k, &v)| (k.to_vec(), v)).collect();
+    let report = tree.optimize();
+    assert_eq!(report.after.children, report.before.children);
+    assert_eq!(
+        report.after.internal_nodes(),
+        report.before.internal_nodes()
+    );
+    assert!(report.after.slots < report.before.slots);
+    assert!(report.after.fill_ratio() > report.before.fill_ratio());
+    assert_eq!(tree.check_invariants(), Ok(()));
+    assert_eq!(tree.len(), expected.len());
+    assert!(tree
+        .entries()
+        .map(|(k, &v)| (k.to_vec(), v))
+        .eq(expected.into_iter()));
+    assert_eq!(tree.get(&key(5)), Some(&5));
+
+    let mut empty = ArtTree::<u32>::new();
+    assert_eq!(empty.optimize(), OptimizeReport::default());
+    assert_eq!(empty.node_stats().fill_ratio(), 1.0);
+}
+
+#[test]
+fn art_children_of_lists_the_next_bytes() {
+    let mut tree = ArtTree::new();
+    for (i, key) in [
+        "etc/hosts\0",
+        "etc/passwd\0",
+        "usr/bin/cc\0",
+        "usr/bin/ld\0",
+        "usr/lib/libc\0",
+        "var/log/kern.log\0",
+    ]
+    .iter()
+    .enumerate()
+    {
+        tree.insert(key.as_bytes(), i);
+    }
+
+    assert_eq!(
+        tree.children_of(b""),
+        vec![
+            (b'e', Branch::Subtree),
+            (b'u', Branch::Subtree),
+            (
+                b'v',
+                Branch::Leaf {
+                    key: &b"var/log/kern.log\0"[..],
+                    value: &5
+                }
+            ),
+        ]
+    );
+    assert_eq!(
+        tree.children_of(b"etc/"),
+        vec![
+            (
+                b'h',
+                Branch::Leaf {
+                    key: &b"etc/hosts\0"[..],
+                    value: &0
+                }
+            ),
+            (
+                b'p',
+                Branch::Leaf {
+                    key: &b"etc/passwd\0"[..],
+                    value: &1
+                }
+            ),
+        ]
+    );
+    // The prefix ends inside the compressed path "sr/" of the node under 'u'
+    assert_eq!(tree.children_of(b"us"), vec![(b'r', Branch::Subtree)]);
+    assert_eq!(
+        tree.children_of(b"usr/"),
+        vec![
+            (b'b', Branch::Subtree),
+            (
+                b'l',
+                Branch::Leaf {
+                    key: &b"usr/lib/libc\0"[..],
+                    value: &4
+                }
+            )
+        ]
+    );
+    assert_eq!(
+        tree.children_of(b"var/lo"),
+        vec![(
+            b'g',
+            Branch::Leaf {
+                key: &b"var/log/kern.log\0"[..],
+                value: &5
+            }
+        )]
+    );
+    assert!(tree.children_of(b"usr/bin/cc\0").is_empty());
+    assert!(tree.children_of(b"opt/").is_empty());
+    assert!(ArtTree::<u32>::new().children_of(b"").is_empty());
+}
+
+#[test]
+fn art_common_prefix_of_all_keys_and_of_a_range() {
+    let mut tree = ArtTree::new();
+    assert_eq!(tree.common_prefix(), b"");
+    assert_eq!(tree.common_prefix_range::<std::ops::RangeFull>(..), b"");
+
+    tree.insert(b"user/0042/name\0", 0);
+    assert_eq!(tree.common_prefix(), b"user/0042/name\0");
+    let keys = [
+        "user/0042/mail\0",
+        "user/0042/phone\0",
+        "user/0107/name\0",
+        "user/0107/mail\0",
+        "user/0150/name\0",
+        "user/0999/name\0",
+    ];
+    for (i, key) in keys.iter().enumerate() {
+        tree.insert(key.as_bytes(), i + 1);
+    }
+    assert_eq!(tree.common_prefix(), b"user/0");
+
+    let prefix = |start: &str, end: &str| {
+        tree.common_prefix_range(start.as_bytes()..end.as_bytes())
+            .to_vec()
+    };
+    assert_eq!(prefix("user/0042/", "user/0043/"), b"user/0042/");
+    assert_eq!(prefix("user/0100", "user/0200"), b"user/01");
+    // The end bound excludes the only key that would shorten the prefix
+    assert_eq!(prefix("user/0107", "user/0150/name\0"), b"user/0107/");
+    assert_eq!(
+        prefix("user/0107/mail\0", "user/0107/n"),
+        b"user/0107/mail\0"
+    );
+    assert_eq!(prefix("user/1", "user/2"), b"");
+    assert_eq!(
+        tree.common_prefix_range(&b"user/0107/mail\0"[..]..=&b"user/0150/name\0"[..]),
+        b"user/01"
+    );
+    assert_eq!(tree.common_prefix_range(&b"user/0150"[..]..), b"user/0");
+
+    // The prefix of all keys agrees with the prefix of the full range, even when the path of the
+    // root is longer than the prefix stored inline in the node
+    let mut tree = ArtTree::new();
+    for i in 0..1_000u32 {
+        tree.insert(format!("tenant-7/events/2024/{:05}\0", i * 7).as_bytes(), i);
+    }
+    assert_eq!(tree.common_prefix(), b"tenant-7/events/2024/0");
+    assert_eq!(
+        tree.common_prefix_range::<std::ops::RangeFull>(..),
+        tree.common_prefix()
+    );
+}
@@ -0,0 +1,76 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::art_bi_map::*;
+
+fn make_map() -> ArtBiMap<String> {
+    let mut map = ArtBiMap::new();
+    for (id, name) in [(1u32, "ann"), (2, "anne"), (3, "bob")] {
+        assert_eq!(
+            map.insert(&id.to_be_bytes(), name.to_string()),
+            Overwritten::Neither
+        );
+    }
+    map
+}
+
+#[test]
+fn test_lookups_in_both_directions() {
+    let map = make_map();
+    assert_eq!(map.len(), 3);
+    assert_eq!(map.get_by_key(&2u32.to_be_bytes()).unwrap(), "anne");
+    assert_eq!(map.get_by_value(b"ann"), Some(&1u32.to_be_bytes()[..]));
+    assert_eq!(map.get_by_value(b"an"), None);
+    assert!(map.contains_value(b"bob"));
+    assert!(!map.contains_key(&4u32.to_be_bytes()));
+
+    let names: Vec<_> = map.iter().map(|(_, name)| name.as_str()).collect();
+    assert_eq!(names, ["ann", "anne", "bob"]);
+    let ids: Vec<_> = map.iter_by_value().map(|(id, _)| id[3]).collect();
+    assert_eq!(ids, [1, 2, 3]);
+}
+
+#[test]
+fn test_insert_replaces_pairs_of_the_key_and_the_value() {
+    let mut map = make_map();
+    let id = |id: u32| Box::<[u8]>::from(&id.to_be_bytes()[..]);
+
+    assert_eq!(
+        map.insert(&1u32.to_be_bytes(), "ann".to_string()),
+        Overwritten::Pair(id(1), "ann".to_string())
+    );
+    assert_eq!(
+        map.insert(&1u32.to_be_bytes(), "carl".to_string()),
+        Overwritten::Key(id(1), "ann".to_string())
+    );
+    assert!(!map.contains_value(b"ann"));
+    assert_eq!(
+        map.insert(&4u32.to_be_bytes(), "bob".to_string()),
+        Overwritten::Value(id(3), "bob".to_string())
+    );
+    assert_eq!(map.get_by_key(&3u32.to_be_bytes()), None);
+    assert_eq!(
+        map.insert(&2u32.to_be_bytes(), "carl".to_string()),
+        Overwritten::Both((id(2), "anne".to_string()), (id(1), "carl".to_string()))
+    );
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.get_by_value(b"carl"), Some(&2u32.to_be_bytes()[..]));
+}
+
+#[test]
+fn test_removal_keeps_both_directions_in_sync() {
+    let mut map = make_map();
+    assert_eq!(
+        map.remove_by_value(b"anne"),
+        Some((Box::from(&2u32.to_be_bytes()[..]), "anne".to_string()))
+    );
+    assert!(!map.contains_key(&2u32.to_be_bytes()));
+    assert_eq!(map.remove_by_value(b"anne"), None);
+
+    assert_eq!(
+        map.remove_by_key(&1u32.to_be_bytes()).map(|(_, name)| name),
+        Some("ann".to_string())
+    );
+    assert!(!map.contains_value(b"ann"));
+    assert_eq!(map.len(), 1);
+    assert_eq!(format!("{:?}", map), "{[0, 0, 0, 3]: \"bob\"}");
+}
@@ -0,0 +1,96 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::art_bit_set::ArtBitSet;
+use rand::Rng;
+use std::collections::BTreeSet;
+
+/// Returns clusters of ids around random bases
+fn make_clustered_ids(rng: &mut impl Rng, clusters: usize) -> Vec<u64> {
+    let mut ids = Vec::new();
+    for _ in 0..clusters {
+        let base = rng.gen::<u64>() >> rng.gen_range(0..64);
+        let len = rng.gen_range(1..300);
+        ids.extend((0..len).filter_map(|i| base.checked_add(i * rng.gen_range(1..4))));
+    }
+    ids
+}
+
+#[test]
+fn art_bit_set_matches_btree_set() {
+    let mut rng = rand::thread_rng();
+    let mut set = ArtBitSet::new();
+    let mut model = BTreeSet::new();
+    let ids = make_clustered_ids(&mut rng, 50);
+
+    for &id in &ids {
+        assert_eq!(set.insert(id), model.insert(id));
+    }
+    for &id in ids.iter().step_by(3) {
+        assert_eq!(set.remove(id), model.remove(&id));
+        assert!(!set.remove(id));
+    }
+    assert_eq!(set.len(), model.len());
+    assert!(set.iter().eq(model.iter().copied()));
+    assert_eq!(set.first(), model.iter().next().copied());
+    assert_eq!(set.last(), model.iter().next_back().copied());
+
+    for &id in &ids {
+        assert_eq!(set.contains(id), model.contains(&id));
+        let probe = id.wrapping_add(rng.gen_range(0..200)).wrapping_sub(100);
+        assert_eq!(set.contains(probe), model.contains(&probe));
+        assert_eq!(
+            set.next_set_after(probe),
+            model
+                .range(probe.saturating_add(1)..)
+                .next()
+                .copied()
+                .filter(|_| probe != u64::MAX),
+            "after {}",
+            probe
+        );
+    }
+}
+
+#[test]
+fn art_bit_set_edges() {
+    let mut set: ArtBitSet = vec![0, 63, 64, u64::MAX].into_iter().collect();
+    assert_eq!(set.len(), 4);
+    assert_eq!(set.next_set_after(0), Some(63));
+    assert_eq!(set.next_set_after(63), Some(64));
+    assert_eq!(set.next_set_after(64), Some(u64::MAX));
+    assert_eq!(set.next_set_after(u64::MAX), None);
+    assert_eq!(set.last(), Some(u64::MAX));
+    assert_eq!(format!("{:?}", set), "{0, 63, 64, 18446744073709551615}");
+
+    for x in [0, 63, 64, u64::MAX] {
+        assert!(set.remove(x));
+    }
+    assert!(set.is_empty());
+    assert_eq!(set.first(), None);
+    assert_eq!(set.next_set_after(0), None);
+    assert_eq!(set, ArtBitSet::new());
+}
+
+#[test]
+fn art_bit_set_union_and_intersection() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..20 {
+        let a_ids = make_clustered_ids(&mut rng, 10);
+        let mut b_ids = make_clustered_ids(&mut rng, 10);
+        b_ids.extend(a_ids.iter().filter(|_| rng.gen_bool(0.3)));
+        let a: ArtBitSet = a_ids.iter().copied().collect();
+        let b: ArtBitSet = b_ids.iter().copied().collect();
+        let a_model: BTreeSet<_> = a_ids.into_iter().collect();
+        let b_model: BTreeSet<_> = b_ids.into_iter().collect();
+
+        let union = a.union(&b);
+        assert!(union.iter().eq(a_model.union(&b_model).copied()));
+        assert_eq!(union.len(), a_model.union(&b_model).count());
+        let intersection = a.intersection(&b);
+        assert!(intersection
+            .iter()
+            .eq(a_model.intersection(&b_model).copied()));
+        assert_eq!(intersection.len(), a_model.intersection(&b_model).count());
+        assert_eq!(intersection, b.intersection(&a));
+    }
+}
@@ -0,0 +1,80 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::art_index::*;
+
+#[derive(Debug, Clone, PartialEq)]
+struct User {
+    name: String,
+    team: &'static str,
+}
+
+fn user(name: &str, team: &'static str) -> User {
+    User {
+        name: name.to_string(),
+        team,
+    }
+}
+
+fn make_users() -> Vec<User> {
+    vec![
+        user("ann", "red"),
+        user("bob", "blue"),
+        user("anne", "red"),
+        user("ann", "green"),
+        user("", "blue"),
+    ]
+}
+
+#[test]
+fn test_lookup_returns_positions_of_all_items_with_a_key() {
+    let users = make_users();
+    let by_name = ArtIndex::with_items(&users, |user: &User| user.name.clone());
+
+    assert_eq!(by_name.len(), 5);
+    assert_eq!(by_name.positions(b"ann"), &[0, 3]);
+    assert_eq!(by_name.positions(b"anne"), &[2]);
+    assert_eq!(by_name.first_position(b""), Some(4));
+    assert_eq!(by_name.positions(b"an"), &[] as &[usize]);
+    assert!(!by_name.contains_key(b"carl"));
+
+    // "ann" is a prefix of "anne", which the encoding of the keys allows
+    assert_eq!(
+        by_name.positions_with_prefix(b"an").collect::<Vec<_>>(),
+        vec![0, 3, 2]
+    );
+    assert_eq!(by_name.positions_with_prefix(b"").count(), 5);
+}
+
+#[test]
+fn test_insert_and_remove_follow_the_collection() {
+    let mut users = make_users();
+    let mut by_team = ArtIndex::new(|user: &User| user.team);
+    for (position, user) in users.iter().enumerate() {
+        by_team.insert_item(position, user);
+    }
+    assert_eq!(by_team.positions(b"red"), &[0, 2]);
+
+    assert!(by_team.remove_item(0, &users[0]));
+    assert!(!by_team.remove_item(0, &users[0]));
+    assert!(!by_team.remove_item(1, &users[0]));
+    assert_eq!(by_team.positions(b"red"), &[2]);
+    by_team.insert_item(0, &users[0]);
+
+    // Removing "bob" moves the last user, who is also in the blue team, to position 1
+    by_team.swap_remove_item(&users, 1);
+    users.swap_remove(1);
+    assert_eq!(by_team.positions(b"blue"), &[1]);
+    assert_eq!(users[1], user("", "blue"));
+
+    by_team.swap_remove_item(&users, 3);
+    users.swap_remove(3);
+    assert_eq!(by_team.len(), users.len());
+    assert!(!by_team.contains_key(b"green"));
+
+    users.push(user("carl", "green"));
+    users.reverse();
+    by_team.rebuild(&users);
+    assert_eq!(by_team.positions(b"red"), &[1, 3]);
+    assert_eq!(by_team.positions(b"green"), &[0]);
+    assert_eq!(by_team.len(), 4);
+}
@@ -0,0 +1,93 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::art_interval_map::*;
+
+fn sample_map() -> ArtIntervalMap<&'static str> {
+    let mut map = ArtIntervalMap::new();
+    assert!(map.insert(b"apple", b"banana", "a").is_ok());
+    assert!(map.insert(b"cherry", b"grape", "c").is_ok());
+    assert!(map.insert(b"melon", b"peach", "m").is_ok());
+    map
+}
+
+#[test]
+fn test_lookup_finds_covering_interval() {
+    let map = sample_map();
+
+    assert_eq!(map.lookup(b"apple").map(|(_, _, v)| *v), Some("a"));
+    assert_eq!(map.lookup(b"avocado").map(|(_, _, v)| *v), Some("a"));
+    assert_eq!(map.lookup(b"banana"), None);
+    assert_eq!(map.lookup(b"date").map(|(_, _, v)| *v), Some("c"));
+    assert_eq!(map.lookup(b"kiwi"), None);
+    assert_eq!(map.lookup(b"aardvark"), None);
+    assert_eq!(map.lookup(b"zucchini"), None);
+}
+
+#[test]
+fn test_overlapping_insert_is_rejected() {
+    let mut map = sample_map();
+
+    assert_eq!(map.insert(b"fig", b"kiwi", "f"), Err("f"));
+    assert_eq!(map.insert(b"kiwi", b"nectarine", "k"), Err("k"));
+    assert_eq!(map.insert(b"lemon", b"lemon", "l"), Err("l"));
+    assert!(map.insert(b"grape", b"lemon", "g").is_ok());
+    assert_eq!(map.len(), 4);
+}
+
+#[test]
+fn test_overlaps_returns_intersecting_intervals() {
+    let map = sample_map();
+
+    let starts: Vec<_> = map
+        .overlaps(b"date", b"orange")
+        .map(|(start, _, _)| start.to_vec())
+        .collect();
+    assert_eq!(starts, vec![b"cherry".to_vec(), b"melon".to_vec()]);
+
+    assert_eq!(map.overlaps(b"grape", b"melon").count(), 0);
+}
+
+#[test]
+fn test_remove() {
+    let mut map = sample_map();
+
+    let (end, value) = map.remove(b"cherry").unwrap();
+    assert_eq!(&*end, b"grape");
+    assert_eq!(value, "c");
+    assert_eq!(map.lookup(b"date"), None);
+    assert_eq!(map.iter().count(), 2);
+}
+
+#[test]
+fn test_adjacent_intervals_with_bounds_that_are_prefixes() {
+    let mut map = ArtIntervalMap::new();
+    assert!(map.insert(b"a", b"aa", 1).is_ok());
+    assert!(map.insert(b"aa", b"b", 2).is_ok());
+    assert!(map.insert(b"", b"a", 0).is_ok());
+    assert!(map.insert(b"b", b"b\0", 3).is_ok());
+    assert_eq!(map.insert(b"a\0", b"a\x01", 9), Err(9));
+    assert_eq!(map.len(), 4);
+
+    assert_eq!(map.lookup(b""), Some((&b""[..], &b"a"[..], &0)));
+    assert_eq!(map.lookup(b"a").map(|(_, _, v)| *v), Some(1));
+    assert_eq!(map.lookup(b"a\x60").map(|(_, _, v)| *v), Some(1));
+    assert_eq!(map.lookup(b"aa").map(|(_, _, v)| *v), Some(2));
+    assert_eq!(map.lookup(b"ab").map(|(_, _, v)| *v), Some(2));
+    assert_eq!(map.lookup(b"b").map(|(_, _, v)| *v), Some(3));
+    assert_eq!(map.lookup(b"b\0"), None);
+
+    let starts: Vec<_> = map
+        .overlaps(b"a\0", b"aa\0")
+        .map(|(start, _, _)| start.to_vec())
+        .collect();
+    assert_eq!(starts, vec![b"a".to_vec(), b"aa".to_vec()]);
+    let starts: Vec<_> = map.iter().map(|(start, _, _)| start.to_vec()).collect();
+    assert_eq!(
+        starts,
+        vec![b"".to_vec(), b"a".to_vec(), b"aa".to_vec(), b"b".to_vec()]
+    );
+
+    assert_eq!(map.remove(b"a"), Some((b"aa".to_vec().into(), 1)));
+    assert_eq!(map.lookup(b"a"), None);
+    assert_eq!(map.lookup(b"aa").map(|(_, _, v)| *v), Some(2));
+}
@@ -0,0 +1,87 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::art_map::*;
+use std::collections::BTreeMap;
+
+/// Orders signed integers by flipping the sign bit of their big-endian bytes
+struct SignFlip;
+
+impl KeyEncoder<i64> for SignFlip {
+    type Encoded = [u8; 8];
+
+    fn encode(key: &i64) -> Self::Encoded {
+        ((*key as u64) ^ (1 << 63)).to_be_bytes()
+    }
+}
+
+impl KeyDecoder<i64> for SignFlip {
+    fn decode(bytes: &[u8]) -> i64 {
+        let mut key = [0; 8];
+        key.copy_from_slice(bytes);
+        (u64::from_be_bytes(key) ^ (1 << 63)) as i64
+    }
+}
+
+/// Case-insensitive, null-terminated string keys, which cannot be decoded back
+struct Lowercase;
+
+impl KeyEncoder<String> for Lowercase {
+    type Encoded = Vec<u8>;
+
+    fn encode(key: &String) -> Self::Encoded {
+        let mut bytes = key.to_lowercase().into_bytes();
+        bytes.push(0);
+        bytes
+    }
+}
+
+#[test]
+fn art_map_with_signed_keys_matches_btree() {
+    let keys = [
+        -5_000_000_000i64,
+        -3,
+        -1,
+        0,
+        1,
+        2,
+        1 << 40,
+        i64::MIN,
+        i64::MAX,
+    ];
+    let mut map = ArtMap::<i64, usize, SignFlip>::new();
+    let mut expected = BTreeMap::new();
+    for (i, &key) in keys.iter().enumerate() {
+        map.insert(key, i);
+        expected.insert(key, i);
+    }
+
+    assert_eq!(
+        map.iter().map(|(k, v)| (k, *v)).collect::<Vec<_>>(),
+        expected.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        map.range(-3..=1).map(|(k, _)| k).collect::<Vec<_>>(),
+        vec![-3, -1, 0, 1]
+    );
+    assert_eq!(map.minimum().map(|(k, _)| k), Some(i64::MIN));
+    assert_eq!(map.pop_last().map(|(k, _)| k), Some(i64::MAX));
+    *map.entry(-1).or_insert(0) += 100;
+    assert_eq!(map.get(&-1), Some(&102));
+}
+
+#[test]
+fn art_map_with_encode_only_keys() {
+    let mut map = ArtMap::<String, u32, Lowercase>::new();
+    map.insert("Hello".to_string(), 1);
+    assert_eq!(map.insert("HELLO".to_string(), 2), Some(1));
+    map.insert("World".to_string(), 3);
+
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.get(&"hello".to_string()), Some(&2));
+    assert_eq!(map.values().collect::<Vec<_>>(), vec![&2, &3]);
+    match map.entry("wORLD".to_string()) {
+        Entry::Occupied(entry) => assert_eq!(entry.key(), "wORLD"),
+        Entry::Vacant(_) => panic!("expected an occupied entry"),
+    }
+    assert_eq!(map.delete("world".to_string()), Some(3));
+}
@@ -0,0 +1,85 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::art_multi_map::*;
+
+#[test]
+fn test_insert_keeps_all_values_in_order() {
+    let mut map = ArtMultiMap::<u32>::new();
+    map.insert(&[1, 2, 3], 17);
+    map.insert(&[1, 2, 3], 18);
+    map.insert(&[1, 2, 4], 19);
+
+    assert_eq!(map.get_all(&[1, 2, 3]), &[17, 18]);
+    assert_eq!(map.get_all(&[1, 2, 4]), &[19]);
+    assert!(map.get_all(&[1, 2, 5]).is_empty());
+    assert_eq!(map.len(), 3);
+}
+
+#[test]
+fn test_remove_value_removes_key_with_last_value() {
+    let mut map = ArtMultiMap::<u32>::new();
+    map.insert(&[1, 2, 3], 17);
+    map.insert(&[1, 2, 3], 18);
+
+    assert!(map.remove_value(&[1, 2, 3], &17));
+    assert!(!map.remove_value(&[1, 2, 3], &17));
+    assert!(map.contains_key(&[1, 2, 3]));
+
+    assert!(map.remove_value(&[1, 2, 3], &18));
+    assert!(!map.contains_key(&[1, 2, 3]));
+    assert!(map.is_empty());
+}
+
+#[test]
+fn test_remove_all() {
+    let mut map = ArtMultiMap::<u32>::new();
+    map.insert(&[1, 2, 3], 17);
+    map.insert(&[1, 2, 3], 18);
+    map.insert(&[1, 2, 4], 19);
+
+    assert_eq!(map.remove_all(&[1, 2, 3]), vec![17, 18]);
+    assert!(map.remove_all(&[1, 2, 3]).is_empty());
+    assert_eq!(map.len(), 1);
+}
+
+#[test]
+fn test_iter_yields_key_per_value_in_key_order() {
+    let mut map = ArtMultiMap::<u32>::new();
+    map.insert(&[2, 0], 20);
+    map.insert(&[1, 0], 10);
+    map.insert(&[2, 0], 21);
+
+    let entries: Vec<_> = map.iter().map(|(k, v)| (k.to_vec(), *v)).collect();
+    assert_eq!(
+        entries,
+        vec![(vec![1, 0], 10), (vec![2, 0], 20), (vec![2, 0], 21)]
+    );
+}
+
+#[test]
+fn test_keys_that_are_prefixes_of_each_other() {
+    let mut map = ArtMultiMap::<u32>::new();
+    map.insert(b"ab", 2);
+    map.insert(b"a", 1);
+    map.insert(b"", 0);
+    map.insert(b"a\0", 3);
+    map.insert(b"a", 4);
+
+    assert_eq!(map.get_all(b"a"), &[1, 4]);
+    assert_eq!(map.get_all(b""), &[0]);
+    assert!(!map.contains_key(b"a\0\0"));
+    let entries: Vec<_> = map.iter().map(|(k, v)| (k.to_vec(), *v)).collect();
+    assert_eq!(
+        entries,
+        vec![
+            (b"".to_vec(), 0),
+            (b"a".to_vec(), 1),
+            (b"a".to_vec(), 4),
+            (b"a\0".to_vec(), 3),
+            (b"ab".to_vec(), 2)
+        ]
+    );
+    assert!(map.remove_value(b"a", &1));
+    assert_eq!(map.remove_all(b"a"), vec![4]);
+    assert_eq!(map.len(), 3);
+}
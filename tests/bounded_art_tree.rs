@@ -0,0 +1,105 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::bounded_art_tree::*;
+
+/// Returns the bytes that the given keys take in a tree
+fn bytes_of(keys: &[&[u8]]) -> usize {
+    let mut tree = BoundedArtTree::new(usize::MAX);
+    for key in keys {
+        tree.insert(key, 0u64).unwrap();
+    }
+    tree.memory_bytes()
+}
+
+fn keys<V>(tree: &BoundedArtTree<V>) -> Vec<Vec<u8>> {
+    tree.iter().map(|(key, _)| key.to_vec()).collect()
+}
+
+#[test]
+fn test_evicts_least_recently_used() {
+    let mut tree = BoundedArtTree::new(bytes_of(&[b"a", b"b", b"c"]));
+    tree.insert(b"a", 1u64).unwrap();
+    tree.insert(b"b", 2).unwrap();
+    tree.insert(b"c", 3).unwrap();
+    assert_eq!(tree.evictions(), 0);
+
+    // Reading "a" leaves "b" as the least recently used, peeking does not count as a use
+    assert_eq!(tree.get(b"a"), Some(&1));
+    assert_eq!(tree.peek(b"b"), Some(&2));
+    assert_eq!(tree.insert(b"d", 4), Ok(None));
+    assert_eq!(keys(&tree), [b"a", b"c", b"d"]);
+    assert_eq!(tree.keys_by_use().collect::<Vec<_>>(), [b"c", b"a", b"d"]);
+    assert_eq!(tree.evictions(), 1);
+    assert!(tree.memory_bytes() <= tree.budget());
+
+    // Replacing a value takes no room and counts as a use
+    assert_eq!(tree.insert(b"c", 5), Ok(Some(3)));
+    assert_eq!(tree.insert(b"e", 6), Ok(None));
+    assert_eq!(keys(&tree), [b"c", b"d", b"e"]);
+
+    assert_eq!(tree.delete(b"d"), Some(4));
+    assert_eq!(tree.delete(b"d"), None);
+    assert_eq!(tree.memory_bytes(), bytes_of(&[b"c", b"e"]));
+}
+
+#[test]
+fn test_policy_chooses_the_victims() {
+    let budget = bytes_of(&[b"a", b"b", b"c"]);
+    let mut tree = BoundedArtTree::with_policy(budget, |_, _| Eviction::Minimum);
+    for (i, key) in [b"c", b"a", b"b", b"d"].iter().enumerate() {
+        tree.insert(*key, i).unwrap();
+    }
+    assert_eq!(keys(&tree), [b"b", b"c", b"d"]);
+
+    // A custom policy sees the tree with the new entry
+    let mut tree = BoundedArtTree::with_policy(budget, |tree, key| {
+        assert!(tree.contains_key(key));
+        match tree.iter().max_by_key(|(_, value)| **value) {
+            Some((victim, _)) if victim != key => Eviction::Key(victim.to_vec()),
+            _ => Eviction::Reject,
+        }
+    });
+    tree.insert(b"a", 30u64).unwrap();
+    tree.insert(b"b", 10).unwrap();
+    tree.insert(b"c", 20).unwrap();
+    assert_eq!(tree.insert(b"d", 5), Ok(None));
+    assert_eq!(keys(&tree), [b"b", b"c", b"d"]);
+    assert_eq!(tree.insert(b"e", 50), Err(Rejected(50)));
+    assert_eq!(keys(&tree), [b"b", b"c", b"d"]);
+    assert_eq!(tree.memory_bytes(), bytes_of(&[b"b", b"c", b"d"]));
+}
+
+#[test]
+fn test_rejects_entries_that_do_not_fit() {
+    let mut tree = BoundedArtTree::with_policy(bytes_of(&[b"a", b"b"]), |_, _| Eviction::Reject);
+    tree.insert(b"a", 1u64).unwrap();
+    tree.insert(b"b", 2).unwrap();
+    assert_eq!(tree.insert(b"c", 3), Err(Rejected(3)));
+    assert_eq!(tree.len(), 2);
+    assert_eq!(tree.evictions(), 0);
+
+    // Evicting everything else does not make room for an entry larger than the budget
+    let mut tree = BoundedArtTree::new(bytes_of(&[b"a"]));
+    tree.insert(b"a", 1u64).unwrap();
+    let large = [0u8; 1024];
+    assert_eq!(tree.insert(&large, 2), Err(Rejected(2)));
+    assert!(tree.is_empty());
+    assert_eq!(tree.memory_bytes(), 0);
+}
+
+#[test]
+fn test_stays_within_budget_under_churn() {
+    let budget = 64 * 1024;
+    let mut tree = BoundedArtTree::new(budget);
+    for i in 0..20_000u64 {
+        let key = i.wrapping_mul(0x9e37_79b9_7f4a_7c15).to_be_bytes();
+        tree.insert(&key, i).unwrap();
+        if i % 3 == 0 {
+            tree.get(&key);
+        }
+        assert!(tree.memory_bytes() <= budget);
+    }
+    assert!(tree.evictions() > 0);
+    let remaining: Vec<&[u8]> = tree.keys_by_use().collect();
+    assert_eq!(tree.memory_bytes(), bytes_of(&remaining));
+}
@@ -0,0 +1,113 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::compressed_art_tree::*;
+
+/// Run-length encoding as (count, byte) pairs
+struct RunLength;
+
+impl ValueCodec for RunLength {
+    fn compress(&self, value: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for &b in value {
+            match out.len() {
+                n if n >= 2 && out[n - 1] == b && out[n - 2] < u8::MAX => out[n - 2] += 1,
+                _ => out.extend_from_slice(&[1, b]),
+            }
+        }
+        out
+    }
+
+    fn decompress(&self, compressed: &[u8], len: usize) -> Vec<u8> {
+        let mut out = Vec::with_capacity(len);
+        for pair in compressed.chunks(2) {
+            out.extend(std::iter::repeat_n(pair[1], pair[0] as usize));
+        }
+        out
+    }
+}
+
+/// A JSON-like blob repeating its fields
+#[cfg(any(feature = "lz4", feature = "zstd"))]
+fn blob(i: u32) -> Vec<u8> {
+    format!(
+        "{{\"id\":{},\"tags\":[{}],\"padding\":\"{}\"}}",
+        i,
+        "\"tag\",".repeat(20),
+        " ".repeat(200)
+    )
+    .into_bytes()
+}
+
+#[cfg(any(feature = "lz4", feature = "zstd"))]
+fn check_round_trip<C: ValueCodec>(codec: C) -> CompressionStats {
+    let mut tree = CompressedArtTree::new(codec);
+    for i in 0..200u32 {
+        tree.insert(format!("doc/{}\0", i).as_bytes(), &blob(i));
+    }
+    tree.insert(b"small\0", b"tiny");
+    for i in 0..200u32 {
+        assert_eq!(
+            tree.get(format!("doc/{}\0", i).as_bytes()).unwrap()[..],
+            blob(i)[..]
+        );
+    }
+    assert!(tree
+        .iter()
+        .all(|(key, value)| key == b"small\0" || value[0] == b'{'));
+    tree.stats()
+}
+
+#[test]
+fn test_values_above_the_threshold_are_compressed() {
+    let mut tree = CompressedArtTree::with_threshold(RunLength, 8);
+    assert_eq!(tree.insert(b"a\0", &[7; 100]), None);
+    assert_eq!(tree.insert(b"b\0", b"short"), None);
+    // Every byte differs, run-length encoding would double the size
+    assert_eq!(tree.insert(b"c\0", b"0123456789"), None);
+    assert_eq!(
+        tree.stats(),
+        CompressionStats {
+            plain_values: 2,
+            plain_bytes: 15,
+            compressed_values: 1,
+            original_bytes: 100,
+            compressed_bytes: 2,
+        }
+    );
+    assert_eq!(tree.stats().ratio(), 115.0 / 17.0);
+
+    assert_eq!(tree.get(b"a\0").unwrap()[..], [7; 100][..]);
+    assert_eq!(tree.get(b"b\0").unwrap()[..], b"short"[..]);
+    let keys: Vec<_> = tree.scan_prefix(b"").map(|(key, _)| key.to_vec()).collect();
+    assert_eq!(keys, [b"a\0", b"b\0", b"c\0"]);
+
+    assert_eq!(tree.insert(b"a\0", b"replaced"), Some(vec![7; 100]));
+    assert_eq!(tree.delete(b"c\0"), Some(b"0123456789".to_vec()));
+    assert_eq!(tree.delete(b"c\0"), None);
+    assert_eq!(tree.len(), 2);
+    assert_eq!(
+        tree.stats(),
+        CompressionStats {
+            plain_values: 2,
+            plain_bytes: 13,
+            ..CompressionStats::default()
+        }
+    );
+    assert_eq!(tree.stats().ratio(), 1.0);
+}
+
+#[cfg(feature = "lz4")]
+#[test]
+fn test_lz4_round_trip() {
+    let stats = check_round_trip(Lz4);
+    assert_eq!((stats.compressed_values, stats.plain_values), (200, 1));
+    assert!(stats.ratio() > 3.0, "{:?}", stats);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_zstd_round_trip() {
+    let stats = check_round_trip(Zstd::default());
+    assert_eq!((stats.compressed_values, stats.plain_values), (200, 1));
+    assert!(stats.ratio() > 3.0, "{:?}", stats);
+}
@@ -0,0 +1,79 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::dense_art_map::DenseU64ArtMap;
+use rand::Rng;
+use std::collections::BTreeMap;
+
+#[test]
+fn dense_art_map_matches_btree_map() {
+    let mut rng = rand::thread_rng();
+    let mut map = DenseU64ArtMap::new();
+    let mut model = BTreeMap::new();
+
+    for _ in 0..200 {
+        // Dense runs with a few holes at random bases
+        let base = rng.gen::<u64>() >> rng.gen_range(0..64);
+        let end = base.saturating_add(rng.gen_range(1..500));
+        for key in base..end {
+            if rng.gen_bool(0.1) {
+                continue;
+            }
+            let value = rng.gen::<u32>();
+            assert_eq!(map.insert(key, value), model.insert(key, value));
+        }
+    }
+    let keys: Vec<u64> = model.keys().copied().collect();
+    for &key in keys.iter().step_by(5) {
+        assert_eq!(map.delete(key), model.remove(&key));
+        assert_eq!(map.delete(key), None);
+    }
+    for &key in keys.iter().step_by(7) {
+        *map.get_mut(key).unwrap_or(&mut 0) += 1;
+        if let Some(value) = model.get_mut(&key) {
+            *value += 1;
+        }
+    }
+
+    assert_eq!(map.len(), model.len());
+    assert!(map.iter().eq(model.iter().map(|(&k, v)| (k, v))));
+    assert_eq!(map.first(), model.iter().next().map(|(&k, v)| (k, v)));
+    assert_eq!(map.last(), model.iter().next_back().map(|(&k, v)| (k, v)));
+    for &key in keys.iter().step_by(3) {
+        assert_eq!(map.get(key), model.get(&key));
+        assert_eq!(map.contains_key(key + 1), model.contains_key(&(key + 1)));
+        let end = key.saturating_add(rng.gen_range(0..300));
+        assert!(map
+            .range(key..end)
+            .eq(model.range(key..end).map(|(&k, v)| (k, v))));
+        assert!(map
+            .range(key..=end)
+            .eq(model.range(key..=end).map(|(&k, v)| (k, v))));
+    }
+    assert!(map.range(..).eq(model.iter().map(|(&k, v)| (k, v))));
+
+    for (key, value) in map.iter_mut() {
+        *value = key as u32;
+    }
+    assert!(map.iter().all(|(key, &value)| value == key as u32));
+}
+
+#[test]
+fn dense_art_map_stores_a_leaf_per_chunk() {
+    let mut map: DenseU64ArtMap<u64> = (1_000..1_000 + 64 * 100).map(|k| (k, k)).collect();
+    // The run starts in the middle of a chunk
+    assert_eq!(map.len(), 6_400);
+    assert_eq!(map.chunks(), 101);
+    assert_eq!(map.first(), Some((1_000, &1_000)));
+
+    map.extend((0..100).map(|k| (k * 1_000_000, k)));
+    assert_eq!(map.chunks(), 101 + 100);
+    for key in 1_000..1_000 + 64 * 100 {
+        assert_eq!(map.delete(key), Some(key));
+    }
+    assert_eq!(map.chunks(), 100);
+    assert_eq!(map.len(), 100);
+    assert_eq!(map.last(), Some((99_000_000, &99)));
+    assert_eq!(map.get(u64::MAX), None);
+    map.insert(u64::MAX, 1);
+    assert_eq!(map.range(u64::MAX..).count(), 1);
+}
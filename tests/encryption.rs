@@ -0,0 +1,128 @@
+#![cfg(feature = "encryption")]
+
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::art::ArtTree;
+use adaptive_radix_tree::encryption::*;
+use adaptive_radix_tree::flat_art::{self, FlatArt, FlatError, FlatOptions};
+use adaptive_radix_tree::snapshot::{SnapshotError, SnapshotReader};
+use std::io::{Read, Write};
+
+/// A tree large enough to span several sealed blocks
+fn make_tree() -> ArtTree<String> {
+    let mut tree = ArtTree::new();
+    for i in 0..5_000u32 {
+        let key = format!("{}/{}\0", i % 11, i);
+        tree.insert(key.as_bytes(), format!("value {}", i));
+    }
+    tree
+}
+
+#[test]
+fn test_encrypted_snapshot_round_trip() {
+    let key = EncryptionKey::generate();
+    let tree = make_tree();
+    let sealed = tree.write_snapshot_encrypted(Vec::new(), &key).unwrap();
+    assert!(sealed.len() > 64 * 1024);
+    assert!(!sealed.windows(9).any(|w| w == b"value 123"));
+
+    let loaded = ArtTree::<String>::read_snapshot_encrypted(&sealed[..], &key).unwrap();
+    assert!(loaded.entries().eq(tree.entries()));
+
+    // Readers refuse to mix up encrypted and plain streams
+    assert!(matches!(
+        ArtTree::<String>::read_snapshot(&sealed[..]),
+        Err(SnapshotError::Encrypted)
+    ));
+    let plain = tree.write_snapshot(Vec::new()).unwrap();
+    assert!(matches!(
+        SnapshotReader::encrypted(&plain[..], &key),
+        Err(SnapshotError::Unencrypted)
+    ));
+}
+
+#[test]
+fn test_encrypted_snapshot_detects_tampering() {
+    let key = EncryptionKey::new([7; 32]);
+    let sealed = make_tree()
+        .write_snapshot_encrypted(Vec::new(), &key)
+        .unwrap();
+    let read =
+        |buf: &[u8], key: &EncryptionKey| ArtTree::<String>::read_snapshot_encrypted(buf, key);
+
+    assert!(matches!(
+        read(&sealed, &EncryptionKey::new([8; 32])),
+        Err(SnapshotError::Decryption)
+    ));
+    let mut modified = sealed.clone();
+    let middle = modified.len() / 2;
+    modified[middle] ^= 1;
+    assert!(matches!(
+        read(&modified, &key),
+        Err(SnapshotError::Decryption)
+    ));
+
+    // Cutting the stream at the end of a block drops the last block
+    let first_block = 8 + 24 + 5 + 64 * 1024 + 16;
+    let truncated = read(&sealed[..first_block], &key);
+    assert!(
+        matches!(truncated, Err(SnapshotError::Io(_))),
+        "{:?}",
+        truncated
+    );
+}
+
+#[test]
+fn test_seal_writer_round_trip() {
+    let key = EncryptionKey::generate();
+    let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    let mut writer = SealWriter::new(Vec::new(), &key, b"context").unwrap();
+    writer.write_all(&data).unwrap();
+    let sealed = writer.finish().unwrap();
+
+    let mut opened = Vec::new();
+    OpenReader::new(&sealed[..], &key, b"context")
+        .unwrap()
+        .read_to_end(&mut opened)
+        .unwrap();
+    assert_eq!(opened, data);
+
+    let err = OpenReader::new(&sealed[..], &key, b"other")
+        .unwrap()
+        .read_to_end(&mut Vec::new())
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(format!("{:?}", key), "EncryptionKey(..)");
+}
+
+#[test]
+fn test_encrypted_flat_tree() {
+    let key = EncryptionKey::generate();
+    let tree = make_tree();
+    #[allow(unused_mut)]
+    let mut layouts = vec![FlatOptions::default().front_coded(true)];
+    #[cfg(feature = "lz4")]
+    layouts.push(FlatOptions::default().compressed(true));
+
+    for options in layouts {
+        let buf = tree.to_flat_with(options);
+        let sealed = flat_art::encrypt(&buf, &key).unwrap();
+        assert_eq!(
+            FlatArt::<&str>::new(&sealed).err(),
+            Some(FlatError::Encrypted)
+        );
+        assert_eq!(flat_art::encrypt(&sealed, &key), Err(FlatError::Encrypted));
+        assert_eq!(
+            flat_art::decrypt(&sealed, &EncryptionKey::generate()),
+            Err(FlatError::Decryption)
+        );
+
+        let opened = flat_art::decrypt(&sealed, &key).unwrap();
+        assert_eq!(opened, buf);
+        #[cfg(feature = "lz4")]
+        let opened = flat_art::decompress(&opened).unwrap();
+        let flat = FlatArt::<&str>::new(&opened).unwrap();
+        assert_eq!(flat.get(b"3/3\0"), Some("value 3"));
+        assert_eq!(flat.len(), tree.len());
+    }
+}
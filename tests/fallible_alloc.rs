@@ -0,0 +1,118 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::art::*;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::ptr;
+
+/// Allocator failing once the current thread used up its allowance of allocations
+struct Limited;
+
+thread_local! {
+    /// Allocations the thread may still make, unlimited if negative
+    static ALLOWED: Cell<isize> = const { Cell::new(-1) };
+}
+
+unsafe impl GlobalAlloc for Limited {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let allowed = ALLOWED.with(|allowed| {
+            let n = allowed.get();
+            if n > 0 {
+                allowed.set(n - 1);
+            }
+            n != 0
+        });
+        if allowed {
+            System.alloc(layout)
+        } else {
+            ptr::null_mut()
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Limited = Limited;
+
+/// Runs `f` with at most `allowed` allocations
+#[cfg(not(feature = "validate"))]
+fn with_allocations<T>(allowed: isize, f: impl FnOnce() -> T) -> T {
+    ALLOWED.with(|cell| cell.set(allowed));
+    let result = f();
+    ALLOWED.with(|cell| cell.set(-1));
+    result
+}
+
+// The checks of the `validate` feature allocate on every insert
+#[cfg(not(feature = "validate"))]
+#[test]
+fn test_failed_allocations_leave_the_tree_unchanged() {
+    let mut tree = ArtTree::<u64>::new();
+    tree.insert(b"apple\0", 1);
+    tree.insert(b"banana\0", 2);
+    let before: Vec<_> = tree.entries().map(|(k, v)| (k.to_vec(), *v)).collect();
+
+    // The key, the leaf, the child array of the spare node and the node itself are allocated in
+    // turn, the key "apricot" splits the leaf of "apple"
+    for allowed in 0..4 {
+        let result = with_allocations(allowed, || tree.try_insert_alloc(b"apricot\0", 3));
+        assert_eq!(result, Err(InsertError::AllocFailed(3)), "{}", allowed);
+        let entries: Vec<_> = tree.entries().map(|(k, v)| (k.to_vec(), *v)).collect();
+        assert_eq!(entries, before);
+    }
+    let result = with_allocations(4, || tree.try_insert_alloc(b"apricot\0", 3));
+    assert_eq!(result, Ok(None));
+    assert_eq!(tree.get(b"apricot\0"), Some(&3));
+
+    // Replacing a value allocates nothing
+    let result = with_allocations(0, || tree.try_insert_alloc(b"apple\0", 4));
+    assert_eq!(result, Ok(Some(1)));
+    assert_eq!(tree.len(), 3);
+    tree.check_invariants().unwrap();
+}
+
+// The checks of the `validate` feature allocate on every insert
+#[cfg(not(feature = "validate"))]
+#[test]
+fn test_failed_allocations_leave_full_nodes_unchanged() {
+    // The root is a full Node4 and a full Node48 in turn
+    for &children in &[4u8, 48] {
+        let mut tree = ArtTree::<u8>::new();
+        for c in 0..children {
+            tree.insert(&[c, 0], c);
+        }
+        let before: Vec<_> = tree.entries().map(|(k, v)| (k.to_vec(), *v)).collect();
+        let stats = tree.memory_stats();
+
+        // The key, the leaf, the child array of the spare node, the node itself and the child
+        // array of the grown node are allocated in turn
+        let allocations = 5;
+        for allowed in 0..allocations {
+            let result = with_allocations(allowed, || tree.try_insert_alloc(&[children, 0], 0));
+            assert_eq!(result, Err(InsertError::AllocFailed(0)), "{}", allowed);
+            let entries: Vec<_> = tree.entries().map(|(k, v)| (k.to_vec(), *v)).collect();
+            assert_eq!(entries, before);
+            assert_eq!(tree.memory_stats(), stats);
+        }
+        let result = with_allocations(allocations, || tree.try_insert_alloc(&[children, 0], 0));
+        assert_eq!(result, Ok(None));
+        assert_eq!(tree.len(), children as usize + 1);
+        tree.check_invariants().unwrap();
+    }
+}
+
+#[test]
+fn test_try_insert_alloc_builds_the_same_tree() {
+    let mut tree = ArtTree::<u32>::new();
+    let mut expected = ArtTree::<u32>::new();
+    for i in 0..5_000u32 {
+        let key = i.wrapping_mul(2_654_435_761).to_be_bytes();
+        assert_eq!(tree.try_insert_alloc(&key, i), Ok(None));
+        expected.insert(&key, i);
+    }
+    assert_eq!(tree.memory_stats(), expected.memory_stats());
+    assert!(tree.entries().eq(expected.entries()));
+}
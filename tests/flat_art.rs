@@ -0,0 +1,202 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::art::ArtTree;
+use adaptive_radix_tree::flat_art::*;
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+/// Terminated path-like keys of varying length with long shared prefixes
+fn make_entries() -> BTreeMap<Vec<u8>, String> {
+    (0..5_000u32)
+        .map(|i| {
+            let key = format!("{}/{}/{}\0", i % 7, "segment".repeat(i as usize % 4), i);
+            (key.into_bytes(), format!("value {}", i))
+        })
+        .collect()
+}
+
+/// The buffer layouts to test, with the buffers readable by `FlatArt::new`
+fn layouts() -> Vec<FlatOptions> {
+    #[allow(unused_mut)]
+    let mut layouts = vec![
+        FlatOptions::default(),
+        FlatOptions::default().front_coded(true),
+    ];
+    #[cfg(feature = "lz4")]
+    layouts.push(FlatOptions::default().front_coded(true).compressed(true));
+    layouts
+}
+
+fn readable(buf: Vec<u8>) -> Vec<u8> {
+    #[cfg(feature = "lz4")]
+    return decompress(&buf).unwrap();
+    #[cfg(not(feature = "lz4"))]
+    buf
+}
+
+#[test]
+fn flat_art_matches_source_tree() {
+    let expected = make_entries();
+    let mut tree = ArtTree::<String>::new();
+    for (key, value) in &expected {
+        tree.insert(key, value.clone());
+    }
+    for options in layouts() {
+        let buf = readable(tree.to_flat_with(options));
+        let flat = FlatArt::<&str>::new(&buf).unwrap();
+
+        assert_eq!(flat.len(), expected.len());
+        for (key, value) in &expected {
+            assert_eq!(flat.get(key), Some(value.as_str()));
+        }
+        assert_eq!(flat.get(b"3/"), None);
+        assert_eq!(flat.get(b"3/segment/10\0tail"), None);
+        assert!(!flat.contains_key(b""));
+
+        let entries: Vec<_> = flat
+            .iter()
+            .map(|(key, value)| (key.into_owned(), value))
+            .collect();
+        let expected_entries: Vec<_> = expected
+            .iter()
+            .map(|(key, value)| (key.clone(), value.as_str()))
+            .collect();
+        assert_eq!(entries, expected_entries, "{:?}", options);
+    }
+}
+
+#[test]
+fn flat_art_front_coding_shrinks_redundant_keys() {
+    let expected = make_entries();
+    let mut tree = ArtTree::<String>::new();
+    for (key, value) in &expected {
+        tree.insert(key, value.clone());
+    }
+    let plain = tree.to_flat();
+    let front_coded = tree.to_flat_with(FlatOptions::default().front_coded(true));
+    assert!(front_coded.len() * 10 < plain.len() * 7);
+    #[cfg(feature = "lz4")]
+    {
+        let compressed =
+            tree.to_flat_with(FlatOptions::default().front_coded(true).compressed(true));
+        assert!(compressed.len() * 2 < front_coded.len());
+        assert_eq!(
+            FlatArt::<&str>::new(&compressed).unwrap_err(),
+            FlatError::Compressed
+        );
+        assert_eq!(decompress(&compressed).unwrap(), front_coded);
+    }
+}
+
+#[test]
+fn flat_art_scans_prefixes_and_ranges() {
+    let expected = make_entries();
+    for options in layouts() {
+        let mut builder = FlatArtBuilder::with_options(options);
+        for (key, value) in &expected {
+            builder.push(key, value);
+        }
+        let buf = readable(builder.finish());
+        check_prefixes_and_ranges(&FlatArt::new(&buf).unwrap(), &expected);
+    }
+}
+
+fn check_prefixes_and_ranges(flat: &FlatArt<&str>, expected: &BTreeMap<Vec<u8>, String>) {
+    for prefix in [&b""[..], b"3", b"3/", b"3/segmentseg", b"4/segment/1", b"8"] {
+        let scanned: Vec<_> = flat.scan_prefix(prefix).map(|(key, _)| key).collect();
+        let expected_keys: Vec<_> = expected
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .map(|key| &key[..])
+            .collect();
+        assert_eq!(scanned, expected_keys, "prefix {:?}", prefix);
+    }
+
+    let bounds = [
+        &b"0"[..],
+        b"2/segment/2",
+        b"2/segment/2000\0",
+        b"5/",
+        b"6/x",
+        b"9",
+    ];
+    for &start in &bounds {
+        for &end in &bounds {
+            let ranges = [
+                (Bound::Included(start), Bound::Excluded(end)),
+                (Bound::Excluded(start), Bound::Included(end)),
+                (Bound::Unbounded, Bound::Included(end)),
+                (Bound::Included(start), Bound::Unbounded),
+            ];
+            for range in ranges {
+                if start > end && range.0 != Bound::Unbounded && range.1 != Bound::Unbounded {
+                    continue;
+                }
+                let scanned: Vec<_> = flat.range(range).map(|(key, _)| key.to_vec()).collect();
+                let expected_keys: Vec<_> = expected
+                    .range::<[u8], _>(range)
+                    .map(|(key, _)| key.clone())
+                    .collect();
+                assert_eq!(scanned, expected_keys, "range {:?}", range);
+            }
+        }
+    }
+}
+
+#[test]
+fn flat_art_decodes_numbers_and_empty_trees() {
+    let mut tree = ArtTree::<u64>::new();
+    for i in 0..1_000u64 {
+        tree.insert(&(i * 7919).to_be_bytes(), i);
+    }
+    let buf = tree.to_flat();
+    let flat = FlatArt::<u64>::new(&buf).unwrap();
+    assert_eq!(flat.get(&(500 * 7919u64).to_be_bytes()), Some(500));
+    assert_eq!(flat.iter().map(|(_, value)| value).sum::<u64>(), 499_500);
+
+    let buf = FlatArtBuilder::new().finish();
+    let empty = FlatArt::<u64>::new(&buf).unwrap();
+    assert!(empty.is_empty());
+    assert_eq!(empty.get(b"key"), None);
+    assert_eq!(empty.iter().count(), 0);
+    assert_eq!(empty.scan_prefix(b"").count(), 0);
+    assert_eq!(
+        empty
+            .range((Bound::<&[u8]>::Unbounded, Bound::Unbounded))
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn flat_art_rejects_foreign_buffers() {
+    let buf = ArtTree::<u64>::new().to_flat();
+    assert_eq!(
+        FlatArt::<u64>::new(&buf[..8]).unwrap_err(),
+        FlatError::TooShort
+    );
+    assert_eq!(
+        FlatArt::<u64>::new(&[0; 16]).unwrap_err(),
+        FlatError::BadMagic
+    );
+    let mut newer = buf.clone();
+    newer[4] = 2;
+    assert_eq!(
+        FlatArt::<u64>::new(&newer).unwrap_err(),
+        FlatError::UnsupportedVersion(2)
+    );
+    let mut newer = buf.clone();
+    newer[6] = 0x80;
+    assert_eq!(
+        FlatArt::<u64>::new(&newer).unwrap_err(),
+        FlatError::UnsupportedFlags(0x80)
+    );
+}
+
+#[test]
+#[should_panic(expected = "a key is a prefix of another key")]
+fn flat_art_builder_rejects_prefix_keys() {
+    let mut builder = FlatArtBuilder::new();
+    builder.push(b"abc", &1u32);
+    builder.push(b"abcd", &2u32);
+}
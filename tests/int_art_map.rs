@@ -0,0 +1,43 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::int_art_map::*;
+
+#[test]
+fn test_u128_keys_are_ordered_numerically() {
+    let mut artmap = U128ArtMap::<&str>::new();
+    artmap.insert(u128::MAX, "max");
+    artmap.insert(1 << 64, "high");
+    artmap.insert(255, "low");
+    artmap.insert(256, "middle");
+
+    assert_eq!(artmap.minimum(), Some((255, &"low")));
+    assert_eq!(artmap.maximum(), Some((u128::MAX, &"max")));
+    assert_eq!(artmap.delete(255), Some("low"));
+    assert_eq!(artmap.pop_first(), Some((256, "middle")));
+    assert_eq!(artmap.pop_first(), Some((1 << 64, "high")));
+}
+
+#[test]
+fn test_narrow_keys_cover_the_whole_domain() {
+    let mut artmap = IntArtMap::<u16, u16>::new();
+    let keys: Vec<u16> = (0..=u16::MAX).step_by(7).collect();
+    for &key in keys.iter().rev() {
+        artmap.insert(key, key);
+    }
+
+    let mut popped = Vec::new();
+    while let Some((key, value)) = artmap.pop_first() {
+        assert_eq!(key, value);
+        popped.push(key);
+    }
+    assert_eq!(popped, keys);
+}
+
+#[test]
+fn test_u32_get_mut() {
+    let mut artmap = U32ArtMap::<u32>::new();
+    artmap.insert(7, 1);
+    *artmap.get_mut(&7).unwrap() += 1;
+    assert_eq!(artmap.get_mut(&7), Some(&mut 2));
+    assert_eq!(artmap.get_mut(&8), None);
+}
@@ -0,0 +1,28 @@
+#![cfg(feature = "js")]
+
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::js::JsArtMap;
+
+// Only the methods not creating JavaScript objects run outside of a JavaScript host
+#[test]
+fn test_js_art_map_keys_may_be_prefixes() {
+    let mut map = JsArtMap::new();
+    assert!(!map.set(b"app", b"1"));
+    assert!(!map.set(b"apple", b"2"));
+    assert!(!map.set(b"a\0b", b"3"));
+    assert!(map.set(b"app", b"4"));
+    assert_eq!(map.size(), 3);
+
+    assert_eq!(map.get(b"app"), Some(b"4".to_vec()));
+    assert_eq!(map.get(b"apple"), Some(b"2".to_vec()));
+    assert_eq!(map.get(b"ap"), None);
+    assert!(map.has(b"a\0b"));
+    assert!(!map.has(b"a"));
+
+    assert!(map.delete(b"app"));
+    assert!(!map.delete(b"app"));
+    assert_eq!(map.get(b"apple"), Some(b"2".to_vec()));
+    map.clear();
+    assert_eq!(map.size(), 0);
+}
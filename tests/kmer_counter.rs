@@ -0,0 +1,53 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::kmer_counter::*;
+use std::collections::HashMap;
+
+#[test]
+fn kmer_counter_matches_naive_counting() {
+    let sequence = b"ACGTACGTTTGACNNACGTAcgtaGGGGGGCATTACA";
+    let k = 5;
+    let mut counter = KmerCounter::<u64>::new(k);
+    counter.add_sequence(sequence);
+
+    let mut expected = HashMap::new();
+    for window in sequence.windows(k) {
+        if window.iter().all(|b| b"ACGTacgt".contains(b)) {
+            *expected.entry(window.to_ascii_uppercase()).or_insert(0) += 1;
+        }
+    }
+
+    assert_eq!(counter.len(), expected.len());
+    let mut previous = None;
+    for (kmer, count) in counter.iter() {
+        let bases = counter.decode(kmer);
+        assert_eq!(expected[&bases], count);
+        assert_eq!(counter.encode(&bases), Some(kmer));
+        assert!(previous < Some(bases.clone()));
+        previous = Some(bases);
+    }
+
+    let acgta = counter.encode(b"ACGTA").unwrap();
+    assert_eq!(counter.count(acgta), 3);
+    assert_eq!(counter.increment(acgta), 4);
+    assert_eq!(counter.encode(b"ACGTN"), None);
+    assert_eq!(counter.encode(b"ACGT"), None);
+
+    *expected.get_mut(&b"ACGTA"[..]).unwrap() += 1;
+    let mut histogram = std::collections::BTreeMap::new();
+    for count in expected.values() {
+        *histogram.entry(*count).or_insert(0) += 1;
+    }
+    assert_eq!(counter.histogram(), histogram);
+}
+
+#[test]
+fn kmer_counter_supports_full_width_keys() {
+    let mut counter = KmerCounter::<u128>::new(64);
+    let sequence: Vec<u8> = b"ACGT".iter().cycle().take(200).copied().collect();
+    counter.add_sequence(&sequence);
+    assert_eq!(counter.len(), 4);
+    assert_eq!(counter.iter().map(|(_, count)| count).sum::<u64>(), 137);
+    let first = counter.iter().next().unwrap().0;
+    assert_eq!(&counter.decode(first)[..4], b"ACGT");
+}
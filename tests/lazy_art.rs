@@ -0,0 +1,166 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::flat_art::FlatOptions;
+use adaptive_radix_tree::lazy_art::*;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+
+/// Subtrees kept in memory, counting the loads
+#[derive(Default)]
+struct MemoryStore {
+    subtrees: Vec<Vec<u8>>,
+    loads: Cell<usize>,
+}
+
+impl SubtreeStore for MemoryStore {
+    type Handle = usize;
+    type Error = String;
+
+    fn load(&self, handle: &usize) -> Result<Vec<u8>, String> {
+        self.loads.set(self.loads.get() + 1);
+        self.subtrees
+            .get(*handle)
+            .cloned()
+            .ok_or_else(|| format!("no subtree {}", handle))
+    }
+}
+
+fn make_entries() -> BTreeMap<Vec<u8>, u64> {
+    (0..20_000u64)
+        .map(|i| (format!("{:03}/{}\0", i % 97, i).into_bytes(), i))
+        .collect()
+}
+
+fn build(
+    entries: &BTreeMap<Vec<u8>, u64>,
+    options: FlatOptions,
+    cache_bytes: usize,
+) -> LazyArt<MemoryStore> {
+    let mut store = MemoryStore::default();
+    let mut prefixes = Vec::new();
+    let mut builder = LazyArtBuilder::with_options(4, options, |prefix: &[u8], subtree| {
+        prefixes.push(prefix.to_vec());
+        store.subtrees.push(subtree);
+        Ok::<_, String>(store.subtrees.len() - 1)
+    });
+    for (key, value) in entries {
+        builder.push(key, value).unwrap();
+    }
+    assert_eq!(builder.subtrees(), 96);
+    let directory = builder.finish().unwrap();
+    assert_eq!(prefixes.len(), 97);
+    assert_eq!(prefixes[5], b"005/");
+    LazyArt::new(directory, store, cache_bytes)
+}
+
+#[test]
+fn lazy_art_loads_subtrees_on_demand() {
+    let entries = make_entries();
+    let index = build(&entries, FlatOptions::default(), usize::MAX);
+    assert_eq!(index.directory().len(), 97);
+    assert_eq!(index.store().loads.get(), 0);
+
+    for (key, value) in &entries {
+        assert_eq!(index.get::<u64>(key), Ok(Some(*value)));
+    }
+    assert_eq!(index.get::<u64>(b"005/missing\0"), Ok(None));
+    assert_eq!(index.get::<u64>(b"999/1\0"), Ok(None));
+    assert_eq!(index.contains_key(b"00"), Ok(false));
+    assert_eq!(index.contains_key(b"042/42\0"), Ok(true));
+
+    // Every subtree was loaded once, a missing prefix loads nothing
+    assert_eq!(index.store().loads.get(), 97);
+    let stats = index.cache_stats();
+    assert_eq!(stats.misses, 97);
+    assert_eq!(stats.hits, entries.len() as u64 + 2 - 97);
+    assert_eq!(stats.subtrees, 97);
+
+    let subtree = index.subtree(b"042/42\0").unwrap().unwrap();
+    let keys: Vec<_> = subtree
+        .flat::<u64>()
+        .iter()
+        .map(|(key, _)| key.into_owned())
+        .collect();
+    let expected: Vec<_> = entries
+        .keys()
+        .filter(|key| key.starts_with(b"042/"))
+        .cloned()
+        .collect();
+    assert_eq!(keys, expected);
+
+    index.clear_cache();
+    assert_eq!(index.get::<u64>(b"042/42\0"), Ok(Some(42)));
+    assert_eq!(index.store().loads.get(), 98);
+}
+
+#[test]
+fn lazy_art_evicts_least_recently_used_subtrees() {
+    let entries = make_entries();
+    let uncached = build(&entries, FlatOptions::default(), 0);
+    let subtree_bytes = (0..97)
+        .map(|i| {
+            let key = format!("{:03}/{}\0", i, i);
+            uncached
+                .subtree(key.as_bytes())
+                .unwrap()
+                .unwrap()
+                .byte_len()
+        })
+        .max()
+        .unwrap();
+    assert_eq!(uncached.cache_stats().subtrees, 0);
+    let index = build(&entries, FlatOptions::default(), 3 * subtree_bytes);
+
+    let hot = [&b"000/0\0"[..], b"001/1\0"];
+    for i in 0..200 {
+        for key in &hot {
+            assert!(index.contains_key(key).unwrap());
+        }
+        let cold = format!("{:03}/{}\0", 2 + i % 95, 2 + i % 95);
+        assert!(index.contains_key(cold.as_bytes()).unwrap());
+    }
+    // The hot subtrees stay cached while the cold ones take turns in the last slot
+    let stats = index.cache_stats();
+    assert_eq!(stats.subtrees, 3);
+    assert!(stats.bytes <= 3 * subtree_bytes);
+    assert_eq!(index.store().loads.get(), 2 + 200);
+    assert_eq!(stats.evictions, 199);
+}
+
+#[test]
+fn lazy_art_reports_store_errors() {
+    let entries = make_entries();
+    let index = build(&entries, FlatOptions::default(), usize::MAX);
+    let index = LazyArt::new(
+        index.directory().clone(),
+        MemoryStore::default(),
+        usize::MAX,
+    );
+    assert_eq!(
+        index.get::<u64>(b"003/3\0"),
+        Err(LoadError::Store("no subtree 3".to_string()))
+    );
+    assert_eq!(index.get::<u64>(b"x"), Ok(None));
+}
+
+#[cfg(feature = "lz4")]
+#[test]
+fn lazy_art_inflates_compressed_subtrees() {
+    let entries = make_entries();
+    let index = build(
+        &entries,
+        FlatOptions::default().front_coded(true).compressed(true),
+        usize::MAX,
+    );
+    for (key, value) in entries.iter().step_by(7) {
+        assert_eq!(index.get::<u64>(key), Ok(Some(*value)));
+    }
+}
+
+#[test]
+#[should_panic(expected = "keys have to be pushed in ascending order")]
+fn lazy_art_builder_rejects_unsorted_subtrees() {
+    let mut builder = LazyArtBuilder::new(1, |_: &[u8], _| Ok::<_, ()>(0));
+    builder.push(b"b\0", &1u32).unwrap();
+    builder.push(b"a\0", &2u32).unwrap();
+}
@@ -0,0 +1,95 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::lww_art_map::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+#[test]
+fn lww_art_map_keeps_latest_write() {
+    let mut map = LwwArtMap::new();
+    assert!(map.insert(b"k", "a", LwwTag::new(10, 1)));
+    assert!(!map.insert(b"k", "old", LwwTag::new(9, 2)));
+    // The node id breaks ties between writes at the same timestamp
+    assert!(map.merge_remote(b"k", Some("b"), LwwTag::new(10, 2)));
+    assert!(!map.merge_remote(b"k", Some("c"), LwwTag::new(10, 2)));
+    assert_eq!(map.get(b"k"), Some(&"b"));
+
+    assert!(map.delete(b"k", LwwTag::new(11, 1)));
+    assert!(!map.insert(b"k", "late", LwwTag::new(10, 3)));
+    assert_eq!(map.get(b"k"), None);
+    assert_eq!(map.tag(b"k"), Some(LwwTag::new(11, 1)));
+    assert!(map.is_empty());
+
+    // A deletion of an unknown key wins against older writes arriving later
+    assert!(map.delete(b"x", LwwTag::new(5, 1)));
+    assert!(!map.insert(b"x", "older", LwwTag::new(4, 1)));
+    assert!(map.insert(b"y", "new", LwwTag::new(12, 1)));
+    let entries: Vec<_> = map
+        .entries()
+        .map(|(k, v, t)| (k[0], v.copied(), t))
+        .collect();
+    assert_eq!(
+        entries,
+        vec![
+            (b'k', None, LwwTag::new(11, 1)),
+            (b'x', None, LwwTag::new(5, 1)),
+            (b'y', Some("new"), LwwTag::new(12, 1)),
+        ]
+    );
+    assert_eq!(map.iter().count(), 1);
+    assert_eq!(map.len(), 1);
+}
+
+#[test]
+fn lww_art_map_replicas_converge() {
+    let mut rng = rand::thread_rng();
+    // Writes at the same timestamp come from different nodes, so that every tag is unique
+    let writes: Vec<_> = (0..2_000u64)
+        .map(|i| {
+            let key = [rng.gen_range(0..50u8)];
+            let value = if rng.gen_bool(0.2) { None } else { Some(i) };
+            (key, value, LwwTag::new(rng.gen_range(0..500), i))
+        })
+        .collect();
+
+    // Each replica sees a share of the writes in its own order, some of them twice
+    let mut replicas: Vec<LwwArtMap<u64>> = (0..3).map(|_| LwwArtMap::new()).collect();
+    for replica in &mut replicas {
+        let mut seen: Vec<_> = writes.iter().filter(|_| rng.gen_bool(0.6)).collect();
+        seen.extend(seen.clone().into_iter().take(100));
+        seen.shuffle(&mut rng);
+        for (key, value, tag) in seen {
+            replica.merge_remote(key, *value, *tag);
+        }
+    }
+
+    let mut expected = LwwArtMap::new();
+    for (key, value, tag) in &writes {
+        expected.merge_remote(key, *value, *tag);
+    }
+    let state = |map: &LwwArtMap<u64>| -> Vec<_> {
+        map.entries()
+            .map(|(k, v, t)| (k.to_vec(), v.copied(), t))
+            .collect()
+    };
+
+    let (mut forward, mut backward) = (replicas[0].clone(), replicas[2].clone());
+    for replica in &replicas[1..] {
+        forward.merge(replica);
+    }
+    for replica in replicas[..2].iter().rev() {
+        backward.merge(replica);
+    }
+    backward.merge(&forward);
+    assert_eq!(state(&forward), state(&backward));
+    assert_eq!(forward.len(), forward.iter().count());
+
+    for replica in &replicas {
+        expected.merge(replica);
+    }
+    let mut all = LwwArtMap::new();
+    for (key, value, tag) in &writes {
+        all.merge_remote(key, *value, *tag);
+    }
+    assert_eq!(state(&expected), state(&all));
+}
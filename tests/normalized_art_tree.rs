@@ -0,0 +1,57 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::normalized_art_tree::*;
+use std::borrow::Cow;
+use std::ops::Bound;
+
+/// Drops the separators `-` and `_` and lowercases ASCII letters
+fn strip_separators(key: &[u8]) -> Cow<'_, [u8]> {
+    Cow::Owned(
+        key.iter()
+            .filter(|&&b| b != b'-' && b != b'_')
+            .map(u8::to_ascii_lowercase)
+            .collect(),
+    )
+}
+
+#[test]
+fn test_every_operation_normalizes_keys() {
+    let mut tree = NormalizedArtTree::new(strip_separators);
+    assert_eq!(tree.insert(b"User-Id\0", 1), None);
+    assert_eq!(tree.insert(b"user_id\0", 2), Some(1));
+    assert_eq!(tree.len(), 1);
+    assert_eq!(tree.get(b"USERID\0"), Some(&2));
+    assert!(tree.contains_key(b"u-s-e-r-i-d\0"));
+    *tree.get_mut(b"UserId\0").unwrap() += 1;
+    assert_eq!(tree.tree().get(b"userid\0"), Some(&3));
+    assert_eq!(&tree.normalize(b"A-b")[..], b"ab");
+
+    tree.insert(b"User-Name\0", 4);
+    tree.insert(b"Group\0", 5);
+    let keys: Vec<_> = tree.iter().map(|(key, _)| key.to_vec()).collect();
+    assert_eq!(keys, [&b"group\0"[..], b"userid\0", b"username\0"]);
+    let users: Vec<_> = tree.scan_prefix(b"USER-").map(|(_, v)| *v).collect();
+    assert_eq!(users, [3, 4]);
+    let range: Vec<_> = tree
+        .range((
+            Bound::Excluded(&b"GROUP\0"[..]),
+            Bound::Included(&b"User-Id\0"[..]),
+        ))
+        .map(|(_, v)| *v)
+        .collect();
+    assert_eq!(range, [3]);
+
+    assert_eq!(tree.delete(b"user-name\0"), Some(4));
+    assert_eq!(tree.delete(b"username\0"), None);
+    assert_eq!(tree.into_inner().len(), 2);
+}
+
+#[test]
+fn test_ascii_case_insensitive() {
+    let mut tree = NormalizedArtTree::ascii_case_insensitive();
+    tree.insert(b"Key\0", 1);
+    assert_eq!(tree.get(b"KEY\0"), Some(&1));
+    assert!(matches!(tree.normalize(b"key"), Cow::Borrowed(_)));
+    let clone = tree.clone();
+    assert_eq!(clone.get(b"kEy\0"), Some(&1));
+}
@@ -0,0 +1,93 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::scheduler::*;
+use rand::Rng;
+use std::collections::BTreeMap;
+
+#[test]
+fn scheduler_expires_due_values_in_order() {
+    let mut scheduler = Scheduler::new();
+    assert_eq!(scheduler.next_deadline(), None);
+    let late = scheduler.schedule(30, "late");
+    let first = scheduler.schedule(10, "first");
+    let second = scheduler.schedule(10, "second");
+    scheduler.schedule(20, "middle");
+    assert_eq!(first.deadline(), 10);
+    assert_eq!(scheduler.next_deadline(), Some(10));
+    assert_eq!(scheduler.get(second), Some(&"second"));
+
+    assert_eq!(scheduler.expire_until(9).count(), 0);
+    // Values due at the same deadline expire in the order they were scheduled
+    let expired: Vec<_> = scheduler.expire_until(20).collect();
+    assert_eq!(
+        expired,
+        vec![
+            (first, "first"),
+            (second, "second"),
+            (expired[2].0, "middle")
+        ]
+    );
+    assert_eq!(scheduler.cancel(first), None);
+    assert_eq!(scheduler.len(), 1);
+    assert_eq!(scheduler.peek(), Some((late, &"late")));
+
+    let early = scheduler.reschedule(late, 5).unwrap();
+    assert_eq!(scheduler.reschedule(late, 6), None);
+    assert_eq!(scheduler.next_deadline(), Some(5));
+    assert_eq!(scheduler.cancel(early), Some("late"));
+    assert!(scheduler.is_empty());
+
+    scheduler.schedule(u64::MAX, "never");
+    scheduler.schedule(0, "now");
+    let expired: Vec<_> = scheduler.expire_until(u64::MAX).map(|(_, v)| v).collect();
+    assert_eq!(expired, vec!["now", "never"]);
+}
+
+#[test]
+fn scheduler_matches_btree_map() {
+    let mut rng = rand::thread_rng();
+    let mut scheduler = Scheduler::new();
+    let mut model = BTreeMap::new();
+    let mut handles = Vec::new();
+    let mut now = 0;
+    // Rescheduled values expire after the values scheduled before at the same deadline, the
+    // model orders them by the step they were last scheduled at
+    for i in 0..20_000u64 {
+        match rng.gen_range(0..10) {
+            0..=5 => {
+                let at = now + rng.gen_range(0..1_000);
+                let handle = scheduler.schedule(at, i);
+                model.insert((at, i), i);
+                handles.push((handle, (at, i)));
+            }
+            6 | 7 if !handles.is_empty() => {
+                let (handle, key) = handles.swap_remove(rng.gen_range(0..handles.len()));
+                assert_eq!(scheduler.cancel(handle), model.remove(&key));
+            }
+            8 if !handles.is_empty() => {
+                let idx = rng.gen_range(0..handles.len());
+                let (handle, key) = handles[idx];
+                let at = now + rng.gen_range(0..1_000);
+                let moved = scheduler.reschedule(handle, at);
+                assert_eq!(moved.is_some(), model.contains_key(&key));
+                if let Some(moved) = moved {
+                    let value = model.remove(&key).unwrap();
+                    model.insert((at, i), value);
+                    handles[idx] = (moved, (at, i));
+                }
+            }
+            _ => {
+                now += rng.gen_range(0..200);
+                let mut expected = model.split_off(&(now + 1, 0));
+                std::mem::swap(&mut expected, &mut model);
+                let expired: Vec<_> = scheduler
+                    .expire_until(now)
+                    .map(|(handle, value)| (handle.deadline(), value))
+                    .collect();
+                let expected: Vec<_> = expected.into_iter().map(|((at, _), v)| (at, v)).collect();
+                assert_eq!(expired, expected);
+            }
+        }
+        assert_eq!(scheduler.len(), model.len());
+    }
+}
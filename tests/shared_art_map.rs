@@ -0,0 +1,113 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::art::ArtTree;
+use adaptive_radix_tree::shared_art_map::*;
+use std::thread;
+
+#[test]
+fn shared_art_map_reads_and_writes() {
+    let map = SharedArtMap::new();
+    assert!(map.is_empty());
+    assert_eq!(map.insert(b"a\0", 1), None);
+    assert_eq!(map.insert(b"a\0", 2), Some(1));
+    map.insert(b"ab\0", 3);
+    map.insert(b"b\0", 4);
+    assert_eq!(map.len(), 3);
+    assert!(map.contains_key(b"ab\0"));
+    assert_eq!(map.get(b"a\0"), Some(2));
+    assert_eq!(map.get_with(b"b\0", |v| v * 10), Some(40));
+    assert_eq!(map.update(b"b\0", |v| *v += 1), Some(()));
+    assert_eq!(map.update(b"c\0", |v| *v += 1), None);
+    assert_eq!(map.get(b"b\0"), Some(5));
+
+    assert_eq!(
+        map.collect_prefix(b"a"),
+        vec![(b"a\0".to_vec(), 2), (b"ab\0".to_vec(), 3)]
+    );
+    assert_eq!(
+        map.collect_range(&b"ab"[..]..),
+        vec![(b"ab\0".to_vec(), 3), (b"b\0".to_vec(), 5)]
+    );
+    let mut sum = 0;
+    map.for_each(|_, v| sum += v);
+    assert_eq!(sum, 10);
+
+    // Clones share the tree
+    let other = map.clone();
+    assert_eq!(other.delete(b"a\0"), Some(2));
+    assert_eq!(map.get(b"a\0"), None);
+    map.retain(|_, v| *v > 3);
+    assert_eq!(other.len(), 1);
+    assert_eq!(
+        other.with_read(|tree| tree.minimum().map(|(_, v)| *v)),
+        Some(5)
+    );
+    other.with_write(|tree| tree.insert(b"z\0", 9));
+    assert_eq!(map.read().get(b"z\0"), Some(&9));
+
+    let map = SharedArtMap::from(ArtTree::with_max_key_len(4));
+    assert_eq!(map.with_read(|tree| tree.max_key_len()), 4);
+    map.insert(b"k\0", "v");
+    assert!(format!("{:?}", map).starts_with("SharedArtMap"));
+}
+
+#[test]
+fn shared_art_map_writes_while_iterating_a_copy() {
+    let map = SharedArtMap::new();
+    for i in 0..1_000u32 {
+        map.insert(&i.to_be_bytes(), i);
+    }
+
+    // Iterating over a copy leaves the lock free for writes by the same thread
+    for (key, value) in map.collect_prefix(&[0, 0, 1]) {
+        map.insert(&key, value * 2);
+    }
+    assert_eq!(map.get(&300u32.to_be_bytes()), Some(600));
+    assert_eq!(map.get(&200u32.to_be_bytes()), Some(200));
+}
+
+#[test]
+fn shared_art_map_concurrent_readers_and_writers() {
+    let map = SharedArtMap::new();
+    let writers: Vec<_> = (0..4u32)
+        .map(|t| {
+            let map = map.clone();
+            thread::spawn(move || {
+                for i in 0..2_000u32 {
+                    let key = (i * 4 + t).to_be_bytes();
+                    map.insert(&key, i);
+                    if i % 3 == 0 {
+                        map.update(&key, |v| *v += 1);
+                    }
+                }
+            })
+        })
+        .collect();
+    let readers: Vec<_> = (0..2)
+        .map(|_| {
+            let map = map.clone();
+            thread::spawn(move || {
+                for _ in 0..50 {
+                    // Every scan sees a consistent, sorted tree
+                    let mut last: Option<Vec<u8>> = None;
+                    let mut count = 0;
+                    map.for_each(|key, _| {
+                        assert!(last.as_deref().is_none_or(|last| last < key));
+                        last = Some(key.to_vec());
+                        count += 1;
+                    });
+                    assert!(count <= 8_000);
+                }
+            })
+        })
+        .collect();
+    for handle in writers.into_iter().chain(readers) {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(map.len(), 8_000);
+    for i in 0..2_000u32 {
+        let expected = if i % 3 == 0 { i + 1 } else { i };
+        assert_eq!(map.get(&(i * 4 + 3).to_be_bytes()), Some(expected));
+    }
+}
@@ -0,0 +1,145 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::art::ArtTree;
+use adaptive_radix_tree::snapshot::*;
+
+/// Terminated keys sharing long prefixes, every hundredth one with a value of over 100 KB
+fn make_tree() -> ArtTree<String> {
+    let mut tree = ArtTree::new();
+    for i in 0..5_000u32 {
+        let key = format!("user/{}/session/{}\0", i % 13, i);
+        let value = if i % 100 == 0 {
+            "long value ".repeat(10_000 + i as usize)
+        } else {
+            format!("value {}", i)
+        };
+        tree.insert(key.as_bytes(), value);
+    }
+    tree
+}
+
+#[test]
+fn snapshot_round_trips_the_tree() {
+    let tree = make_tree();
+    let buf = tree.write_snapshot(Vec::new()).unwrap();
+    let loaded = ArtTree::<String>::read_snapshot(&buf[..]).unwrap();
+    assert_eq!(loaded.len(), tree.len());
+    assert!(loaded.entries().eq(tree.entries()));
+    assert_eq!(loaded.memory_stats(), tree.memory_stats());
+
+    // Front coding stores the shared parts of the keys once
+    let full_bytes: usize = tree
+        .entries()
+        .map(|(key, value)| 12 + key.len() + value.len())
+        .sum();
+    let key_bytes: usize = tree.entries().map(|(key, _)| key.len()).sum();
+    assert!(buf.len() < full_bytes - key_bytes / 2);
+
+    let empty = ArtTree::<u64>::new().write_snapshot(Vec::new()).unwrap();
+    assert!(ArtTree::<u64>::read_snapshot(&empty[..])
+        .unwrap()
+        .is_empty());
+
+    // The empty key is the only key of its tree
+    let mut only_empty = ArtTree::new();
+    only_empty.insert(b"", "value".to_string());
+    let buf = only_empty.write_snapshot(Vec::new()).unwrap();
+    let loaded = ArtTree::<String>::read_snapshot(&buf[..]).unwrap();
+    assert!(loaded.entries().eq(only_empty.entries()));
+}
+
+#[test]
+fn snapshot_reader_borrows_entries() {
+    let mut writer = SnapshotWriter::new(Vec::new()).unwrap();
+    writer.push(b"apple\0", "red").unwrap();
+    writer.push(b"apricot\0", "orange").unwrap();
+    writer.push(b"banana\0", "yellow").unwrap();
+    assert_eq!(writer.len(), 3);
+    let buf = writer.finish().unwrap();
+
+    let mut reader = SnapshotReader::new(&buf[..]).unwrap();
+    let mut entries = Vec::new();
+    while let Some((key, value)) = reader.read_entry::<&str>().unwrap() {
+        entries.push((key.to_vec(), value.to_string()));
+    }
+    assert_eq!(reader.len(), 3);
+    assert_eq!(
+        entries,
+        [
+            (b"apple\0".to_vec(), "red".to_string()),
+            (b"apricot\0".to_vec(), "orange".to_string()),
+            (b"banana\0".to_vec(), "yellow".to_string()),
+        ]
+    );
+    assert!(reader.read_entry::<&str>().unwrap().is_none());
+}
+
+#[test]
+#[should_panic(expected = "ascending order")]
+fn snapshot_writer_rejects_unordered_keys() {
+    let mut writer = SnapshotWriter::new(Vec::new()).unwrap();
+    writer.push(b"b\0", &1u32).unwrap();
+    let _ = writer.push(b"a\0", &2u32);
+}
+
+#[test]
+fn snapshot_reader_rejects_damaged_streams() {
+    let buf = make_tree().write_snapshot(Vec::new()).unwrap();
+
+    let truncated = ArtTree::<String>::read_snapshot(&buf[..buf.len() - 3]);
+    assert!(matches!(truncated, Err(SnapshotError::Io(_))));
+
+    let mut bad_magic = buf.clone();
+    bad_magic[0] = b'X';
+    assert!(matches!(
+        ArtTree::<String>::read_snapshot(&bad_magic[..]),
+        Err(SnapshotError::BadMagic)
+    ));
+
+    let mut wrong_count = buf.clone();
+    let last = wrong_count.len() - 8;
+    wrong_count[last] ^= 1;
+    assert!(matches!(
+        ArtTree::<String>::read_snapshot(&wrong_count[..]),
+        Err(SnapshotError::Corrupt)
+    ));
+
+    // The second key of "a\0", "b\0" claims to share 2 bytes with the first one
+    let mut writer = SnapshotWriter::new(Vec::new()).unwrap();
+    writer.push(b"a\0", &1u32).unwrap();
+    writer.push(b"b\0", &2u32).unwrap();
+    let mut unordered = writer.finish().unwrap();
+    let second = 8 + 12 + 2 + 4;
+    unordered[second] = 2;
+    assert!(matches!(
+        ArtTree::<u32>::read_snapshot(&unordered[..]),
+        Err(SnapshotError::Corrupt)
+    ));
+}
+
+#[test]
+fn snapshot_reader_rejects_values_of_another_size() {
+    let mut writer = SnapshotWriter::new(Vec::new()).unwrap();
+    writer.push(b"a\0", &1u64).unwrap();
+    writer.push(b"b\0", &2u64).unwrap();
+    let buf = writer.finish().unwrap();
+
+    // The value of "a\0" claims to be 4 bytes long
+    let mut resized = buf.clone();
+    resized[8 + 8] = 4;
+    assert!(matches!(
+        ArtTree::<u64>::read_snapshot(&resized[..]),
+        Err(SnapshotError::Corrupt)
+    ));
+
+    // Well-formed records whose values are no `u64`
+    let mut writer = SnapshotWriter::new(Vec::new()).unwrap();
+    writer.push(b"a\0", &b"abc"[..]).unwrap();
+    let short = writer.finish().unwrap();
+    let mut reader = SnapshotReader::new(&short[..]).unwrap();
+    assert!(matches!(
+        reader.read_entry::<u64>(),
+        Err(SnapshotError::Corrupt)
+    ));
+    assert!(ArtTree::<u64>::read_snapshot(&buf[..]).is_ok());
+}
@@ -0,0 +1,95 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::string_art_map::*;
+
+#[test]
+fn string_map_orders_keys_bytewise() {
+    let keys = ["b", "a", "ab", "a\0", "a\0b", "", "abc", "\0"];
+    let mut map = StringArtMap::new();
+    for (i, key) in keys.iter().enumerate() {
+        assert_eq!(map.insert(key, i), None);
+    }
+    assert_eq!(map.len(), keys.len());
+
+    let mut sorted = keys.to_vec();
+    sorted.sort_unstable();
+    assert_eq!(map.keys().collect::<Vec<_>>(), sorted);
+    for (i, key) in keys.iter().enumerate() {
+        assert_eq!(map.get(key), Some(&i));
+    }
+    assert_eq!(map.get("A"), None);
+    assert_eq!(map.delete("a\0"), Some(3));
+    assert!(!map.contains_key("a\0"));
+}
+
+#[test]
+fn string_map_case_insensitive_keeps_original_key() {
+    let mut map = StringArtMap::case_insensitive();
+    assert!(map.is_case_insensitive());
+    map.insert("Foo", 1);
+    assert_eq!(map.insert("FOO", 2), Some(1));
+    map.insert("bar", 3);
+    map.insert("Ärger", 4);
+
+    assert_eq!(map.len(), 3);
+    assert_eq!(map.get("foo"), Some(&2));
+    assert_eq!(map.get_key_value("fOo"), Some(("Foo", &2)));
+    assert_eq!(map.get("äRGER"), Some(&4));
+    *map.get_mut("BAR").unwrap() += 10;
+    assert_eq!(
+        map.iter().collect::<Vec<_>>(),
+        vec![("bar", &13), ("Foo", &2), ("Ärger", &4)]
+    );
+    assert_eq!(map.delete("foo"), Some(2));
+    assert_eq!(map.len(), 2);
+}
+
+#[test]
+fn string_map_follows_sort_key_collation() {
+    // Swedish alphabet order: å, ä and ö come after z
+    let sort_key = |key: &str| {
+        key.chars()
+            .flat_map(|c| {
+                let weight = match c.to_lowercase().next().unwrap() {
+                    'å' => 27,
+                    'ä' => 28,
+                    'ö' => 29,
+                    c => c as u32 - 'a' as u32 + 1,
+                };
+                (weight as u16).to_be_bytes()
+            })
+            .collect()
+    };
+    let mut map = StringArtMap::with_sort_key(sort_key);
+    for (i, key) in ["öl", "ärta", "zebra", "åsna", "Apa", "ost"]
+        .iter()
+        .enumerate()
+    {
+        map.insert(key, i);
+    }
+    assert!(!map.is_case_insensitive());
+    assert_eq!(
+        map.keys().collect::<Vec<_>>(),
+        vec!["Apa", "ost", "zebra", "åsna", "ärta", "öl"]
+    );
+    assert_eq!(map.get("apa"), Some(&4));
+}
+
+#[test]
+fn string_map_rekey_moves_value() {
+    let mut map = StringArtMap::case_insensitive();
+    map.insert("src/lib.rs", vec![1]);
+    map.insert("src/main.rs", vec![2]);
+
+    assert!(map.rekey("src/lib.rs", "src/art.rs"));
+    assert!(!map.contains_key("src/lib.rs"));
+    assert_eq!(map.get("src/art.rs"), Some(&vec![1]));
+    assert!(!map.rekey("src/lib.rs", "src/other.rs"));
+
+    assert!(map.rekey("src/art.rs", "src/main.rs"));
+    assert_eq!(map.len(), 1);
+    assert_eq!(map.get("src/main.rs"), Some(&vec![1]));
+
+    assert!(map.rekey("src/main.rs", "SRC/Main.rs"));
+    assert_eq!(map.keys().collect::<Vec<_>>(), vec!["SRC/Main.rs"]);
+}
@@ -0,0 +1,164 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::art::ArtTree;
+use adaptive_radix_tree::succinct_art::SuccinctArt;
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+/// Terminated keys below nodes of every fan-out: the first byte takes all 256 values, and the
+/// second byte between 1 and 256 values. Every third key continues after the second byte, so
+/// the trie cuts it off into a tail.
+fn make_entries() -> BTreeMap<Vec<u8>, u64> {
+    let mut entries = BTreeMap::new();
+    for first in 0..=255u8 {
+        let fan_out = if first % 16 == 0 {
+            256
+        } else {
+            first as usize % 20 + 1
+        };
+        for s in 0..fan_out {
+            let mut key = vec![first, (s * 131 % 256) as u8];
+            if s % 3 == 1 {
+                key.extend_from_slice(format!("/tail{}", first).as_bytes());
+            }
+            key.push(0);
+            entries.insert(key, entries.len() as u64);
+        }
+    }
+    entries
+}
+
+fn make_tree(entries: &BTreeMap<Vec<u8>, u64>) -> ArtTree<u64> {
+    let mut tree = ArtTree::new();
+    for (key, value) in entries {
+        tree.insert(key, *value);
+    }
+    tree
+}
+
+#[test]
+fn succinct_art_matches_source_tree() {
+    let expected = make_entries();
+    let succinct: SuccinctArt<u64> = make_tree(&expected).freeze().to_succinct();
+
+    assert_eq!(succinct.len(), expected.len());
+    for (key, value) in &expected {
+        assert_eq!(succinct.get(key), Some(value));
+    }
+    assert_eq!(succinct.get(&[16]), None);
+    assert_eq!(succinct.get(&[16, 131, 0]), None);
+    assert_eq!(succinct.get(b"\x10\x83/tail16"), None);
+    assert_eq!(succinct.get(&[1, 200, 0]), None);
+    assert!(!succinct.contains_key(b""));
+
+    let entries: Vec<_> = succinct.iter().map(|(key, value)| (key, *value)).collect();
+    let expected_entries: Vec<_> = expected.into_iter().collect();
+    assert_eq!(entries, expected_entries);
+}
+
+#[test]
+fn succinct_art_scans_prefixes_and_ranges() {
+    let expected = make_entries();
+    let succinct = make_tree(&expected).into_succinct();
+
+    for prefix in [
+        &b""[..],
+        &[16],
+        &[16, 131],
+        b"\x10\x83/tail1",
+        &[5],
+        &[255, 0],
+    ] {
+        let scanned: Vec<_> = succinct.scan_prefix(prefix).map(|(key, _)| key).collect();
+        let expected_keys: Vec<_> = expected
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect();
+        assert_eq!(scanned, expected_keys, "prefix {:?}", prefix);
+    }
+
+    let bounds = [
+        &[0][..],
+        &[16],
+        &[16, 131, b'/'],
+        &[16, 200],
+        &[100, 3, 0],
+        &[255, 255],
+    ];
+    for &start in &bounds {
+        for &end in &bounds {
+            let ranges = [
+                (Bound::Included(start), Bound::Excluded(end)),
+                (Bound::Excluded(start), Bound::Included(end)),
+                (Bound::Unbounded, Bound::Included(end)),
+                (Bound::Included(start), Bound::Unbounded),
+            ];
+            for range in ranges {
+                if start > end && range.0 != Bound::Unbounded && range.1 != Bound::Unbounded {
+                    continue;
+                }
+                let scanned: Vec<_> = succinct.range(range).map(|(key, _)| key).collect();
+                let expected_keys: Vec<_> = expected
+                    .range::<[u8], _>(range)
+                    .map(|(key, _)| key.clone())
+                    .collect();
+                assert_eq!(scanned, expected_keys, "range {:?}", range);
+            }
+        }
+    }
+}
+
+#[test]
+fn succinct_art_handles_tiny_trees() {
+    let empty = ArtTree::<u64>::new().into_succinct();
+    assert!(empty.is_empty());
+    assert_eq!(empty.get(b""), None);
+    assert_eq!(empty.iter().count(), 0);
+    assert_eq!(empty.range(..).count(), 0);
+
+    let mut tree = ArtTree::new();
+    tree.insert(b"", 1u64);
+    let only_empty = tree.into_succinct();
+    assert_eq!(only_empty.get(b""), Some(&1));
+    assert_eq!(only_empty.get(b"a"), None);
+    assert_eq!(only_empty.iter().collect::<Vec<_>>(), [(vec![], &1)]);
+    assert_eq!(only_empty.scan_prefix(b"a").count(), 0);
+    assert_eq!(
+        only_empty
+            .range((Bound::Excluded(&b""[..]), Bound::Unbounded))
+            .count(),
+        0
+    );
+
+    let mut tree = ArtTree::new();
+    tree.insert(b"key", 2u64);
+    let single = tree.into_succinct();
+    assert_eq!(single.get(b"key"), Some(&2));
+    assert_eq!(single.get(b"ke"), None);
+    assert_eq!(
+        single.scan_prefix(b"ke").collect::<Vec<_>>(),
+        [(b"key".to_vec(), &2)]
+    );
+}
+
+#[test]
+fn succinct_art_is_smaller_than_the_source_tree() {
+    // Hashed keys give the tree nodes of all types
+    let mut tree = ArtTree::new();
+    for i in 0..50_000u64 {
+        tree.insert(&i.wrapping_mul(0x9e37_79b9_7f4a_7c15).to_be_bytes(), i);
+    }
+    let tree_bytes = tree.memory_stats().total_bytes();
+    let frozen = tree.freeze();
+    let succinct = frozen.to_succinct();
+    assert!(
+        succinct.memory_bytes() * 2 < tree_bytes,
+        "{} bytes compiled from {}",
+        succinct.memory_bytes(),
+        tree_bytes
+    );
+    for (key, value) in frozen.entries() {
+        assert_eq!(succinct.get(key), Some(value));
+    }
+}
@@ -0,0 +1,61 @@
+#![cfg(feature = "test-util")]
+
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::test_util::*;
+
+#[test]
+fn test_util_generated_logs_replay_cleanly() {
+    for seed in 0..20 {
+        let log = OpLog::generate(seed, 2_000);
+        assert_eq!(log, OpLog::generate(seed, 2_000));
+        if let Err(mismatch) = log.replay() {
+            panic!("seed {}: {}", seed, mismatch);
+        }
+        assert_eq!(log.minimize(), None);
+    }
+}
+
+#[test]
+fn test_util_recorder_logs_applied_ops() {
+    let mut recorder = Recorder::new();
+    assert_eq!(
+        recorder.apply(Op::Insert(vec![1, 0], 10)),
+        Outcome::Value(None)
+    );
+    assert_eq!(
+        recorder.apply(Op::Insert(vec![2, 0], 20)),
+        Outcome::Value(None)
+    );
+    assert_eq!(
+        recorder.apply(Op::Get(vec![1, 0])),
+        Outcome::Value(Some(10))
+    );
+    assert_eq!(
+        recorder.apply(Op::Range(vec![1, 0], vec![3, 0])),
+        Outcome::Entries(vec![(vec![1, 0], 10), (vec![2, 0], 20)])
+    );
+    assert_eq!(
+        recorder.apply(Op::PopLast),
+        Outcome::Entry(Some((vec![2, 0], 20)))
+    );
+    assert_eq!(recorder.tree().len(), 1);
+
+    let log = recorder.into_log();
+    assert_eq!(log.len(), 5);
+    assert_eq!(log.replay(), Ok(()));
+}
+
+#[test]
+fn test_util_minimize_keeps_a_failing_log_failing() {
+    // Keys that are prefixes of each other break the tree's invariants, so the replay fails
+    let mut log = OpLog::generate(7, 200);
+    log.ops.insert(100, Op::Insert(vec![5, 5, 5], 1));
+    log.ops
+        .insert(150, Op::Insert(vec![5, 5, 5, 5, 5, 5, 5, 5], 2));
+    log.ops.insert(160, Op::Get(vec![5, 5, 5, 5, 5, 5, 5, 5]));
+
+    let minimized = log.minimize().expect("the log fails to replay");
+    assert!(minimized.replay().is_err());
+    assert!(minimized.len() <= 3, "{:?}", minimized);
+}
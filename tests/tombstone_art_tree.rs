@@ -0,0 +1,76 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::tombstone_art_tree::*;
+use adaptive_radix_tree::versioned_art_tree::GcStats;
+
+#[test]
+fn test_deletes_leave_tombstones() {
+    let mut tree = TombstoneArtTree::<u32>::new();
+    tree.insert(b"a", 1);
+    tree.insert(b"b", 2);
+    tree.insert(b"c", 3);
+
+    assert_eq!(tree.delete(b"b"), Some(2));
+    let deleted = tree.current_version();
+    assert_eq!(tree.delete(b"b"), None);
+    assert_eq!(tree.delete(b"x"), None);
+    assert_eq!(tree.current_version(), deleted);
+    assert_eq!(tree.get(b"b"), None);
+    assert!(!tree.contains_key(b"b"));
+    assert_eq!(tree.tombstone(b"b"), Some(deleted));
+    assert_eq!(tree.tombstone(b"a"), None);
+    assert_eq!((tree.len(), tree.tombstones()), (2, 1));
+
+    let live: Vec<_> = tree.iter().map(|(k, v)| (k.to_vec(), *v)).collect();
+    assert_eq!(live, vec![(b"a".to_vec(), 1), (b"c".to_vec(), 3)]);
+    let entries: Vec<_> = tree.entries().map(|(k, v)| (k[0], v.copied())).collect();
+    assert_eq!(
+        entries,
+        vec![(b'a', Some(1)), (b'b', None), (b'c', Some(3))]
+    );
+    let tombstones: Vec<_> = tree.iter_tombstones().map(|(k, v)| (k[0], v)).collect();
+    assert_eq!(tombstones, vec![(b'b', deleted)]);
+
+    // Inserting the key again replaces its tombstone
+    assert_eq!(tree.insert(b"b", 4), None);
+    assert_eq!(tree.tombstone(b"b"), None);
+    assert_eq!((tree.len(), tree.tombstones()), (3, 0));
+}
+
+#[test]
+fn test_purge_tombstones() {
+    let mut tree = TombstoneArtTree::<u32>::new();
+    for i in 0..100u32 {
+        tree.insert(&i.to_be_bytes(), i);
+    }
+    for i in (0..100u32).step_by(3) {
+        *tree.get_mut(&i.to_be_bytes()).unwrap() += 1;
+        tree.delete(&i.to_be_bytes());
+    }
+    assert_eq!(tree.tombstones(), 34);
+    assert_eq!(tree.purge_tombstones(), 34);
+    assert_eq!(tree.purge_tombstones(), 0);
+    assert_eq!(tree.len(), 66);
+    assert_eq!(tree.entries().count(), 66);
+    assert!(tree.iter().all(|(_, v)| v % 3 != 0));
+}
+
+#[test]
+fn test_gc_removes_tombstones_up_to_horizon() {
+    let mut tree = TombstoneArtTree::<u32>::new();
+    for i in 0..1_000u32 {
+        tree.insert(&i.to_be_bytes(), i);
+    }
+    for i in 0..600u32 {
+        tree.delete(&i.to_be_bytes());
+    }
+    let horizon = tree.current_version() - 100;
+
+    let stats = tree.gc(horizon);
+    assert_eq!(stats.entries, 500);
+    assert!(stats.bytes > 0);
+    assert_eq!(tree.tombstones(), 100);
+    assert!(tree.iter_tombstones().all(|(_, version)| version > horizon));
+    assert_eq!(tree.len(), 400);
+    assert_eq!(tree.gc(horizon), GcStats::default());
+}
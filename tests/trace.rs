@@ -0,0 +1,120 @@
+#![cfg(feature = "tracing")]
+
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::art::ArtTree;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+/// Writes the fields of an event or span as `name=value` pairs
+struct Fields<'a>(&'a mut String);
+
+impl Visit for Fields<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.push_str(&format!(" {}={:?}", field.name(), value));
+    }
+}
+
+/// Records the events and spans it sees, one line each
+#[derive(Clone, Default)]
+struct Recorder {
+    lines: Arc<Mutex<Vec<String>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Recorder {
+    fn lines_with(&self, pattern: &str) -> Vec<String> {
+        let lines = self.lines.lock().unwrap();
+        lines
+            .iter()
+            .filter(|l| l.contains(pattern))
+            .cloned()
+            .collect()
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut line = format!("span {}", span.metadata().name());
+        span.record(&mut Fields(&mut line));
+        self.lines.lock().unwrap().push(line);
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _: &Id, values: &Record<'_>) {
+        let mut line = "record".to_string();
+        values.record(&mut Fields(&mut line));
+        self.lines.lock().unwrap().push(line);
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut line = "event".to_string();
+        event.record(&mut Fields(&mut line));
+        self.lines.lock().unwrap().push(line);
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+#[test]
+fn trace_reports_structural_operations() {
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let mut tree = ArtTree::new();
+        for c in 0..5u8 {
+            tree.insert(&[b'a', b'b', c], c);
+        }
+        tree.insert(b"ax\x00", 9);
+        for c in 0..5u8 {
+            tree.delete(&[b'a', b'b', c]);
+        }
+    });
+
+    let upgrades = recorder.lines_with("node upgrade");
+    assert_eq!(upgrades.len(), 1);
+    assert!(upgrades[0].contains("path=b\"ab\""));
+    assert!(upgrades[0].contains("from=Node4"));
+    let splits = recorder.lines_with("prefix split");
+    assert_eq!(splits.len(), 1);
+    assert!(splits[0].contains("path=b\"a\""));
+    assert!(splits[0].contains("prefix_len=2 at=1"));
+    let downgrades = recorder.lines_with("node downgrade");
+    assert_eq!(downgrades.len(), 1);
+    assert!(downgrades[0].contains("from=Node16"));
+}
+
+#[test]
+fn trace_reports_detached_subtrees_and_bulk_loads() {
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let mut tree = ArtTree::new();
+        for i in 0..100u32 {
+            tree.insert(format!("{}/{:03}", i % 2, i).as_bytes(), i);
+        }
+        let snapshot = tree.write_snapshot(Vec::new()).unwrap();
+        let loaded = ArtTree::<u32>::read_snapshot(&snapshot[..]).unwrap();
+        assert_eq!(loaded.len(), 100);
+        assert_eq!(tree.drain_range(&b"0/"[..]..&b"1/"[..]).count(), 50);
+    });
+
+    let detached = recorder.lines_with("subtree detach");
+    assert!(!detached.is_empty());
+    assert!(detached.iter().all(|l| l.contains("path=b\"0/0")));
+    let phases = recorder.lines_with("span bulk_load");
+    assert!(phases[0].contains("phase=\"write_snapshot\""));
+    assert!(phases[1].contains("phase=\"read_snapshot\""));
+    assert!(phases[2].contains("phase=\"finish_tree\""));
+    assert_eq!(recorder.lines_with("record entries=100").len(), 3);
+}
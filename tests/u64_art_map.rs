@@ -104,7 +104,400 @@ fn test_when_executing_random_operations_min_max_returns_same_as_btree() {
             }
         }
 
-        assert_eq!(artmap.minimum().unwrap().0, *btree.iter().next().unwrap().0);
-        assert_eq!(artmap.maximum().unwrap().0, *btree.iter().last().unwrap().0);
+        assert_eq!(
+            artmap.minimum().map(|(k, _)| k),
+            btree.keys().next().copied()
+        );
+        assert_eq!(
+            artmap.maximum().map(|(k, _)| k),
+            btree.keys().next_back().copied()
+        );
     }
 }
+
+#[test]
+fn test_get_contains_key_and_len() {
+    let mut artmap = U64ArtMap::<String>::new();
+    assert!(artmap.is_empty());
+    artmap.insert(100, "a".to_string());
+    artmap.insert(u64::MAX, "b".to_string());
+
+    assert_eq!(artmap.len(), 2);
+    assert!(!artmap.is_empty());
+    assert_eq!(artmap.get(&100).map(String::as_str), Some("a"));
+    assert_eq!(artmap.get(&101), None);
+    assert!(artmap.contains_key(&u64::MAX));
+    assert!(!artmap.contains_key(&0));
+
+    artmap.delete(100);
+    artmap.delete(100);
+    assert_eq!(artmap.len(), 1);
+}
+
+#[test]
+fn test_iterators_yield_keys_in_order() {
+    let mut artmap = U64ArtMap::<u64>::new();
+    let mut btree = BTreeMap::new();
+    let mut rng = rand::thread_rng();
+    for _ in 0..1000 {
+        let key = rng.gen::<u64>() >> rng.gen_range(0..64);
+        artmap.insert(key, key / 2);
+        btree.insert(key, key / 2);
+    }
+
+    let entries: Vec<_> = artmap.iter().map(|(k, v)| (k, *v)).collect();
+    assert_eq!(entries, btree.clone().into_iter().collect::<Vec<_>>());
+    assert_eq!(
+        artmap.keys().collect::<Vec<_>>(),
+        btree.keys().copied().collect::<Vec<_>>()
+    );
+    assert_eq!(artmap.values().len(), btree.len());
+
+    for (k, v) in artmap.iter_mut() {
+        *v = k;
+    }
+    assert!(artmap.iter().all(|(k, v)| k == *v));
+    assert_eq!(
+        artmap.values().copied().collect::<Vec<_>>(),
+        artmap.keys().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_range_matches_btree() {
+    use std::ops::Bound;
+
+    let mut artmap = U64ArtMap::<u64>::new();
+    let mut btree = BTreeMap::new();
+    let mut rng = rand::thread_rng();
+    for _ in 0..1000 {
+        let key = rng.gen_range(0..5000u64) << rng.gen_range(0..40);
+        artmap.insert(key, key);
+        btree.insert(key, key);
+    }
+
+    for _ in 0..200 {
+        let a = rng.gen_range(0..5000u64) << rng.gen_range(0..40);
+        let b = rng.gen_range(0..5000u64) << rng.gen_range(0..40);
+        let (low, high) = (a.min(b), a.max(b));
+        let bounds = (Bound::Excluded(low), Bound::Included(high));
+
+        let expected: Vec<_> = btree.range(bounds).map(|(k, _)| *k).collect();
+        let actual: Vec<_> = artmap.range(bounds).map(|(k, _)| k).collect();
+        assert_eq!(actual, expected);
+
+        let expected: Vec<_> = btree.range(low..high).map(|(k, _)| *k).collect();
+        let actual: Vec<_> = artmap.range(low..high).map(|(k, _)| k).collect();
+        assert_eq!(actual, expected);
+    }
+    assert_eq!(artmap.range(..).count(), btree.len());
+}
+
+#[test]
+fn test_entry_counts_ids() {
+    let mut artmap = U64ArtMap::<u32>::new();
+    for id in [7u64, 3, 7, 7, 1 << 40, 3].iter() {
+        *artmap.entry(*id).or_insert(0) += 1;
+    }
+    assert_eq!(
+        artmap.iter().map(|(k, v)| (k, *v)).collect::<Vec<_>>(),
+        vec![(3, 2), (7, 3), (1 << 40, 1)]
+    );
+
+    match artmap.entry(7) {
+        Entry::Occupied(entry) => {
+            assert_eq!(*entry.key(), 7);
+            assert_eq!(entry.remove(), 3);
+        }
+        Entry::Vacant(_) => panic!("key 7 should be occupied"),
+    }
+    match artmap.entry(7) {
+        Entry::Occupied(_) => panic!("key 7 should be vacant"),
+        Entry::Vacant(entry) => {
+            *entry.insert(10) += 1;
+        }
+    }
+    artmap.entry(3).and_modify(|v| *v *= 10).or_default();
+    artmap.entry(4).and_modify(|v| *v *= 10).or_default();
+
+    assert_eq!(artmap.get(&7), Some(&11));
+    assert_eq!(artmap.get(&3), Some(&20));
+    assert_eq!(artmap.get(&4), Some(&0));
+}
+
+#[test]
+fn test_collect_extend_and_into_iter() {
+    let mut artmap: U64ArtMap<&str> = vec![(3, "c"), (1, "a")].into_iter().collect();
+    artmap.extend(vec![(2, "b"), (3, "C")]);
+    assert_eq!(artmap.len(), 3);
+
+    for (_, value) in &mut artmap {
+        *value = if *value == "C" { "c" } else { value };
+    }
+    let keys: Vec<_> = (&artmap).into_iter().map(|(k, _)| k).collect();
+    assert_eq!(keys, vec![1, 2, 3]);
+
+    let owned: Vec<_> = artmap.into_iter().collect();
+    assert_eq!(owned, vec![(1, "a"), (2, "b"), (3, "c")]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_round_trip_as_integer_map() {
+    let artmap: U64ArtMap<String> = vec![(300, "c".to_string()), (2, "a".to_string())]
+        .into_iter()
+        .collect();
+
+    let json = serde_json::to_string(&artmap).unwrap();
+    assert_eq!(json, r#"{"2":"a","300":"c"}"#);
+
+    let decoded: U64ArtMap<String> = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        decoded.into_iter().collect::<Vec<_>>(),
+        artmap.into_iter().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_retain_and_drain() {
+    let mut artmap: U64ArtMap<u64> = (0..1000u64).map(|k| (k * 31, k)).collect();
+    artmap.retain(|k, v| {
+        *v += 1;
+        k % 2 == 0
+    });
+
+    assert_eq!(artmap.len(), 500);
+    assert!(artmap.iter().all(|(k, v)| k % 2 == 0 && *v == k / 31 + 1));
+
+    let drained: Vec<_> = artmap.drain().collect();
+    assert_eq!(drained.len(), 500);
+    assert!(drained.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    assert!(artmap.is_empty());
+    assert_eq!(artmap.minimum(), None);
+
+    artmap.insert(5, 5);
+    assert_eq!(artmap.iter().count(), 1);
+}
+
+#[test]
+fn test_pops_match_btree_as_work_queue() {
+    let mut artmap = U64ArtMap::<u64>::new();
+    let mut btree = BTreeMap::new();
+    let mut rng = rand::thread_rng();
+    let mut next = 0u64;
+    for _ in 0..20000 {
+        match rng.gen_range(0..4) {
+            0 => {
+                assert_eq!(
+                    artmap.pop_first(),
+                    btree
+                        .keys()
+                        .next()
+                        .copied()
+                        .map(|k| (k, btree.remove(&k).unwrap()))
+                );
+            }
+            1 => {
+                assert_eq!(
+                    artmap.pop_last(),
+                    btree
+                        .keys()
+                        .next_back()
+                        .copied()
+                        .map(|k| (k, btree.remove(&k).unwrap()))
+                );
+            }
+            _ => {
+                next += rng.gen_range(1..1000);
+                artmap.insert(next, next);
+                btree.insert(next, next);
+            }
+        }
+        assert_eq!(artmap.len(), btree.len());
+        assert_eq!(
+            artmap.peek_first(),
+            btree.iter().next().map(|(k, v)| (*k, v))
+        );
+        assert_eq!(
+            artmap.peek_last(),
+            btree.iter().next_back().map(|(k, v)| (*k, v))
+        );
+    }
+}
+
+#[test]
+fn u64_window_and_purge_older_than() {
+    let mut artmap = U64ArtMap::new();
+    let mut btree = BTreeMap::new();
+    let mut timestamp = 1_600_000_000_000u64;
+    for i in 0..20_000u64 {
+        timestamp += i % 7 * 13 + 1;
+        artmap.insert(timestamp, i);
+        btree.insert(timestamp, i);
+    }
+
+    let keys: Vec<_> = btree.keys().copied().collect();
+    let (start, end) = (keys[5_000], keys[6_000]);
+    assert_eq!(
+        artmap.window(start, end).collect::<Vec<_>>(),
+        btree
+            .range(start..end)
+            .map(|(k, v)| (*k, v))
+            .collect::<Vec<_>>()
+    );
+
+    for cutoff in [keys[0], keys[1], keys[777], keys[10_000] + 1, keys[19_999]].iter() {
+        let kept = btree.split_off(cutoff);
+        assert_eq!(artmap.purge_older_than(*cutoff), btree.len());
+        btree = kept;
+        assert_eq!(artmap.len(), btree.len());
+        assert_eq!(
+            artmap.peek_first().map(|(k, _)| k),
+            btree.keys().next().copied()
+        );
+    }
+    assert_eq!(artmap.purge_older_than(keys[19_999]), 0);
+    assert_eq!(
+        artmap.iter().collect::<Vec<_>>(),
+        vec![(keys[19_999], &19_999)]
+    );
+}
+
+#[test]
+fn u64_pop_first_if_as_timer_queue() {
+    let mut timers = U64ArtMap::new();
+    for deadline in [30u64, 10, 20, 40].iter() {
+        timers.insert(*deadline, format!("timer {}", deadline));
+    }
+
+    let mut fired = Vec::new();
+    while let Some((deadline, _)) = timers.pop_first_if(|&deadline, _| deadline <= 25) {
+        fired.push(deadline);
+    }
+    assert_eq!(fired, vec![10, 20]);
+    assert_eq!(timers.pop_last_if(|&deadline, _| deadline > 100), None);
+    assert_eq!(
+        timers.pop_last_if(|_, name| name == "timer 40"),
+        Some((40, "timer 40".to_string()))
+    );
+    assert_eq!(timers.len(), 1);
+}
+
+#[test]
+fn u64_drain_range() {
+    let mut artmap: U64ArtMap<u64> = (0..1_000u64).map(|i| (i * 3, i)).collect();
+    let drained: Vec<_> = artmap.drain_range(300..=600).collect();
+    assert_eq!(drained, (100..=200).map(|i| (i * 3, i)).collect::<Vec<_>>());
+    assert_eq!(artmap.len(), 899);
+    assert_eq!(artmap.range(250..650).count(), 32);
+}
+
+#[test]
+fn u64_rekey() {
+    let mut artmap: U64ArtMap<String> = (0..10u64).map(|i| (i, i.to_string())).collect();
+    assert!(artmap.rekey(&3, &30));
+    assert!(!artmap.rekey(&3, &31));
+    assert_eq!(artmap.get(&30), Some(&"3".to_string()));
+    assert_eq!(artmap.keys().last(), Some(30));
+    assert!(artmap.rekey(&30, &4));
+    assert_eq!(artmap.get(&4), Some(&"3".to_string()));
+    assert_eq!(artmap.len(), 9);
+}
+
+#[test]
+fn u64_reschedule_first() {
+    // Keys are deadlines times 16 plus the timer id, values are the periods
+    let mut timers = U64ArtMap::<u64>::new();
+    assert_eq!(timers.reschedule_first(&1), None);
+    for id in 1..10u64 {
+        timers.insert(id * 10 * 16 + id, id * 10);
+    }
+
+    // The value stays at its address while its deadline moves
+    let value = timers.get(&(10 * 16 + 1)).unwrap() as *const u64;
+    assert_eq!(timers.reschedule_first(&(20 * 16 + 1)), Some(10 * 16 + 1));
+    assert_eq!(timers.get(&(20 * 16 + 1)).unwrap() as *const u64, value);
+
+    let mut now = 0;
+    let mut fired = [0; 10];
+    for _ in 0..1_000 {
+        let (key, &period) = timers.peek_first().unwrap();
+        let (deadline, id) = (key / 16, key % 16);
+        assert!(deadline >= now);
+        now = deadline;
+        fired[id as usize] += 1;
+        let next = (deadline + period) * 16 + id;
+        assert_eq!(timers.reschedule_first(&next), Some(key));
+    }
+    assert_eq!(timers.len(), 9);
+    assert!(fired[1] > fired[2] && fired[2] > fired[9]);
+
+    // Moving onto a pending key replaces its value
+    let first = timers.peek_first().unwrap().0;
+    let last = timers.peek_last().unwrap().0;
+    assert_eq!(timers.reschedule_first(&last), Some(first));
+    assert_eq!(timers.len(), 8);
+    assert!(timers.reschedule(&last, &(last + 16)));
+    assert!(!timers.reschedule(&last, &(last + 32)));
+}
+
+#[test]
+fn u64_swap() {
+    let mut artmap: U64ArtMap<String> = (0..100u64).map(|i| (i, i.to_string())).collect();
+    assert!(artmap.swap(&1, &99));
+    assert_eq!(artmap.get(&1), Some(&"99".to_string()));
+    assert_eq!(artmap.get(&99), Some(&"1".to_string()));
+    assert!(!artmap.swap(&1, &100));
+    assert_eq!(artmap.get(&1), Some(&"99".to_string()));
+}
+
+#[test]
+fn u64_next_after_and_prev_before_mut() {
+    let mut artmap: U64ArtMap<u64> = (0..10u64).map(|i| (i * 10, i)).collect();
+    *artmap.next_after_mut(&20).unwrap().1 += 100;
+    assert_eq!(artmap.get(&30), Some(&103));
+    assert_eq!(
+        artmap.prev_before_mut(&25).map(|(k, v)| (k, *v)),
+        Some((20, 2))
+    );
+    assert_eq!(artmap.next_after_mut(&90), None);
+    assert_eq!(artmap.prev_before_mut(&0), None);
+}
+
+#[test]
+fn u64_nearest_matches_btree_probes() {
+    let mut artmap = U64ArtMap::new();
+    assert_eq!(artmap.nearest(42), None);
+    let mut btree = BTreeMap::new();
+    for i in 0..2_000u64 {
+        let key = i * i % 100_003 * 37;
+        artmap.insert(key, i);
+        btree.insert(key, i);
+    }
+    artmap.insert(u64::MAX, 0);
+    btree.insert(u64::MAX, 0);
+
+    let mut rng = rand::thread_rng();
+    for _ in 0..5_000 {
+        let key = rng.gen_range(0..4_000_000);
+        let below = btree.range(..=key).next_back();
+        let above = btree.range(key..).next();
+        let expected = match (below, above) {
+            (Some(b), Some(a)) if a.0 - key < key - b.0 => a,
+            (Some(b), _) => b,
+            (None, a) => a.unwrap(),
+        };
+        assert_eq!(artmap.nearest(key), Some((*expected.0, expected.1)));
+        assert_eq!(artmap.floor(&key), below.map(|(k, v)| (*k, v)));
+        assert_eq!(artmap.ceiling(&key), above.map(|(k, v)| (*k, v)));
+    }
+
+    // Ties go to the smaller key
+    let mut artmap = U64ArtMap::new();
+    artmap.insert(10, "ten");
+    artmap.insert(20, "twenty");
+    assert_eq!(artmap.nearest(15), Some((10, &"ten")));
+    assert_eq!(artmap.nearest(16), Some((20, &"twenty")));
+    assert_eq!(artmap.nearest(0), Some((10, &"ten")));
+    assert_eq!(artmap.nearest(u64::MAX), Some((20, &"twenty")));
+}
@@ -0,0 +1,99 @@
+extern crate adaptive_radix_tree;
+
+use adaptive_radix_tree::versioned_art_tree::*;
+
+#[test]
+fn test_reads_at_older_versions() {
+    let mut tree = VersionedArtTree::<u32>::new();
+    let v1 = tree.insert(&[1, 2, 3], 17);
+    let v2 = tree.insert(&[1, 2, 3], 18);
+    let v3 = tree.delete(&[1, 2, 3]).unwrap();
+
+    assert_eq!(tree.get_at(&[1, 2, 3], v1 - 1), None);
+    assert_eq!(tree.get_at(&[1, 2, 3], v1), Some(&17));
+    assert_eq!(tree.get_at(&[1, 2, 3], v2), Some(&18));
+    assert_eq!(tree.get_at(&[1, 2, 3], v3), None);
+    assert_eq!(tree.get(&[1, 2, 3]), None);
+    assert_eq!(tree.delete(&[1, 2, 3]), None);
+    assert_eq!(tree.current_version(), v3);
+}
+
+#[test]
+fn test_iter_at_returns_snapshot() {
+    let mut tree = VersionedArtTree::<u32>::new();
+    tree.insert(&[1], 10);
+    let snapshot = tree.insert(&[2], 20);
+    tree.insert(&[3], 30);
+    tree.delete(&[1]);
+
+    let at_snapshot: Vec<_> = tree.iter_at(snapshot).map(|(k, v)| (k[0], *v)).collect();
+    assert_eq!(at_snapshot, vec![(1, 10), (2, 20)]);
+
+    let latest: Vec<_> = tree
+        .iter_at(tree.current_version())
+        .map(|(k, v)| (k[0], *v))
+        .collect();
+    assert_eq!(latest, vec![(2, 20), (3, 30)]);
+}
+
+#[test]
+fn test_gc_keeps_reads_after_horizon() {
+    let mut tree = VersionedArtTree::<u32>::new();
+    tree.insert(&[1], 10);
+    tree.insert(&[1], 11);
+    let horizon = tree.insert(&[2], 20);
+    tree.insert(&[1], 12);
+    tree.delete(&[2]);
+
+    assert_eq!(tree.gc(horizon).entries, 1);
+    assert_eq!(tree.get_at(&[1], horizon), Some(&11));
+    assert_eq!(tree.get_at(&[2], horizon), Some(&20));
+    assert_eq!(tree.get(&[1]), Some(&12));
+
+    assert_eq!(tree.gc(tree.current_version()).entries, 3);
+    assert_eq!(tree.get(&[1]), Some(&12));
+    assert_eq!(tree.get(&[2]), None);
+}
+
+#[test]
+fn test_history_limit_prunes_old_versions() {
+    let mut tree = VersionedArtTree::<u32>::with_history_limit(2);
+    let v1 = tree.insert(&[1], 10);
+    assert_eq!(tree.get_version(&[1], 0), Some((v1, Some(&10))));
+    assert_eq!(tree.get_version(&[1], 1), None);
+
+    let v2 = tree.insert(&[1], 11);
+    tree.insert(&[2], 20);
+    let v4 = tree.insert(&[1], 12);
+    // The value before the last update
+    assert_eq!(tree.get_version(&[1], 1), Some((v2, Some(&11))));
+    assert_eq!(tree.get_version(&[1], 2), None);
+    assert_eq!(tree.get_at(&[1], v1), None);
+
+    let v5 = tree.delete(&[1]).unwrap();
+    let history: Vec<_> = tree.history(&[1]).collect();
+    assert_eq!(history, vec![(v4, Some(&12)), (v5, None)]);
+    assert_eq!(tree.get(&[1]), None);
+    assert_eq!(tree.history(&[3]).count(), 0);
+    assert_eq!(tree.get_version(&[2], 0).map(|(_, v)| v), Some(Some(&20)));
+}
+
+#[test]
+fn test_gc_reports_reclaimed_bytes() {
+    let mut tree = VersionedArtTree::<u64>::new();
+    for round in 0..10u64 {
+        for i in 0..1_000u32 {
+            tree.insert(&i.to_be_bytes(), round);
+        }
+    }
+    for i in 0..500u32 {
+        tree.delete(&i.to_be_bytes());
+    }
+
+    let stats = tree.gc(tree.current_version());
+    // Deleted keys lose their whole history, the others all but the latest version
+    assert_eq!(stats.entries, 500 * 11 + 500 * 9);
+    assert!(stats.bytes >= stats.entries * 16);
+    assert_eq!(tree.iter_at(tree.current_version()).count(), 500);
+    assert_eq!(tree.gc(tree.current_version()), GcStats::default());
+}